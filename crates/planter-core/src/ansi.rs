@@ -0,0 +1,74 @@
+//! ANSI escape stripping for plain-text log and PTY reads.
+
+/// Escape introducer byte.
+const ESC: u8 = 0x1b;
+/// Bell byte, one of the string-sequence terminators.
+const BEL: u8 = 0x07;
+/// Longest unterminated escape held back for the next read; longer ones are dropped.
+const MAX_PENDING_ESCAPE: usize = 256;
+
+/// Removes ANSI escape sequences (CSI/SGR, OSC and other string sequences, short escapes).
+///
+/// Returns the stripped bytes and how many input bytes they account for. An escape cut
+/// off by the end of `input` is left unconsumed so a read resuming at the consumed offset
+/// sees it whole; pass `at_end` when no more bytes will follow to drop it instead. An
+/// incomplete escape at the very start of `input` is dropped too, since holding it back
+/// would consume nothing and a reader resuming at the same offset would never advance.
+pub fn strip_ansi(input: &[u8], at_end: bool) -> (Vec<u8>, usize) {
+    let mut out = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while index < input.len() {
+        if input[index] != ESC {
+            out.push(input[index]);
+            index += 1;
+            continue;
+        }
+        match escape_len(&input[index..]) {
+            Some(len) => index += len,
+            None if at_end || index == 0 || input.len() - index > MAX_PENDING_ESCAPE => {
+                return (out, input.len());
+            }
+            None => return (out, index),
+        }
+    }
+    (out, input.len())
+}
+
+/// Returns the length of the escape at the start of `bytes`, or `None` when it is incomplete.
+fn escape_len(bytes: &[u8]) -> Option<usize> {
+    match *bytes.get(1)? {
+        b'[' => {
+            for (index, &byte) in bytes.iter().enumerate().skip(2) {
+                match byte {
+                    0x20..=0x3f => {}
+                    0x40..=0x7e => return Some(index + 1),
+                    // A stray byte aborts the sequence and is kept as text.
+                    _ => return Some(index),
+                }
+            }
+            None
+        }
+        b']' | b'P' | b'X' | b'^' | b'_' => {
+            for index in 2..bytes.len() {
+                match bytes[index] {
+                    BEL => return Some(index + 1),
+                    ESC => return (*bytes.get(index + 1)? == b'\\').then_some(index + 2),
+                    _ => {}
+                }
+            }
+            None
+        }
+        0x20..=0x2f => {
+            for (index, &byte) in bytes.iter().enumerate().skip(2) {
+                match byte {
+                    0x20..=0x2f => {}
+                    0x30..=0x7e => return Some(index + 1),
+                    _ => return Some(index),
+                }
+            }
+            None
+        }
+        0x30..=0x7e => Some(2),
+        _ => Some(1),
+    }
+}
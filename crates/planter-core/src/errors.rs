@@ -3,6 +3,7 @@ use thiserror::Error;
 
 /// Stable error classes exchanged over the planter protocol.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorCode {
     /// The request was malformed or semantically invalid.
@@ -30,3 +31,21 @@ pub struct PlanterError {
     /// Optional extended context for debugging.
     pub detail: Option<String>,
 }
+
+impl PlanterError {
+    /// Converts an I/O failure during `action`, reporting a full disk as unavailable.
+    pub fn from_io(action: &str, err: std::io::Error) -> Self {
+        if err.kind() == std::io::ErrorKind::StorageFull {
+            return Self {
+                code: ErrorCode::Unavailable,
+                message: format!("out of disk space: {action}"),
+                detail: Some(err.to_string()),
+            };
+        }
+        Self {
+            code: ErrorCode::Internal,
+            message: action.to_string(),
+            detail: Some(err.to_string()),
+        }
+    }
+}
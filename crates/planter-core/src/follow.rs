@@ -0,0 +1,51 @@
+//! Offset-based follow loop shared by log and PTY readers.
+
+/// One chunk returned by a read at an offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowChunk {
+    /// Absolute offset where `data` starts; may differ from the requested offset after a reset or tail.
+    pub offset: u64,
+    /// Bytes read.
+    pub data: Vec<u8>,
+    /// True when no more bytes are currently available.
+    pub eof: bool,
+    /// True when the source has finished and no more bytes will arrive.
+    pub complete: bool,
+    /// True when the source was truncated and reading restarted from its beginning.
+    pub reset: bool,
+    /// Offset to resume from when `data` was filtered and no longer spans the bytes read.
+    pub next_offset: Option<u64>,
+}
+
+impl FollowChunk {
+    /// Returns whether a reader should stop after this chunk.
+    ///
+    /// Followers stop only once the source completes; one-shot readers also stop when caught up.
+    pub fn is_last(&self, follow: bool) -> bool {
+        self.complete || (!follow && self.eof && self.data.is_empty())
+    }
+}
+
+/// Reads chunks from `offset` onward, handing each to `sink` until the last chunk.
+///
+/// Each read resumes right after the previous chunk's bytes, so resets and tail reads
+/// that move the offset are tracked automatically. For the same reason `read` may
+/// reconnect and retry after a dropped connection without losing or repeating bytes.
+pub async fn drive_follow<E>(
+    mut offset: u64,
+    follow: bool,
+    mut read: impl AsyncFnMut(u64) -> Result<FollowChunk, E>,
+    mut sink: impl AsyncFnMut(FollowChunk) -> Result<(), E>,
+) -> Result<(), E> {
+    loop {
+        let chunk = read(offset).await?;
+        offset = chunk
+            .next_offset
+            .unwrap_or_else(|| chunk.offset.saturating_add(chunk.data.len() as u64));
+        let last = chunk.is_last(follow);
+        sink(chunk).await?;
+        if last {
+            return Ok(());
+        }
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Correlates a response to a request in IPC streams.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct ReqId(
     /// Monotonic numeric request identifier.
@@ -10,6 +11,7 @@ pub struct ReqId(
 
 /// Identifies an isolated execution cell.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct CellId(
     /// Opaque cell identifier string.
@@ -18,6 +20,7 @@ pub struct CellId(
 
 /// Identifies a launched job.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct JobId(
     /// Opaque job identifier string.
@@ -26,6 +29,7 @@ pub struct JobId(
 
 /// Identifies an interactive PTY session.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct SessionId(
     /// Monotonic numeric PTY session identifier.
@@ -1,16 +1,28 @@
 //! Shared core protocol types and helpers used by all planter binaries.
 
+pub mod ansi;
 pub mod errors;
+pub mod follow;
 pub mod ids;
 pub mod paths;
 pub mod protocol;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod time;
+pub mod version;
 
+pub use ansi::strip_ansi;
 pub use errors::{ErrorCode, PlanterError};
+pub use follow::{FollowChunk, drive_follow};
 pub use ids::{CellId, JobId, ReqId, SessionId};
-pub use paths::default_state_dir;
+pub use paths::{SANDBOX_EXEC_PATH, default_state_dir};
 pub use protocol::{
-    CellInfo, CellSpec, CommandSpec, ExitStatus, JobInfo, LogStream, PROTOCOL_VERSION, PtyAction,
-    Request, RequestEnvelope, ResourceLimits, Response, ResponseEnvelope, TerminationReason,
+    CellFileEntry, CellInfo, CellSpec, CommandSpec, DEFAULT_MAX_PTY_INPUT_BYTES, DaemonDiagnostics,
+    DiagnosticsReport, ErrorCount, EventKind, ExitStatus, HealthStatus, JobInfo, JournalEvent,
+    LogStream, LogStreamInfo, PROTOCOL_VERSION, PtyAction, Request, RequestEnvelope,
+    ResourceCounts, ResourceLimits, Response, ResponseEnvelope, SandboxDiagnostics, SandboxMode,
+    SandboxOperation, SignalInfo, SnapshotJob, StateSnapshot, TerminationReason, WorkerDiagnostics,
+    WorkerMode,
 };
 pub use time::now_ms;
+pub use version::{VERSION_FILE_NAME, VersionFile};
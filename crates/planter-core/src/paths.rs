@@ -1,5 +1,8 @@
 use std::{env, path::PathBuf};
 
+/// System path for the macOS sandbox runner.
+pub const SANDBOX_EXEC_PATH: &str = "/usr/bin/sandbox-exec";
+
 /// Resolves the daemon state directory from environment and platform defaults.
 pub fn default_state_dir() -> PathBuf {
     if let Some(override_dir) = env::var_os("PLANTER_STATE_DIR") {
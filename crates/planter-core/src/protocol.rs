@@ -5,37 +5,59 @@ use serde::{Deserialize, Serialize};
 use crate::{CellId, ErrorCode, JobId, ReqId, SessionId};
 
 /// Wire protocol version expected by current binaries.
-pub const PROTOCOL_VERSION: u32 = 2;
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Default daemon cap on one `PtyInput` payload; clients split longer input into chunks this size.
+pub const DEFAULT_MAX_PTY_INPUT_BYTES: usize = 64 * 1024;
 
 /// Request envelope carrying metadata plus a typed request body.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RequestEnvelope<T> {
     /// Client-generated request identifier.
     pub req_id: ReqId,
     /// Typed request payload.
     pub body: T,
+    /// Milliseconds after receipt by which the server ends blocking waits, if bounded.
+    #[serde(default)]
+    pub deadline_ms: Option<u64>,
 }
 
 /// Response envelope carrying metadata plus a typed response body.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ResponseEnvelope<T> {
     /// Request identifier echoed from the request envelope.
     pub req_id: ReqId,
     /// Typed response payload.
     pub body: T,
+    /// True for intermediate frames of a streaming request; the final frame leaves it false.
+    #[serde(default)]
+    pub more: bool,
 }
 
 /// Defines a new cell's metadata and base environment.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CellSpec {
     /// Friendly cell name.
     pub name: String,
     /// Environment variables applied to all cell jobs.
     pub env: BTreeMap<String, String>,
+    /// Sandbox policy for this cell's jobs; the daemon default applies when unset.
+    #[serde(default)]
+    pub sandbox_mode: Option<SandboxMode>,
+    /// Daemon environment variable names whose values jobs inherit beneath cell and command env.
+    #[serde(default)]
+    pub inherit_host_env: Vec<String>,
+    /// `planter-execd` executable for this cell's worker; must be on the daemon's allowlist.
+    #[serde(default)]
+    pub worker_bin_override: Option<String>,
 }
 
 /// Optional limits that apply to a launched job.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ResourceLimits {
     /// Maximum runtime in milliseconds before timeout.
     pub timeout_ms: Option<u64>,
@@ -43,10 +65,26 @@ pub struct ResourceLimits {
     pub max_rss_bytes: Option<u64>,
     /// Maximum accumulated log bytes across streams.
     pub max_log_bytes: Option<u64>,
+    /// Hard cap on open file descriptors (`RLIMIT_NOFILE`).
+    #[serde(default)]
+    pub max_open_files: Option<u64>,
+    /// Hard cap on processes for the job's user (`RLIMIT_NPROC`).
+    #[serde(default)]
+    pub max_processes: Option<u64>,
+    /// Hard cap on CPU time in seconds (`RLIMIT_CPU`).
+    #[serde(default)]
+    pub max_cpu_seconds: Option<u64>,
+    /// Scheduling niceness from -20 (highest priority) to 19 (lowest).
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// CPU core indices the job is pinned to (Linux only).
+    #[serde(default)]
+    pub cpu_affinity: Option<Vec<usize>>,
 }
 
 /// Command launch specification for job execution.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CommandSpec {
     /// Executable and argument vector.
     pub argv: Vec<String>,
@@ -61,6 +99,7 @@ pub struct CommandSpec {
 
 /// Materialized metadata for a created cell.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CellInfo {
     /// Stable cell identifier.
     pub id: CellId,
@@ -70,10 +109,38 @@ pub struct CellInfo {
     pub created_at_ms: u64,
     /// Absolute path to the cell directory.
     pub dir: String,
+    /// True once the cell stops accepting new jobs ahead of removal.
+    #[serde(default)]
+    pub draining: bool,
+}
+
+/// One entry in a cell directory listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CellFileEntry {
+    /// Entry file name.
+    pub name: String,
+    /// Size in bytes; zero for directories.
+    pub size: u64,
+    /// True when the entry is a directory.
+    pub is_dir: bool,
+    /// Last modification time in UNIX milliseconds, when available.
+    pub mtime_ms: Option<u64>,
+}
+
+/// One signal name the daemon accepts, with its number on the daemon's platform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SignalInfo {
+    /// Signal name without the `SIG` prefix, e.g. `TERM`.
+    pub name: String,
+    /// Platform signal number.
+    pub number: i32,
 }
 
 /// Why a job transitioned out of running state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TerminationReason {
     /// Process exited on its own.
@@ -94,6 +161,7 @@ pub enum TerminationReason {
 
 /// Current process completion state.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ExitStatus {
     /// Process is still running.
@@ -104,6 +172,7 @@ pub enum ExitStatus {
 
 /// Log stream selector for read operations.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum LogStream {
     /// Standard output stream.
@@ -114,6 +183,7 @@ pub enum LogStream {
 
 /// PTY operation acknowledged by the daemon.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum PtyAction {
     /// Session was created.
@@ -126,8 +196,93 @@ pub enum PtyAction {
     Closed,
 }
 
+/// Kind of lifecycle event recorded in the daemon journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A cell was created.
+    CellCreated,
+    /// A cell was renamed.
+    CellRenamed,
+    /// A cell was removed.
+    CellRemoved,
+    /// A cell drain completed or timed out.
+    CellDrained,
+    /// A cell's worker was restarted.
+    WorkerRestarted,
+    /// A job was launched.
+    JobStarted,
+    /// A job was signaled for termination.
+    JobKilled,
+    /// A PTY session was opened.
+    PtyOpened,
+    /// A PTY session was closed.
+    PtyClosed,
+    /// Idle PTY sessions were closed by a reap.
+    PtyReaped,
+    /// Cells and jobs were restored from a snapshot.
+    StateRestored,
+}
+
+/// One structured entry from the daemon event journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JournalEvent {
+    /// Event timestamp in UNIX milliseconds.
+    pub ts_ms: u64,
+    /// Event kind.
+    pub kind: EventKind,
+    /// Related cell identifier, when known.
+    #[serde(default)]
+    pub cell_id: Option<CellId>,
+    /// Related job identifier, when known.
+    #[serde(default)]
+    pub job_id: Option<JobId>,
+    /// Optional free-form event context.
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Sandboxing policy applied to launched jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxMode {
+    /// Launch without sandbox restrictions.
+    Disabled,
+    /// Attempt sandboxing and fall back to unsandboxed execution on failure.
+    Permissive,
+    /// Require sandboxing; fail if sandbox launch cannot be applied.
+    Enforced,
+}
+
+impl SandboxMode {
+    /// Returns the stable lowercase name used on the wire and in structured logs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SandboxMode::Disabled => "disabled",
+            SandboxMode::Permissive => "permissive",
+            SandboxMode::Enforced => "enforced",
+        }
+    }
+}
+
+/// Filesystem operation attempted by a sandbox check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxOperation {
+    /// Read a file or list a directory.
+    Read,
+    /// Create or append to a file, or create a file inside a directory.
+    Write,
+}
+
 /// Materialized metadata for a launched job.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct JobInfo {
     /// Stable job identifier.
     pub id: JobId,
@@ -135,7 +290,10 @@ pub struct JobInfo {
     pub cell_id: CellId,
     /// Command specification used for launch.
     pub command: CommandSpec,
-    /// Start timestamp in UNIX milliseconds.
+    /// UNIX milliseconds when the daemon received the launch request.
+    #[serde(default)]
+    pub requested_at_ms: u64,
+    /// UNIX milliseconds when the worker acknowledged the process started.
     pub started_at_ms: u64,
     /// Optional finish timestamp in UNIX milliseconds.
     pub finished_at_ms: Option<u64>,
@@ -146,39 +304,251 @@ pub struct JobInfo {
     /// Optional reason for termination.
     #[serde(default)]
     pub termination_reason: Option<TerminationReason>,
+    /// Sandbox policy configured when the job launched, if recorded.
+    #[serde(default)]
+    pub sandbox_mode: Option<SandboxMode>,
+    /// True when the job was actually launched under a sandbox.
+    #[serde(default)]
+    pub sandboxed: bool,
+    /// Why a job whose sandbox mode was not `disabled` ran without a sandbox.
+    #[serde(default)]
+    pub sandbox_fallback_reason: Option<String>,
+    /// Milliseconds from receiving the launch request to the worker reporting the spawn.
+    #[serde(default)]
+    pub spawn_latency_ms: Option<u64>,
+    /// Fully merged environment passed to the process, when recorded at launch.
+    #[serde(default)]
+    pub effective_env: Option<BTreeMap<String, String>>,
+    /// False when the job's cell metadata is gone, leaving the job orphaned.
+    #[serde(default = "default_cell_exists")]
+    pub cell_exists: bool,
+    /// Bytes written to the stdout log so far.
+    #[serde(default)]
+    pub stdout_bytes: u64,
+    /// Bytes written to the stderr log so far.
+    #[serde(default)]
+    pub stderr_bytes: u64,
+}
+
+/// On-disk size of one job log stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogStreamInfo {
+    /// Bytes in the log file.
+    pub current_bytes: u64,
+}
+
+/// Job record captured in a [`StateSnapshot`], with the log paths needed to restore it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnapshotJob {
+    /// Job metadata as reported by `JobStatus`.
+    pub job: JobInfo,
+    /// Stdout log file path.
+    pub stdout_path: String,
+    /// Stderr log file path.
+    pub stderr_path: String,
+}
+
+/// Consistent copy of the daemon's cell and job metadata for backup and restore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StateSnapshot {
+    /// Snapshot timestamp in UNIX milliseconds.
+    pub taken_at_ms: u64,
+    /// State directory of the daemon the snapshot was taken from.
+    pub state_root: String,
+    /// Directory that daemon wrote job logs to.
+    pub logs_dir: String,
+    /// Every cell record, ordered by id.
+    pub cells: Vec<CellInfo>,
+    /// Every job record, ordered by id.
+    pub jobs: Vec<SnapshotJob>,
+}
+
+/// Read-only snapshot of daemon state meant for attaching to bug reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiagnosticsReport {
+    /// Daemon build, protocol versions, and configuration.
+    pub daemon: DaemonDiagnostics,
+    /// Counts of cells, jobs, and PTY sessions.
+    pub resources: ResourceCounts,
+    /// Cell workers currently cached by the daemon.
+    pub workers: Vec<WorkerDiagnostics>,
+    /// Sandbox configuration and availability.
+    pub sandbox: SandboxDiagnostics,
+    /// Error responses returned since the daemon started, by code.
+    pub errors: Vec<ErrorCount>,
+}
+
+/// Daemon build, protocol versions, and configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DaemonDiagnostics {
+    /// Daemon version string.
+    pub version: String,
+    /// Client wire protocol version.
+    pub protocol: u32,
+    /// Daemon-to-worker protocol version.
+    pub exec_protocol: u32,
+    /// State root directory.
+    pub state_root: String,
+    /// Wait between TERM and KILL when a kill request omits one.
+    pub default_kill_grace_ms: u64,
+    /// Daemon-wide cap on concurrently running jobs, if any.
+    pub max_running_jobs: Option<u64>,
+    /// Maximum bytes accepted in one PTY input message.
+    pub max_pty_input_bytes: u64,
+    /// Whether idle cell workers stop as soon as their last job finishes.
+    pub eager_worker_shutdown: bool,
+    /// Largest frame the daemon has read or written, including rejected oversized frames.
+    pub largest_frame_bytes: u64,
+}
+
+/// Counts of live daemon resources.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ResourceCounts {
+    /// Existing cells.
+    pub cells: u64,
+    /// Persisted job records.
+    pub jobs: u64,
+    /// Job records still marked running.
+    pub running_jobs: u64,
+    /// PTY sessions whose shell is still running.
+    pub pty_sessions: u64,
+}
+
+/// Overall daemon health reported by `Health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// The daemon and every probed worker answered in time.
+    Ok,
+    /// Overloaded or partly impaired but still serving requests.
+    Degraded,
+    /// Workers cannot be started or answered with an error.
+    Unhealthy,
+}
+
+/// How a cached cell worker runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerMode {
+    /// Dedicated `planter-execd` process.
+    Process,
+    /// Task inside the daemon process.
+    InProcess,
+}
+
+/// One cached cell worker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WorkerDiagnostics {
+    /// Cell served by the worker.
+    pub cell_id: CellId,
+    /// How the worker runs.
+    pub mode: WorkerMode,
+}
+
+/// Sandbox configuration and availability.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SandboxDiagnostics {
+    /// Default sandbox mode for new jobs.
+    pub mode: SandboxMode,
+    /// Whether the `sandbox-exec` runner is installed.
+    pub sandbox_exec_available: bool,
+}
+
+/// Number of error responses returned with one code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorCount {
+    /// Error code.
+    pub code: ErrorCode,
+    /// Responses returned with this code.
+    pub count: u64,
 }
 
 /// RPC request variants supported by the daemon.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Request {
     /// Returns daemon and protocol version metadata.
     Version {},
     /// Returns liveness status.
-    Health {},
+    Health {
+        /// When true, also start or ping the shared PTY worker and ping cached cell workers.
+        #[serde(default)]
+        deep: bool,
+    },
+    /// Lists the signal names the daemon accepts on this platform.
+    SupportedSignals {},
+    /// Returns a read-only diagnostics bundle for bug reports.
+    Diagnostics {},
     /// Creates a new cell.
     CellCreate {
         /// Cell creation specification.
         spec: CellSpec,
     },
+    /// Changes a cell's friendly name, keeping its id and directory.
+    CellRename {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// Replacement friendly name.
+        new_name: String,
+    },
     /// Starts a new job within a cell.
     JobRun {
         /// Target cell identifier.
         cell_id: CellId,
         /// Command to execute.
         cmd: CommandSpec,
+        /// Derives the job id from the launch spec and reuses an existing job with that id.
+        #[serde(default)]
+        deterministic_id: bool,
+        /// Names the job process after its job id so `ps` output maps back to planter jobs.
+        #[serde(default)]
+        process_title: bool,
+        /// Leaves `TMPDIR`, `TMP`, and `TEMP` alone instead of pointing them at a per-job
+        /// directory inside the cell.
+        #[serde(default)]
+        system_tmp: bool,
     },
     /// Fetches current job status.
     JobStatus {
         /// Target job identifier.
         job_id: JobId,
     },
+    /// Blocks until a job finishes, answering with `JobStatus`.
+    JobWait {
+        /// Target job identifier.
+        job_id: JobId,
+        /// Milliseconds to wait before answering with the still-running status; unbounded when absent.
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
+    /// Streams `UsageSample` frames for a running job until it exits, then `JobUsageEnded`.
+    JobUsageSubscribe {
+        /// Target job identifier.
+        job_id: JobId,
+        /// Milliseconds between samples; raised to a small floor.
+        interval_ms: u64,
+    },
     /// Requests job termination.
     JobKill {
         /// Target job identifier.
         job_id: JobId,
         /// When true, perform forceful termination.
         force: bool,
+        /// Milliseconds to wait between TERM and KILL; the daemon default applies when absent.
+        #[serde(default)]
+        grace_ms: Option<u64>,
     },
     /// Removes a cell and optionally its active jobs.
     CellRemove {
@@ -187,6 +557,69 @@ pub enum Request {
         /// When true, remove even if jobs are active.
         force: bool,
     },
+    /// Stops new jobs in a cell, waits for running jobs, then removes it.
+    CellDrain {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// Maximum time to wait for running jobs in milliseconds.
+        timeout_ms: u64,
+    },
+    /// Replaces a cell's worker, marking jobs it was running as terminated.
+    WorkerRestart {
+        /// Target cell identifier.
+        cell_id: CellId,
+    },
+    /// Probes whether a job in a cell may perform a filesystem operation on a path.
+    SandboxCheck {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// Absolute path to probe.
+        path: String,
+        /// Operation to attempt.
+        operation: SandboxOperation,
+    },
+    /// Writes one chunk of a file inside a cell directory.
+    CellPut {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// Byte offset of this chunk; zero truncates, later chunks must append.
+        offset: u64,
+        /// Chunk bytes.
+        data: Vec<u8>,
+        /// Writes into a staging file that only lands at `relative_path` on `CellPutFinalize`.
+        #[serde(default)]
+        staged: bool,
+    },
+    /// Verifies a staged upload's checksum and atomically moves it into place.
+    CellPutFinalize {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// Expected lowercase hex SHA-256 of the whole file.
+        sha256: String,
+    },
+    /// Reads one chunk of a file inside a cell directory.
+    CellGet {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// Byte offset to start reading from.
+        offset: u64,
+        /// Maximum bytes to return.
+        max_bytes: u32,
+    },
+    /// Lists one directory inside a cell directory.
+    CellListFiles {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// Directory path relative to the cell directory; the cell root when absent.
+        #[serde(default)]
+        subpath: Option<String>,
+    },
     /// Reads job logs from a stream with offset-based pagination.
     LogsRead {
         /// Target job identifier.
@@ -201,6 +634,51 @@ pub enum Request {
         follow: bool,
         /// Follow wait timeout in milliseconds.
         wait_ms: u64,
+        /// Removes ANSI escape sequences from the returned bytes.
+        #[serde(default)]
+        strip_ansi: bool,
+        /// Reads the window ending at `offset` instead of starting there, walking toward the
+        /// start of the log; an offset past the end anchors at the end.
+        #[serde(default)]
+        reverse: bool,
+        /// Truncation generation from the previous chunk; if the log was truncated since, the
+        /// read restarts at zero with `reset` set.
+        #[serde(default)]
+        generation: Option<u64>,
+    },
+    /// Returns whatever log bytes are on disk at `offset` without ever waiting.
+    LogsPeek {
+        /// Target job identifier.
+        job_id: JobId,
+        /// Selected stream.
+        stream: LogStream,
+        /// Byte offset to start reading from.
+        offset: u64,
+        /// Maximum bytes to return.
+        max_bytes: u32,
+    },
+    /// Truncates a job's logs to zero bytes without stopping the job.
+    LogsTruncate {
+        /// Target job identifier.
+        job_id: JobId,
+        /// Stream to truncate; both streams when absent.
+        #[serde(default)]
+        stream: Option<LogStream>,
+    },
+    /// Flushes a job's log files to disk so later reads see all written output.
+    LogsSync {
+        /// Target job identifier.
+        job_id: JobId,
+    },
+    /// Reports a job's log sizes and log quota.
+    LogsInfo {
+        /// Target job identifier.
+        job_id: JobId,
+    },
+    /// Ends every in-progress follow read of a job's logs as if its wait had elapsed.
+    CancelFollow {
+        /// Target job identifier.
+        job_id: JobId,
     },
     /// Opens an interactive PTY session.
     PtyOpen {
@@ -216,6 +694,12 @@ pub enum Request {
         cols: u16,
         /// Initial terminal rows.
         rows: u16,
+        /// When false, skip the rc-file `cd` jail and prompt lock.
+        #[serde(default = "default_jail_cwd")]
+        jail_cwd: bool,
+        /// When true, tee output to disk so scrollback trimmed from memory stays readable.
+        #[serde(default)]
+        persist_scrollback: bool,
     },
     /// Sends input bytes to a PTY session.
     PtyInput {
@@ -236,6 +720,24 @@ pub enum Request {
         follow: bool,
         /// Follow wait timeout in milliseconds.
         wait_ms: u64,
+        /// When set, read the last N buffered bytes and ignore `offset`.
+        #[serde(default)]
+        from_end: Option<u32>,
+        /// Ends the chunk on a UTF-8 codepoint boundary instead of mid-sequence.
+        #[serde(default)]
+        utf8_boundary: bool,
+        /// Removes ANSI escape sequences from the returned bytes.
+        #[serde(default)]
+        strip_ansi: bool,
+    },
+    /// Returns whatever PTY output is buffered at `offset` without waiting or counting as activity.
+    PtyPeek {
+        /// Target PTY session identifier.
+        session_id: SessionId,
+        /// Byte offset to start reading from.
+        offset: u64,
+        /// Maximum bytes to return.
+        max_bytes: u32,
     },
     /// Resizes an existing PTY session.
     PtyResize {
@@ -253,10 +755,49 @@ pub enum Request {
         /// When true, force-close the session.
         force: bool,
     },
+    /// Force-closes every PTY session idle longer than the threshold.
+    PtyReap {
+        /// Idle threshold in milliseconds; input, resizes, and output reads count as activity.
+        idle_ms: u64,
+    },
+    /// Ends every in-progress follow read of a PTY session as if its wait had elapsed.
+    PtyCancelFollow {
+        /// Target PTY session identifier.
+        session_id: SessionId,
+    },
+    /// Renders the macOS sandbox profile a PTY session runs under, without spawning anything.
+    PtySandboxProfile {
+        /// Existing session to render for; the next session to be opened when absent.
+        #[serde(default)]
+        session_id: Option<SessionId>,
+    },
+    /// Captures every cell and job record for backup.
+    StateSnapshot {},
+    /// Recreates cell and job records from a snapshot; no processes are restarted.
+    StateRestore {
+        /// Snapshot to import; none of its ids may already exist.
+        snapshot: StateSnapshot,
+    },
+    /// Returns recent lifecycle events from the daemon journal.
+    Events {
+        /// Only return events at or after this UNIX millisecond timestamp.
+        since_ms: u64,
+        /// Maximum number of most recent events to return.
+        limit: u32,
+    },
+    /// Executes sub-requests sequentially and returns their responses in order.
+    Batch {
+        /// Sub-requests to execute; nested batches are rejected.
+        requests: Vec<Request>,
+        /// When true, stop after the first error response.
+        #[serde(default)]
+        stop_on_error: bool,
+    },
 }
 
 /// RPC response variants returned by the daemon.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Response {
     /// Protocol version details.
@@ -268,14 +809,32 @@ pub enum Response {
     },
     /// Service health result.
     Health {
-        /// Health status string.
-        status: String,
+        /// Overall health status.
+        status: HealthStatus,
+        /// Why the status is not `ok`.
+        #[serde(default)]
+        detail: Option<String>,
+    },
+    /// Signal names accepted by the daemon.
+    SupportedSignals {
+        /// Accepted signals in ascending number order.
+        signals: Vec<SignalInfo>,
+    },
+    /// Diagnostics bundle.
+    Diagnostics {
+        /// Daemon state snapshot.
+        report: DiagnosticsReport,
     },
     /// Cell creation acknowledgment.
     CellCreated {
         /// Created cell metadata.
         cell: CellInfo,
     },
+    /// Cell rename acknowledgment.
+    CellRenamed {
+        /// Updated cell metadata.
+        cell: CellInfo,
+    },
     /// Job start acknowledgment.
     JobStarted {
         /// Started job metadata.
@@ -290,23 +849,91 @@ pub enum Response {
     JobKilled {
         /// Terminated job identifier.
         job_id: JobId,
-        /// Signal description used for termination.
+        /// Signal description used for termination; `none` when nothing was sent.
         signal: String,
         /// Latest job status after signal delivery.
         status: ExitStatus,
+        /// False when the job had already finished and no signal was sent.
+        #[serde(default = "default_delivered")]
+        delivered: bool,
     },
     /// Cell removal acknowledgment.
     CellRemoved {
         /// Removed cell identifier.
         cell_id: CellId,
     },
+    /// Cell drain result.
+    CellDrained {
+        /// Drained cell identifier.
+        cell_id: CellId,
+        /// True when all jobs finished and the cell was removed.
+        removed: bool,
+        /// Jobs still running when the drain timeout elapsed.
+        pending_jobs: Vec<JobId>,
+    },
+    /// Worker restart result.
+    WorkerRestarted {
+        /// Cell whose worker was replaced.
+        cell_id: CellId,
+        /// Jobs that were running in the old worker and are now marked terminated.
+        terminated_jobs: Vec<JobId>,
+    },
+    /// Sandbox check result.
+    SandboxChecked {
+        /// Probed cell identifier.
+        cell_id: CellId,
+        /// Probed path.
+        path: String,
+        /// Attempted operation.
+        operation: SandboxOperation,
+        /// True when the probe performed the operation successfully.
+        allowed: bool,
+        /// True when the probe ran under the cell's sandbox profile.
+        sandboxed: bool,
+        /// Probe error output explaining a denial, when any.
+        #[serde(default)]
+        detail: Option<String>,
+    },
+    /// Cell file write acknowledgment.
+    CellPutAck {
+        /// Target cell identifier.
+        cell_id: CellId,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// File size after the write.
+        size: u64,
+    },
+    /// Chunk of a file read from a cell directory.
+    CellFileChunk {
+        /// Source cell identifier.
+        cell_id: CellId,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// Offset where this chunk starts.
+        offset: u64,
+        /// Raw file bytes.
+        data: Vec<u8>,
+        /// True when the chunk reaches the end of the file.
+        eof: bool,
+    },
+    /// Directory listing from a cell directory.
+    CellFiles {
+        /// Source cell identifier.
+        cell_id: CellId,
+        /// Listed directory relative to the cell directory.
+        subpath: Option<String>,
+        /// Entries sorted by name; symlinks are omitted.
+        entries: Vec<CellFileEntry>,
+        /// True when the directory held more entries than were returned.
+        truncated: bool,
+    },
     /// Chunk of job log output.
     LogsChunk {
         /// Job identifier.
         job_id: JobId,
         /// Stream from which bytes were read.
         stream: LogStream,
-        /// Offset immediately after this chunk.
+        /// Offset where this chunk starts.
         offset: u64,
         /// Raw log bytes.
         data: Vec<u8>,
@@ -314,6 +941,65 @@ pub enum Response {
         eof: bool,
         /// True when the source stream is complete and closed.
         complete: bool,
+        /// True when the log was truncated past the requested offset; data restarts at zero.
+        #[serde(default)]
+        reset: bool,
+        /// True when a later read may return more bytes; false once a finished job's log is fully read.
+        #[serde(default = "default_more_expected")]
+        more_expected: bool,
+        /// Offset to resume from when `strip_ansi` shortened `data`; `offset + data.len()` otherwise.
+        #[serde(default)]
+        next_offset: Option<u64>,
+        /// How many times the stream has been truncated; pass it back as the next read's `generation`.
+        #[serde(default)]
+        generation: u64,
+    },
+    /// Log bytes available at a peeked offset.
+    LogsPeeked {
+        /// Job identifier.
+        job_id: JobId,
+        /// Stream from which bytes were read.
+        stream: LogStream,
+        /// Offset where this chunk starts.
+        offset: u64,
+        /// Raw log bytes.
+        data: Vec<u8>,
+        /// Current log file length; `length - offset` bytes are pending in total.
+        length: u64,
+        /// True when the job has finished, so the log will not grow.
+        complete: bool,
+    },
+    /// Log truncation acknowledgment.
+    LogsTruncated {
+        /// Job identifier.
+        job_id: JobId,
+        /// Truncated stream; both streams when absent.
+        stream: Option<LogStream>,
+    },
+    /// Log sync acknowledgment.
+    LogsSynced {
+        /// Job identifier.
+        job_id: JobId,
+    },
+    /// Log sizes for a job.
+    LogsInfo {
+        /// Job identifier.
+        job_id: JobId,
+        /// Stdout log size.
+        stdout: LogStreamInfo,
+        /// Stderr log size.
+        stderr: LogStreamInfo,
+        /// Bytes across both streams.
+        total_bytes: u64,
+        /// Log quota from the job's limits, when one was set.
+        max_log_bytes: Option<u64>,
+    },
+    /// Follow cancellation acknowledgment.
+    FollowCancelled {
+        /// Job identifier.
+        job_id: JobId,
+        /// Number of follow reads that were ended early.
+        cancelled: u32,
     },
     /// PTY open acknowledgment.
     PtyOpened {
@@ -326,7 +1012,7 @@ pub enum Response {
     PtyChunk {
         /// PTY session identifier.
         session_id: SessionId,
-        /// Offset immediately after this chunk.
+        /// Absolute offset where this chunk starts.
         offset: u64,
         /// Raw PTY bytes.
         data: Vec<u8>,
@@ -336,6 +1022,22 @@ pub enum Response {
         complete: bool,
         /// Exit code when complete.
         exit_code: Option<i32>,
+        /// Offset to resume from when `strip_ansi` shortened `data`; `offset + data.len()` otherwise.
+        #[serde(default)]
+        next_offset: Option<u64>,
+    },
+    /// PTY output available at a peeked offset.
+    PtyPeeked {
+        /// PTY session identifier.
+        session_id: SessionId,
+        /// Absolute offset where this chunk starts; later than requested when trimmed.
+        offset: u64,
+        /// Raw output bytes.
+        data: Vec<u8>,
+        /// Total output bytes produced so far; `length - offset` bytes are pending in total.
+        length: u64,
+        /// True when the shell has exited, so output will not grow.
+        complete: bool,
     },
     /// PTY control acknowledgment.
     PtyAck {
@@ -344,6 +1046,37 @@ pub enum Response {
         /// Operation that was acknowledged.
         action: PtyAction,
     },
+    /// Idle PTY sessions closed by a reap.
+    PtyReaped {
+        /// Closed session identifiers in ascending order.
+        session_ids: Vec<SessionId>,
+    },
+    /// PTY follow cancellation acknowledgment.
+    PtyFollowCancelled {
+        /// PTY session identifier.
+        session_id: SessionId,
+        /// Number of follow reads that were ended early.
+        cancelled: u32,
+    },
+    /// Rendered PTY sandbox profile.
+    PtySandboxProfileRendered {
+        /// Session the profile was rendered for.
+        session_id: SessionId,
+        /// Sandbox profile source as passed to `sandbox-exec -f`.
+        profile: String,
+    },
+    /// Snapshot of every cell and job record.
+    StateSnapshot {
+        /// Captured metadata.
+        snapshot: StateSnapshot,
+    },
+    /// Counts of records imported by a restore.
+    StateRestored {
+        /// Cells recreated.
+        cells: u32,
+        /// Jobs recreated; any that were running are recorded as finished.
+        jobs: u32,
+    },
     /// Point-in-time resource usage sample.
     UsageSample {
         /// Job identifier.
@@ -355,6 +1088,23 @@ pub enum Response {
         /// Sample timestamp in UNIX milliseconds.
         timestamp_ms: u64,
     },
+    /// Final frame of a usage subscription, sent once the job has exited.
+    JobUsageEnded {
+        /// Job identifier.
+        job_id: JobId,
+        /// Final job status.
+        status: ExitStatus,
+    },
+    /// Responses for a batch, one per executed sub-request.
+    Batch {
+        /// Sub-responses in request order.
+        responses: Vec<Response>,
+    },
+    /// Recent journal events, oldest first.
+    Events {
+        /// Matching events.
+        events: Vec<JournalEvent>,
+    },
     /// Structured error response.
     Error {
         /// High-level error category.
@@ -365,3 +1115,23 @@ pub enum Response {
         detail: Option<String>,
     },
 }
+
+/// Default for job cell presence when the field is absent on the wire.
+fn default_cell_exists() -> bool {
+    true
+}
+
+/// Default for PTY cwd jailing when the field is absent on the wire.
+fn default_jail_cwd() -> bool {
+    true
+}
+
+/// Default for kill delivery when the field is absent on the wire.
+fn default_delivered() -> bool {
+    true
+}
+
+/// Default for log continuation when the field is absent on the wire.
+fn default_more_expected() -> bool {
+    true
+}
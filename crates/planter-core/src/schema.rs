@@ -0,0 +1,15 @@
+//! JSON Schema export of the wire protocol for client code generation.
+
+use schemars::schema_for;
+use serde_json::{Value, json};
+
+use crate::{PROTOCOL_VERSION, Request, RequestEnvelope, Response, ResponseEnvelope};
+
+/// Returns JSON Schemas for request and response envelopes tagged with the protocol version.
+pub fn protocol_schema() -> Value {
+    json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "request": schema_for!(RequestEnvelope<Request>),
+        "response": schema_for!(ResponseEnvelope<Response>),
+    })
+}
@@ -0,0 +1,38 @@
+//! Version record planterd leaves in its state directory for socket-free inspection.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// File name, relative to the state directory, of the daemon version record.
+pub const VERSION_FILE_NAME: &str = "version.json";
+
+/// Daemon build and protocol versions as recorded at startup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionFile {
+    /// Daemon crate version.
+    pub daemon: String,
+    /// Protocol version the daemon speaks.
+    pub protocol: u32,
+}
+
+impl VersionFile {
+    /// Atomically writes the record to `state_dir/version.json`.
+    pub fn write(&self, state_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(state_dir)?;
+        let path = state_dir.join(VERSION_FILE_NAME);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(
+            &tmp,
+            serde_json::to_vec_pretty(self).map_err(io::Error::other)?,
+        )?;
+        fs::rename(&tmp, &path)
+    }
+
+    /// Reads the record from `state_dir/version.json`.
+    pub fn read(state_dir: &Path) -> io::Result<Self> {
+        let bytes = fs::read(state_dir.join(VERSION_FILE_NAME))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
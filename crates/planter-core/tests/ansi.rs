@@ -0,0 +1,51 @@
+use planter_core::strip_ansi;
+
+#[test]
+/// Verifies SGR colors, cursor moves, and OSC titles are removed from colored output.
+fn strips_color_cursor_and_title_sequences() {
+    let input = b"\x1b]0;build\x07\x1b[1;31merror\x1b[0m: \x1b[2Kfailed\x1b(B\n";
+    let (data, consumed) = strip_ansi(input, false);
+    assert_eq!(data, b"error: failed\n");
+    assert_eq!(consumed, input.len());
+}
+
+#[test]
+/// Verifies an escape split across reads is held back and stripped once complete.
+fn holds_back_escape_split_at_chunk_end() {
+    let output = b"ok \x1b[32mgreen\x1b[0m done";
+    let split = 6;
+
+    let (first, consumed) = strip_ansi(&output[..split], false);
+    assert_eq!(first, b"ok ");
+    assert_eq!(consumed, 3);
+
+    let (rest, rest_consumed) = strip_ansi(&output[consumed..], false);
+    assert_eq!(rest, b"green done");
+    assert_eq!(consumed + rest_consumed, output.len());
+}
+
+#[test]
+/// Verifies a dangling escape is dropped once no more bytes will follow.
+fn drops_dangling_escape_at_end() {
+    assert_eq!(strip_ansi(b"tail\x1b[3", false), (b"tail".to_vec(), 4));
+    assert_eq!(strip_ansi(b"tail\x1b[3", true), (b"tail".to_vec(), 7));
+}
+
+#[test]
+/// Verifies a window holding only part of an escape is consumed so followers keep advancing.
+fn consumes_escape_longer_than_window() {
+    let output = b"\x1b]0;a very long window title\x07after";
+    let window = 8;
+
+    let (first, consumed) = strip_ansi(&output[..window], false);
+    assert!(first.is_empty());
+    assert_eq!(consumed, window);
+
+    let mut offset = consumed;
+    while offset < output.len() {
+        let end = (offset + window).min(output.len());
+        let (_, consumed) = strip_ansi(&output[offset..end], end == output.len());
+        assert!(consumed > 0, "stalled at offset {offset}");
+        offset += consumed;
+    }
+}
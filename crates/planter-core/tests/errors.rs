@@ -0,0 +1,18 @@
+use std::io;
+
+use planter_core::{ErrorCode, PlanterError};
+
+#[test]
+/// Verifies a full disk surfaces as `Unavailable` rather than an internal error.
+fn out_of_space_maps_to_unavailable() {
+    let err = PlanterError::from_io(
+        "write json file",
+        io::Error::from(io::ErrorKind::StorageFull),
+    );
+    assert_eq!(err.code, ErrorCode::Unavailable);
+    assert_eq!(err.message, "out of disk space: write json file");
+
+    let err = PlanterError::from_io("write json file", io::Error::other("io failure"));
+    assert_eq!(err.code, ErrorCode::Internal);
+    assert_eq!(err.message, "write json file");
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use planter_core::{FollowChunk, drive_follow};
+
+/// Builds a chunk starting at `offset`.
+fn chunk(offset: u64, data: &[u8], eof: bool, complete: bool) -> FollowChunk {
+    FollowChunk {
+        offset,
+        data: data.to_vec(),
+        eof,
+        complete,
+        reset: false,
+        next_offset: None,
+    }
+}
+
+/// Drives scripted chunks and returns the offsets requested plus the bytes delivered.
+async fn run(follow: bool, chunks: Vec<FollowChunk>) -> (Vec<u64>, Vec<u8>) {
+    let mut script = VecDeque::from(chunks);
+    let mut requested = Vec::new();
+    let mut delivered = Vec::new();
+    drive_follow(
+        0,
+        follow,
+        async |offset| {
+            requested.push(offset);
+            script.pop_front().ok_or("read past the last chunk")
+        },
+        async |chunk| {
+            delivered.extend_from_slice(&chunk.data);
+            Ok(())
+        },
+    )
+    .await
+    .expect("drive follow");
+    (requested, delivered)
+}
+
+#[tokio::test]
+/// Verifies a one-shot read stops once caught up, even before the source completes.
+async fn one_shot_stops_at_empty_eof() {
+    let (requested, delivered) = run(
+        false,
+        vec![chunk(0, b"abc", true, false), chunk(3, b"", true, false)],
+    )
+    .await;
+    assert_eq!(requested, vec![0, 3]);
+    assert_eq!(delivered, b"abc");
+}
+
+#[tokio::test]
+/// Verifies a follower keeps polling past empty reads until the source completes.
+async fn follow_continues_until_complete() {
+    let (requested, delivered) = run(
+        true,
+        vec![
+            chunk(0, b"ab", true, false),
+            chunk(2, b"", true, false),
+            chunk(2, b"cd", true, false),
+            chunk(4, b"", true, true),
+        ],
+    )
+    .await;
+    assert_eq!(requested, vec![0, 2, 2, 4]);
+    assert_eq!(delivered, b"abcd");
+}
+
+#[tokio::test]
+/// Verifies a completed chunk with data is delivered and ends the loop.
+async fn complete_chunk_is_delivered_then_stops() {
+    let (requested, delivered) = run(true, vec![chunk(0, b"done", true, true)]).await;
+    assert_eq!(requested, vec![0]);
+    assert_eq!(delivered, b"done");
+}
+
+#[tokio::test]
+/// Verifies the next read resumes after chunks whose offset moved after a reset or tail.
+async fn offset_tracks_reset_and_tail() {
+    let mut reset = chunk(0, b"xy", false, false);
+    reset.reset = true;
+    let (requested, _) = run(
+        true,
+        vec![
+            chunk(40, b"tail", false, false),
+            reset,
+            chunk(2, b"", true, true),
+        ],
+    )
+    .await;
+    assert_eq!(requested, vec![0, 44, 2]);
+}
+
+#[tokio::test]
+/// Verifies the next read resumes at `next_offset` when filtering shortened the data.
+async fn offset_uses_next_offset_of_filtered_chunks() {
+    let mut filtered = chunk(0, b"red", false, false);
+    filtered.next_offset = Some(9);
+    let (requested, delivered) = run(true, vec![filtered, chunk(9, b"", true, true)]).await;
+    assert_eq!(requested, vec![0, 9]);
+    assert_eq!(delivered, b"red");
+}
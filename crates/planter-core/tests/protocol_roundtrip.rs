@@ -11,6 +11,7 @@ fn request_envelope_roundtrip_cbor() {
     let input = RequestEnvelope {
         req_id: ReqId(42),
         body: Request::Version {},
+        deadline_ms: None,
     };
 
     let encoded = serde_cbor::to_vec(&input).expect("request encode should succeed");
@@ -25,8 +26,12 @@ fn request_envelope_roundtrip_cbor() {
             spec: CellSpec {
                 name: "demo".to_string(),
                 env: BTreeMap::from([(String::from("FOO"), String::from("bar"))]),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
             },
         },
+        deadline_ms: None,
     };
 
     let encoded = serde_cbor::to_vec(&create_request).expect("request encode should succeed");
@@ -43,7 +48,11 @@ fn request_envelope_roundtrip_cbor() {
             max_bytes: 1024,
             follow: true,
             wait_ms: 500,
+            strip_ansi: true,
+            reverse: false,
+            generation: None,
         },
+        deadline_ms: None,
     };
     let encoded = serde_cbor::to_vec(&logs_request).expect("request encode should succeed");
     let decoded: RequestEnvelope<Request> =
@@ -58,7 +67,11 @@ fn request_envelope_roundtrip_cbor() {
             max_bytes: 2048,
             follow: true,
             wait_ms: 250,
+            from_end: Some(512),
+            utf8_boundary: true,
+            strip_ansi: false,
         },
+        deadline_ms: None,
     };
     let encoded = serde_cbor::to_vec(&pty_request).expect("request encode should succeed");
     let decoded: RequestEnvelope<Request> =
@@ -75,6 +88,7 @@ fn response_envelope_roundtrip_cbor() {
             daemon: "0.1.0".to_string(),
             protocol: PROTOCOL_VERSION,
         },
+        more: false,
     };
 
     let encoded = serde_cbor::to_vec(&version).expect("response encode should succeed");
@@ -90,6 +104,7 @@ fn response_envelope_roundtrip_cbor() {
             message: "bad request".to_string(),
             detail: Some("missing field body".to_string()),
         },
+        more: false,
     };
 
     let encoded = serde_cbor::to_vec(&error).expect("error encode should succeed");
@@ -112,15 +127,30 @@ fn response_envelope_roundtrip_cbor() {
                         timeout_ms: Some(1000),
                         max_rss_bytes: None,
                         max_log_bytes: None,
+                        max_open_files: Some(64),
+                        max_processes: None,
+                        max_cpu_seconds: Some(30),
+                        nice: Some(10),
+                        cpu_affinity: None,
                     }),
                 },
+                requested_at_ms: 1,
                 started_at_ms: 1,
                 finished_at_ms: None,
                 pid: Some(100),
                 status: planter_core::ExitStatus::Running,
                 termination_reason: None,
+                sandbox_mode: Some(planter_core::SandboxMode::Permissive),
+                sandboxed: false,
+                sandbox_fallback_reason: None,
+                spawn_latency_ms: Some(3),
+                effective_env: None,
+                cell_exists: true,
+                stdout_bytes: 12,
+                stderr_bytes: 0,
             },
         },
+        more: false,
     };
 
     let encoded = serde_cbor::to_vec(&run_response).expect("response encode should succeed");
@@ -137,7 +167,12 @@ fn response_envelope_roundtrip_cbor() {
             data: b"hello".to_vec(),
             eof: true,
             complete: true,
+            reset: false,
+            more_expected: false,
+            next_offset: Some(12),
+            generation: 0,
         },
+        more: false,
     };
 
     let encoded = serde_cbor::to_vec(&logs).expect("response encode should succeed");
@@ -154,7 +189,9 @@ fn response_envelope_roundtrip_cbor() {
             eof: false,
             complete: false,
             exit_code: None,
+            next_offset: None,
         },
+        more: false,
     };
 
     let encoded = serde_cbor::to_vec(&pty).expect("response encode should succeed");
@@ -0,0 +1,140 @@
+#![cfg(feature = "schema")]
+
+use planter_core::{PROTOCOL_VERSION, schema::protocol_schema};
+use serde_json::Value;
+
+/// Request `type` tags expected in the exported schema; update when adding variants.
+const REQUEST_VARIANTS: &[&str] = &[
+    "version",
+    "health",
+    "supported_signals",
+    "diagnostics",
+    "cell_create",
+    "cell_rename",
+    "job_run",
+    "job_status",
+    "job_wait",
+    "job_usage_subscribe",
+    "job_kill",
+    "cell_remove",
+    "cell_drain",
+    "worker_restart",
+    "sandbox_check",
+    "cell_put",
+    "cell_put_finalize",
+    "cell_get",
+    "cell_list_files",
+    "logs_read",
+    "logs_peek",
+    "logs_truncate",
+    "logs_sync",
+    "logs_info",
+    "cancel_follow",
+    "pty_open",
+    "pty_input",
+    "pty_read",
+    "pty_peek",
+    "pty_resize",
+    "pty_close",
+    "pty_reap",
+    "pty_cancel_follow",
+    "pty_sandbox_profile",
+    "state_snapshot",
+    "state_restore",
+    "events",
+    "batch",
+];
+
+/// Response `type` tags expected in the exported schema; update when adding variants.
+const RESPONSE_VARIANTS: &[&str] = &[
+    "version",
+    "health",
+    "supported_signals",
+    "diagnostics",
+    "cell_created",
+    "cell_renamed",
+    "job_started",
+    "job_status",
+    "job_killed",
+    "cell_removed",
+    "cell_drained",
+    "worker_restarted",
+    "sandbox_checked",
+    "cell_put_ack",
+    "cell_file_chunk",
+    "cell_files",
+    "logs_chunk",
+    "logs_peeked",
+    "logs_truncated",
+    "logs_synced",
+    "logs_info",
+    "follow_cancelled",
+    "pty_opened",
+    "pty_chunk",
+    "pty_peeked",
+    "pty_ack",
+    "pty_reaped",
+    "pty_follow_cancelled",
+    "pty_sandbox_profile_rendered",
+    "state_snapshot",
+    "state_restored",
+    "usage_sample",
+    "job_usage_ended",
+    "batch",
+    "events",
+    "error",
+];
+
+/// Collects the `type` tag constants of a tagged enum definition.
+fn variant_tags(schema: &Value, section: &str, definition: &str) -> Vec<String> {
+    schema[section]["$defs"][definition]["oneOf"]
+        .as_array()
+        .unwrap_or_else(|| panic!("{definition} schema should list variants"))
+        .iter()
+        .map(|variant| {
+            variant["properties"]["type"]["const"]
+                .as_str()
+                .unwrap_or_else(|| panic!("{definition} variant should carry a type tag"))
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+/// Verifies the exported schema lists exactly the known request and response variants.
+fn schema_variants_stay_in_sync() {
+    let schema = protocol_schema();
+
+    assert_eq!(schema["protocol_version"], PROTOCOL_VERSION);
+    assert_eq!(
+        variant_tags(&schema, "request", "Request"),
+        REQUEST_VARIANTS
+    );
+    assert_eq!(
+        variant_tags(&schema, "response", "Response"),
+        RESPONSE_VARIANTS
+    );
+}
+
+#[test]
+/// Verifies variant fields and their documentation are carried into the schema.
+fn schema_describes_variant_fields() {
+    let schema = protocol_schema();
+    let requests = schema["request"]["$defs"]["Request"]["oneOf"]
+        .as_array()
+        .expect("request variants");
+    let job_run = requests
+        .iter()
+        .find(|variant| variant["properties"]["type"]["const"] == "job_run")
+        .expect("job_run variant");
+
+    assert!(job_run["properties"]["cmd"].is_object());
+    assert_eq!(job_run["properties"]["deterministic_id"]["default"], false);
+    assert_eq!(job_run["properties"]["system_tmp"]["default"], false);
+    assert!(
+        job_run["required"]
+            .as_array()
+            .expect("required fields")
+            .contains(&Value::from("cell_id"))
+    );
+}
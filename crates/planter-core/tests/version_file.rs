@@ -0,0 +1,33 @@
+use planter_core::{PROTOCOL_VERSION, VERSION_FILE_NAME, VersionFile};
+use tempfile::tempdir;
+
+#[test]
+/// Verifies the version record is written under the state dir and parses back.
+fn version_file_roundtrip() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    let record = VersionFile {
+        daemon: "1.2.3".to_string(),
+        protocol: PROTOCOL_VERSION,
+    };
+
+    record.write(&state_dir).expect("write version file");
+
+    let raw: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(state_dir.join(VERSION_FILE_NAME)).expect("version file exists"),
+    )
+    .expect("plain json");
+    assert_eq!(raw["daemon"], "1.2.3");
+    assert_eq!(
+        VersionFile::read(&state_dir).expect("read version file"),
+        record
+    );
+}
+
+#[test]
+/// Verifies a missing version record reports `NotFound`.
+fn missing_version_file_is_not_found() {
+    let tmp = tempdir().expect("tempdir");
+    let err = VersionFile::read(tmp.path()).expect_err("no file written");
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
@@ -1,10 +1,19 @@
 use std::collections::BTreeMap;
 
-use planter_core::{CommandSpec, ErrorCode, ExitStatus, JobId, SessionId, TerminationReason};
+use planter_core::{
+    CommandSpec, ErrorCode, ExitStatus, JobId, LogStream, PlanterError, SandboxMode, SessionId,
+    TerminationReason,
+};
 use serde::{Deserialize, Serialize};
 
 /// Protocol version used by `planterd` <-> `planter-execd` control RPC.
 pub const EXECD_PROTOCOL_VERSION: u32 = 1;
+/// Hello rejection message a worker sends when the daemon speaks another protocol version.
+pub const UNSUPPORTED_PROTOCOL_MESSAGE: &str = "unsupported exec protocol version";
+/// Default wait between TERM and KILL when terminating a job gracefully.
+pub const DEFAULT_KILL_GRACE_MS: u64 = 250;
+/// Longest wait between TERM and KILL a kill may ask for.
+pub const MAX_KILL_GRACE_MS: u64 = 5 * 60 * 1000;
 
 /// Worker request envelope with request id metadata.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -38,6 +47,20 @@ pub enum ExecPtyAction {
     Closed,
 }
 
+/// Sandbox a job is launched under, as resolved by the daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSandbox {
+    /// Sandbox policy; `Enforced` refuses to launch the job without the sandbox.
+    pub mode: SandboxMode,
+    /// Compiled sandbox profile path, when the platform provides one.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Real path of the cell directory the profile grants; the job starts there unless its
+    /// command sets a cwd.
+    #[serde(default)]
+    pub cell_dir: Option<String>,
+}
+
 /// Worker-specific error codes used in `ExecResponse::ExecError`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -76,13 +99,19 @@ pub enum ExecRequest {
         /// Job identifier assigned by daemon.
         job_id: JobId,
         /// Command specification.
-        cmd: CommandSpec,
+        cmd: Box<CommandSpec>,
         /// Effective environment overrides.
         env: BTreeMap<String, String>,
         /// Destination path for stdout log stream.
         stdout_path: String,
         /// Destination path for stderr log stream.
         stderr_path: String,
+        /// Names the process after the job id.
+        #[serde(default)]
+        process_title: bool,
+        /// Sandbox to launch the job under; absent launches it unsandboxed.
+        #[serde(default)]
+        sandbox: Option<JobSandbox>,
     },
     /// Reads current state for a job.
     JobStatus {
@@ -95,6 +124,9 @@ pub enum ExecRequest {
         job_id: JobId,
         /// When true, apply a forceful kill.
         force: bool,
+        /// Milliseconds to wait for a voluntary exit between TERM and KILL.
+        #[serde(default = "default_kill_grace_ms")]
+        grace_ms: u64,
     },
     /// Opens an interactive PTY session.
     PtyOpen {
@@ -110,6 +142,12 @@ pub enum ExecRequest {
         cols: u16,
         /// Initial rows.
         rows: u16,
+        /// When false, skip the rc-file `cd` jail and prompt lock.
+        #[serde(default = "default_jail_cwd")]
+        jail_cwd: bool,
+        /// When true, tee output to the session's on-disk scrollback log.
+        #[serde(default)]
+        persist_scrollback: bool,
     },
     /// Writes input bytes to a PTY session.
     PtyInput {
@@ -130,6 +168,24 @@ pub enum ExecRequest {
         follow: bool,
         /// Follow timeout in milliseconds.
         wait_ms: u64,
+        /// When set, read the last N buffered bytes and ignore `offset`.
+        #[serde(default)]
+        from_end: Option<u32>,
+        /// Ends the chunk on a UTF-8 codepoint boundary instead of mid-sequence.
+        #[serde(default)]
+        utf8_boundary: bool,
+        /// Removes ANSI escape sequences from the returned bytes.
+        #[serde(default)]
+        strip_ansi: bool,
+    },
+    /// Reads buffered PTY output without waiting or counting as activity.
+    PtyPeek {
+        /// Target session identifier.
+        session_id: SessionId,
+        /// Read offset in bytes.
+        offset: u64,
+        /// Maximum bytes to return.
+        max_bytes: u32,
     },
     /// Resizes a PTY session.
     PtyResize {
@@ -147,11 +203,35 @@ pub enum ExecRequest {
         /// When true, force-close resources.
         force: bool,
     },
+    /// Force-closes every PTY session idle longer than the threshold.
+    PtyReap {
+        /// Idle threshold in milliseconds.
+        idle_ms: u64,
+    },
+    /// Renders the sandbox profile for a PTY session without spawning.
+    PtySandboxProfile {
+        /// Existing session to render for; the next session id when absent.
+        #[serde(default)]
+        session_id: Option<SessionId>,
+    },
     /// Samples process usage for a job.
     UsageProbe {
         /// Target job identifier.
         job_id: JobId,
     },
+    /// Truncates a running job's log files in place.
+    LogsTruncate {
+        /// Target job identifier.
+        job_id: JobId,
+        /// Stream to truncate; both streams when absent.
+        #[serde(default)]
+        stream: Option<LogStream>,
+    },
+    /// Flushes a tracked job's log files to disk.
+    LogsSync {
+        /// Target job identifier.
+        job_id: JobId,
+    },
     /// Shuts the worker down.
     Shutdown {
         /// When true, stop immediately.
@@ -169,6 +249,9 @@ pub enum ExecResponse {
         protocol: u32,
         /// Worker process id.
         worker_pid: u32,
+        /// State root the worker writes logs and sessions under.
+        #[serde(default)]
+        state_root: Option<String>,
     },
     /// Ping response.
     Pong {},
@@ -178,6 +261,15 @@ pub enum ExecResponse {
         job_id: JobId,
         /// Child pid if available.
         pid: Option<u32>,
+        /// True when the worker launched the job under a sandbox.
+        #[serde(default)]
+        sandboxed: bool,
+        /// Why the worker fell back to launching the job without a sandbox.
+        #[serde(default)]
+        sandbox_fallback_reason: Option<String>,
+        /// Environment the process was actually launched with, when the worker reports it.
+        #[serde(default)]
+        env: Option<BTreeMap<String, String>>,
     },
     /// Current job status.
     JobStatus {
@@ -201,7 +293,7 @@ pub enum ExecResponse {
     PtyChunk {
         /// Session identifier.
         session_id: SessionId,
-        /// Offset after this chunk.
+        /// Absolute offset where this chunk starts.
         offset: u64,
         /// Raw output bytes.
         data: Vec<u8>,
@@ -211,6 +303,22 @@ pub enum ExecResponse {
         complete: bool,
         /// Exit code when complete.
         exit_code: Option<i32>,
+        /// Offset to resume from when ANSI stripping shortened `data`.
+        #[serde(default)]
+        next_offset: Option<u64>,
+    },
+    /// PTY output available at a peeked offset.
+    PtyPeeked {
+        /// Session identifier.
+        session_id: SessionId,
+        /// Absolute offset where this chunk starts.
+        offset: u64,
+        /// Raw output bytes.
+        data: Vec<u8>,
+        /// Total output bytes produced so far.
+        length: u64,
+        /// True when underlying shell has exited.
+        complete: bool,
     },
     /// PTY control acknowledgment.
     PtyAck {
@@ -219,6 +327,18 @@ pub enum ExecResponse {
         /// Operation acknowledged by worker.
         action: ExecPtyAction,
     },
+    /// Idle PTY sessions closed by a reap.
+    PtyReaped {
+        /// Closed session identifiers in ascending order.
+        session_ids: Vec<SessionId>,
+    },
+    /// Rendered PTY sandbox profile.
+    PtySandboxProfileRendered {
+        /// Session the profile was rendered for.
+        session_id: SessionId,
+        /// Sandbox profile source.
+        profile: String,
+    },
     /// Usage sample payload.
     UsageSample {
         /// Job identifier.
@@ -230,6 +350,16 @@ pub enum ExecResponse {
         /// Sample timestamp in UNIX milliseconds.
         timestamp_ms: u64,
     },
+    /// Log truncation acknowledgment.
+    LogsTruncated {
+        /// Job identifier.
+        job_id: JobId,
+    },
+    /// Log sync acknowledgment.
+    LogsSynced {
+        /// Job identifier.
+        job_id: JobId,
+    },
     /// Structured worker error.
     ExecError {
         /// High-level worker error category.
@@ -241,6 +371,40 @@ pub enum ExecResponse {
     },
 }
 
+impl ExecResponse {
+    /// Turns an `ExecError` reply into a daemon error and passes any other reply through.
+    ///
+    /// The worker's message and detail are kept verbatim so clients see what it reported.
+    pub fn into_result(self) -> Result<Self, PlanterError> {
+        match self {
+            ExecResponse::ExecError {
+                code,
+                message,
+                detail,
+            } => Err(PlanterError {
+                code: code.into(),
+                message,
+                detail,
+            }),
+            other => Ok(other),
+        }
+    }
+}
+
+impl From<ExecErrorCode> for ErrorCode {
+    /// Maps worker-local error categories into shared daemon error categories.
+    fn from(value: ExecErrorCode) -> Self {
+        match value {
+            ExecErrorCode::InvalidRequest => ErrorCode::InvalidRequest,
+            ExecErrorCode::NotFound => ErrorCode::NotFound,
+            ExecErrorCode::Unauthorized => ErrorCode::Unavailable,
+            ExecErrorCode::Unavailable => ErrorCode::Unavailable,
+            ExecErrorCode::Unsupported => ErrorCode::InvalidRequest,
+            ExecErrorCode::Internal => ErrorCode::Internal,
+        }
+    }
+}
+
 impl From<ErrorCode> for ExecErrorCode {
     /// Maps shared daemon error categories into worker-local error categories.
     fn from(value: ErrorCode) -> Self {
@@ -255,6 +419,16 @@ impl From<ErrorCode> for ExecErrorCode {
     }
 }
 
+/// Default kill grace period when the field is absent on the wire.
+fn default_kill_grace_ms() -> u64 {
+    DEFAULT_KILL_GRACE_MS
+}
+
+/// Default for PTY cwd jailing when the field is absent on the wire.
+fn default_jail_cwd() -> bool {
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -288,7 +462,7 @@ mod tests {
             req_id: 7,
             body: ExecRequest::RunJob {
                 job_id: planter_core::JobId("job-1".to_string()),
-                cmd: CommandSpec {
+                cmd: Box::new(CommandSpec {
                     argv: vec![
                         "/bin/sh".to_string(),
                         "-c".to_string(),
@@ -297,10 +471,12 @@ mod tests {
                     cwd: None,
                     env: BTreeMap::new(),
                     limits: None,
-                },
+                }),
                 env: BTreeMap::new(),
                 stdout_path: "/tmp/stdout.log".to_string(),
                 stderr_path: "/tmp/stderr.log".to_string(),
+                process_title: false,
+                sandbox: None,
             },
         };
         let bytes = serde_cbor::to_vec(&request).expect("encode request");
@@ -2,29 +2,46 @@
 
 mod pty;
 
+pub use pty::{DEFAULT_READER_JOIN_TIMEOUT, count_live_sessions, sweep_orphaned_sessions};
+
 use std::{
     collections::HashMap,
     fs,
-    os::fd::{FromRawFd, RawFd},
-    path::Path,
+    os::{
+        fd::{AsRawFd, FromRawFd, RawFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::{Path, PathBuf},
     process::{Command as StdCommand, Stdio},
-    time::Duration,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
 };
 
-use planter_core::{ErrorCode, ExitStatus, JobId, PlanterError, TerminationReason, now_ms};
+use planter_core::{
+    ErrorCode, ExitStatus, JobId, LogStream, PlanterError, SANDBOX_EXEC_PATH, SandboxMode,
+    SessionId, TerminationReason, now_ms,
+};
 use planter_execd_proto::{
-    EXECD_PROTOCOL_VERSION, ExecErrorCode, ExecPtyAction, ExecRequest, ExecRequestEnvelope,
-    ExecResponse, ExecResponseEnvelope,
+    DEFAULT_KILL_GRACE_MS, EXECD_PROTOCOL_VERSION, ExecErrorCode, ExecPtyAction, ExecRequest,
+    ExecRequestEnvelope, ExecResponse, ExecResponseEnvelope, JobSandbox, MAX_KILL_GRACE_MS,
+    UNSUPPORTED_PROTOCOL_MESSAGE,
 };
 use planter_ipc::{
     IpcError,
-    codec::{decode, encode},
-    framing::{read_frame, write_frame},
+    codec::{decode, encode_into_framed},
+    framing::read_frame,
 };
+use planter_platform::{apply_rlimits, fd_path};
 use thiserror::Error;
-use tokio::{net::UnixStream, process::Child, process::Command, time::sleep};
+use tokio::{net::UnixStream, process::Child, process::Command, task::JoinHandle, time::sleep};
+use tracing::{Instrument, Span, field};
 
-use crate::pty::{PtyManager, PtySandboxMode};
+use crate::pty::{PtyManager, PtyOpenSpec, PtyReadSpec, PtySandboxMode};
+
+/// Host variables always passed through to jobs so bare program names resolve.
+const DEFAULT_JOB_ENV_PASSTHROUGH: &[&str] = &["PATH"];
+/// How often a TERM'd job is checked for exit during its grace period.
+const KILL_GRACE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// Startup configuration injected by the parent daemon.
 #[derive(Debug, Clone)]
@@ -35,6 +52,12 @@ pub struct WorkerConfig {
     pub auth_token: String,
     /// Root state directory for worker-managed artifacts.
     pub state_root: std::path::PathBuf,
+    /// Host environment variable names passed through to jobs.
+    pub env_allowlist: Vec<String>,
+    /// Keep the worker's controlling terminal for jobs instead of starting each in a new session.
+    pub inherit_tty: bool,
+    /// How long closing a PTY session waits for its output reader before detaching it.
+    pub pty_reader_join_timeout: Duration,
 }
 
 /// Fatal errors that stop the worker control loop.
@@ -55,20 +78,84 @@ pub enum WorkerError {
 struct WorkerRuntime {
     /// Active jobs by id.
     jobs: HashMap<JobId, WorkerJob>,
-    /// PTY session manager.
-    pty: PtyManager,
+    /// PTY session manager, shared with the peek lane.
+    pty: Arc<PtyManager>,
+    /// Host environment variable names passed through to jobs.
+    env_allowlist: Vec<String>,
+    /// Worker environment, captured at startup, that allowlisted variables are read from.
+    host_env: std::collections::BTreeMap<String, String>,
+    /// Whether jobs keep the worker's controlling terminal.
+    inherit_tty: bool,
 }
 
 /// Mutable state tracked for one launched job.
 struct WorkerJob {
-    /// Child process handle.
-    child: Child,
+    /// Child process handle, shared with any kill escalation still waiting out its grace period.
+    child: Arc<Mutex<Child>>,
     /// Current running/exited status.
     status: ExitStatus,
     /// Completion timestamp if finished.
     finished_at_ms: Option<u64>,
     /// Optional reason captured when process is terminated.
     termination_reason: Option<TerminationReason>,
+    /// Stdout log path.
+    stdout_path: PathBuf,
+    /// Stderr log path.
+    stderr_path: PathBuf,
+}
+
+/// Profile and cell directory a sandboxed job launches with.
+struct SandboxLaunch<'a> {
+    /// Compiled sandbox profile path.
+    profile: &'a str,
+    /// Cell directory the profile grants, opened so the job starts in exactly that directory.
+    cell_dir: Option<fs::File>,
+}
+
+/// Log destinations and launch options for one job.
+struct JobLaunch {
+    /// Destination path for stdout log stream.
+    stdout_path: String,
+    /// Destination path for stderr log stream.
+    stderr_path: String,
+    /// Names the process after the job id.
+    process_title: bool,
+    /// Sandbox to launch the job under.
+    sandbox: Option<JobSandbox>,
+}
+
+/// Builds the span one request is handled in, carrying the job or PTY session it targets.
+///
+/// `PtyOpen` records its session id once the session exists.
+fn request_span(request: &ExecRequest) -> Span {
+    let span = tracing::info_span!("request", job_id = field::Empty, session_id = field::Empty);
+    match request {
+        ExecRequest::RunJob { job_id, .. }
+        | ExecRequest::JobStatus { job_id }
+        | ExecRequest::JobSignal { job_id, .. }
+        | ExecRequest::UsageProbe { job_id }
+        | ExecRequest::LogsTruncate { job_id, .. }
+        | ExecRequest::LogsSync { job_id } => {
+            span.record("job_id", field::display(&job_id.0));
+        }
+        ExecRequest::PtyInput { session_id, .. }
+        | ExecRequest::PtyRead { session_id, .. }
+        | ExecRequest::PtyPeek { session_id, .. }
+        | ExecRequest::PtyResize { session_id, .. }
+        | ExecRequest::PtyClose { session_id, .. }
+        | ExecRequest::PtySandboxProfile {
+            session_id: Some(session_id),
+        } => {
+            span.record("session_id", session_id.0);
+        }
+        ExecRequest::Hello { .. }
+        | ExecRequest::Ping {}
+        | ExecRequest::PtyOpen { .. }
+        | ExecRequest::PtySandboxProfile { session_id: None }
+        | ExecRequest::PtyReap { .. }
+        | ExecRequest::Shutdown { .. } => {}
+    }
+    span
 }
 
 /// Converts an inherited fd into a nonblocking tokio unix stream.
@@ -88,12 +175,46 @@ pub fn control_stream_from_fd(fd: RawFd) -> Result<UnixStream, WorkerError> {
 }
 
 /// Serves the worker request loop on an authenticated control stream.
+///
+/// Everything the worker logs while serving is tagged with its `cell_id`, and each request's
+/// logs also with the `job_id` or `session_id` it targets.
 pub async fn serve_control_stream(
+    stream: UnixStream,
+    config: WorkerConfig,
+) -> Result<(), WorkerError> {
+    let span = tracing::info_span!("worker", cell_id = %config.cell_id);
+    serve_requests(stream, None, config).instrument(span).await
+}
+
+/// Serves like [`serve_control_stream`], also answering PTY peeks on `peek_lane`.
+///
+/// The lane is served alongside the control stream once the handshake succeeds, so a peek
+/// never waits behind a request the control stream is still handling.
+pub async fn serve_control_stream_with_peek_lane(
+    stream: UnixStream,
+    peek_lane: UnixStream,
+    config: WorkerConfig,
+) -> Result<(), WorkerError> {
+    let span = tracing::info_span!("worker", cell_id = %config.cell_id);
+    serve_requests(stream, Some(peek_lane), config)
+        .instrument(span)
+        .await
+}
+
+/// Runs the hello handshake and request loop behind [`serve_control_stream`].
+async fn serve_requests(
     mut stream: UnixStream,
+    mut peek_lane: Option<UnixStream>,
     config: WorkerConfig,
 ) -> Result<(), WorkerError> {
     let mut authed = false;
-    let mut runtime = WorkerRuntime::new(config.state_root.clone());
+    let mut response_frame = Vec::new();
+    let mut runtime = WorkerRuntime::new(
+        config.state_root.clone(),
+        config.env_allowlist.clone(),
+        config.inherit_tty,
+        config.pty_reader_join_timeout,
+    );
 
     loop {
         let frame = read_frame(&mut stream).await?;
@@ -110,7 +231,7 @@ pub async fn serve_control_stream(
                     if protocol != EXECD_PROTOCOL_VERSION {
                         ExecResponse::ExecError {
                             code: ExecErrorCode::InvalidRequest,
-                            message: "unsupported exec protocol version".to_string(),
+                            message: UNSUPPORTED_PROTOCOL_MESSAGE.to_string(),
                             detail: Some(format!(
                                 "expected={EXECD_PROTOCOL_VERSION} got={protocol}"
                             )),
@@ -132,6 +253,7 @@ pub async fn serve_control_stream(
                         ExecResponse::HelloAck {
                             protocol: EXECD_PROTOCOL_VERSION,
                             worker_pid: std::process::id(),
+                            state_root: Some(config.state_root.display().to_string()),
                         }
                     }
                 }
@@ -142,15 +264,19 @@ pub async fn serve_control_stream(
                 },
             };
 
-            write_response(&mut stream, req_id, response).await?;
+            write_response(&mut stream, &mut response_frame, req_id, response).await?;
             if !authed {
                 return Ok(());
             }
+            if let Some(lane) = peek_lane.take() {
+                tokio::spawn(serve_peek_lane(lane, Arc::clone(&runtime.pty)).in_current_span());
+            }
             continue;
         }
 
-        let (response, should_exit) = runtime.handle_request(request.body).await;
-        write_response(&mut stream, req_id, response).await?;
+        let span = request_span(&request.body);
+        let (response, should_exit) = runtime.handle_request(request.body).instrument(span).await;
+        write_response(&mut stream, &mut response_frame, req_id, response).await?;
         if should_exit {
             return Ok(());
         }
@@ -159,10 +285,21 @@ pub async fn serve_control_stream(
 
 impl WorkerRuntime {
     /// Creates an empty runtime and PTY manager for the worker.
-    fn new(state_root: std::path::PathBuf) -> Self {
+    fn new(
+        state_root: std::path::PathBuf,
+        env_allowlist: Vec<String>,
+        inherit_tty: bool,
+        pty_reader_join_timeout: Duration,
+    ) -> Self {
         Self {
             jobs: HashMap::new(),
-            pty: PtyManager::new(state_root, PtySandboxMode::Disabled),
+            pty: Arc::new(
+                PtyManager::new(state_root, PtySandboxMode::Disabled)
+                    .with_reader_join_timeout(pty_reader_join_timeout),
+            ),
+            env_allowlist,
+            host_env: std::env::vars().collect(),
+            inherit_tty,
         }
     }
 
@@ -176,18 +313,30 @@ impl WorkerRuntime {
                 env,
                 stdout_path,
                 stderr_path,
+                process_title,
+                sandbox,
             } => {
-                let result = self
-                    .run_job(job_id, cmd, env, stdout_path, stderr_path)
-                    .await;
+                let launch = JobLaunch {
+                    stdout_path,
+                    stderr_path,
+                    process_title,
+                    sandbox,
+                };
+                let result = self.run_job(job_id, *cmd, env, launch).await;
                 (map_result(result), false)
             }
             ExecRequest::JobStatus { job_id } => {
                 let result = self.job_status(job_id).await;
                 (map_result(result), false)
             }
-            ExecRequest::JobSignal { job_id, force } => {
-                let result = self.job_signal(job_id, force).await;
+            ExecRequest::JobSignal {
+                job_id,
+                force,
+                grace_ms,
+            } => {
+                let result = self
+                    .job_signal(job_id, force, Duration::from_millis(grace_ms))
+                    .await;
                 (map_result(result), false)
             }
             ExecRequest::PtyOpen {
@@ -197,13 +346,27 @@ impl WorkerRuntime {
                 env,
                 cols,
                 rows,
+                jail_cwd,
+                persist_scrollback,
             } => {
                 let result = self
                     .pty
-                    .open(shell, args, cwd, env, cols, rows)
-                    .map(|opened| ExecResponse::PtyOpened {
-                        session_id: opened.session_id,
-                        pid: opened.pid,
+                    .open(PtyOpenSpec {
+                        shell,
+                        args,
+                        cwd,
+                        env,
+                        cols,
+                        rows,
+                        jail_cwd,
+                        persist_scrollback,
+                    })
+                    .map(|opened| {
+                        Span::current().record("session_id", opened.session_id.0);
+                        ExecResponse::PtyOpened {
+                            session_id: opened.session_id,
+                            pid: opened.pid,
+                        }
                     });
                 (map_result(result), false)
             }
@@ -223,21 +386,44 @@ impl WorkerRuntime {
                 max_bytes,
                 follow,
                 wait_ms,
+                from_end,
+                utf8_boundary,
+                strip_ansi,
             } => {
                 let result = self
                     .pty
-                    .read(session_id, offset, max_bytes, follow, wait_ms)
+                    .read(
+                        session_id,
+                        PtyReadSpec {
+                            offset,
+                            max_bytes,
+                            follow,
+                            wait_ms,
+                            from_end,
+                            utf8_boundary,
+                            strip_ansi,
+                        },
+                    )
                     .await
                     .map(|chunk| ExecResponse::PtyChunk {
                         session_id,
                         offset: chunk.offset,
-                        data: chunk.data,
+                        data: chunk.data.into(),
                         eof: chunk.eof,
                         complete: chunk.complete,
                         exit_code: chunk.exit_code,
+                        next_offset: chunk.next_offset,
                     });
                 (map_result(result), false)
             }
+            ExecRequest::PtyPeek {
+                session_id,
+                offset,
+                max_bytes,
+            } => (
+                peek_response(&self.pty, session_id, offset, max_bytes),
+                false,
+            ),
             ExecRequest::PtyResize {
                 session_id,
                 cols,
@@ -253,19 +439,55 @@ impl WorkerRuntime {
                 (map_result(result), false)
             }
             ExecRequest::PtyClose { session_id, force } => {
+                let result =
+                    self.pty
+                        .close(session_id, force)
+                        .await
+                        .map(|()| ExecResponse::PtyAck {
+                            session_id,
+                            action: ExecPtyAction::Closed,
+                        });
+                (map_result(result), false)
+            }
+            ExecRequest::PtyReap { idle_ms } => {
                 let result = self
                     .pty
-                    .close(session_id, force)
-                    .map(|()| ExecResponse::PtyAck {
-                        session_id,
-                        action: ExecPtyAction::Closed,
-                    });
+                    .reap_idle(Duration::from_millis(idle_ms))
+                    .await
+                    .map(|session_ids| ExecResponse::PtyReaped { session_ids });
+                (map_result(result), false)
+            }
+            ExecRequest::PtySandboxProfile { session_id } => {
+                if !cfg!(target_os = "macos") {
+                    return (
+                        ExecResponse::ExecError {
+                            code: ExecErrorCode::Unsupported,
+                            message: "pty sandbox profiles are only available on macOS".to_string(),
+                            detail: None,
+                        },
+                        false,
+                    );
+                }
+                let result = self.pty.sandbox_profile(session_id).map(|rendered| {
+                    ExecResponse::PtySandboxProfileRendered {
+                        session_id: rendered.session_id,
+                        profile: rendered.profile,
+                    }
+                });
                 (map_result(result), false)
             }
             ExecRequest::UsageProbe { job_id } => {
                 let result = self.usage_probe(job_id).await;
                 (map_result(result), false)
             }
+            ExecRequest::LogsTruncate { job_id, stream } => {
+                let result = self.truncate_logs(job_id, stream);
+                (map_result(result), false)
+            }
+            ExecRequest::LogsSync { job_id } => {
+                let result = self.sync_logs(job_id);
+                (map_result(result), false)
+            }
             ExecRequest::Shutdown { force } => {
                 self.shutdown(force).await;
                 (ExecResponse::Pong {}, true)
@@ -282,13 +504,16 @@ impl WorkerRuntime {
     }
 
     /// Spawns a new child process and tracks it under the provided job id.
+    ///
+    /// Sandboxed jobs run through `sandbox-exec` with the daemon's compiled profile. When that
+    /// isn't possible an `Enforced` job is refused, while a `Permissive` one runs unsandboxed
+    /// and reports why.
     async fn run_job(
         &mut self,
         job_id: JobId,
         cmd: planter_core::CommandSpec,
         env: std::collections::BTreeMap<String, String>,
-        stdout_path: String,
-        stderr_path: String,
+        launch: JobLaunch,
     ) -> Result<ExecResponse, PlanterError> {
         if cmd.argv.is_empty() {
             return Err(PlanterError {
@@ -306,48 +531,100 @@ impl WorkerRuntime {
             });
         }
 
-        ensure_parent_dir(&stdout_path)?;
-        ensure_parent_dir(&stderr_path)?;
-        let stdout_file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&stdout_path)
-            .map_err(|err| io_to_planter_error("open stdout log", err))?;
-        let stderr_file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&stderr_path)
-            .map_err(|err| io_to_planter_error("open stderr log", err))?;
-
-        let mut command = Command::new(&cmd.argv[0]);
-        if cmd.argv.len() > 1 {
-            command.args(&cmd.argv[1..]);
-        }
-        if let Some(cwd) = cmd.cwd {
-            command.current_dir(cwd);
-        }
-        command.envs(env);
-        command.stdout(Stdio::from(stdout_file));
-        command.stderr(Stdio::from(stderr_file));
-
-        let child = command
-            .spawn()
-            .map_err(|err| io_to_planter_error("spawn job", err))?;
+        let enforced = launch
+            .sandbox
+            .as_ref()
+            .is_some_and(|sandbox| sandbox.mode == SandboxMode::Enforced);
+        let (sandbox_launch, mut sandbox_fallback_reason) =
+            match sandbox_launch(launch.sandbox.as_ref()) {
+                Ok(sandbox_launch) => (sandbox_launch, None),
+                Err(reason) if enforced => return Err(sandbox_unavailable(reason)),
+                Err(reason) => {
+                    tracing::warn!(reason, "launching permissive job without a sandbox");
+                    (None, Some(reason))
+                }
+            };
+
+        // A missing cwd also fails the spawn with NotFound; name it before it's blamed on argv[0].
+        let cwd_dir = cmd.cwd.as_deref().map(open_job_cwd).transpose()?;
+
+        ensure_parent_dir(&launch.stdout_path)?;
+        ensure_parent_dir(&launch.stderr_path)?;
+        let stdout_file = open_log_file(&launch.stdout_path)
+            .map_err(|err| PlanterError::from_io("open stdout log", err))?;
+        let stderr_file = open_log_file(&launch.stderr_path)
+            .map_err(|err| PlanterError::from_io("open stderr log", err))?;
+
+        let env = build_job_env(&self.host_env, &self.env_allowlist, env);
+        let program = &cmd.argv[0];
+        let spawn = |sandbox: Option<&SandboxLaunch<'_>>| -> std::io::Result<Child> {
+            let mut command = match sandbox {
+                Some(sandbox) => {
+                    let mut command = Command::new(SANDBOX_EXEC_PATH);
+                    command.arg("-f").arg(sandbox.profile).arg(program);
+                    command
+                }
+                None => Command::new(program),
+            };
+            if launch.process_title && sandbox.is_none() {
+                set_process_title(&mut command, &job_id);
+            }
+            if cmd.argv.len() > 1 {
+                command.args(&cmd.argv[1..]);
+            }
+            apply_rlimits(&mut command, cmd.limits.as_ref());
+            if !self.inherit_tty {
+                detach_from_terminal(&mut command);
+            }
+            // An explicit cwd wins; otherwise a sandboxed job starts in the cell its profile grants.
+            if let Some(dir) = cwd_dir
+                .as_ref()
+                .or(sandbox.and_then(|sandbox| sandbox.cell_dir.as_ref()))
+            {
+                enter_dir_on_spawn(&mut command, dir);
+            }
+            command.env_clear();
+            command.envs(&env);
+            command.stdout(Stdio::from(stdout_file.try_clone()?));
+            command.stderr(Stdio::from(stderr_file.try_clone()?));
+            command.spawn()
+        };
+
+        let spawned = match &sandbox_launch {
+            Some(sandbox) => match spawn(Some(sandbox)) {
+                Err(err) if !enforced => {
+                    tracing::warn!(error = %err, "sandbox launch failed; launching permissive job without a sandbox");
+                    sandbox_fallback_reason = Some(format!("sandbox launch failed: {err}"));
+                    spawn(None)
+                }
+                spawned => spawned,
+            },
+            None => spawn(None),
+        };
+        let child = spawned.map_err(|err| spawn_to_planter_error(&cmd.argv[0], err))?;
         let pid = child.id();
+        let sandboxed = sandbox_launch.is_some() && sandbox_fallback_reason.is_none();
+        tracing::debug!(pid, sandboxed, "job started");
 
         self.jobs.insert(
             job_id.clone(),
             WorkerJob {
-                child,
+                child: Arc::new(Mutex::new(child)),
                 status: ExitStatus::Running,
                 finished_at_ms: None,
                 termination_reason: None,
+                stdout_path: PathBuf::from(launch.stdout_path),
+                stderr_path: PathBuf::from(launch.stderr_path),
             },
         );
 
-        Ok(ExecResponse::JobStarted { job_id, pid })
+        Ok(ExecResponse::JobStarted {
+            job_id,
+            pid,
+            sandboxed,
+            sandbox_fallback_reason,
+            env: Some(env),
+        })
     }
 
     /// Returns current status for a tracked job, refreshing process state first.
@@ -367,10 +644,14 @@ impl WorkerRuntime {
         &mut self,
         job_id: JobId,
         force: bool,
+        grace: Duration,
     ) -> Result<ExecResponse, PlanterError> {
         let job = self.get_job_mut(&job_id)?;
         if matches!(job.status, ExitStatus::Running) {
-            signal_job(&mut job.child, force).await;
+            tracing::debug!(force, "signalling job");
+            // The reply doesn't wait out the grace period, so a long one never holds up the
+            // worker's other requests.
+            signal_job(&job.child, force, grace);
             job.status = ExitStatus::Exited { code: None };
             job.finished_at_ms = Some(now_ms());
             job.termination_reason = Some(if force {
@@ -391,7 +672,7 @@ impl WorkerRuntime {
     async fn usage_probe(&mut self, job_id: JobId) -> Result<ExecResponse, PlanterError> {
         let job = self.get_job_mut(&job_id)?;
         refresh_job(job)?;
-        let pid = job.child.id();
+        let pid = lock_child(&job.child).id();
         let rss_bytes = pid.and_then(|pid| read_rss_bytes(pid).ok().flatten());
 
         Ok(ExecResponse::UsageSample {
@@ -402,11 +683,49 @@ impl WorkerRuntime {
         })
     }
 
-    /// Terminates all running jobs during worker shutdown.
+    /// Truncates a tracked job's log files while its child keeps appending.
+    fn truncate_logs(
+        &mut self,
+        job_id: JobId,
+        stream: Option<LogStream>,
+    ) -> Result<ExecResponse, PlanterError> {
+        let job = self.get_job_mut(&job_id)?;
+        let paths = match stream {
+            Some(LogStream::Stdout) => vec![&job.stdout_path],
+            Some(LogStream::Stderr) => vec![&job.stderr_path],
+            None => vec![&job.stdout_path, &job.stderr_path],
+        };
+        for path in paths {
+            fs::OpenOptions::new()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_len(0))
+                .map_err(|err| PlanterError::from_io("truncate log", err))?;
+        }
+        Ok(ExecResponse::LogsTruncated { job_id })
+    }
+
+    /// Flushes a tracked job's log files to disk.
+    fn sync_logs(&mut self, job_id: JobId) -> Result<ExecResponse, PlanterError> {
+        let job = self.get_job_mut(&job_id)?;
+        for path in [&job.stdout_path, &job.stderr_path] {
+            fs::File::open(path)
+                .and_then(|file| file.sync_all())
+                .map_err(|err| PlanterError::from_io("sync log", err))?;
+        }
+        Ok(ExecResponse::LogsSynced { job_id })
+    }
+
+    /// Terminates all running jobs during worker shutdown, waiting out their grace periods.
     async fn shutdown(&mut self, force: bool) {
+        let mut escalations = Vec::new();
         for job in self.jobs.values_mut() {
             if matches!(job.status, ExitStatus::Running) {
-                signal_job(&mut job.child, force).await;
+                escalations.extend(signal_job(
+                    &job.child,
+                    force,
+                    Duration::from_millis(DEFAULT_KILL_GRACE_MS),
+                ));
                 job.status = ExitStatus::Exited { code: None };
                 job.finished_at_ms = Some(now_ms());
                 job.termination_reason = Some(if force {
@@ -416,6 +735,10 @@ impl WorkerRuntime {
                 });
             }
         }
+        // Exiting first would drop the escalations and leave TERM-ignoring jobs running.
+        for escalation in escalations {
+            let _ = escalation.await;
+        }
     }
 
     /// Looks up a mutable job by id or returns a not-found error.
@@ -440,24 +763,186 @@ fn map_result(result: Result<ExecResponse, PlanterError>) -> ExecResponse {
     }
 }
 
-/// Encodes and writes one response envelope to the control stream.
+/// Encodes and writes one response envelope to the control stream through the reused `frame`.
+/// Answers PTY peeks on the peek lane until the daemon closes it.
+///
+/// Any other request is rejected; the lane only exists so peeks skip the control queue.
+async fn serve_peek_lane(mut lane: UnixStream, pty: Arc<PtyManager>) {
+    let mut response_frame = Vec::new();
+    loop {
+        let Ok(frame) = read_frame(&mut lane).await else {
+            return;
+        };
+        let request: ExecRequestEnvelope = match decode(&frame) {
+            Ok(request) => request,
+            Err(err) => {
+                tracing::warn!(error = %err, "undecodable peek lane request");
+                return;
+            }
+        };
+        let response = match request.body {
+            ExecRequest::PtyPeek {
+                session_id,
+                offset,
+                max_bytes,
+            } => peek_response(&pty, session_id, offset, max_bytes),
+            _ => ExecResponse::ExecError {
+                code: ExecErrorCode::InvalidRequest,
+                message: "peek lane only serves pty peek".to_string(),
+                detail: None,
+            },
+        };
+        if write_response(&mut lane, &mut response_frame, request.req_id, response)
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Peeks at a PTY session's buffered output without consuming it.
+fn peek_response(
+    pty: &PtyManager,
+    session_id: SessionId,
+    offset: u64,
+    max_bytes: u32,
+) -> ExecResponse {
+    let result = pty
+        .peek(session_id, offset, max_bytes)
+        .map(|peeked| ExecResponse::PtyPeeked {
+            session_id,
+            offset: peeked.offset,
+            data: peeked.data.into(),
+            length: peeked.length,
+            complete: peeked.complete,
+        });
+    map_result(result)
+}
+
 async fn write_response(
     stream: &mut UnixStream,
+    frame: &mut Vec<u8>,
     req_id: u64,
     body: ExecResponse,
 ) -> Result<(), WorkerError> {
     let response = ExecResponseEnvelope { req_id, body };
-    let payload = encode(&response)?;
-    write_frame(stream, &payload).await?;
+    encode_into_framed(stream, frame, &response).await?;
     Ok(())
 }
 
+/// Starts the job in a new session so it has no controlling terminal and leads its own group.
+fn detach_from_terminal(command: &mut Command) {
+    // SAFETY: the hook runs between fork and exec and only calls async-signal-safe setsid.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Names the job process after its job id.
+///
+/// The job id replaces `argv[0]`, which `ps` shows for the job.
+fn set_process_title(command: &mut Command, job_id: &JobId) {
+    command.arg0(&job_id.0);
+}
+
+/// Picks the profile a job's sandbox launches with, or explains why it can't be sandboxed.
+///
+/// Returns `Ok(None)` for jobs that don't ask for a sandbox.
+fn sandbox_launch(sandbox: Option<&JobSandbox>) -> Result<Option<SandboxLaunch<'_>>, String> {
+    let Some(sandbox) = sandbox.filter(|sandbox| sandbox.mode != SandboxMode::Disabled) else {
+        return Ok(None);
+    };
+    let Some(profile) = sandbox.profile.as_deref() else {
+        return Err("no sandbox profile is available for this cell".to_string());
+    };
+    if !Path::new(SANDBOX_EXEC_PATH).exists() {
+        return Err(format!("sandbox runtime missing at {SANDBOX_EXEC_PATH}"));
+    }
+    let cell_dir = sandbox
+        .cell_dir
+        .as_deref()
+        .map(open_sandbox_cell_dir)
+        .transpose()?;
+    Ok(Some(SandboxLaunch { profile, cell_dir }))
+}
+
+/// Opens the cell directory a sandbox profile was rendered against, refusing it when its path
+/// no longer leads to that directory.
+///
+/// The job then starts in the opened directory, so a swap after this check can't move it.
+fn open_sandbox_cell_dir(cell_dir: &str) -> Result<fs::File, String> {
+    let dir = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW)
+        .open(cell_dir)
+        .map_err(|err| format!("open cell directory {cell_dir}: {err}"))?;
+    let opened = fd_path(dir.as_raw_fd())
+        .map_err(|err| format!("resolve cell directory {cell_dir}: {err}"))?;
+    if opened != Path::new(cell_dir) {
+        return Err(format!(
+            "cell directory {cell_dir} was replaced after its sandbox profile was rendered"
+        ));
+    }
+    Ok(dir)
+}
+
+/// Starts the job in an already opened directory instead of resolving a path at spawn time.
+fn enter_dir_on_spawn(command: &mut Command, dir: &fs::File) {
+    let fd = dir.as_raw_fd();
+    // SAFETY: the hook runs between fork and exec and only calls async-signal-safe fchdir;
+    // `dir` stays open until spawn returns.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::fchdir(fd) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Refuses an `Enforced` job that can't be launched under its sandbox.
+fn sandbox_unavailable(reason: String) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Unavailable,
+        message: "sandbox unavailable for enforced job".to_string(),
+        detail: Some(reason),
+    }
+}
+
+/// Builds a job environment from allowlisted host variables plus explicit overrides.
+fn build_job_env(
+    host_env: &std::collections::BTreeMap<String, String>,
+    allowlist: &[String],
+    overrides: std::collections::BTreeMap<String, String>,
+) -> std::collections::BTreeMap<String, String> {
+    let mut env = std::collections::BTreeMap::new();
+    let names = DEFAULT_JOB_ENV_PASSTHROUGH
+        .iter()
+        .copied()
+        .chain(allowlist.iter().map(String::as_str));
+    for name in names {
+        if let Some(value) = host_env.get(name) {
+            env.insert(name.to_string(), value.clone());
+        }
+    }
+    env.extend(overrides);
+    env
+}
+
 /// Ensures the parent directory exists for a file path.
 fn ensure_parent_dir(path: &str) -> Result<(), PlanterError> {
     if let Some(parent) = Path::new(path).parent()
         && !parent.as_os_str().is_empty()
     {
-        fs::create_dir_all(parent).map_err(|err| io_to_planter_error("create parent dir", err))?;
+        fs::create_dir_all(parent)
+            .map_err(|err| PlanterError::from_io("create parent dir", err))?;
     }
     Ok(())
 }
@@ -468,10 +953,9 @@ fn refresh_job(job: &mut WorkerJob) -> Result<(), PlanterError> {
         return Ok(());
     }
 
-    if let Some(status) = job
-        .child
+    if let Some(status) = lock_child(&job.child)
         .try_wait()
-        .map_err(|err| io_to_planter_error("probe job status", err))?
+        .map_err(|err| PlanterError::from_io("probe job status", err))?
     {
         job.status = ExitStatus::Exited {
             code: status.code(),
@@ -485,26 +969,72 @@ fn refresh_job(job: &mut WorkerJob) -> Result<(), PlanterError> {
     Ok(())
 }
 
-/// Sends graceful/forceful signals to a child process tree.
-async fn signal_job(child: &mut Child, force: bool) {
-    if let Some(pid) = child.id() {
-        if force {
-            let _ = send_signal(pid, "KILL");
-            let _ = send_signal_to_children(pid, "KILL");
-        } else {
-            let _ = send_signal(pid, "TERM");
-            let _ = send_signal_to_children(pid, "TERM");
-            sleep(Duration::from_millis(250)).await;
-            if process_alive(pid).unwrap_or(false) {
+/// Creates an empty log file in append mode so writes follow any later truncation.
+fn open_log_file(path: &str) -> Result<fs::File, std::io::Error> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.set_len(0)?;
+    Ok(file)
+}
+
+/// Sends graceful/forceful signals to a child process tree, allowing `grace` for a voluntary exit.
+///
+/// A graceful signal returns at once; the KILL fallback runs in the returned background task.
+fn signal_job(child: &Arc<Mutex<Child>>, force: bool, grace: Duration) -> Option<JoinHandle<()>> {
+    let mut locked = lock_child(child);
+    let Some(pid) = locked.id() else {
+        let _ = locked.start_kill();
+        return None;
+    };
+    if force {
+        let _ = send_signal(pid, "KILL");
+        let _ = send_signal_to_children(pid, "KILL");
+        return None;
+    }
+    let _ = send_signal(pid, "TERM");
+    let _ = send_signal_to_children(pid, "TERM");
+    drop(locked);
+    // Older daemons don't bound the grace period, and an unbounded one would overflow.
+    let grace = grace.min(Duration::from_millis(MAX_KILL_GRACE_MS));
+    let deadline = Instant::now()
+        .checked_add(grace)
+        .unwrap_or_else(Instant::now);
+    Some(tokio::spawn(kill_after_grace(
+        Arc::clone(child),
+        pid,
+        deadline,
+    )))
+}
+
+/// KILLs a TERM'd child process tree unless it exits before `deadline`.
+///
+/// The child stays locked from the exit check through the KILL, so it can't be reaped and its
+/// pid reused in between.
+async fn kill_after_grace(child: Arc<Mutex<Child>>, pid: u32, deadline: Instant) {
+    loop {
+        {
+            let mut child = lock_child(&child);
+            if matches!(child.try_wait(), Ok(Some(_))) {
+                return;
+            }
+            if Instant::now() >= deadline {
                 let _ = send_signal(pid, "KILL");
                 let _ = send_signal_to_children(pid, "KILL");
+                return;
             }
         }
-    } else {
-        let _ = child.start_kill();
+        sleep(KILL_GRACE_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())))
+            .await;
     }
 }
 
+/// Locks a job's child handle; every holder leaves it consistent, so poisoning is ignored.
+fn lock_child(child: &Mutex<Child>) -> MutexGuard<'_, Child> {
+    child.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// Sends a unix signal to a process id.
 fn send_signal(pid: u32, signal: &str) -> Result<(), std::io::Error> {
     let status = StdCommand::new("/bin/kill")
@@ -533,16 +1063,6 @@ fn send_signal_to_children(pid: u32, signal: &str) -> Result<(), std::io::Error>
         "pkill -{signal} -P {pid} failed with status {status}"
     )))
 }
-
-/// Returns whether a pid is currently alive.
-fn process_alive(pid: u32) -> Result<bool, std::io::Error> {
-    let status = StdCommand::new("/bin/kill")
-        .arg("-0")
-        .arg(pid.to_string())
-        .status()?;
-    Ok(status.success())
-}
-
 /// Samples RSS bytes for a pid using `ps`.
 fn read_rss_bytes(pid: u32) -> Result<Option<u64>, std::io::Error> {
     let output = StdCommand::new("/bin/ps")
@@ -561,28 +1081,63 @@ fn read_rss_bytes(pid: u32) -> Result<Option<u64>, std::io::Error> {
     Ok(rss_kb.map(|v| v.saturating_mul(1024)))
 }
 
-/// Converts I/O errors into standardized planter errors.
-fn io_to_planter_error(action: &str, err: std::io::Error) -> PlanterError {
+/// Opens a job's working directory, so the spawn enters the directory checked here.
+fn open_job_cwd(cwd: &str) -> Result<fs::File, PlanterError> {
+    let opened = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY)
+        .open(cwd);
+    let message = match opened {
+        Ok(dir) => return Ok(dir),
+        Err(err) if err.raw_os_error() == Some(libc::ENOTDIR) => {
+            format!("working directory is not a directory: {cwd}")
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            format!("working directory not found: {cwd}")
+        }
+        Err(err) => return Err(PlanterError::from_io("open working directory", err)),
+    };
+    Err(PlanterError {
+        code: ErrorCode::InvalidRequest,
+        message,
+        detail: None,
+    })
+}
+
+/// Attributes missing or non-executable programs to the caller instead of the worker.
+fn spawn_to_planter_error(program: &str, err: std::io::Error) -> PlanterError {
+    let message = match err.kind() {
+        std::io::ErrorKind::NotFound => format!("executable not found: {program}"),
+        std::io::ErrorKind::PermissionDenied => format!("executable not permitted: {program}"),
+        _ => return PlanterError::from_io("spawn job", err),
+    };
     PlanterError {
-        code: ErrorCode::Internal,
-        message: action.to_string(),
+        code: ErrorCode::InvalidRequest,
+        message,
         detail: Some(err.to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{WorkerConfig, serve_control_stream};
-    use planter_core::{CommandSpec, ExitStatus, JobId};
+    use super::{
+        DEFAULT_READER_JOIN_TIMEOUT, WorkerConfig, WorkerRuntime, open_sandbox_cell_dir,
+        serve_control_stream,
+    };
+    use planter_core::{CommandSpec, ExitStatus, JobId, SandboxMode};
     use planter_execd_proto::{
         EXECD_PROTOCOL_VERSION, ExecErrorCode, ExecRequest, ExecRequestEnvelope, ExecResponse,
-        ExecResponseEnvelope,
+        ExecResponseEnvelope, JobSandbox,
     };
     use planter_ipc::{
         codec::{decode, encode},
         framing::{read_frame, write_frame},
     };
-    use std::os::fd::{FromRawFd, IntoRawFd};
+    use std::os::{
+        fd::{FromRawFd, IntoRawFd},
+        unix::fs::PermissionsExt,
+    };
+    use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
     use tokio::io::AsyncWriteExt;
     use tokio::net::UnixStream;
@@ -617,6 +1172,9 @@ mod tests {
             cell_id: "cell-123".to_string(),
             auth_token: "token-123".to_string(),
             state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
         };
         let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
 
@@ -632,8 +1190,16 @@ mod tests {
         .await;
         assert_eq!(hello.req_id, 1);
         match hello.body {
-            ExecResponse::HelloAck { protocol, .. } => {
-                assert_eq!(protocol, EXECD_PROTOCOL_VERSION)
+            ExecResponse::HelloAck {
+                protocol,
+                state_root,
+                ..
+            } => {
+                assert_eq!(protocol, EXECD_PROTOCOL_VERSION);
+                assert_eq!(
+                    state_root,
+                    Some(tmp.path().join("state").display().to_string())
+                );
             }
             other => panic!("unexpected response: {other:?}"),
         }
@@ -655,6 +1221,9 @@ mod tests {
             cell_id: "cell-123".to_string(),
             auth_token: "token-123".to_string(),
             state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
         };
         let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
 
@@ -674,7 +1243,7 @@ mod tests {
             2,
             ExecRequest::RunJob {
                 job_id: JobId("job-1".to_string()),
-                cmd: CommandSpec {
+                cmd: Box::new(CommandSpec {
                     argv: vec![
                         "/bin/sh".to_string(),
                         "-c".to_string(),
@@ -683,10 +1252,12 @@ mod tests {
                     cwd: None,
                     env: Default::default(),
                     limits: None,
-                },
+                }),
                 env: Default::default(),
                 stdout_path: tmp.path().join("stdout.log").display().to_string(),
                 stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
             },
         )
         .await;
@@ -720,50 +1291,792 @@ mod tests {
     }
 
     #[tokio::test]
-    /// Verifies hello fails when auth token does not match worker config.
-    async fn rejects_wrong_auth_token() {
+    /// Verifies job-handling logs carry the worker's cell id and the request's job id.
+    async fn job_logs_are_tagged_with_cell_and_job() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let writer = Arc::clone(&captured);
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || CapturedLogs(Arc::clone(&writer)))
+            .finish();
+        // The current-thread test runtime runs the spawned worker under this default.
+        let _guard = tracing::subscriber::set_default(subscriber);
+
         let tmp = tempdir().expect("tempdir");
         let (server_stream, mut client_stream) = pair().await;
         let config = WorkerConfig {
             cell_id: "cell-123".to_string(),
             auth_token: "token-123".to_string(),
             state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
         };
         let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+        let _ = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "token-123".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+        let started = send(
+            &mut client_stream,
+            2,
+            ExecRequest::RunJob {
+                job_id: JobId("job-traced".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv: vec!["/bin/sh".to_string(), "-c".to_string(), "true".to_string()],
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: Default::default(),
+                stdout_path: tmp.path().join("stdout.log").display().to_string(),
+                stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
+            },
+        )
+        .await;
+        assert!(matches!(started.body, ExecResponse::JobStarted { .. }));
+        let _ = client_stream.shutdown().await;
+        server.abort();
 
-        let hello = send(
+        let logs =
+            String::from_utf8(captured.lock().expect("captured logs").clone()).expect("utf8 logs");
+        let started_line = logs
+            .lines()
+            .find(|line| line.contains("job started"))
+            .unwrap_or_else(|| panic!("no job started log in {logs:?}"));
+        assert!(started_line.contains("cell_id=cell-123"), "{started_line}");
+        assert!(started_line.contains("job_id=job-traced"), "{started_line}");
+    }
+
+    /// Log sink shared between a test subscriber and its assertions.
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("captured logs").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies an enforced job is refused without a usable sandbox while a permissive one runs
+    /// unsandboxed and reports why.
+    async fn run_job_sandbox_fails_closed_when_enforced() {
+        let tmp = tempdir().expect("tempdir");
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let _ = send(
             &mut client_stream,
             1,
             ExecRequest::Hello {
                 protocol: EXECD_PROTOCOL_VERSION,
-                auth_token: "bad-token".to_string(),
+                auth_token: "token-123".to_string(),
                 cell_id: "cell-123".to_string(),
             },
         )
         .await;
 
-        match hello.body {
-            ExecResponse::ExecError { code, .. } => {
-                assert_eq!(code, ExecErrorCode::Unauthorized);
+        let mut responses = Vec::new();
+        for (req_id, mode) in [(2, SandboxMode::Enforced), (3, SandboxMode::Permissive)] {
+            let response = send(
+                &mut client_stream,
+                req_id,
+                ExecRequest::RunJob {
+                    job_id: JobId(format!("job-{req_id}")),
+                    cmd: Box::new(CommandSpec {
+                        argv: vec!["/bin/true".to_string()],
+                        cwd: None,
+                        env: Default::default(),
+                        limits: None,
+                    }),
+                    env: Default::default(),
+                    stdout_path: tmp.path().join("stdout.log").display().to_string(),
+                    stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                    process_title: false,
+                    sandbox: Some(JobSandbox {
+                        mode,
+                        profile: None,
+                        cell_dir: None,
+                    }),
+                },
+            )
+            .await;
+            responses.push(response.body);
+        }
+
+        match &responses[0] {
+            ExecResponse::ExecError { code, detail, .. } => {
+                assert_eq!(*code, ExecErrorCode::Unavailable);
+                assert_eq!(
+                    detail.as_deref(),
+                    Some("no sandbox profile is available for this cell")
+                );
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match &responses[1] {
+            ExecResponse::JobStarted {
+                sandboxed,
+                sandbox_fallback_reason,
+                ..
+            } => {
+                assert!(!sandboxed);
+                assert_eq!(
+                    sandbox_fallback_reason.as_deref(),
+                    Some("no sandbox profile is available for this cell")
+                );
             }
             other => panic!("unexpected response: {other:?}"),
         }
 
-        server
-            .await
-            .expect("server should exit cleanly")
-            .expect("worker run should succeed");
+        let _ = client_stream.shutdown().await;
+        server.abort();
     }
 
-    #[test]
-    /// Verifies invalid fds are rejected before stream conversion.
-    fn control_stream_from_fd_rejects_bad_fd() {
-        let result = super::control_stream_from_fd(-1);
-        assert!(result.is_err());
+    #[tokio::test]
+    /// Verifies a titled job's `argv[0]` carries its job id.
+    async fn run_job_process_title_sets_argv0() {
+        let tmp = tempdir().expect("tempdir");
+        let stdout = run_titled_job(
+            tmp.path(),
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                r#"echo "$0""#.to_string(),
+            ],
+        )
+        .await;
+        assert_eq!(stdout, "job-titled\n");
+    }
 
-        let (_left, right) =
-            std::os::unix::net::UnixStream::pair().expect("create unix stream pair");
-        let raw = right.into_raw_fd();
-        let _ = unsafe { std::os::unix::net::UnixStream::from_raw_fd(raw) };
+    #[tokio::test]
+    /// Verifies a titled job can still be a `#!` script, whose interpreter reopens it by path.
+    async fn run_job_process_title_runs_shebang_script() {
+        let tmp = tempdir().expect("tempdir");
+        let script = tmp.path().join("hello.sh");
+        std::fs::write(&script, "#!/bin/sh\necho \"script ran\"\n").expect("write script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod script");
+
+        let stdout = run_titled_job(tmp.path(), vec![script.display().to_string()]).await;
+        assert_eq!(stdout, "script ran\n");
+    }
+
+    /// Runs `argv` as a titled job under a fresh worker and returns its stdout once it ends a line.
+    async fn run_titled_job(dir: &std::path::Path, argv: Vec<String>) -> String {
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: dir.join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let _ = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "token-123".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+
+        let stdout_path = dir.join("stdout.log");
+        let started = send(
+            &mut client_stream,
+            2,
+            ExecRequest::RunJob {
+                job_id: JobId("job-titled".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv,
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: [("PATH".to_string(), "/usr/bin:/bin".to_string())]
+                    .into_iter()
+                    .collect(),
+                stdout_path: stdout_path.display().to_string(),
+                stderr_path: dir.join("stderr.log").display().to_string(),
+                process_title: true,
+                sandbox: None,
+            },
+        )
+        .await;
+        assert!(
+            matches!(started.body, ExecResponse::JobStarted { .. }),
+            "unexpected response: {:?}",
+            started.body
+        );
+
+        let mut stdout = String::new();
+        for _ in 0..50 {
+            stdout = std::fs::read_to_string(&stdout_path).unwrap_or_default();
+            if stdout.ends_with('\n') {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        let _ = client_stream.shutdown().await;
+        server.abort();
+        stdout
+    }
+
+    #[tokio::test]
+    /// Verifies jobs start as leaders of their own session by default.
+    async fn run_job_starts_new_session() {
+        let tmp = tempdir().expect("tempdir");
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let _ = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "token-123".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+
+        let started = send(
+            &mut client_stream,
+            2,
+            ExecRequest::RunJob {
+                job_id: JobId("job-session".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv: vec!["/bin/sleep".to_string(), "5".to_string()],
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: Default::default(),
+                stdout_path: tmp.path().join("stdout.log").display().to_string(),
+                stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
+            },
+        )
+        .await;
+        let ExecResponse::JobStarted { pid: Some(pid), .. } = started.body else {
+            panic!("unexpected run response: {:?}", started.body);
+        };
+
+        let pid = pid as libc::pid_t;
+        // SAFETY: getsid only reads the session id of the given process.
+        let sid = unsafe { libc::getsid(pid) };
+        assert_eq!(sid, pid);
+
+        let _ = send(
+            &mut client_stream,
+            3,
+            ExecRequest::JobSignal {
+                job_id: JobId("job-session".to_string()),
+                force: true,
+                grace_ms: 0,
+            },
+        )
+        .await;
+        let _ = client_stream.shutdown().await;
+        server.abort();
+    }
+
+    #[tokio::test]
+    /// Verifies jobs only see host env vars that are allowlisted.
+    async fn run_job_clears_non_allowlisted_host_env() {
+        let tmp = tempdir().expect("tempdir");
+        let mut runtime = WorkerRuntime::new(
+            tmp.path().join("state"),
+            vec!["PLANTER_TEST_WORKER_SHARED".to_string()],
+            false,
+            DEFAULT_READER_JOIN_TIMEOUT,
+        );
+        runtime.host_env = [
+            ("PATH", "/usr/bin:/bin"),
+            ("PLANTER_TEST_WORKER_SECRET", "leaked"),
+            ("PLANTER_TEST_WORKER_SHARED", "shared"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let stdout_path = tmp.path().join("stdout.log");
+        let (started, _) = runtime
+            .handle_request(ExecRequest::RunJob {
+                job_id: JobId("job-env".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "echo \"${PLANTER_TEST_WORKER_SECRET:-unset} ${PLANTER_TEST_WORKER_SHARED:-unset} $JOB_VAR\""
+                            .to_string(),
+                    ],
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: [("JOB_VAR".to_string(), "job".to_string())].into(),
+                stdout_path: stdout_path.display().to_string(),
+                stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
+            })
+            .await;
+        assert!(matches!(started, ExecResponse::JobStarted { .. }));
+
+        let mut output = String::new();
+        for _ in 0..50 {
+            output = std::fs::read_to_string(&stdout_path).unwrap_or_default();
+            if !output.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(output.trim(), "unset shared job");
+    }
+
+    #[tokio::test]
+    /// Verifies log sync opens and flushes each of a tracked job's log files.
+    async fn logs_sync_flushes_each_job_log() {
+        let tmp = tempdir().expect("tempdir");
+        let mut runtime = WorkerRuntime::new(
+            tmp.path().join("state"),
+            Vec::new(),
+            false,
+            DEFAULT_READER_JOIN_TIMEOUT,
+        );
+        let job_id = JobId("job-sync".to_string());
+        let stderr_path = tmp.path().join("stderr.log");
+        let (started, _) = runtime
+            .handle_request(ExecRequest::RunJob {
+                job_id: job_id.clone(),
+                cmd: Box::new(CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "echo out".to_string(),
+                    ],
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: Default::default(),
+                stdout_path: tmp.path().join("stdout.log").display().to_string(),
+                stderr_path: stderr_path.display().to_string(),
+                process_title: false,
+                sandbox: None,
+            })
+            .await;
+        assert!(matches!(started, ExecResponse::JobStarted { .. }));
+
+        let (synced, _) = runtime
+            .handle_request(ExecRequest::LogsSync {
+                job_id: job_id.clone(),
+            })
+            .await;
+        assert_eq!(
+            synced,
+            ExecResponse::LogsSynced {
+                job_id: job_id.clone()
+            }
+        );
+
+        std::fs::remove_file(&stderr_path).expect("remove stderr log");
+        let (failed, _) = runtime
+            .handle_request(ExecRequest::LogsSync { job_id })
+            .await;
+        match failed {
+            ExecResponse::ExecError { code, message, .. } => {
+                assert_eq!(code, ExecErrorCode::Internal);
+                assert_eq!(message, "sync log");
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let (missing, _) = runtime
+            .handle_request(ExecRequest::LogsSync {
+                job_id: JobId("job-missing".to_string()),
+            })
+            .await;
+        assert!(matches!(
+            missing,
+            ExecResponse::ExecError {
+                code: ExecErrorCode::NotFound,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    /// Verifies hello fails when auth token does not match worker config.
+    async fn rejects_wrong_auth_token() {
+        let tmp = tempdir().expect("tempdir");
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: tmp.path().join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let hello = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "bad-token".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+
+        match hello.body {
+            ExecResponse::ExecError { code, .. } => {
+                assert_eq!(code, ExecErrorCode::Unauthorized);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        server
+            .await
+            .expect("server should exit cleanly")
+            .expect("worker run should succeed");
+    }
+
+    #[test]
+    /// Verifies invalid fds are rejected before stream conversion.
+    fn control_stream_from_fd_rejects_bad_fd() {
+        let result = super::control_stream_from_fd(-1);
+        assert!(result.is_err());
+
+        let (_left, right) =
+            std::os::unix::net::UnixStream::pair().expect("create unix stream pair");
+        let raw = right.into_raw_fd();
+        let _ = unsafe { std::os::unix::net::UnixStream::from_raw_fd(raw) };
+    }
+
+    /// Runs one job through a fresh worker and returns the response body.
+    async fn run_single_job(
+        state_root: &std::path::Path,
+        argv0: String,
+        cwd: Option<String>,
+    ) -> ExecResponse {
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: state_root.join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let _ = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "token-123".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+        let response = send(
+            &mut client_stream,
+            2,
+            ExecRequest::RunJob {
+                job_id: JobId("job-spawn".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv: vec![argv0],
+                    cwd,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: Default::default(),
+                stdout_path: state_root.join("stdout.log").display().to_string(),
+                stderr_path: state_root.join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
+            },
+        )
+        .await;
+
+        let _ = client_stream.shutdown().await;
+        server.abort();
+        response.body
+    }
+
+    #[tokio::test]
+    /// Verifies a missing executable is reported as a caller error naming the path.
+    async fn run_job_missing_executable_is_invalid_request() {
+        let tmp = tempdir().expect("tempdir");
+        let missing = tmp.path().join("no-such-binary").display().to_string();
+
+        match run_single_job(tmp.path(), missing.clone(), None).await {
+            ExecResponse::ExecError { code, message, .. } => {
+                assert_eq!(code, ExecErrorCode::InvalidRequest);
+                assert_eq!(message, format!("executable not found: {missing}"));
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a missing working directory is reported as such, not as a missing executable.
+    async fn run_job_missing_cwd_is_invalid_request() {
+        let tmp = tempdir().expect("tempdir");
+        let missing = tmp.path().join("no-such-dir").display().to_string();
+
+        match run_single_job(tmp.path(), "/bin/sh".to_string(), Some(missing.clone())).await {
+            ExecResponse::ExecError { code, message, .. } => {
+                assert_eq!(code, ExecErrorCode::InvalidRequest);
+                assert_eq!(message, format!("working directory not found: {missing}"));
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a job starts in the directory its cwd named when the worker opened it.
+    async fn run_job_starts_in_opened_cwd() {
+        let tmp = tempdir().expect("tempdir");
+        let cwd = tmp.path().join("work");
+        std::fs::create_dir_all(&cwd).expect("create cwd");
+        let pwd_path = tmp.path().join("pwd.out");
+        let script = tmp.path().join("print-pwd.sh");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\npwd -P > {}\n", pwd_path.display()),
+        )
+        .expect("write script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod script");
+
+        let response = run_single_job(
+            tmp.path(),
+            script.display().to_string(),
+            Some(cwd.display().to_string()),
+        )
+        .await;
+        assert!(
+            matches!(response, ExecResponse::JobStarted { .. }),
+            "{response:?}"
+        );
+        for _ in 0..100 {
+            if std::fs::read_to_string(&pwd_path).is_ok_and(|pwd| pwd.ends_with('\n')) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let pwd = std::fs::read_to_string(&pwd_path).expect("read pwd");
+        assert_eq!(
+            std::path::Path::new(pwd.trim()),
+            std::fs::canonicalize(&cwd).expect("canonical cwd")
+        );
+    }
+
+    #[test]
+    /// Verifies a sandboxed job's cell directory is refused once its path leads elsewhere,
+    /// whether the directory itself or a parent was swapped for a symlink.
+    fn sandbox_cell_dir_refuses_swapped_directory() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let cell = root.join("cell-swap");
+        std::fs::create_dir_all(&cell).expect("create cell dir");
+        let real = std::fs::canonicalize(&cell).expect("canonical cell dir");
+        let real = real.display().to_string();
+        assert!(open_sandbox_cell_dir(&real).is_ok());
+
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(outside.join("cell-swap")).expect("create outside dir");
+        let moved = tmp.path().join("moved-cell");
+        std::fs::rename(&cell, &moved).expect("move cell dir");
+        std::os::unix::fs::symlink(outside.join("cell-swap"), &cell).expect("swap cell dir");
+        let err = open_sandbox_cell_dir(&real).expect_err("symlinked cell dir should be refused");
+        assert!(err.contains("open cell directory"), "{err}");
+
+        std::fs::remove_file(&cell).expect("remove symlink");
+        std::fs::rename(&moved, &cell).expect("restore cell dir");
+        std::fs::rename(&root, tmp.path().join("moved-state")).expect("move state root");
+        std::os::unix::fs::symlink(&outside, &root).expect("swap state root");
+        let err = open_sandbox_cell_dir(&real).expect_err("swapped parent should be refused");
+        assert!(err.contains("was replaced"), "{err}");
+    }
+
+    #[tokio::test]
+    /// Verifies a non-executable program is reported as a caller error naming the path.
+    async fn run_job_non_executable_is_invalid_request() {
+        let tmp = tempdir().expect("tempdir");
+        let script = tmp.path().join("not-executable.sh");
+        std::fs::write(&script, "#!/bin/sh\ntrue\n").expect("write script");
+        let script = script.display().to_string();
+
+        match run_single_job(tmp.path(), script.clone(), None).await {
+            ExecResponse::ExecError { code, message, .. } => {
+                assert_eq!(code, ExecErrorCode::InvalidRequest);
+                assert_eq!(message, format!("executable not permitted: {script}"));
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    /// Starts a job that traps TERM and needs about a second to clean up, then signals it.
+    ///
+    /// Returns how long the signal took to answer and whether the job finished its cleanup
+    /// within two seconds.
+    async fn signal_slow_shutdown_job(
+        state_root: &std::path::Path,
+        grace_ms: u64,
+    ) -> (std::time::Duration, bool) {
+        let (server_stream, mut client_stream) = pair().await;
+        let config = WorkerConfig {
+            cell_id: "cell-123".to_string(),
+            auth_token: "token-123".to_string(),
+            state_root: state_root.join("state"),
+            env_allowlist: Vec::new(),
+            inherit_tty: false,
+            pty_reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
+        };
+        let server = tokio::spawn(async move { serve_control_stream(server_stream, config).await });
+
+        let _ = send(
+            &mut client_stream,
+            1,
+            ExecRequest::Hello {
+                protocol: EXECD_PROTOCOL_VERSION,
+                auth_token: "token-123".to_string(),
+                cell_id: "cell-123".to_string(),
+            },
+        )
+        .await;
+
+        let stdout_path = state_root.join("stdout.log");
+        let marker = state_root.join("cleaned-up");
+        let script = format!(
+            "trap 'sleep 1; touch {}; exit 0' TERM; echo ready; while :; do sleep 0.05; done",
+            marker.display()
+        );
+        let started = send(
+            &mut client_stream,
+            2,
+            ExecRequest::RunJob {
+                job_id: JobId("job-trap".to_string()),
+                cmd: Box::new(CommandSpec {
+                    argv: vec!["/bin/sh".to_string(), "-c".to_string(), script],
+                    cwd: None,
+                    env: Default::default(),
+                    limits: None,
+                }),
+                env: Default::default(),
+                stdout_path: stdout_path.display().to_string(),
+                stderr_path: state_root.join("stderr.log").display().to_string(),
+                process_title: false,
+                sandbox: None,
+            },
+        )
+        .await;
+        assert!(matches!(started.body, ExecResponse::JobStarted { .. }));
+
+        for _ in 0..100 {
+            if std::fs::read_to_string(&stdout_path).is_ok_and(|out| out.contains("ready")) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let signal_sent = std::time::Instant::now();
+        let signaled = send(
+            &mut client_stream,
+            3,
+            ExecRequest::JobSignal {
+                job_id: JobId("job-trap".to_string()),
+                force: false,
+                grace_ms,
+            },
+        )
+        .await;
+        let replied_in = signal_sent.elapsed();
+        assert!(matches!(signaled.body, ExecResponse::JobStatus { .. }));
+
+        let ping = send(&mut client_stream, 4, ExecRequest::Ping {}).await;
+        assert!(matches!(ping.body, ExecResponse::Pong {}));
+        while !marker.exists() && signal_sent.elapsed() < std::time::Duration::from_secs(2) {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let _ = client_stream.shutdown().await;
+        server.abort();
+        (replied_in, marker.exists())
+    }
+
+    #[tokio::test]
+    /// Verifies a grace period longer than the job's cleanup lets it exit on its own, without
+    /// the worker waiting out the grace before answering.
+    async fn job_signal_long_grace_allows_voluntary_exit() {
+        let tmp = tempdir().expect("tempdir");
+
+        let (replied_in, cleaned_up) = signal_slow_shutdown_job(tmp.path(), 30_000).await;
+        assert!(cleaned_up);
+        assert!(
+            replied_in < std::time::Duration::from_millis(500),
+            "{replied_in:?}"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies an out-of-range grace period is capped instead of overflowing the deadline.
+    async fn job_signal_huge_grace_does_not_overflow() {
+        let tmp = tempdir().expect("tempdir");
+
+        assert!(signal_slow_shutdown_job(tmp.path(), u64::MAX).await.1);
+    }
+
+    #[tokio::test]
+    /// Verifies a grace period shorter than the job's cleanup escalates to KILL.
+    async fn job_signal_short_grace_kills_before_cleanup() {
+        let tmp = tempdir().expect("tempdir");
+
+        assert!(!signal_slow_shutdown_job(tmp.path(), 100).await.1);
     }
 }
@@ -1,7 +1,11 @@
-use std::{path::PathBuf, process::ExitCode};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
 
 use clap::Parser;
-use planter_execd::{WorkerConfig, control_stream_from_fd, serve_control_stream};
+use planter_execd::{
+    DEFAULT_READER_JOIN_TIMEOUT, WorkerConfig, control_stream_from_fd, serve_control_stream,
+    serve_control_stream_with_peek_lane,
+};
+use planter_platform::{RootPolicy, current_euid, root_policy};
 
 /// CLI arguments for launching a `planter-execd` worker process.
 #[derive(Debug, Parser)]
@@ -10,6 +14,9 @@ struct Args {
     /// Inherited UNIX socket fd used for daemon control RPC.
     #[arg(long)]
     control_fd: i32,
+    /// Inherited UNIX socket fd the daemon sends PTY peeks on, bypassing the control queue.
+    #[arg(long)]
+    peek_fd: Option<i32>,
     /// Shared auth token expected in the hello request.
     #[arg(long)]
     auth_token: String,
@@ -19,6 +26,18 @@ struct Args {
     /// Root state directory for worker data.
     #[arg(long)]
     state_root: PathBuf,
+    /// Repeated host environment variable names passed through to jobs.
+    #[arg(long = "env-allow", value_name = "NAME")]
+    env_allow: Vec<String>,
+    /// Let jobs keep this worker's controlling terminal instead of detaching them.
+    #[arg(long)]
+    inherit_tty: bool,
+    /// How long closing a PTY session waits for its output reader thread, in milliseconds.
+    #[arg(long, default_value_t = DEFAULT_READER_JOIN_TIMEOUT.as_millis() as u64)]
+    pty_reader_join_timeout_ms: u64,
+    /// Start even when running as root, which lets jobs act with full host privileges.
+    #[arg(long)]
+    allow_root: bool,
 }
 
 /// Entrypoint that maps worker startup failures to process exit code.
@@ -37,15 +56,41 @@ async fn main() -> ExitCode {
 async fn run() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_target(false).init();
     let args = Args::parse();
+    match root_policy(current_euid(), args.allow_root) {
+        RootPolicy::Unprivileged => {}
+        RootPolicy::AllowedRoot => tracing::warn!(
+            "running as root because --allow-root was passed; jobs run with full host privileges"
+        ),
+        RootPolicy::RefusedRoot => {
+            return Err(
+                "refusing to run as root: jobs would run with full host privileges; \
+                 run as an unprivileged user or pass --allow-root"
+                    .into(),
+            );
+        }
+    }
 
-    tracing::info!(state_root = %args.state_root.display(), "starting planter-execd");
+    tracing::info!(
+        cell_id = %args.cell_id,
+        state_root = %args.state_root.display(),
+        "starting planter-execd"
+    );
 
     let stream = control_stream_from_fd(args.control_fd)?;
     let config = WorkerConfig {
         cell_id: args.cell_id,
         auth_token: args.auth_token,
         state_root: args.state_root,
+        env_allowlist: args.env_allow,
+        inherit_tty: args.inherit_tty,
+        pty_reader_join_timeout: Duration::from_millis(args.pty_reader_join_timeout_ms),
     };
-    serve_control_stream(stream, config).await?;
+    match args.peek_fd {
+        Some(peek_fd) => {
+            let peek_lane = control_stream_from_fd(peek_fd)?;
+            serve_control_stream_with_peek_lane(stream, peek_lane, config).await?;
+        }
+        None => serve_control_stream(stream, config).await?,
+    }
     Ok(())
 }
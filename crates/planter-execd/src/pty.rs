@@ -1,19 +1,21 @@
 //! PTY session management with optional nested sandbox execution.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, VecDeque},
     fs,
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     process::Command as StdCommand,
     sync::{
         Arc, Mutex,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
+    thread::JoinHandle,
     time::{Duration, Instant},
 };
 
-use planter_core::{ErrorCode, PlanterError, SessionId};
+use bytes::{Buf, Bytes, BytesMut};
+use planter_core::{ErrorCode, PlanterError, SANDBOX_EXEC_PATH, SessionId};
 use portable_pty::{Child, CommandBuilder, MasterPty, PtySize, native_pty_system};
 use tokio::time::sleep;
 
@@ -29,8 +31,16 @@ pub enum PtySandboxMode {
     Enforced,
 }
 
-/// Path to the system sandbox launcher.
-const SANDBOX_EXEC_PATH: &str = "/usr/bin/sandbox-exec";
+/// Session-relative file receiving persisted PTY output.
+const SCROLLBACK_FILE: &str = "output.log";
+/// Session-relative file recording the shell pid, used to spot orphaned session directories.
+const SHELL_PID_FILE: &str = "shell.pid";
+/// In-memory output window kept per session once output is persisted to disk.
+const MAX_RETAINED_OUTPUT_BYTES: usize = 1024 * 1024;
+/// Default for how long `close` waits for a session's reader thread to exit.
+pub const DEFAULT_READER_JOIN_TIMEOUT: Duration = Duration::from_millis(500);
+/// Backoff between PTY reads that would block, to avoid busy-spinning.
+const READ_RETRY_DELAY: Duration = Duration::from_millis(5);
 /// Minimal profile used to probe nested sandbox support.
 const NESTED_SANDBOX_PROBE_PROFILE: &str = "(version 1) (allow default)";
 /// Shared sandbox profile fragments used to build PTY profiles.
@@ -72,6 +82,48 @@ pub struct PtyManager {
     sessions: Mutex<HashMap<SessionId, Arc<PtySession>>>,
     /// Monotonic session id generator.
     next_id: AtomicU64,
+    /// Number of reader threads that have not exited yet.
+    live_readers: Arc<AtomicUsize>,
+    /// How long `close` waits for a session's reader thread before detaching it.
+    reader_join_timeout: Duration,
+}
+
+/// Launch parameters for a new PTY session.
+pub struct PtyOpenSpec {
+    /// Shell binary path.
+    pub shell: String,
+    /// Shell argument vector.
+    pub args: Vec<String>,
+    /// Optional initial working directory.
+    pub cwd: Option<String>,
+    /// Environment overrides.
+    pub env: BTreeMap<String, String>,
+    /// Initial terminal columns.
+    pub cols: u16,
+    /// Initial terminal rows.
+    pub rows: u16,
+    /// Whether generated rc files confine `cd` to the build cell.
+    pub jail_cwd: bool,
+    /// Whether output is teed to `output.log` under the session root.
+    pub persist_scrollback: bool,
+}
+
+/// Read parameters for PTY output.
+pub struct PtyReadSpec {
+    /// Absolute offset to start reading from.
+    pub offset: u64,
+    /// Maximum bytes to return.
+    pub max_bytes: u32,
+    /// Whether to wait for output when none is buffered.
+    pub follow: bool,
+    /// Follow wait timeout in milliseconds.
+    pub wait_ms: u64,
+    /// When set, read the last N buffered bytes and ignore `offset`.
+    pub from_end: Option<u32>,
+    /// Whether the chunk must end on a UTF-8 codepoint boundary.
+    pub utf8_boundary: bool,
+    /// Whether to remove ANSI escape sequences from the returned bytes.
+    pub strip_ansi: bool,
 }
 
 /// Result payload for PTY open operations.
@@ -84,55 +136,95 @@ pub struct PtyOpenResult {
 
 /// Result payload for PTY read operations.
 pub struct PtyReadResult {
-    /// Offset used for the read call.
+    /// Absolute offset where the returned bytes start.
     pub offset: u64,
     /// Raw output bytes.
-    pub data: Vec<u8>,
+    pub data: Bytes,
     /// True when no more bytes are currently buffered.
     pub eof: bool,
     /// True when session has fully completed.
     pub complete: bool,
     /// Exit code when complete.
     pub exit_code: Option<i32>,
+    /// Offset to resume from when ANSI stripping shortened `data`.
+    pub next_offset: Option<u64>,
+}
+
+/// Result payload for PTY peek operations.
+pub struct PtyPeekResult {
+    /// Absolute offset where the returned bytes start.
+    pub offset: u64,
+    /// Raw output bytes.
+    pub data: Bytes,
+    /// Total output bytes produced so far.
+    pub length: u64,
+    /// True when the shell has exited and its output is fully captured.
+    pub complete: bool,
+}
+
+/// Result payload for PTY sandbox profile rendering.
+pub struct PtySandboxProfileResult {
+    /// Session the profile was rendered for.
+    pub session_id: SessionId,
+    /// Sandbox profile source.
+    pub profile: String,
 }
 
 /// In-memory state for a single PTY session.
 struct PtySession {
     /// Writable PTY input stream.
     writer: Mutex<Box<dyn Write + Send>>,
-    /// PTY master handle for control operations.
-    master: Mutex<Box<dyn MasterPty + Send>>,
+    /// PTY master handle for control operations, dropped on close.
+    master: Mutex<Option<Box<dyn MasterPty + Send>>>,
     /// Child process handle.
     child: Mutex<Box<dyn Child + Send>>,
-    /// Buffered PTY output bytes.
-    buffer: Mutex<Vec<u8>>,
+    /// Buffered PTY output scrollback.
+    buffer: Mutex<Scrollback>,
     /// Completion marker for the reader thread.
     complete: AtomicBool,
     /// Captured process exit code.
     exit_code: Mutex<Option<i32>>,
+    /// Output reader thread, joined on close.
+    reader: Mutex<Option<JoinHandle<()>>>,
+    /// Last time a client opened, wrote to, resized, or read output from the session.
+    last_activity: Mutex<Instant>,
 }
 
 impl PtyManager {
     /// Creates an empty PTY manager for the provided state root.
+    ///
+    /// Session ids continue above any `sessions/pty-*` directory kept from an earlier worker,
+    /// so a new session never reuses a directory holding persisted scrollback.
     pub fn new(state_root: PathBuf, sandbox_mode: PtySandboxMode) -> Self {
+        let next_id = first_free_session_id(&state_root);
         Self {
             state_root,
             sandbox_mode,
             sessions: Mutex::new(HashMap::new()),
-            next_id: AtomicU64::new(1),
+            next_id: AtomicU64::new(next_id),
+            live_readers: Arc::new(AtomicUsize::new(0)),
+            reader_join_timeout: DEFAULT_READER_JOIN_TIMEOUT,
         }
     }
 
+    /// Sets how long `close` waits for a session's reader thread before detaching it.
+    pub fn with_reader_join_timeout(mut self, timeout: Duration) -> Self {
+        self.reader_join_timeout = timeout;
+        self
+    }
+
     /// Opens a new PTY session and spawns the requested shell command.
-    pub fn open(
-        &self,
-        shell: String,
-        args: Vec<String>,
-        cwd: Option<String>,
-        env: BTreeMap<String, String>,
-        cols: u16,
-        rows: u16,
-    ) -> Result<PtyOpenResult, PlanterError> {
+    pub fn open(&self, spec: PtyOpenSpec) -> Result<PtyOpenResult, PlanterError> {
+        let PtyOpenSpec {
+            shell,
+            args,
+            cwd,
+            env,
+            cols,
+            rows,
+            jail_cwd,
+            persist_scrollback,
+        } = spec;
         if shell.trim().is_empty() {
             return Err(PlanterError {
                 code: ErrorCode::InvalidRequest,
@@ -143,13 +235,22 @@ impl PtyManager {
         validate_shell_path(&shell)?;
 
         let session_id = SessionId(self.next_id.fetch_add(1, Ordering::Relaxed));
-        let layout = self.prepare_layout(session_id)?;
+        let layout = self.prepare_layout(session_id, jail_cwd)?;
         let shell_args = normalize_shell_args(&shell, &layout, args);
         let cwd = cwd.unwrap_or_else(|| layout.build_cell.display().to_string());
         let env = build_isolated_env(&shell, &layout, cwd.clone(), env);
         let (program, program_args) =
             self.resolve_spawn_command(session_id, &layout, &shell, shell_args)?;
         let launched_with_sandbox = program == SANDBOX_EXEC_PATH;
+        let scrollback = if persist_scrollback {
+            Scrollback::persisted(
+                layout.session_root.join(SCROLLBACK_FILE),
+                MAX_RETAINED_OUTPUT_BYTES,
+            )
+            .map_err(|err| pty_to_error("create scrollback log", err.to_string()))?
+        } else {
+            Scrollback::in_memory()
+        };
 
         let pty_system = native_pty_system();
         let pair = pty_system
@@ -181,8 +282,22 @@ impl PtyManager {
                 .try_wait()
                 .map_err(|err| pty_to_error("probe sandboxed pty process", err.to_string()))?;
             if let Some(status) = exited_early {
+                let startup_output = read_startup_output(&*pair.master, 512);
+                if startup_output
+                    .as_deref()
+                    .is_some_and(is_shell_exec_denied_by_sandbox)
+                {
+                    return Err(PlanterError {
+                        code: ErrorCode::InvalidRequest,
+                        message: format!(
+                            "shell {shell} is not permitted by the pty sandbox profile; \
+                             choose a shell under /bin or /usr or add a profile fragment allowing it"
+                        ),
+                        detail: startup_output,
+                    });
+                }
                 let mut detail = format!("exit_code={}", status.exit_code());
-                if let Some(startup_output) = read_startup_output(&*pair.master, 512) {
+                if let Some(startup_output) = startup_output {
                     detail.push_str(", startup_output=");
                     detail.push_str(&startup_output);
                 }
@@ -194,6 +309,10 @@ impl PtyManager {
             }
         }
         let pid = child.process_id();
+        if let Some(pid) = pid {
+            fs::write(layout.session_root.join(SHELL_PID_FILE), pid.to_string())
+                .map_err(|err| pty_to_error("write session pid file", err.to_string()))?;
+        }
 
         let reader = pair
             .master
@@ -206,14 +325,21 @@ impl PtyManager {
 
         let session = Arc::new(PtySession {
             writer: Mutex::new(writer),
-            master: Mutex::new(pair.master),
+            master: Mutex::new(Some(pair.master)),
             child: Mutex::new(child),
-            buffer: Mutex::new(Vec::new()),
+            buffer: Mutex::new(scrollback),
             complete: AtomicBool::new(false),
             exit_code: Mutex::new(None),
+            reader: Mutex::new(None),
+            last_activity: Mutex::new(Instant::now()),
         });
 
-        spawn_reader_thread(Arc::clone(&session), reader);
+        let handle =
+            spawn_reader_thread(Arc::clone(&session), reader, Arc::clone(&self.live_readers));
+        *session
+            .reader
+            .lock()
+            .map_err(|_| lock_error("pty reader lock poisoned"))? = Some(handle);
 
         self.sessions
             .lock()
@@ -230,6 +356,7 @@ impl PtyManager {
         }
 
         let session = self.get_session(session_id)?;
+        session.touch()?;
         let mut writer = session
             .writer
             .lock()
@@ -246,23 +373,30 @@ impl PtyManager {
     pub async fn read(
         &self,
         session_id: SessionId,
-        offset: u64,
-        max_bytes: u32,
-        follow: bool,
-        wait_ms: u64,
+        spec: PtyReadSpec,
     ) -> Result<PtyReadResult, PlanterError> {
         let start = Instant::now();
-        let max_bytes = usize::try_from(max_bytes.max(1)).unwrap_or(64 * 1024);
+        let max_bytes = usize::try_from(spec.max_bytes.max(1)).unwrap_or(64 * 1024);
 
         loop {
             let session = self.get_session(session_id)?;
-            let chunk = session.read_chunk(offset, max_bytes)?;
+            let chunk = session.read_chunk(
+                spec.offset,
+                max_bytes,
+                spec.from_end,
+                spec.utf8_boundary,
+                spec.strip_ansi,
+            )?;
+            let advanced = chunk.next_offset.is_some_and(|next| next > chunk.offset);
+            if !chunk.data.is_empty() || advanced {
+                session.touch()?;
+            }
 
-            if !chunk.data.is_empty() || chunk.complete || !follow {
+            if !chunk.data.is_empty() || advanced || chunk.complete || !spec.follow {
                 return Ok(chunk);
             }
 
-            if start.elapsed() >= Duration::from_millis(wait_ms.max(1)) {
+            if start.elapsed() >= Duration::from_millis(spec.wait_ms.max(1)) {
                 return Ok(chunk);
             }
 
@@ -270,13 +404,44 @@ impl PtyManager {
         }
     }
 
+    /// Returns the output buffered at `offset` immediately, leaving the session's idle clock alone.
+    pub fn peek(
+        &self,
+        session_id: SessionId,
+        offset: u64,
+        max_bytes: u32,
+    ) -> Result<PtyPeekResult, PlanterError> {
+        let session = self.get_session(session_id)?;
+        let max_bytes = usize::try_from(max_bytes.max(1)).unwrap_or(64 * 1024);
+        // Read completion first so a shell that exits mid-peek is never reported complete early.
+        let complete = session.complete.load(Ordering::Relaxed);
+        let mut buffer = session
+            .buffer
+            .lock()
+            .map_err(|_| lock_error("pty buffer lock poisoned"))?;
+        let (offset, data, _) = buffer.chunk(offset, max_bytes, None)?;
+        Ok(PtyPeekResult {
+            offset,
+            data,
+            length: buffer.len(),
+            complete,
+        })
+    }
+
     /// Resizes the PTY terminal dimensions for an active session.
     pub fn resize(&self, session_id: SessionId, cols: u16, rows: u16) -> Result<(), PlanterError> {
         let session = self.get_session(session_id)?;
+        session.touch()?;
         let master = session
             .master
             .lock()
             .map_err(|_| lock_error("pty master lock poisoned"))?;
+        let Some(master) = master.as_ref() else {
+            return Err(not_found_error(format!(
+                "session {} is closed",
+                session_id.0
+            )));
+        };
         master
             .resize(PtySize {
                 rows: rows.max(1),
@@ -288,7 +453,7 @@ impl PtyManager {
     }
 
     /// Closes a PTY session and terminates its child process.
-    pub fn close(&self, session_id: SessionId, force: bool) -> Result<(), PlanterError> {
+    pub async fn close(&self, session_id: SessionId, force: bool) -> Result<(), PlanterError> {
         let session = self
             .sessions
             .lock()
@@ -310,9 +475,81 @@ impl PtyManager {
         }
 
         session.complete.store(true, Ordering::Relaxed);
+        session
+            .master
+            .lock()
+            .map_err(|_| lock_error("pty master lock poisoned"))?
+            .take();
+
+        let handle = session
+            .reader
+            .lock()
+            .map_err(|_| lock_error("pty reader lock poisoned"))?
+            .take();
+        if let Some(handle) = handle {
+            join_reader(session_id, handle, self.reader_join_timeout).await;
+        }
         Ok(())
     }
 
+    /// Force-closes every session idle for longer than `idle`, returning the closed ids in order.
+    pub async fn reap_idle(&self, idle: Duration) -> Result<Vec<SessionId>, PlanterError> {
+        let sessions: Vec<(SessionId, Arc<PtySession>)> = self
+            .sessions
+            .lock()
+            .map_err(|_| lock_error("sessions lock poisoned"))?
+            .iter()
+            .map(|(session_id, session)| (*session_id, Arc::clone(session)))
+            .collect();
+
+        let mut reaped = Vec::new();
+        for (session_id, session) in sessions {
+            if session.idle_for()? <= idle {
+                continue;
+            }
+            match self.close(session_id, true).await {
+                Ok(()) => reaped.push(session_id),
+                Err(err) if err.code == ErrorCode::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+        reaped.sort_by_key(|session_id| session_id.0);
+        Ok(reaped)
+    }
+
+    /// Renders the sandbox profile for `session_id`, or for the next session to open when absent.
+    ///
+    /// Fails without rendering anything when PTY sandboxing is disabled.
+    pub fn sandbox_profile(
+        &self,
+        session_id: Option<SessionId>,
+    ) -> Result<PtySandboxProfileResult, PlanterError> {
+        if self.sandbox_mode == PtySandboxMode::Disabled {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "pty sandbox is disabled".to_string(),
+                detail: None,
+            });
+        }
+        let session_id = match session_id {
+            Some(session_id) => {
+                self.get_session(session_id)?;
+                session_id
+            }
+            None => SessionId(self.next_id.load(Ordering::Relaxed)),
+        };
+        let session_root = self.session_root(session_id);
+        Ok(PtySandboxProfileResult {
+            session_id,
+            profile: render_sandbox_profile(
+                &self.state_root,
+                &session_root.join("home"),
+                &session_root.join("build-cell"),
+                session_id,
+            ),
+        })
+    }
+
     /// Retrieves a cloned session handle by id.
     fn get_session(&self, session_id: SessionId) -> Result<Arc<PtySession>, PlanterError> {
         self.sessions
@@ -323,12 +560,20 @@ impl PtyManager {
             .ok_or_else(|| not_found_error(format!("session {} does not exist", session_id.0)))
     }
 
-    /// Creates per-session filesystem layout and startup rc files.
-    fn prepare_layout(&self, session_id: SessionId) -> Result<SessionLayout, PlanterError> {
-        let session_root = self
-            .state_root
+    /// Returns the directory holding a session's per-session artifacts.
+    fn session_root(&self, session_id: SessionId) -> PathBuf {
+        self.state_root
             .join("sessions")
-            .join(format!("pty-{}", session_id.0));
+            .join(format!("pty-{}", session_id.0))
+    }
+
+    /// Creates per-session filesystem layout and startup rc files.
+    fn prepare_layout(
+        &self,
+        session_id: SessionId,
+        jail_cwd: bool,
+    ) -> Result<SessionLayout, PlanterError> {
+        let session_root = self.session_root(session_id);
         let build_cell = session_root.join("build-cell");
         let session_home = session_root.join("home");
         let session_tmp = session_root.join("tmp");
@@ -341,9 +586,9 @@ impl PtyManager {
             .map_err(|err| pty_to_error("create session home directory", err.to_string()))?;
         fs::create_dir_all(&session_tmp)
             .map_err(|err| pty_to_error("create session tmp directory", err.to_string()))?;
-        fs::write(&bash_rc, render_bash_rc(&build_cell).as_bytes())
+        fs::write(&bash_rc, render_bash_rc(&build_cell, jail_cwd).as_bytes())
             .map_err(|err| pty_to_error("write session bash rc", err.to_string()))?;
-        fs::write(&zsh_rc, render_zsh_rc(&build_cell).as_bytes())
+        fs::write(&zsh_rc, render_zsh_rc(&build_cell, jail_cwd).as_bytes())
             .map_err(|err| pty_to_error("write session zsh rc", err.to_string()))?;
 
         Ok(SessionLayout {
@@ -437,56 +682,345 @@ impl PtyManager {
 }
 
 impl PtySession {
+    /// Records client activity, resetting the session's idle clock.
+    fn touch(&self) -> Result<(), PlanterError> {
+        *self
+            .last_activity
+            .lock()
+            .map_err(|_| lock_error("pty activity lock poisoned"))? = Instant::now();
+        Ok(())
+    }
+
+    /// Returns how long the session has gone without client activity.
+    fn idle_for(&self) -> Result<Duration, PlanterError> {
+        Ok(self
+            .last_activity
+            .lock()
+            .map_err(|_| lock_error("pty activity lock poisoned"))?
+            .elapsed())
+    }
+
     /// Reads a buffered output chunk and session completion metadata.
-    fn read_chunk(&self, offset: u64, max_bytes: usize) -> Result<PtyReadResult, PlanterError> {
-        let buffer = self
+    fn read_chunk(
+        &self,
+        offset: u64,
+        max_bytes: usize,
+        from_end: Option<u32>,
+        utf8_boundary: bool,
+        strip_ansi: bool,
+    ) -> Result<PtyReadResult, PlanterError> {
+        let mut buffer = self
             .buffer
             .lock()
             .map_err(|_| lock_error("pty buffer lock poisoned"))?;
 
-        let len = buffer.len();
-        let start = usize::try_from(offset).unwrap_or(len).min(len);
-        let end = start.saturating_add(max_bytes).min(len);
-        let data = buffer[start..end].to_vec();
-        let eof = end >= len;
-        let complete = eof && self.complete.load(Ordering::Relaxed);
+        let (start, mut data, mut eof) = buffer.chunk(offset, max_bytes, from_end)?;
+        drop(buffer);
+        let finished = self.complete.load(Ordering::Relaxed);
+        if utf8_boundary && !(eof && finished) {
+            // A chunk holding only part of a codepoint comes back empty at the same offset.
+            let complete_len = utf8_complete_len(&data);
+            if complete_len < data.len() {
+                data.truncate(complete_len);
+                eof = false;
+            }
+        }
+        let complete = eof && finished;
         let exit_code = *self
             .exit_code
             .lock()
             .map_err(|_| lock_error("pty exit code lock poisoned"))?;
+        let mut next_offset = None;
+        if strip_ansi {
+            let (stripped, consumed) = planter_core::strip_ansi(&data, complete);
+            next_offset = Some(start + consumed as u64);
+            data = Bytes::from(stripped);
+        }
 
         Ok(PtyReadResult {
-            offset,
+            offset: start,
             data,
             eof,
             complete,
             exit_code,
+            next_offset,
         })
     }
 }
 
-/// Spawns a background reader that copies PTY output into the session buffer.
-fn spawn_reader_thread(session: Arc<PtySession>, mut reader: Box<dyn Read + Send>) {
-    std::thread::spawn(move || {
-        let mut buf = [0_u8; 4096];
+/// PTY output kept in memory, optionally teed to disk so trimmed history stays readable.
+///
+/// Output accumulates in `pending` and is sealed into shared segments when read. Reads return
+/// slices of those segments, which are copied into the wire response after the lock is released.
+struct Scrollback {
+    /// Absolute output offset of the first byte held in memory.
+    base: u64,
+    /// Sealed output segments, oldest first, keyed by absolute start offset.
+    segments: VecDeque<(u64, Bytes)>,
+    /// Output received since the last seal.
+    pending: BytesMut,
+    /// Bytes held across `segments` and `pending`.
+    retained: usize,
+    /// In-memory window size kept while output is persisted.
+    max_retained: usize,
+    /// On-disk log path and handle receiving every output byte, when enabled.
+    log: Option<(PathBuf, fs::File)>,
+}
 
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    if let Ok(mut bytes) = session.buffer.lock() {
-                        bytes.extend_from_slice(&buf[..n]);
-                    } else {
-                        break;
-                    }
-                }
-                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
-                Err(_) => break,
+impl Scrollback {
+    /// Creates an unbounded in-memory scrollback.
+    fn in_memory() -> Self {
+        Self {
+            base: 0,
+            segments: VecDeque::new(),
+            pending: BytesMut::new(),
+            retained: 0,
+            max_retained: usize::MAX,
+            log: None,
+        }
+    }
+
+    /// Creates a scrollback that tees output to `path` and keeps `max_retained` bytes in memory.
+    ///
+    /// Fails rather than truncating when `path` already holds another session's output.
+    fn persisted(path: PathBuf, max_retained: usize) -> std::io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            max_retained,
+            log: Some((path, file)),
+            ..Self::in_memory()
+        })
+    }
+
+    /// Returns the total number of output bytes observed.
+    fn len(&self) -> u64 {
+        self.base + self.retained as u64
+    }
+
+    /// Appends output, trimming the in-memory window only while the disk log is healthy.
+    fn push(&mut self, data: &[u8]) {
+        if let Some((path, file)) = &mut self.log
+            && let Err(err) = file.write_all(data)
+        {
+            tracing::warn!(
+                path = %path.display(),
+                error = %err,
+                "pty scrollback write failed; keeping further output in memory only"
+            );
+            self.log = None;
+        }
+
+        self.pending.extend_from_slice(data);
+        self.retained += data.len();
+        if self.log.is_some() && self.retained > self.max_retained {
+            self.trim(self.retained - self.max_retained);
+        }
+    }
+
+    /// Drops the oldest `excess` bytes from memory.
+    fn trim(&mut self, mut excess: usize) {
+        self.retained -= excess;
+        self.base += excess as u64;
+        while excess > 0 {
+            let Some((start, segment)) = self.segments.front_mut() else {
+                self.pending.advance(excess);
+                return;
+            };
+            if segment.len() <= excess {
+                excess -= segment.len();
+                self.segments.pop_front();
+            } else {
+                segment.advance(excess);
+                *start += excess as u64;
+                return;
+            }
+        }
+    }
+
+    /// Moves pending output into a shared segment that reads can slice.
+    fn seal(&mut self) {
+        if !self.pending.is_empty() {
+            let start = self.len() - self.pending.len() as u64;
+            self.segments
+                .push_back((start, self.pending.split().freeze()));
+        }
+    }
+
+    /// Returns `(start, data, eof)` for an offset or tail read, using disk below the memory base.
+    fn chunk(
+        &mut self,
+        offset: u64,
+        max_bytes: usize,
+        from_end: Option<u32>,
+    ) -> Result<(u64, Bytes, bool), PlanterError> {
+        let total = usize::try_from(self.len()).unwrap_or(usize::MAX);
+        let (start, end) = chunk_bounds(total, offset, max_bytes, from_end);
+        let (mut start, end) = (start as u64, end as u64);
+        let eof = end >= total as u64;
+
+        if start < self.base {
+            if let Some((path, _)) = &self.log {
+                let mut file = fs::File::open(path)
+                    .map_err(|err| pty_to_error("open scrollback log", err.to_string()))?;
+                file.seek(SeekFrom::Start(start))
+                    .map_err(|err| pty_to_error("seek scrollback log", err.to_string()))?;
+                let mut data = Vec::new();
+                file.take(end - start)
+                    .read_to_end(&mut data)
+                    .map_err(|err| pty_to_error("read scrollback log", err.to_string()))?;
+                return Ok((start, Bytes::from(data), eof));
             }
+            start = self.base;
+        }
+
+        Ok((start, self.slice(start, end.max(start)), eof))
+    }
+
+    /// Returns retained bytes in `[start, end)`, sharing a segment when the range fits in one.
+    fn slice(&mut self, start: u64, end: u64) -> Bytes {
+        if start == end {
+            return Bytes::new();
+        }
+        self.seal();
+        let first = self
+            .segments
+            .partition_point(|(segment_start, _)| *segment_start <= start)
+            - 1;
+        let mut segments = self
+            .segments
+            .range(first..)
+            .map_while(|(segment_start, segment)| {
+                let segment_end = segment_start + segment.len() as u64;
+                (*segment_start < end).then(|| {
+                    let from = start.saturating_sub(*segment_start) as usize;
+                    let to = (end.min(segment_end) - segment_start) as usize;
+                    segment.slice(from..to)
+                })
+            });
+        let head = segments.next().unwrap_or_default();
+        if head.len() as u64 == end - start {
+            return head;
+        }
+        let mut joined = BytesMut::with_capacity((end - start) as usize);
+        joined.extend_from_slice(&head);
+        for part in segments {
+            joined.extend_from_slice(&part);
+        }
+        joined.freeze()
+    }
+
+    /// Copies the retained in-memory window, for assertions.
+    #[cfg(test)]
+    fn retained_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self
+            .segments
+            .iter()
+            .flat_map(|(_, segment)| segment.iter().copied())
+            .collect();
+        bytes.extend_from_slice(&self.pending);
+        bytes
+    }
+}
+
+/// Returns the length of `data` up to its last complete UTF-8 sequence.
+///
+/// Only a trailing lead byte whose continuation bytes are missing is cut; invalid or
+/// non-UTF-8 output is left untouched.
+fn utf8_complete_len(data: &[u8]) -> usize {
+    let Some(lead) = data
+        .iter()
+        .rev()
+        .take(4)
+        .position(|byte| byte & 0xC0 != 0x80)
+        .map(|from_end| data.len() - 1 - from_end)
+    else {
+        return data.len();
+    };
+    let needed = match data[lead] {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    };
+    if lead + needed > data.len() {
+        lead
+    } else {
+        data.len()
+    }
+}
+
+/// Computes the `[start, end)` buffer range for an offset or tail read.
+fn chunk_bounds(
+    len: usize,
+    offset: u64,
+    max_bytes: usize,
+    from_end: Option<u32>,
+) -> (usize, usize) {
+    let start = match from_end {
+        Some(tail) => {
+            let tail = usize::try_from(tail).unwrap_or(usize::MAX).min(max_bytes);
+            len.saturating_sub(tail)
         }
+        None => usize::try_from(offset).unwrap_or(len).min(len),
+    };
+    let end = start.saturating_add(max_bytes).min(len);
+    (start, end)
+}
 
+/// Spawns a background reader that copies PTY output into the session buffer.
+fn spawn_reader_thread(
+    session: Arc<PtySession>,
+    mut reader: Box<dyn Read + Send>,
+    live_readers: Arc<AtomicUsize>,
+) -> JoinHandle<()> {
+    live_readers.fetch_add(1, Ordering::Relaxed);
+    std::thread::spawn(move || {
+        pump_output(reader.as_mut(), &session.buffer);
         session.complete.store(true, Ordering::Relaxed);
+        live_readers.fetch_sub(1, Ordering::Relaxed);
+    })
+}
+
+/// Waits up to `timeout` for a reader thread to exit, detaching it otherwise.
+///
+/// The join runs on the blocking pool so the wait never stalls the async runtime.
+async fn join_reader(session_id: SessionId, handle: JoinHandle<()>, timeout: Duration) {
+    let joined = tokio::task::spawn_blocking(move || {
+        let _ = handle.join();
     });
+    if tokio::time::timeout(timeout, joined).await.is_err() {
+        tracing::warn!(
+            session_id = session_id.0,
+            timeout_ms = timeout.as_millis() as u64,
+            "pty reader thread did not exit after close; detaching"
+        );
+    }
+}
+
+/// Copies reader output into `buffer` until EOF or a fatal read error.
+fn pump_output(reader: &mut dyn Read, buffer: &Mutex<Scrollback>) {
+    let mut buf = [0_u8; 4096];
+
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if let Ok(mut scrollback) = buffer.lock() {
+                    scrollback.push(&buf[..n]);
+                } else {
+                    break;
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(READ_RETRY_DELAY);
+            }
+            Err(_) => break,
+        }
+    }
 }
 
 /// Paths and files prepared for an individual PTY session.
@@ -604,19 +1138,86 @@ fn build_isolated_env(
     env
 }
 
-/// Renders the bash startup script used inside PTY sessions.
-fn render_bash_rc(build_cell: &Path) -> String {
-    let build_cell = shell_single_quote(build_cell.to_string_lossy().as_ref());
-    format!(
-        r#"
-export PLANTER_BUILD_CELL='{build_cell}'
-builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true
-stty sane 2>/dev/null || true
-stty erase '^?' 2>/dev/null || true
-bind '"\C-h": backward-delete-char'
-bind '"\C-?": backward-delete-char'
-bind '"\e[3~": backward-delete-char'
-cd() {{
+/// Removes `sessions/pty-*` directories left behind by shells that are no longer running.
+///
+/// Directories holding persisted scrollback are kept so their output can still be recovered,
+/// as are directories whose recorded shell is still alive. Returns how many were removed.
+pub fn sweep_orphaned_sessions(state_root: &Path) -> std::io::Result<usize> {
+    let mut removed = 0;
+    for session_root in session_dirs(state_root)? {
+        if session_root.join(SCROLLBACK_FILE).exists() || recorded_shell_alive(&session_root) {
+            continue;
+        }
+        fs::remove_dir_all(&session_root)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Counts `sessions/pty-*` directories whose recorded shell is still running.
+pub fn count_live_sessions(state_root: &Path) -> std::io::Result<usize> {
+    Ok(session_dirs(state_root)?
+        .iter()
+        .filter(|session_root| recorded_shell_alive(session_root))
+        .count())
+}
+
+/// Lists `sessions/pty-*` directories under the state root.
+fn session_dirs(state_root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(state_root.join("sessions")) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with("pty-") && entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+/// Returns the session id one above the highest `sessions/pty-<id>` directory on disk.
+fn first_free_session_id(state_root: &Path) -> u64 {
+    session_dirs(state_root)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|session_root| {
+            session_root
+                .file_name()?
+                .to_str()?
+                .strip_prefix("pty-")?
+                .parse::<u64>()
+                .ok()
+        })
+        .max()
+        .map_or(1, |highest| highest.saturating_add(1))
+}
+
+/// Returns whether a session directory records a shell pid that is still running.
+fn recorded_shell_alive(session_root: &Path) -> bool {
+    fs::read_to_string(session_root.join(SHELL_PID_FILE))
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok())
+        .is_some_and(shell_pid_alive)
+}
+
+/// Returns whether a recorded shell pid still names a running process.
+fn shell_pid_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 performs only the existence and permission check and delivers nothing.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// `cd` override and prompt hook that keep bash sessions inside the build cell.
+const BASH_CD_JAIL: &str = r#"cd() {
   if [ "$#" -eq 0 ]; then
     builtin cd "$PLANTER_BUILD_CELL"
     return $?
@@ -630,19 +1231,58 @@ cd() {{
       return 1
       ;;
   esac
-}}
+}
 readonly -f cd
 PROMPT_COMMAND='builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true'
 readonly PROMPT_COMMAND
-PS1='planter:\w\$ '
+"#;
+
+/// `cd` override and prompt hook that keep zsh sessions inside the build cell.
+const ZSH_CD_JAIL: &str = r#"function cd() {
+  if [[ "$#" -eq 0 ]]; then
+    builtin cd "$PLANTER_BUILD_CELL"
+    return $?
+  fi
+  case "$1" in
+    "$PLANTER_BUILD_CELL"|"$PLANTER_BUILD_CELL/"*)
+      builtin cd "$1"
+      ;;
+    *)
+      print -u2 -- "planter: blocked cd outside build cell: $1"
+      return 1
+      ;;
+  esac
+}
+function precmd() {
+  builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true
+}
+"#;
+
+/// Renders the bash startup script used inside PTY sessions.
+fn render_bash_rc(build_cell: &Path, jail_cwd: bool) -> String {
+    let build_cell = shell_single_quote(build_cell.to_string_lossy().as_ref());
+    let mut rc = format!(
+        r#"
+export PLANTER_BUILD_CELL='{build_cell}'
+builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true
+stty sane 2>/dev/null || true
+stty erase '^?' 2>/dev/null || true
+bind '"\C-h": backward-delete-char'
+bind '"\C-?": backward-delete-char'
+bind '"\e[3~": backward-delete-char'
 "#
-    )
+    );
+    if jail_cwd {
+        rc.push_str(BASH_CD_JAIL);
+    }
+    rc.push_str("PS1='planter:\\w\\$ '\n");
+    rc
 }
 
 /// Renders the zsh startup script used inside PTY sessions.
-fn render_zsh_rc(build_cell: &Path) -> String {
+fn render_zsh_rc(build_cell: &Path, jail_cwd: bool) -> String {
     let build_cell = shell_single_quote(build_cell.to_string_lossy().as_ref());
-    format!(
+    let mut rc = format!(
         r#"
 export PLANTER_BUILD_CELL='{build_cell}'
 builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true
@@ -657,27 +1297,13 @@ bindkey -M emacs '\e[3~' backward-delete-char
 bindkey -M viins '^?' backward-delete-char
 bindkey -M viins '^H' backward-delete-char
 bindkey -M viins '\e[3~' backward-delete-char
-function cd() {{
-  if [[ "$#" -eq 0 ]]; then
-    builtin cd "$PLANTER_BUILD_CELL"
-    return $?
-  fi
-  case "$1" in
-    "$PLANTER_BUILD_CELL"|"$PLANTER_BUILD_CELL/"*)
-      builtin cd "$1"
-      ;;
-    *)
-      print -u2 -- "planter: blocked cd outside build cell: $1"
-      return 1
-      ;;
-  esac
-}}
-function precmd() {{
-  builtin cd "$PLANTER_BUILD_CELL" 2>/dev/null || true
-}}
-PROMPT='planter:%~ %# '
 "#
-    )
+    );
+    if jail_cwd {
+        rc.push_str(ZSH_CD_JAIL);
+    }
+    rc.push_str("PROMPT='planter:%~ %# '\n");
+    rc
 }
 
 /// Escapes a value for safe inclusion in single-quoted shell strings.
@@ -770,6 +1396,12 @@ fn is_nested_sandbox_denied_by_parent(exit_code: Option<i32>, stderr: &[u8]) ->
         || (exit_code == Some(71) && stderr.contains("operation not permitted"))
 }
 
+/// Detects `sandbox-exec` reporting that the profile refused to exec the shell binary.
+fn is_shell_exec_denied_by_sandbox(startup_output: &str) -> bool {
+    let output = startup_output.to_ascii_lowercase();
+    output.contains("execvp()") && output.contains("operation not permitted")
+}
+
 /// Normalizes command output for compact error details.
 fn format_output_for_detail(output: &[u8], max_chars: usize) -> Option<String> {
     let compact = String::from_utf8_lossy(output)
@@ -922,7 +1554,22 @@ fn not_found_error(message: String) -> PlanterError {
 
 #[cfg(test)]
 mod tests {
-    use super::is_nested_sandbox_denied_by_parent;
+    use std::{
+        collections::{BTreeMap, VecDeque},
+        fs,
+        io::{self, Read},
+        path::Path,
+        sync::{Mutex, atomic::Ordering},
+        time::{Duration, Instant},
+    };
+
+    use planter_core::{ErrorCode, SessionId};
+
+    use super::{
+        PtyManager, PtyOpenSpec, PtyReadSpec, PtySandboxMode, SCROLLBACK_FILE, Scrollback,
+        chunk_bounds, is_nested_sandbox_denied_by_parent, is_shell_exec_denied_by_sandbox,
+        pump_output, render_bash_rc, render_zsh_rc, utf8_complete_len,
+    };
 
     #[test]
     /// Detects known stderr pattern for nested sandbox permission denial.
@@ -938,4 +1585,459 @@ mod tests {
         let stderr = b"sandbox-exec: invalid profile";
         assert!(!is_nested_sandbox_denied_by_parent(Some(1), stderr));
     }
+
+    #[test]
+    /// Detects the exec denial `sandbox-exec` prints for a shell the profile forbids.
+    fn detects_shell_exec_denial_message() {
+        let output = "sandbox-exec: execvp() of '/opt/shells/fish' failed: Operation not permitted";
+        assert!(is_shell_exec_denied_by_sandbox(output));
+        assert!(!is_shell_exec_denied_by_sandbox(
+            "sandbox-exec: sandbox_apply: Operation not permitted"
+        ));
+        assert!(!is_shell_exec_denied_by_sandbox(
+            "sandbox-exec: execvp() of '/bin/nope' failed: No such file or directory"
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[tokio::test]
+    /// Verifies an enforced PTY with a shell outside the profile's readable paths gets a clear error.
+    async fn enforced_pty_reports_shell_blocked_by_sandbox() {
+        if !Path::new(super::SANDBOX_EXEC_PATH).exists() {
+            return;
+        }
+        let state = tempfile::tempdir().expect("state tempdir");
+        let shells = tempfile::tempdir().expect("shell tempdir");
+        let shell = shells.path().join("sh");
+        std::fs::copy("/bin/sh", &shell).expect("copy shell");
+
+        let manager = PtyManager::new(state.path().to_path_buf(), PtySandboxMode::Enforced);
+        let err = match manager.open(PtyOpenSpec {
+            shell: shell.display().to_string(),
+            args: Vec::new(),
+            cwd: None,
+            env: BTreeMap::new(),
+            cols: 80,
+            rows: 24,
+            jail_cwd: false,
+            persist_scrollback: false,
+        }) {
+            // Nested sandboxing is unavailable here, so the shell launched unconfined.
+            Ok(opened) => {
+                let _ = manager.close(opened.session_id, true).await;
+                return;
+            }
+            Err(err) => err,
+        };
+        assert_eq!(err.code, planter_core::ErrorCode::InvalidRequest);
+        assert!(err.message.contains("not permitted"), "{}", err.message);
+    }
+
+    #[test]
+    /// Verifies disabling the cwd jail drops the `cd` override and prompt hooks.
+    fn rc_files_omit_cd_jail_when_disabled() {
+        let build_cell = Path::new("/tmp/planter/build-cell");
+
+        let bash_jailed = render_bash_rc(build_cell, true);
+        let bash_free = render_bash_rc(build_cell, false);
+        assert_ne!(bash_jailed, bash_free);
+        assert!(bash_jailed.contains("readonly -f cd"));
+        assert!(bash_jailed.contains("PROMPT_COMMAND="));
+        assert!(!bash_free.contains("readonly -f cd"));
+        assert!(!bash_free.contains("PROMPT_COMMAND="));
+        assert!(bash_free.contains("PS1='planter:\\w\\$ '"));
+
+        let zsh_jailed = render_zsh_rc(build_cell, true);
+        let zsh_free = render_zsh_rc(build_cell, false);
+        assert_ne!(zsh_jailed, zsh_free);
+        assert!(zsh_jailed.contains("function cd()"));
+        assert!(zsh_jailed.contains("function precmd()"));
+        assert!(!zsh_free.contains("function cd()"));
+        assert!(!zsh_free.contains("function precmd()"));
+        assert!(zsh_free.contains("builtin cd \"$PLANTER_BUILD_CELL\""));
+    }
+
+    #[test]
+    /// Verifies tail reads return the last N bytes and their absolute start offset.
+    fn tail_read_returns_last_bytes_and_absolute_offset() {
+        let buffer = b"hello, planter";
+
+        let (start, end) = chunk_bounds(buffer.len(), 0, 4096, Some(7));
+        assert_eq!(start, 7);
+        assert_eq!(&buffer[start..end], b"planter");
+
+        let (start, end) = chunk_bounds(buffer.len(), 0, 4096, Some(64));
+        assert_eq!(start, 0);
+        assert_eq!(&buffer[start..end], buffer);
+
+        let (start, end) = chunk_bounds(buffer.len(), 0, 3, Some(7));
+        assert_eq!(start, 11);
+        assert_eq!(&buffer[start..end], b"ter");
+
+        let (start, end) = chunk_bounds(buffer.len(), 7, 4096, None);
+        assert_eq!(start, 7);
+        assert_eq!(&buffer[start..end], b"planter");
+    }
+
+    /// Reader that replays a scripted sequence of read results.
+    struct ScriptedReader {
+        /// Remaining results, consumed front to back.
+        steps: VecDeque<io::Result<Vec<u8>>>,
+    }
+
+    impl Read for ScriptedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.steps.pop_front() {
+                Some(Ok(data)) => {
+                    buf[..data.len()].copy_from_slice(&data);
+                    Ok(data.len())
+                }
+                Some(Err(err)) => Err(err),
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    /// Verifies a transient `WouldBlock` does not end output capture.
+    fn pump_output_retries_would_block() {
+        let mut reader = ScriptedReader {
+            steps: VecDeque::from(vec![
+                Ok(b"hello ".to_vec()),
+                Err(io::Error::from(io::ErrorKind::WouldBlock)),
+                Ok(b"world".to_vec()),
+                Err(io::Error::from(io::ErrorKind::Other)),
+                Ok(b"after fatal".to_vec()),
+            ]),
+        };
+        let buffer = Mutex::new(Scrollback::in_memory());
+
+        pump_output(&mut reader, &buffer);
+
+        assert_eq!(
+            buffer.lock().expect("buffer").retained_bytes(),
+            b"hello world"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies closing sessions joins their reader threads instead of leaking them.
+    async fn close_joins_reader_threads() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+
+        for _ in 0..20 {
+            let opened = manager
+                .open(PtyOpenSpec {
+                    shell: "/bin/sh".to_string(),
+                    args: vec!["-c".to_string(), "sleep 30".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    cols: 80,
+                    rows: 24,
+                    jail_cwd: false,
+                    persist_scrollback: false,
+                })
+                .expect("open session");
+            manager
+                .close(opened.session_id, true)
+                .await
+                .expect("close session");
+
+            assert_eq!(manager.live_readers.load(Ordering::Relaxed), 0);
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a new manager numbers sessions past kept directories and leaves their scrollback.
+    async fn new_sessions_skip_kept_session_dirs() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let kept = tmp.path().join("sessions").join("pty-3");
+        fs::create_dir_all(&kept).expect("create kept session dir");
+        fs::write(kept.join(SCROLLBACK_FILE), b"earlier output").expect("write scrollback");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+
+        let opened = manager
+            .open(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "sleep 30".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: true,
+            })
+            .expect("open session");
+        manager
+            .close(opened.session_id, true)
+            .await
+            .expect("close session");
+
+        assert_eq!(opened.session_id, SessionId(4));
+        assert_eq!(
+            fs::read(kept.join(SCROLLBACK_FILE)).expect("read kept scrollback"),
+            b"earlier output"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies reaping closes sessions idle past the threshold and leaves fresh ones open.
+    async fn reap_idle_closes_only_idle_sessions() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+        let open = || {
+            manager
+                .open(PtyOpenSpec {
+                    shell: "/bin/sh".to_string(),
+                    args: vec!["-c".to_string(), "sleep 30".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    cols: 80,
+                    rows: 24,
+                    jail_cwd: false,
+                    persist_scrollback: false,
+                })
+                .expect("open session")
+                .session_id
+        };
+
+        let idle = open();
+        std::thread::sleep(Duration::from_millis(300));
+        let fresh = open();
+
+        let reaped = manager
+            .reap_idle(Duration::from_millis(150))
+            .await
+            .expect("reap idle sessions");
+        assert_eq!(reaped, vec![idle]);
+        assert!(manager.get_session(idle).is_err());
+        assert!(manager.get_session(fresh).is_ok());
+
+        manager
+            .close(fresh, true)
+            .await
+            .expect("close fresh session");
+    }
+
+    #[tokio::test]
+    /// Verifies a boundary read of only a split codepoint returns nothing and does not advance.
+    async fn utf8_boundary_read_of_split_codepoint_is_empty() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+        let session_id = manager
+            .open(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec![
+                    "-c".to_string(),
+                    "printf '\\342\\202'; sleep 30".to_string(),
+                ],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .expect("open session")
+            .session_id;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while manager.peek(session_id, 0, 8).expect("peek").length < 2 && Instant::now() < deadline
+        {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let chunk = manager
+            .read(
+                session_id,
+                PtyReadSpec {
+                    offset: 0,
+                    max_bytes: 8,
+                    follow: false,
+                    wait_ms: 0,
+                    from_end: None,
+                    utf8_boundary: true,
+                    strip_ansi: false,
+                },
+            )
+            .await
+            .expect("read split codepoint");
+        assert_eq!(chunk.offset, 0);
+        assert!(chunk.data.is_empty(), "{:?}", chunk.data);
+        assert!(!chunk.eof);
+
+        manager
+            .close(session_id, true)
+            .await
+            .expect("close session");
+    }
+
+    #[tokio::test]
+    /// Verifies peeks return buffered output and its length at once without touching idleness.
+    async fn peek_returns_immediately_with_length() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+        let session_id = manager
+            .open(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "printf hello; sleep 30".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .expect("open session")
+            .session_id;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let peeked = loop {
+            let peeked = manager.peek(session_id, 0, 3).expect("peek");
+            if peeked.length >= 5 || Instant::now() >= deadline {
+                break peeked;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(&peeked.data[..], b"hel");
+        assert_eq!(peeked.length, 5);
+        assert!(!peeked.complete);
+
+        let started = Instant::now();
+        let peeked = manager.peek(session_id, 5, 1024).expect("peek at end");
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(peeked.data.is_empty());
+        assert_eq!(peeked.length, 5);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(
+            manager
+                .reap_idle(Duration::from_millis(50))
+                .await
+                .expect("reap idle"),
+            vec![session_id]
+        );
+    }
+
+    #[test]
+    /// Verifies rendered profiles allow writes under the session home and protect its rc files.
+    fn sandbox_profile_allows_session_home_writes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Enforced);
+
+        let rendered = manager.sandbox_profile(None).expect("render next profile");
+        assert_eq!(rendered.session_id.0, 1);
+        let home = tmp.path().join("sessions").join("pty-1").join("home");
+        let profile = rendered.profile;
+        assert!(
+            profile.contains(&format!(
+                "(allow file-write* (subpath \"{}\"))",
+                home.display()
+            )),
+            "{profile}"
+        );
+        assert!(
+            profile.contains(&format!(
+                "(deny file-write* (literal \"{}\"))",
+                home.join(".planter_bashrc").display()
+            )),
+            "{profile}"
+        );
+        assert!(profile.contains("pty-1"), "{profile}");
+
+        let Err(missing) = manager.sandbox_profile(Some(SessionId(7))) else {
+            panic!("unknown session should fail");
+        };
+        assert_eq!(missing.code, ErrorCode::NotFound);
+    }
+
+    #[test]
+    /// Verifies no profile is rendered when PTY sandboxing is disabled.
+    fn sandbox_profile_is_refused_when_disabled() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let manager = PtyManager::new(tmp.path().to_path_buf(), PtySandboxMode::Disabled);
+
+        let Err(err) = manager.sandbox_profile(None) else {
+            panic!("disabled sandbox should not render a profile");
+        };
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert_eq!(err.message, "pty sandbox is disabled");
+    }
+
+    #[test]
+    /// Verifies offsets trimmed from memory are still served from the on-disk log.
+    fn scrollback_serves_trimmed_offsets_from_disk() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("output.log");
+        let mut scrollback = Scrollback::persisted(path.clone(), 8).expect("scrollback");
+
+        scrollback.push(b"hello ");
+        scrollback.push(b"planter world");
+        assert_eq!(scrollback.base, 11);
+        assert_eq!(scrollback.retained_bytes(), b"er world");
+        assert_eq!(std::fs::read(&path).expect("log"), b"hello planter world");
+
+        let (start, data, eof) = scrollback.chunk(0, 5, None).expect("disk chunk");
+        assert_eq!((start, &data[..], eof), (0, b"hello".as_slice(), false));
+
+        let (start, data, eof) = scrollback.chunk(6, 64, None).expect("spanning chunk");
+        assert_eq!(
+            (start, &data[..], eof),
+            (6, b"planter world".as_slice(), true)
+        );
+
+        let (start, data, eof) = scrollback.chunk(0, 64, Some(5)).expect("tail chunk");
+        assert_eq!((start, &data[..], eof), (14, b"world".as_slice(), true));
+    }
+
+    #[test]
+    /// Verifies in-memory scrollback keeps all output when nothing is persisted.
+    fn in_memory_scrollback_never_trims() {
+        let mut scrollback = Scrollback::in_memory();
+        scrollback.push(&[b'x'; 4096]);
+        scrollback.push(b"tail");
+
+        assert_eq!(scrollback.base, 0);
+        let (start, data, eof) = scrollback.chunk(0, 8192, None).expect("chunk");
+        assert_eq!(start, 0);
+        assert_eq!(data.len(), 4100);
+        assert!(eof);
+    }
+
+    #[test]
+    /// Verifies reads within sealed output share its storage and spanning reads stay correct.
+    fn scrollback_reads_share_sealed_segments() {
+        let mut scrollback = Scrollback::in_memory();
+        scrollback.push(b"hello ");
+        let (_, first, _) = scrollback.chunk(0, 64, None).expect("first chunk");
+        let (_, again, _) = scrollback.chunk(0, 64, None).expect("repeat chunk");
+        assert_eq!(&first[..], b"hello ");
+        assert_eq!(first.as_ptr(), again.as_ptr());
+
+        scrollback.push(b"world");
+        let (start, data, eof) = scrollback.chunk(6, 64, None).expect("follow chunk");
+        assert_eq!((start, &data[..], eof), (6, b"world".as_slice(), true));
+        let (start, data, eof) = scrollback.chunk(3, 5, None).expect("spanning chunk");
+        assert_eq!((start, &data[..], eof), (3, b"lo wo".as_slice(), false));
+    }
+
+    #[test]
+    /// Verifies boundary trimming drops only a split trailing codepoint so the next read resumes on it.
+    fn utf8_boundary_trims_split_codepoint() {
+        let mut scrollback = Scrollback::in_memory();
+        scrollback.push("h€llo".as_bytes());
+
+        let (start, data, _) = scrollback.chunk(0, 3, None).expect("split chunk");
+        let trimmed = utf8_complete_len(&data);
+        assert_eq!((start, &data[..trimmed]), (0, b"h".as_slice()));
+
+        let next = start + trimmed as u64;
+        let (_, data, _) = scrollback.chunk(next, 4, None).expect("resumed chunk");
+        assert_eq!(utf8_complete_len(&data), data.len());
+        assert_eq!(std::str::from_utf8(&data).expect("utf8"), "€l");
+
+        assert_eq!(utf8_complete_len(b"plain"), 5);
+        assert_eq!(utf8_complete_len(&[0xF0, 0x9F, 0x8C]), 0);
+        assert_eq!(utf8_complete_len(&[0xFF, 0x80]), 2);
+    }
 }
@@ -1,35 +1,110 @@
-use std::{path::Path, time::Duration};
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use planter_core::{ReqId, Request, RequestEnvelope, Response, ResponseEnvelope};
-use tokio::{net::UnixStream, time::timeout};
+use planter_core::{ReqId, Request, RequestEnvelope, Response, ResponseEnvelope, SessionId};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf},
+    net::UnixStream,
+    sync::{Mutex as AsyncMutex, oneshot},
+    task::JoinHandle,
+    time::{sleep, timeout, timeout_at},
+};
 
 use crate::{
     IpcError,
-    codec::{decode, encode},
+    codec::{WireFormat, log_oversized_frame},
     framing::{read_frame, write_frame},
+    pty::{PtyOptions, PtySession},
 };
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Upper bound on the delay between connection attempts.
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Byte stream transport a [`PlanterClient`] can speak framed IPC over.
+trait ClientStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ClientStream for T {}
 
-/// Stateful UNIX socket client for request/response planter IPC.
+/// Stateful socket client for request/response planter IPC.
 pub struct PlanterClient {
     /// Connected socket stream.
-    stream: UnixStream,
+    stream: Box<dyn ClientStream>,
     /// Next request id to assign.
     next_req_id: u64,
     /// Per-call timeout.
     timeout: Duration,
+    /// Payload encoding for requests and expected responses.
+    format: WireFormat,
 }
 
 impl PlanterClient {
     /// Connects a client to the daemon socket path.
     pub async fn connect(path: impl AsRef<Path>) -> Result<Self, IpcError> {
         let stream = UnixStream::connect(path).await?;
-        Ok(Self {
-            stream,
+        Ok(Self::from_stream(stream))
+    }
+
+    /// Connects to the daemon socket, failing with [`IpcError::Timeout`] once `deadline` passes.
+    pub async fn connect_deadline(
+        path: impl AsRef<Path>,
+        deadline: Instant,
+    ) -> Result<Self, IpcError> {
+        timeout_at(deadline.into(), Self::connect(path))
+            .await
+            .map_err(|_| IpcError::Timeout)?
+    }
+
+    /// Connects with up to `attempts` tries, retrying I/O errors after a doubling `backoff`.
+    ///
+    /// Every delay, including the first, is capped at one second.
+    pub async fn connect_with_retry(
+        path: impl AsRef<Path>,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<Self, IpcError> {
+        let path = path.as_ref();
+        let mut delay = backoff.min(MAX_CONNECT_BACKOFF);
+        let mut remaining = attempts.max(1);
+        loop {
+            remaining -= 1;
+            match Self::connect(path).await {
+                Err(IpcError::Io(_)) if remaining > 0 => {
+                    sleep(delay).await;
+                    delay = delay.saturating_mul(2).min(MAX_CONNECT_BACKOFF);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Connects a client to a TLS-over-TCP daemon listener.
+    #[cfg(feature = "tls")]
+    pub async fn connect_tls(
+        addr: std::net::SocketAddr,
+        server_name: &str,
+        config: std::sync::Arc<tokio_rustls::rustls::ClientConfig>,
+    ) -> Result<Self, IpcError> {
+        let stream = crate::tls::connect_stream(addr, server_name, config).await?;
+        Ok(Self::from_stream(stream))
+    }
+
+    /// Wraps an established transport stream with default client settings.
+    fn from_stream(stream: impl ClientStream + 'static) -> Self {
+        Self {
+            stream: Box::new(stream),
             next_req_id: 1,
             timeout: DEFAULT_TIMEOUT,
-        })
+            format: WireFormat::default(),
+        }
     }
 
     /// Overrides the default call timeout.
@@ -38,29 +113,285 @@ impl PlanterClient {
         self
     }
 
+    /// Selects the payload encoding; the server answers in whichever format it receives.
+    pub fn with_wire_format(mut self, format: WireFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     /// Sends one request and waits for the matching response.
     pub async fn call(&mut self, req: Request) -> Result<Response, IpcError> {
+        timeout(self.timeout, self.exchange(req, None))
+            .await
+            .map_err(|_| IpcError::Timeout)?
+    }
+
+    /// Sends one request, failing with [`IpcError::Timeout`] once the absolute `deadline` passes.
+    ///
+    /// The time left is sent as the envelope's `deadline_ms` so the server also stops waiting.
+    /// Reuse one deadline across `connect_deadline` and several calls to bound a whole operation.
+    pub async fn call_deadline(
+        &mut self,
+        req: Request,
+        deadline: Instant,
+    ) -> Result<Response, IpcError> {
+        timeout_at(deadline.into(), self.exchange(req, Some(deadline)))
+            .await
+            .map_err(|_| IpcError::Timeout)?
+    }
+
+    /// Sends a streaming request, passing each intermediate frame to `on_frame`, and returns
+    /// the final response.
+    ///
+    /// Streams can outlive the per-call timeout, so none is applied; wrap the call in a timeout
+    /// to bound it.
+    pub async fn subscribe(
+        &mut self,
+        req: Request,
+        on_frame: impl FnMut(Response),
+    ) -> Result<Response, IpcError> {
+        let req_id = self.send_request(req, None).await?;
+        self.read_final_response(req_id, on_frame).await
+    }
+
+    /// Converts this client into a cloneable handle that keeps several calls in flight.
+    pub fn into_multiplexed(self) -> MultiplexedClient {
+        let (reader, writer) = tokio::io::split(self.stream);
+        let pending: PendingCalls = Arc::new(Mutex::new(Some(HashMap::new())));
+        let router = tokio::spawn(route_responses(reader, self.format, Arc::clone(&pending)));
+        MultiplexedClient {
+            inner: Arc::new(MultiplexedInner {
+                writer: AsyncMutex::new(writer),
+                pending,
+                next_req_id: AtomicU64::new(self.next_req_id),
+                timeout: self.timeout,
+                format: self.format,
+                router,
+            }),
+        }
+    }
+
+    /// Opens a PTY session on this connection and returns a duplex handle driving it.
+    ///
+    /// The connection is multiplexed so output reads, input writes, and resizes overlap.
+    pub async fn open_pty(self, options: PtyOptions) -> Result<PtySession, IpcError> {
+        PtySession::open(self.into_multiplexed(), options).await
+    }
+
+    /// Writes one request frame and reads the matching response without a time bound.
+    ///
+    /// A `deadline` is only forwarded to the server; the caller enforces it locally.
+    /// Intermediate frames of a streaming request are skipped.
+    async fn exchange(
+        &mut self,
+        req: Request,
+        deadline: Option<Instant>,
+    ) -> Result<Response, IpcError> {
+        let req_id = self.send_request(req, deadline).await?;
+        self.read_final_response(req_id, drop).await
+    }
+
+    /// Writes one request frame, returning the request id assigned to it.
+    async fn send_request(
+        &mut self,
+        req: Request,
+        deadline: Option<Instant>,
+    ) -> Result<ReqId, IpcError> {
         let req_id = ReqId(self.next_req_id);
         self.next_req_id = self.next_req_id.saturating_add(1);
 
-        let envelope = RequestEnvelope { req_id, body: req };
-        let payload = encode(&envelope)?;
+        let envelope = RequestEnvelope {
+            req_id,
+            body: req,
+            deadline_ms: deadline.map(|deadline| {
+                u64::try_from(
+                    deadline
+                        .saturating_duration_since(Instant::now())
+                        .as_millis(),
+                )
+                .unwrap_or(u64::MAX)
+            }),
+        };
+        let payload = self.format.encode(&envelope)?;
+
+        write_frame(&mut self.stream, &payload)
+            .await
+            .inspect_err(|err| {
+                if matches!(err, IpcError::FrameTooLarge { .. }) {
+                    log_oversized_frame("request", &payload);
+                }
+            })?;
+        Ok(req_id)
+    }
 
-        let response = timeout(self.timeout, async {
-            write_frame(&mut self.stream, &payload).await?;
+    /// Reads frames for `req_id`, handing intermediate ones to `on_frame`, until the final one.
+    async fn read_final_response(
+        &mut self,
+        req_id: ReqId,
+        mut on_frame: impl FnMut(Response),
+    ) -> Result<Response, IpcError> {
+        loop {
             let response_frame = read_frame(&mut self.stream).await?;
-            decode::<ResponseEnvelope<Response>>(&response_frame)
-        })
-        .await
-        .map_err(|_| IpcError::Timeout)??;
-
-        if response.req_id != req_id {
-            return Err(IpcError::RequestIdMismatch {
-                expected: req_id.0,
-                actual: response.req_id.0,
-            });
+            let response = self
+                .format
+                .decode::<ResponseEnvelope<Response>>(&response_frame)?;
+
+            if response.req_id != req_id {
+                return Err(IpcError::RequestIdMismatch {
+                    expected: req_id.0,
+                    actual: response.req_id.0,
+                });
+            }
+            if !response.more {
+                return Ok(response.body);
+            }
+            on_frame(response.body);
         }
+    }
+}
+
+/// Response slots keyed by request id; `None` once the connection has closed.
+type PendingCalls = Arc<Mutex<Option<HashMap<u64, oneshot::Sender<Response>>>>>;
+
+/// Cloneable client that pipelines concurrent calls over one connection.
+///
+/// Clones share the connection; responses are routed back to callers by `req_id`.
+#[derive(Clone)]
+pub struct MultiplexedClient {
+    /// Connection state shared by all clones.
+    inner: Arc<MultiplexedInner>,
+}
+
+/// Connection state behind a [`MultiplexedClient`].
+struct MultiplexedInner {
+    /// Write half of the connection, held for one frame at a time.
+    writer: AsyncMutex<WriteHalf<Box<dyn ClientStream>>>,
+    /// Calls awaiting their response frame.
+    pending: PendingCalls,
+    /// Next request id to assign.
+    next_req_id: AtomicU64,
+    /// Per-call timeout.
+    timeout: Duration,
+    /// Payload encoding for requests and expected responses.
+    format: WireFormat,
+    /// Task reading response frames and completing pending calls.
+    router: JoinHandle<()>,
+}
 
-        Ok(response.body)
+impl Drop for MultiplexedInner {
+    fn drop(&mut self) {
+        self.router.abort();
     }
 }
+
+impl MultiplexedClient {
+    /// Attaches to an existing PTY session as one more viewer, sharing this connection.
+    ///
+    /// Output replays the last `tail` buffered bytes, or all of it without one. Dropping the
+    /// handle leaves the session open for other viewers.
+    pub fn attach_pty(&self, session_id: SessionId, tail: Option<u32>) -> PtySession {
+        PtySession::attach(self.clone(), session_id, tail)
+    }
+
+    /// Sends one request and waits for its response while other calls stay in flight.
+    pub async fn call(&self, req: Request) -> Result<Response, IpcError> {
+        let req_id = self.inner.next_req_id.fetch_add(1, Ordering::Relaxed);
+        let (slot, response) = oneshot::channel();
+        match self.inner.pending.lock() {
+            Ok(mut pending) => match pending.as_mut() {
+                Some(pending) => {
+                    pending.insert(req_id, slot);
+                }
+                None => return Err(connection_closed()),
+            },
+            Err(_) => return Err(connection_closed()),
+        }
+
+        // Only the wait is timed: cancelling a write halfway would leave a partial frame that
+        // corrupts every later call on the shared connection.
+        let envelope = RequestEnvelope {
+            req_id: ReqId(req_id),
+            body: req,
+            deadline_ms: None,
+        };
+        let written = match self.inner.format.encode(&envelope) {
+            Ok(payload) => write_frame(&mut *self.inner.writer.lock().await, &payload)
+                .await
+                .inspect_err(|err| {
+                    if matches!(err, IpcError::FrameTooLarge { .. }) {
+                        log_oversized_frame("request", &payload);
+                    }
+                }),
+            Err(err) => Err(err),
+        };
+        // Intermediate frames of a streaming request are dropped by the router.
+        let result = match written {
+            Ok(()) => timeout(self.inner.timeout, response)
+                .await
+                .map_err(|_| IpcError::Timeout)
+                .and_then(|result| result.map_err(|_| connection_closed())),
+            Err(err) => Err(err),
+        };
+
+        if result.is_err()
+            && let Ok(mut pending) = self.inner.pending.lock()
+            && let Some(pending) = pending.as_mut()
+        {
+            pending.remove(&req_id);
+        }
+        result
+    }
+}
+
+/// Reads response frames and completes the matching pending calls until the connection ends.
+async fn route_responses(
+    mut reader: ReadHalf<Box<dyn ClientStream>>,
+    format: WireFormat,
+    pending: PendingCalls,
+) {
+    loop {
+        let envelope = match read_frame(&mut reader).await {
+            Ok(frame) => format.decode::<ResponseEnvelope<Response>>(&frame),
+            Err(err) => Err(err),
+        };
+        let envelope = match envelope {
+            Ok(envelope) => envelope,
+            Err(err) => {
+                tracing::debug!(error = %err, "multiplexed connection closed");
+                break;
+            }
+        };
+        if envelope.more {
+            continue;
+        }
+
+        let slot = match pending.lock() {
+            Ok(mut pending) => pending
+                .as_mut()
+                .and_then(|pending| pending.remove(&envelope.req_id.0)),
+            Err(_) => break,
+        };
+        match slot {
+            Some(slot) => {
+                let _ = slot.send(envelope.body);
+            }
+            None => tracing::debug!(
+                req_id = envelope.req_id.0,
+                "dropping response for an abandoned request"
+            ),
+        }
+    }
+
+    // Dropping the slots wakes every waiting caller with a closed-connection error.
+    if let Ok(mut pending) = pending.lock() {
+        pending.take();
+    }
+}
+
+/// Builds the error returned to calls on a connection that has gone away.
+fn connection_closed() -> IpcError {
+    IpcError::Io(io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "multiplexed connection closed",
+    ))
+}
@@ -1,13 +1,203 @@
 use serde::{Serialize, de::DeserializeOwned};
+use serde_cbor::Value;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-use crate::IpcError;
+use crate::{
+    IpcError,
+    framing::{MAX_FRAME_SIZE, record_frame_size},
+};
+
+/// Number of largest body fields listed in oversized-frame diagnostics.
+const OVERSIZED_FIELD_LIMIT: usize = 4;
+
+/// Serialization used for frame payloads; framing is identical for both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    /// Compact CBOR, the default.
+    #[default]
+    Cbor,
+    /// Plain JSON, readable in packet captures during development.
+    Json,
+}
+
+impl WireFormat {
+    /// Detects a payload's format; envelopes are maps, and only JSON ones start with `{`.
+    pub fn detect(payload: &[u8]) -> Self {
+        if payload.first() == Some(&b'{') {
+            Self::Json
+        } else {
+            Self::Cbor
+        }
+    }
+
+    /// Serializes a value in this format.
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, IpcError> {
+        match self {
+            Self::Cbor => encode(value),
+            Self::Json => {
+                serde_json::to_vec(value).map_err(|err| IpcError::Encode(err.to_string()))
+            }
+        }
+    }
+
+    /// Serializes a value in this format straight into a length-prefixed frame and writes it.
+    ///
+    /// `frame` is cleared and reused, so a caller writing many frames serializes each one once
+    /// into the same allocation. On `FrameTooLarge` nothing is written and `frame` still holds
+    /// the header followed by the rejected payload.
+    pub async fn encode_into_framed<W: AsyncWrite + Unpin, T: Serialize>(
+        self,
+        writer: &mut W,
+        frame: &mut Vec<u8>,
+        value: &T,
+    ) -> Result<(), IpcError> {
+        frame.clear();
+        frame.extend_from_slice(&[0; 4]);
+        match self {
+            Self::Cbor => serde_cbor::to_writer(&mut *frame, value)
+                .map_err(|err| IpcError::Encode(err.to_string()))?,
+            Self::Json => serde_json::to_writer(&mut *frame, value)
+                .map_err(|err| IpcError::Encode(err.to_string()))?,
+        }
+        let size: u32 = (frame.len() - 4)
+            .try_into()
+            .map_err(|_| IpcError::FrameTooLarge {
+                size: u32::MAX,
+                max: MAX_FRAME_SIZE,
+            })?;
+
+        record_frame_size(size);
+        if size > MAX_FRAME_SIZE {
+            return Err(IpcError::FrameTooLarge {
+                size,
+                max: MAX_FRAME_SIZE,
+            });
+        }
+
+        frame[..4].copy_from_slice(&size.to_be_bytes());
+        writer.write_all(frame).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Deserializes a payload in this format.
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, IpcError> {
+        match self {
+            Self::Cbor => decode(bytes),
+            Self::Json => {
+                serde_json::from_slice(bytes).map_err(|err| IpcError::Decode(err.to_string()))
+            }
+        }
+    }
+}
 
 /// Serializes a value to CBOR bytes for wire transmission.
 pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, IpcError> {
     serde_cbor::to_vec(value).map_err(|err| IpcError::Encode(err.to_string()))
 }
 
+/// Serializes a value as CBOR directly into a reusable length-prefixed frame and writes it.
+///
+/// Prefer this over [`encode`] plus `write_frame` for large log and PTY chunks; see
+/// [`WireFormat::encode_into_framed`].
+pub async fn encode_into_framed<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    frame: &mut Vec<u8>,
+    value: &T,
+) -> Result<(), IpcError> {
+    WireFormat::Cbor
+        .encode_into_framed(writer, frame, value)
+        .await
+}
+
 /// Deserializes a CBOR frame payload into a typed value.
 pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, IpcError> {
     serde_cbor::from_slice(bytes).map_err(|err| IpcError::Decode(err.to_string()))
 }
+
+/// Message variant and approximate per-field sizes of an encoded envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBreakdown {
+    /// Body `type` tag, or `unknown` when the payload is not a tagged envelope.
+    pub variant: String,
+    /// Encoded body field sizes in bytes, largest first.
+    pub fields: Vec<(String, usize)>,
+}
+
+/// Describes which message variant and fields make up an encoded envelope.
+pub fn describe_frame(payload: &[u8]) -> FrameBreakdown {
+    let mut breakdown = FrameBreakdown {
+        variant: "unknown".to_string(),
+        fields: Vec::new(),
+    };
+    if WireFormat::detect(payload) == WireFormat::Json {
+        describe_json_frame(payload, &mut breakdown);
+        return breakdown;
+    }
+    let Ok(Value::Map(envelope)) = serde_cbor::from_slice::<Value>(payload) else {
+        return breakdown;
+    };
+    let Some(Value::Map(body)) = envelope.get(&Value::Text("body".to_string())) else {
+        return breakdown;
+    };
+
+    for (key, value) in body {
+        let Value::Text(name) = key else {
+            continue;
+        };
+        match value {
+            Value::Text(tag) if name == "type" => breakdown.variant = tag.clone(),
+            _ => {
+                let size = serde_cbor::to_vec(value).map_or(0, |bytes| bytes.len());
+                breakdown.fields.push((name.clone(), size));
+            }
+        }
+    }
+    breakdown
+        .fields
+        .sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0)));
+    breakdown
+}
+
+/// Fills `breakdown` from a JSON envelope payload.
+fn describe_json_frame(payload: &[u8], breakdown: &mut FrameBreakdown) {
+    let Ok(serde_json::Value::Object(mut envelope)) = serde_json::from_slice(payload) else {
+        return;
+    };
+    let Some(serde_json::Value::Object(body)) = envelope.remove("body") else {
+        return;
+    };
+
+    for (name, value) in body {
+        match value {
+            serde_json::Value::String(tag) if name == "type" => breakdown.variant = tag,
+            value => {
+                let size = serde_json::to_vec(&value).map_or(0, |bytes| bytes.len());
+                breakdown.fields.push((name, size));
+            }
+        }
+    }
+    breakdown
+        .fields
+        .sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0)));
+}
+
+/// Logs the variant and largest fields of a payload rejected as too large.
+pub(crate) fn log_oversized_frame(direction: &'static str, payload: &[u8]) {
+    let breakdown = describe_frame(payload);
+    let fields = breakdown
+        .fields
+        .iter()
+        .take(OVERSIZED_FIELD_LIMIT)
+        .map(|(name, size)| format!("{name}={size}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    tracing::warn!(
+        direction,
+        variant = %breakdown.variant,
+        size = payload.len(),
+        max = MAX_FRAME_SIZE,
+        fields = %fields,
+        "frame exceeds maximum size"
+    );
+}
@@ -1,3 +1,4 @@
+use planter_core::PlanterError;
 use thiserror::Error;
 
 /// Transport and serialization failures for planter IPC operations.
@@ -7,10 +8,10 @@ pub enum IpcError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     /// Request/response encoding failed.
-    #[error("failed to encode cbor payload: {0}")]
+    #[error("failed to encode payload: {0}")]
     Encode(String),
     /// Request/response decoding failed.
-    #[error("failed to decode cbor payload: {0}")]
+    #[error("failed to decode payload: {0}")]
     Decode(String),
     /// Operation exceeded configured timeout.
     #[error("request timed out")]
@@ -21,7 +22,16 @@ pub enum IpcError {
     /// Response did not match request identifier.
     #[error("request id mismatch: expected {expected}, got {actual}")]
     RequestIdMismatch { expected: u64, actual: u64 },
+    /// TLS configuration or handshake failed.
+    #[error("tls error: {0}")]
+    Tls(String),
     /// Peer protocol version did not match local expectation.
     #[error("protocol mismatch: expected {expected}, got {actual}")]
     ProtocolMismatch { expected: u32, actual: u32 },
+    /// Daemon answered the request with an error response.
+    #[error("daemon error: {0}")]
+    Daemon(PlanterError),
+    /// Daemon answered with a response of the wrong kind for the request.
+    #[error("unexpected response to {0}")]
+    UnexpectedResponse(&'static str),
 }
@@ -1,3 +1,8 @@
+use std::{
+    io::{self, IoSlice},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::IpcError;
@@ -5,7 +10,23 @@ use crate::IpcError;
 /// Maximum payload size accepted by framing helpers.
 pub const MAX_FRAME_SIZE: u32 = 8 * 1024 * 1024;
 
+/// Largest frame size seen by this process, including rejected frames.
+static LARGEST_FRAME_SIZE: AtomicU32 = AtomicU32::new(0);
+
+/// Returns the largest frame size read or written by this process so far.
+pub fn largest_frame_size() -> u32 {
+    LARGEST_FRAME_SIZE.load(Ordering::Relaxed)
+}
+
+/// Records a frame size for the largest-frame metric.
+pub(crate) fn record_frame_size(size: u32) {
+    LARGEST_FRAME_SIZE.fetch_max(size, Ordering::Relaxed);
+}
+
 /// Writes one length-prefixed frame to the async writer.
+///
+/// Header and payload go out in a single vectored write when the writer supports it.
+/// The trailing `flush` is free on raw sockets and only does work for buffered writers.
 pub async fn write_frame<W: AsyncWrite + Unpin>(
     writer: &mut W,
     payload: &[u8],
@@ -18,6 +39,7 @@ pub async fn write_frame<W: AsyncWrite + Unpin>(
             max: MAX_FRAME_SIZE,
         })?;
 
+    record_frame_size(size);
     if size > MAX_FRAME_SIZE {
         return Err(IpcError::FrameTooLarge {
             size,
@@ -25,18 +47,38 @@ pub async fn write_frame<W: AsyncWrite + Unpin>(
         });
     }
 
-    writer.write_all(&size.to_be_bytes()).await?;
-    writer.write_all(payload).await?;
+    write_all_vectored(writer, &size.to_be_bytes(), payload).await?;
     writer.flush().await?;
     Ok(())
 }
 
+/// Writes `header` then `payload`, resuming vectored writes after partial progress.
+async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut header: &[u8],
+    mut payload: &[u8],
+) -> Result<(), io::Error> {
+    while !header.is_empty() || !payload.is_empty() {
+        let written = writer
+            .write_vectored(&[IoSlice::new(header), IoSlice::new(payload)])
+            .await?;
+        if written == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        let from_header = written.min(header.len());
+        header = &header[from_header..];
+        payload = &payload[written - from_header..];
+    }
+    Ok(())
+}
+
 /// Reads one length-prefixed frame from the async reader.
 pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>, IpcError> {
     let mut header = [0_u8; 4];
     reader.read_exact(&mut header).await?;
 
     let size = u32::from_be_bytes(header);
+    record_frame_size(size);
     if size > MAX_FRAME_SIZE {
         return Err(IpcError::FrameTooLarge {
             size,
@@ -1,12 +1,19 @@
-//! Async CBOR-over-UNIX-socket transport used by planter components.
+//! Async CBOR-over-UNIX-socket transport used by planter components, with optional JSON payloads.
 
 mod error;
 
 pub mod client;
 pub mod codec;
 pub mod framing;
+pub mod pty;
 pub mod server;
+#[cfg(feature = "tls")]
+pub mod tls;
 
-pub use client::PlanterClient;
+pub use client::{MultiplexedClient, PlanterClient};
+pub use codec::WireFormat;
 pub use error::IpcError;
+pub use pty::{PtyOptions, PtySession};
 pub use server::{RequestHandler, serve_unix};
+#[cfg(feature = "tls")]
+pub use tls::{client_config_from_pem, serve_tcp, serve_tcp_listener, server_config_from_pem};
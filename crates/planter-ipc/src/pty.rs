@@ -0,0 +1,400 @@
+//! Duplex handle over a daemon PTY session for embedders such as terminal UIs.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use planter_core::{
+    DEFAULT_MAX_PTY_INPUT_BYTES, ErrorCode, PlanterError, PtyAction, Request, Response, SessionId,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    task::JoinHandle,
+};
+
+use crate::{IpcError, MultiplexedClient};
+
+/// Largest output chunk requested per read.
+const READ_CHUNK_BYTES: u32 = 65536;
+/// How long each follow read waits for output before asking again.
+const FOLLOW_WAIT_MS: u64 = 200;
+
+/// Settings for opening a PTY session with [`crate::PlanterClient::open_pty`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtyOptions {
+    /// Shell binary path.
+    pub shell: String,
+    /// Shell argument vector.
+    pub args: Vec<String>,
+    /// Optional working directory.
+    pub cwd: Option<String>,
+    /// Environment overrides.
+    pub env: BTreeMap<String, String>,
+    /// Initial terminal columns.
+    pub cols: u16,
+    /// Initial terminal rows.
+    pub rows: u16,
+    /// When false, skip the rc-file `cd` jail and prompt lock.
+    pub jail_cwd: bool,
+    /// When true, tee output to disk so scrollback trimmed from memory stays readable.
+    pub persist_scrollback: bool,
+    /// When true, dropping the [`PtySession`] closes the session in the background.
+    pub close_on_drop: bool,
+}
+
+impl PtyOptions {
+    /// Options for `shell` in an 80x24 terminal with the cwd jail on and close-on-drop set.
+    pub fn new(shell: impl Into<String>) -> Self {
+        Self {
+            shell: shell.into(),
+            args: Vec::new(),
+            cwd: None,
+            env: BTreeMap::new(),
+            cols: 80,
+            rows: 24,
+            jail_cwd: true,
+            persist_scrollback: false,
+            close_on_drop: true,
+        }
+    }
+}
+
+/// Output fetch in progress: the offset to resume from and the bytes, or `None` at the end.
+type PendingRead = Pin<Box<dyn Future<Output = Result<(u64, Option<Vec<u8>>), IpcError>> + Send>>;
+
+/// Open PTY session exposing its output as [`AsyncRead`] and its input as [`AsyncWrite`].
+///
+/// Reads follow the output from its start, or from a tail for an attached session, and reach
+/// EOF once the shell has exited and all of its output was read. A session closed elsewhere
+/// fails reads with [`io::ErrorKind::NotFound`]. Writes are accepted as soon as they are sent;
+/// a rejected write fails the next write or flush. Dropping an opened handle closes the
+/// session unless [`PtyOptions::close_on_drop`] was cleared.
+pub struct PtySession {
+    /// Multiplexed connection carrying reads, writes, and control calls.
+    client: MultiplexedClient,
+    /// Daemon-assigned session identifier.
+    session_id: SessionId,
+    /// Shell process id when known.
+    pid: Option<u32>,
+    /// Offset of the next output byte to fetch.
+    offset: u64,
+    /// Buffered bytes to replay instead of starting at `offset`, for the first fetch only.
+    tail: Option<u32>,
+    /// Fetched output not yet handed to the reader.
+    buffered: Vec<u8>,
+    /// Output fetch being polled by `poll_read`.
+    reading: Option<PendingRead>,
+    /// True once the shell exited and all output was read.
+    output_done: bool,
+    /// Input write still awaiting its acknowledgment.
+    writing: Option<JoinHandle<Result<(), IpcError>>>,
+    /// Whether dropping the handle closes the session.
+    close_on_drop: bool,
+}
+
+impl PtySession {
+    /// Opens a session over `client` with `options`.
+    pub(crate) async fn open(
+        client: MultiplexedClient,
+        options: PtyOptions,
+    ) -> Result<Self, IpcError> {
+        let response = client
+            .call(Request::PtyOpen {
+                shell: options.shell,
+                args: options.args,
+                cwd: options.cwd,
+                env: options.env,
+                cols: options.cols,
+                rows: options.rows,
+                jail_cwd: options.jail_cwd,
+                persist_scrollback: options.persist_scrollback,
+            })
+            .await?;
+        match response {
+            Response::PtyOpened { session_id, pid } => Ok(Self {
+                client,
+                session_id,
+                pid,
+                offset: 0,
+                tail: None,
+                buffered: Vec::new(),
+                reading: None,
+                output_done: false,
+                writing: None,
+                close_on_drop: options.close_on_drop,
+            }),
+            other => Err(response_error("pty open", other)),
+        }
+    }
+
+    /// Attaches to an existing session over `client`, replaying its last `tail` buffered bytes
+    /// or, without one, all of its output.
+    ///
+    /// The handle is one viewer among others: dropping it leaves the session open.
+    pub(crate) fn attach(
+        client: MultiplexedClient,
+        session_id: SessionId,
+        tail: Option<u32>,
+    ) -> Self {
+        Self {
+            client,
+            session_id,
+            pid: None,
+            offset: 0,
+            tail,
+            buffered: Vec::new(),
+            reading: None,
+            output_done: false,
+            writing: None,
+            close_on_drop: false,
+        }
+    }
+
+    /// Returns the daemon-assigned session identifier.
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns the shell process id when the daemon reported one.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Resizes the session's terminal.
+    pub async fn resize(&self, cols: u16, rows: u16) -> Result<(), IpcError> {
+        let response = self
+            .client
+            .call(Request::PtyResize {
+                session_id: self.session_id,
+                cols,
+                rows,
+            })
+            .await?;
+        match response {
+            Response::PtyAck {
+                action: PtyAction::Resize,
+                ..
+            } => Ok(()),
+            other => Err(response_error("pty resize", other)),
+        }
+    }
+
+    /// Waits for pending input to be acknowledged, then closes the session.
+    pub async fn close(mut self, force: bool) -> Result<(), IpcError> {
+        self.close_on_drop = false;
+        if let Some(writing) = self.writing.take() {
+            join_write(writing.await)?;
+        }
+        let response = self
+            .client
+            .call(Request::PtyClose {
+                session_id: self.session_id,
+                force,
+            })
+            .await?;
+        match response {
+            Response::PtyAck {
+                action: PtyAction::Closed,
+                ..
+            } => Ok(()),
+            other => Err(response_error("pty close", other)),
+        }
+    }
+
+    /// Waits for the in-flight input write, if any, and reports how it went.
+    fn poll_pending_write(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(writing) = self.writing.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let joined = ready!(Pin::new(writing).poll(cx));
+        self.writing = None;
+        Poll::Ready(join_write(joined).map_err(into_io_error))
+    }
+}
+
+impl AsyncRead for PtySession {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if !this.buffered.is_empty() {
+                let len = buf.remaining().min(this.buffered.len());
+                buf.put_slice(&this.buffered[..len]);
+                this.buffered.drain(..len);
+                return Poll::Ready(Ok(()));
+            }
+            if this.output_done {
+                return Poll::Ready(Ok(()));
+            }
+
+            let reading = this.reading.get_or_insert_with(|| {
+                next_output(
+                    this.client.clone(),
+                    this.session_id,
+                    this.offset,
+                    this.tail.take(),
+                )
+            });
+            let fetched = ready!(reading.as_mut().poll(cx));
+            this.reading = None;
+            match fetched {
+                Ok((offset, Some(data))) => {
+                    this.offset = offset;
+                    this.buffered = data;
+                }
+                Ok((offset, None)) => {
+                    this.offset = offset;
+                    this.output_done = true;
+                }
+                Err(err) => return Poll::Ready(Err(into_io_error(err))),
+            }
+        }
+    }
+}
+
+impl AsyncWrite for PtySession {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending_write(cx))?;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // Sending on its own task keeps the input moving even if the caller stops polling.
+        let client = this.client.clone();
+        let session_id = this.session_id;
+        let data = buf[..buf.len().min(DEFAULT_MAX_PTY_INPUT_BYTES)].to_vec();
+        let len = data.len();
+        this.writing = Some(tokio::spawn(async move {
+            match client.call(Request::PtyInput { session_id, data }).await? {
+                Response::PtyAck { .. } => Ok(()),
+                other => Err(response_error("pty input", other)),
+            }
+        }));
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+}
+
+impl Drop for PtySession {
+    fn drop(&mut self) {
+        if !self.close_on_drop {
+            return;
+        }
+        // Without a runtime there is nothing to send the close on; the daemon's reaper remains.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let client = self.client.clone();
+        let session_id = self.session_id;
+        runtime.spawn(async move {
+            if let Err(err) = client
+                .call(Request::PtyClose {
+                    session_id,
+                    force: false,
+                })
+                .await
+            {
+                tracing::debug!(session_id = session_id.0, error = %err, "failed to close dropped pty session");
+            }
+        });
+    }
+}
+
+/// Follows the session's output from `offset`, or from the last `tail` bytes, until bytes
+/// arrive or the shell has exited.
+fn next_output(
+    client: MultiplexedClient,
+    session_id: SessionId,
+    mut offset: u64,
+    mut tail: Option<u32>,
+) -> PendingRead {
+    Box::pin(async move {
+        loop {
+            let response = client
+                .call(Request::PtyRead {
+                    session_id,
+                    offset,
+                    max_bytes: READ_CHUNK_BYTES,
+                    follow: true,
+                    wait_ms: FOLLOW_WAIT_MS,
+                    from_end: tail.take(),
+                    utf8_boundary: false,
+                    strip_ansi: false,
+                })
+                .await?;
+            let Response::PtyChunk {
+                offset: chunk_offset,
+                data,
+                eof,
+                complete,
+                next_offset,
+                ..
+            } = response
+            else {
+                return Err(response_error("pty read", response));
+            };
+            offset = next_offset.unwrap_or(chunk_offset + data.len() as u64);
+            if !data.is_empty() {
+                return Ok((offset, Some(data)));
+            }
+            if complete && eof {
+                return Ok((offset, None));
+            }
+        }
+    })
+}
+
+/// Unwraps the result of a spawned input write.
+fn join_write(
+    joined: Result<Result<(), IpcError>, tokio::task::JoinError>,
+) -> Result<(), IpcError> {
+    joined.map_err(|err| IpcError::Io(io::Error::other(err)))?
+}
+
+/// Converts a daemon error or mismatched response into an [`IpcError`].
+fn response_error(request: &'static str, response: Response) -> IpcError {
+    match response {
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => IpcError::Daemon(PlanterError {
+            code,
+            message,
+            detail,
+        }),
+        _ => IpcError::UnexpectedResponse(request),
+    }
+}
+
+/// Maps an IPC failure onto the `io::Error` the stream traits return.
+fn into_io_error(err: IpcError) -> io::Error {
+    match err {
+        IpcError::Io(err) => err,
+        IpcError::Timeout => io::Error::new(io::ErrorKind::TimedOut, err),
+        IpcError::Daemon(PlanterError {
+            code: ErrorCode::NotFound,
+            ..
+        }) => io::Error::new(io::ErrorKind::NotFound, err),
+        other => io::Error::other(other),
+    }
+}
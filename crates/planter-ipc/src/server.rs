@@ -1,21 +1,61 @@
-use std::{io::ErrorKind, path::Path, sync::Arc};
+use std::{
+    io::{self, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use planter_core::{ErrorCode, ReqId, Request, RequestEnvelope, Response, ResponseEnvelope};
 use serde::Deserialize;
-use tokio::net::{UnixListener, UnixStream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::UnixListener,
+    sync::{Semaphore, mpsc},
+};
 
 use crate::{
     IpcError,
-    codec::{decode, encode},
-    framing::{read_frame, write_frame},
+    codec::{WireFormat, log_oversized_frame},
+    framing::read_frame,
 };
 
+/// Maximum requests handled concurrently on one connection before reads pause.
+const MAX_IN_FLIGHT_PER_CONNECTION: usize = 32;
+/// Intermediate frames a streaming handler may queue before its sends wait.
+const MAX_QUEUED_FRAMES: usize = 8;
+
+/// A response waiting for the writer, with the wire format its request arrived in.
+type Outgoing = (WireFormat, ResponseEnvelope<Response>);
+
 /// Async request handler used by the IPC server loop.
 #[async_trait]
 pub trait RequestHandler: Send + Sync + 'static {
     /// Handles one decoded request and returns a response payload.
     async fn handle(&self, req: Request) -> Response;
+
+    /// Handles one request whose blocking waits should end by `deadline`, when set.
+    ///
+    /// The default ignores the deadline; handlers with long-polling requests override it.
+    async fn handle_with_deadline(&self, req: Request, deadline: Option<Instant>) -> Response {
+        let _ = deadline;
+        self.handle(req).await
+    }
+
+    /// Handles one request that may send intermediate frames through `frames` before its
+    /// final response.
+    ///
+    /// Each frame goes out with `more` set; a failed send means the client has gone away. The
+    /// default sends none.
+    async fn handle_streaming(
+        &self,
+        req: Request,
+        deadline: Option<Instant>,
+        frames: mpsc::Sender<Response>,
+    ) -> Response {
+        let _ = frames;
+        self.handle_with_deadline(req, deadline).await
+    }
 }
 
 /// Serves the planter IPC protocol over a UNIX domain socket.
@@ -35,12 +75,27 @@ pub async fn serve_unix(path: &Path, handler: Arc<dyn RequestHandler>) -> Result
 }
 
 /// Handles request/response framing for a single accepted connection.
-async fn handle_connection(
-    mut stream: UnixStream,
+///
+/// Requests are handled concurrently and responses are written as they complete,
+/// so clients may pipeline several calls and match responses by `req_id`. Each
+/// response is encoded in the wire format its request arrived in, and a request's
+/// `deadline_ms` is measured from when its frame was decoded.
+pub(crate) async fn handle_connection<S: AsyncRead + AsyncWrite + Send + 'static>(
+    stream: S,
     handler: Arc<dyn RequestHandler>,
 ) -> Result<(), IpcError> {
-    loop {
-        let frame = match read_frame(&mut stream).await {
+    let (mut reader, writer) = tokio::io::split(stream);
+    let (responses, outgoing) = mpsc::channel(MAX_IN_FLIGHT_PER_CONNECTION);
+    let mut writer_task = tokio::spawn(write_responses(writer, outgoing));
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT_PER_CONNECTION));
+
+    let read_result = loop {
+        let frame = tokio::select! {
+            frame = read_frame(&mut reader) => frame,
+            // The writer only stops early on failure; drop the connection like a failed write.
+            result = &mut writer_task => return join_writer(result),
+        };
+        let frame = match frame {
             Ok(frame) => frame,
             Err(IpcError::Io(err))
                 if matches!(
@@ -48,23 +103,46 @@ async fn handle_connection(
                     ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset | ErrorKind::BrokenPipe
                 ) =>
             {
-                return Ok(());
+                break Ok(());
             }
-            Err(err) => return Err(err),
+            Err(err) => break Err(err),
         };
 
-        match decode::<RequestEnvelope<Request>>(&frame) {
+        let format = WireFormat::detect(&frame);
+        match format.decode::<RequestEnvelope<Request>>(&frame) {
             Ok(req) => {
-                let response = handler.handle(req.body).await;
-                let envelope = ResponseEnvelope {
-                    req_id: req.req_id,
-                    body: response,
+                let deadline = req
+                    .deadline_ms
+                    .map(|ms| Instant::now() + Duration::from_millis(ms));
+                let Ok(permit) = Arc::clone(&in_flight).acquire_owned().await else {
+                    break Ok(());
                 };
-                let payload = encode(&envelope)?;
-                write_frame(&mut stream, &payload).await?;
+                let handler = Arc::clone(&handler);
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    let (frames, mut queued) = mpsc::channel(MAX_QUEUED_FRAMES);
+                    let handling = handler.handle_streaming(req.body, deadline, frames);
+                    tokio::pin!(handling);
+                    let body = loop {
+                        tokio::select! {
+                            body = &mut handling => break body,
+                            Some(frame) = queued.recv() => {
+                                if !send_response(format, req.req_id, frame, true, &responses).await {
+                                    // Let the handler notice the closed stream and finish on its own.
+                                    queued.close();
+                                }
+                            }
+                        }
+                    };
+                    while let Ok(frame) = queued.try_recv() {
+                        send_response(format, req.req_id, frame, true, &responses).await;
+                    }
+                    send_response(format, req.req_id, body, false, &responses).await;
+                    drop(permit);
+                });
             }
             Err(err) => {
-                if let Some(req_id) = extract_req_id(&frame) {
+                if let Some(req_id) = extract_req_id(format, &frame) {
                     let envelope = ResponseEnvelope {
                         req_id,
                         body: Response::Error {
@@ -72,15 +150,67 @@ async fn handle_connection(
                             message: "failed to decode request envelope".to_string(),
                             detail: Some(err.to_string()),
                         },
+                        more: false,
                     };
-                    let payload = encode(&envelope)?;
-                    let _ = write_frame(&mut stream, &payload).await;
+                    let _ = responses.send((format, envelope)).await;
                 }
 
-                return Ok(());
+                break Ok(());
+            }
+        }
+    };
+
+    // In-flight handlers hold sender clones, so the writer drains their responses first.
+    drop(responses);
+    let write_result = join_writer(writer_task.await);
+    read_result.and(write_result)
+}
+
+/// Queues one response frame for the writer, returning false once it is gone.
+async fn send_response(
+    format: WireFormat,
+    req_id: ReqId,
+    body: Response,
+    more: bool,
+    responses: &mpsc::Sender<Outgoing>,
+) -> bool {
+    let envelope = ResponseEnvelope { req_id, body, more };
+    responses.send((format, envelope)).await.is_ok()
+}
+
+/// Encodes and writes responses in completion order until every sender is dropped.
+///
+/// Each response is serialized once, straight into a frame buffer reused for the connection.
+async fn write_responses<W: AsyncWrite + Unpin>(
+    mut writer: W,
+    mut outgoing: mpsc::Receiver<Outgoing>,
+) -> Result<(), IpcError> {
+    let mut frame = Vec::new();
+    while let Some((format, envelope)) = outgoing.recv().await {
+        match format
+            .encode_into_framed(&mut writer, &mut frame, &envelope)
+            .await
+        {
+            Ok(()) => {}
+            Err(IpcError::Encode(err)) => {
+                tracing::warn!(error = %err, "failed to encode response");
+            }
+            Err(err) => {
+                if matches!(err, IpcError::FrameTooLarge { .. }) {
+                    log_oversized_frame("response", &frame[4..]);
+                }
+                return Err(err);
             }
         }
     }
+    Ok(())
+}
+
+/// Flattens the response writer task outcome.
+fn join_writer(
+    result: Result<Result<(), IpcError>, tokio::task::JoinError>,
+) -> Result<(), IpcError> {
+    result.map_err(|err| IpcError::Io(io::Error::other(err)))?
 }
 
 /// Minimal decode target used to recover `req_id` from malformed requests.
@@ -91,8 +221,9 @@ struct ReqIdOnly {
 }
 
 /// Extracts a request id from a partially valid request envelope frame.
-fn extract_req_id(frame: &[u8]) -> Option<ReqId> {
-    decode::<ReqIdOnly>(frame)
+fn extract_req_id(format: WireFormat, frame: &[u8]) -> Option<ReqId> {
+    format
+        .decode::<ReqIdOnly>(frame)
         .ok()
         .map(|decoded| decoded.req_id)
 }
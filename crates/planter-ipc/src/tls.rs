@@ -0,0 +1,143 @@
+//! Optional TLS-over-TCP transport with mutual certificate authentication.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{
+    TlsAcceptor, TlsConnector,
+    client::TlsStream,
+    rustls::{
+        ClientConfig, RootCertStore, ServerConfig,
+        crypto::{CryptoProvider, ring},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        server::WebPkiClientVerifier,
+    },
+};
+
+use crate::{IpcError, RequestHandler, server::handle_connection};
+
+/// Builds a server config that presents `cert_chain_pem` and requires client
+/// certificates issued by `client_ca_pem`.
+pub fn server_config_from_pem(
+    cert_chain_pem: &[u8],
+    key_pem: &[u8],
+    client_ca_pem: &[u8],
+) -> Result<Arc<ServerConfig>, IpcError> {
+    let provider = crypto_provider();
+    let client_roots = Arc::new(root_store(client_ca_pem)?);
+    let verifier = WebPkiClientVerifier::builder_with_provider(client_roots, Arc::clone(&provider))
+        .build()
+        .map_err(tls_error)?;
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(parse_certs(cert_chain_pem)?, parse_key(key_pem)?)
+        .map_err(tls_error)?;
+    Ok(Arc::new(config))
+}
+
+/// Builds a client config that trusts `server_ca_pem` and authenticates with
+/// `cert_chain_pem`/`key_pem`.
+pub fn client_config_from_pem(
+    server_ca_pem: &[u8],
+    cert_chain_pem: &[u8],
+    key_pem: &[u8],
+) -> Result<Arc<ClientConfig>, IpcError> {
+    let config = ClientConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .with_root_certificates(root_store(server_ca_pem)?)
+        .with_client_auth_cert(parse_certs(cert_chain_pem)?, parse_key(key_pem)?)
+        .map_err(tls_error)?;
+    Ok(Arc::new(config))
+}
+
+/// Serves the planter IPC protocol over TLS on a TCP address.
+pub async fn serve_tcp(
+    addr: SocketAddr,
+    config: Arc<ServerConfig>,
+    handler: Arc<dyn RequestHandler>,
+) -> Result<(), IpcError> {
+    let listener = TcpListener::bind(addr).await?;
+    serve_tcp_listener(listener, config, handler).await
+}
+
+/// Serves the planter IPC protocol over TLS on an already-bound TCP listener.
+pub async fn serve_tcp_listener(
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    handler: Arc<dyn RequestHandler>,
+) -> Result<(), IpcError> {
+    let acceptor = TlsAcceptor::from(config);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        let handler = Arc::clone(&handler);
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::debug!(error = %err, %peer, "tls handshake failed");
+                    return;
+                }
+            };
+            if let Err(err) = handle_connection(stream, handler).await {
+                tracing::debug!(error = %err, %peer, "connection handler exited with error");
+            }
+        });
+    }
+}
+
+/// Opens a TCP connection and completes the client-side TLS handshake.
+pub(crate) async fn connect_stream(
+    addr: SocketAddr,
+    server_name: &str,
+    config: Arc<ClientConfig>,
+) -> Result<TlsStream<TcpStream>, IpcError> {
+    let server_name = ServerName::try_from(server_name.to_string()).map_err(tls_error)?;
+    let stream = TcpStream::connect(addr).await?;
+    TlsConnector::from(config)
+        .connect(server_name, stream)
+        .await
+        .map_err(tls_error)
+}
+
+/// Returns the crypto provider used for all planter TLS configs.
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+/// Parses a PEM certificate bundle into a root store.
+fn root_store(pem: &[u8]) -> Result<RootCertStore, IpcError> {
+    let mut roots = RootCertStore::empty();
+    for cert in parse_certs(pem)? {
+        roots.add(cert).map_err(tls_error)?;
+    }
+    Ok(roots)
+}
+
+/// Parses all certificates from a PEM bundle.
+fn parse_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, IpcError> {
+    let certs = rustls_pemfile::certs(&mut &pem[..])
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(tls_error)?;
+    if certs.is_empty() {
+        return Err(IpcError::Tls("no certificates found in pem".to_string()));
+    }
+    Ok(certs)
+}
+
+/// Parses the first private key from a PEM bundle.
+fn parse_key(pem: &[u8]) -> Result<PrivateKeyDer<'static>, IpcError> {
+    rustls_pemfile::private_key(&mut &pem[..])
+        .map_err(tls_error)?
+        .ok_or_else(|| IpcError::Tls("no private key found in pem".to_string()))
+}
+
+/// Converts TLS library errors into IPC errors.
+fn tls_error(err: impl std::fmt::Display) -> IpcError {
+    IpcError::Tls(err.to_string())
+}
@@ -1,10 +1,26 @@
-use std::sync::Arc;
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
 
 use async_trait::async_trait;
-use planter_core::{ErrorCode, PROTOCOL_VERSION, Request, Response};
-use planter_ipc::{PlanterClient, RequestHandler, serve_unix};
+use planter_core::{
+    CellId, ErrorCode, ExitStatus, HealthStatus, JobId, PROTOCOL_VERSION, PtyAction, Request,
+    Response, SessionId,
+};
+use planter_ipc::{
+    IpcError, PlanterClient, PtyOptions, RequestHandler, WireFormat,
+    framing::{MAX_FRAME_SIZE, largest_frame_size},
+    serve_unix,
+};
 use tempfile::tempdir;
-use tokio::time::{Duration, sleep};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixListener,
+    sync::mpsc,
+    time::{Duration, Instant, sleep, timeout},
+};
+use tracing_subscriber::fmt::MakeWriter;
 
 /// Minimal request handler used for integration-level client/server roundtrip tests.
 struct TestHandler;
@@ -18,20 +34,46 @@ impl RequestHandler for TestHandler {
                 daemon: "0.1.0".to_string(),
                 protocol: PROTOCOL_VERSION,
             },
-            Request::Health {} => Response::Health {
-                status: "ok".to_string(),
+            Request::Health { .. } => Response::Health {
+                status: HealthStatus::Ok,
+                detail: None,
             },
-            Request::CellCreate { .. }
+            Request::SupportedSignals {}
+            | Request::Diagnostics {}
+            | Request::CellCreate { .. }
+            | Request::CellRename { .. }
             | Request::JobRun { .. }
             | Request::JobStatus { .. }
+            | Request::JobWait { .. }
+            | Request::JobUsageSubscribe { .. }
             | Request::JobKill { .. }
             | Request::CellRemove { .. }
+            | Request::CellDrain { .. }
+            | Request::WorkerRestart { .. }
+            | Request::SandboxCheck { .. }
+            | Request::CellPut { .. }
+            | Request::CellPutFinalize { .. }
+            | Request::CellGet { .. }
+            | Request::CellListFiles { .. }
             | Request::LogsRead { .. }
+            | Request::LogsPeek { .. }
+            | Request::LogsTruncate { .. }
+            | Request::LogsSync { .. }
+            | Request::LogsInfo { .. }
+            | Request::CancelFollow { .. }
             | Request::PtyOpen { .. }
             | Request::PtyInput { .. }
             | Request::PtyRead { .. }
+            | Request::PtyPeek { .. }
             | Request::PtyResize { .. }
-            | Request::PtyClose { .. } => Response::Error {
+            | Request::PtyClose { .. }
+            | Request::PtyReap { .. }
+            | Request::PtyCancelFollow { .. }
+            | Request::PtySandboxProfile { .. }
+            | Request::StateSnapshot {}
+            | Request::StateRestore { .. }
+            | Request::Events { .. }
+            | Request::Batch { .. } => Response::Error {
                 code: ErrorCode::InvalidRequest,
                 message: "unsupported in test".to_string(),
                 detail: None,
@@ -50,20 +92,10 @@ async fn client_server_version_and_health_roundtrip() {
     let server_socket = socket_path.clone();
     let server = tokio::spawn(async move { serve_unix(&server_socket, handler).await });
 
-    let mut client = None;
-    for _ in 0..200 {
-        match PlanterClient::connect(&socket_path).await {
-            Ok(connected) => {
-                client = Some(connected);
-                break;
-            }
-            Err(planter_ipc::IpcError::Io(_)) => {
-                sleep(Duration::from_millis(10)).await;
-            }
-            Err(err) => panic!("client should connect: {err}"),
-        }
-    }
-    let mut client = client.expect("client should connect");
+    let mut client =
+        PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+            .await
+            .expect("client should connect");
 
     let version = client
         .call(Request::Version {})
@@ -77,15 +109,673 @@ async fn client_server_version_and_health_roundtrip() {
     }
 
     let health = client
-        .call(Request::Health {})
+        .call(Request::Health { deep: false })
         .await
         .expect("health call should succeed");
     match health {
-        Response::Health { status } => {
-            assert_eq!(status, "ok");
+        Response::Health { status, .. } => {
+            assert_eq!(status, HealthStatus::Ok);
         }
         other => panic!("unexpected response: {other:?}"),
     }
 
     server.abort();
 }
+
+#[tokio::test]
+/// Verifies JSON and CBOR clients are each answered in their own format on one server.
+async fn json_and_cbor_clients_share_server() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("planterd.sock");
+
+    let handler = Arc::new(TestHandler);
+    let server_socket = socket_path.clone();
+    let server = tokio::spawn(async move { serve_unix(&server_socket, handler).await });
+
+    let mut json = PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+        .await
+        .expect("json client should connect")
+        .with_wire_format(WireFormat::Json);
+    let cbor = PlanterClient::connect(&socket_path)
+        .await
+        .expect("cbor client should connect")
+        .into_multiplexed();
+
+    for _ in 0..2 {
+        match json.call(Request::Version {}).await.expect("json call") {
+            Response::Version { protocol, .. } => assert_eq!(protocol, PROTOCOL_VERSION),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match cbor
+            .call(Request::Health { deep: false })
+            .await
+            .expect("cbor call")
+        {
+            Response::Health { status, .. } => assert_eq!(status, HealthStatus::Ok),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    let json = json.into_multiplexed();
+    match json
+        .call(Request::Health { deep: false })
+        .await
+        .expect("multiplexed json call")
+    {
+        Response::Health { status, .. } => assert_eq!(status, HealthStatus::Ok),
+        other => panic!("unexpected response: {other:?}"),
+    }
+
+    server.abort();
+}
+
+/// Shared in-memory sink for captured tracing output.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("log buffer").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLogs {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[tokio::test]
+/// Verifies an oversized request logs its variant and largest field and updates the metric.
+async fn oversized_request_logs_variant_breakdown() {
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("planterd.sock");
+    let _listener = UnixListener::bind(&socket_path).expect("bind listener");
+    let mut client = PlanterClient::connect(&socket_path)
+        .await
+        .expect("client should connect");
+
+    let err = client
+        .call(Request::CellPut {
+            cell_id: CellId("cell-1".to_string()),
+            relative_path: "blob.bin".to_string(),
+            offset: 0,
+            data: vec![0_u8; MAX_FRAME_SIZE as usize + 1],
+            staged: false,
+        })
+        .await
+        .expect_err("oversized request must fail");
+
+    assert!(matches!(err, IpcError::FrameTooLarge { .. }));
+    assert!(largest_frame_size() > MAX_FRAME_SIZE);
+    let output = String::from_utf8(logs.0.lock().expect("log buffer").clone()).expect("utf8 logs");
+    assert!(output.contains("frame exceeds maximum size"), "{output}");
+    assert!(output.contains("variant=cell_put"), "{output}");
+    assert!(output.contains("fields=data="), "{output}");
+}
+
+#[tokio::test]
+/// Verifies retrying connect succeeds once a late-starting server binds its socket.
+async fn connect_with_retry_waits_for_late_server() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("planterd.sock");
+
+    let server_socket = socket_path.clone();
+    let server = tokio::spawn(async move {
+        sleep(Duration::from_millis(100)).await;
+        serve_unix(&server_socket, Arc::new(TestHandler)).await
+    });
+
+    assert!(PlanterClient::connect(&socket_path).await.is_err());
+    let mut client = PlanterClient::connect_with_retry(&socket_path, 20, Duration::from_millis(20))
+        .await
+        .expect("client should connect after retries");
+    let health = client
+        .call(Request::Health { deep: false })
+        .await
+        .expect("health call should succeed");
+    assert!(matches!(health, Response::Health { .. }));
+
+    server.abort();
+}
+
+#[tokio::test]
+/// Verifies retrying connect gives up with the last I/O error after its attempts.
+async fn connect_with_retry_gives_up_after_attempts() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("missing.sock");
+
+    let err = PlanterClient::connect_with_retry(&socket_path, 3, Duration::from_millis(1))
+        .await
+        .err()
+        .expect("connect must fail");
+
+    assert!(matches!(err, IpcError::Io(_)));
+}
+
+#[tokio::test]
+/// Verifies an oversized initial backoff is capped like later ones.
+async fn connect_with_retry_caps_first_backoff() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("missing.sock");
+
+    let started = std::time::Instant::now();
+    let err = PlanterClient::connect_with_retry(&socket_path, 2, Duration::from_secs(60))
+        .await
+        .err()
+        .expect("connect must fail");
+
+    assert!(matches!(err, IpcError::Io(_)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+/// Verifies connect plus several calls share one absolute deadline.
+async fn call_deadline_bounds_composite_operation() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("silent.sock");
+    let listener = UnixListener::bind(&socket_path).expect("bind listener");
+    let server = tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let started = std::time::Instant::now();
+    let deadline = started + Duration::from_millis(200);
+    let mut client = PlanterClient::connect_deadline(&socket_path, deadline)
+        .await
+        .expect("connect should succeed before the deadline");
+    let err = client
+        .call_deadline(Request::Health { deep: false }, deadline)
+        .await
+        .expect_err("silent server must time out");
+    assert!(matches!(err, IpcError::Timeout));
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(600), "{elapsed:?}");
+
+    let err = client
+        .call_deadline(Request::Health { deep: false }, deadline)
+        .await
+        .expect_err("expired deadline must fail");
+    assert!(matches!(err, IpcError::Timeout));
+    assert!(started.elapsed() < Duration::from_millis(600));
+
+    server.abort();
+}
+
+/// Handler reporting the time left before the envelope deadline it was handed.
+struct DeadlineHandler;
+
+#[async_trait]
+impl RequestHandler for DeadlineHandler {
+    /// Answers without a deadline.
+    async fn handle(&self, req: Request) -> Response {
+        self.handle_with_deadline(req, None).await
+    }
+
+    /// Echoes the remaining milliseconds as the health detail.
+    async fn handle_with_deadline(
+        &self,
+        _req: Request,
+        deadline: Option<std::time::Instant>,
+    ) -> Response {
+        Response::Health {
+            status: HealthStatus::Ok,
+            detail: deadline.map(|deadline| {
+                deadline
+                    .saturating_duration_since(std::time::Instant::now())
+                    .as_millis()
+                    .to_string()
+            }),
+        }
+    }
+}
+
+#[tokio::test]
+/// Verifies `call_deadline` forwards the time left to the server while `call` sends none.
+async fn call_deadline_forwards_deadline_to_handler() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("deadline.sock");
+
+    let handler = Arc::new(DeadlineHandler);
+    let server_socket = socket_path.clone();
+    let server = tokio::spawn(async move { serve_unix(&server_socket, handler).await });
+
+    let mut client =
+        PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+            .await
+            .expect("client should connect");
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    match client
+        .call_deadline(Request::Health { deep: false }, deadline)
+        .await
+        .expect("call should succeed")
+    {
+        Response::Health {
+            detail: Some(remaining),
+            ..
+        } => {
+            let remaining: u64 = remaining.parse().expect("remaining millis");
+            assert!(remaining > 0 && remaining <= 5_000, "{remaining}");
+        }
+        other => panic!("unexpected response: {other:?}"),
+    }
+
+    match client
+        .call(Request::Health { deep: false })
+        .await
+        .expect("call should succeed")
+    {
+        Response::Health { detail, .. } => assert_eq!(detail, None),
+        other => panic!("unexpected response: {other:?}"),
+    }
+
+    server.abort();
+}
+
+/// Handler streaming three usage samples before ending each subscription.
+struct StreamingHandler;
+
+#[async_trait]
+impl RequestHandler for StreamingHandler {
+    /// Answers without streaming.
+    async fn handle(&self, req: Request) -> Response {
+        let (frames, _) = mpsc::channel(1);
+        self.handle_streaming(req, None, frames).await
+    }
+
+    /// Sends one sample per tick, then the completion marker.
+    async fn handle_streaming(
+        &self,
+        _req: Request,
+        _deadline: Option<std::time::Instant>,
+        frames: mpsc::Sender<Response>,
+    ) -> Response {
+        let job_id = JobId("job-usage".to_string());
+        for timestamp_ms in 1..=3 {
+            let _ = frames
+                .send(Response::UsageSample {
+                    job_id: job_id.clone(),
+                    rss_bytes: Some(4096),
+                    cpu_nanos: None,
+                    timestamp_ms,
+                })
+                .await;
+        }
+        Response::JobUsageEnded {
+            job_id,
+            status: ExitStatus::Exited { code: Some(0) },
+        }
+    }
+}
+
+#[tokio::test]
+/// Verifies `subscribe` sees streamed frames in order before the final one, which `call` skips to.
+async fn subscribe_receives_streamed_frames_before_final_response() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("stream.sock");
+
+    let handler = Arc::new(StreamingHandler);
+    let server_socket = socket_path.clone();
+    let server = tokio::spawn(async move { serve_unix(&server_socket, handler).await });
+
+    let mut client =
+        PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+            .await
+            .expect("client should connect");
+    let subscribe = || Request::JobUsageSubscribe {
+        job_id: JobId("job-usage".to_string()),
+        interval_ms: 10,
+    };
+
+    let mut timestamps = Vec::new();
+    let last = client
+        .subscribe(subscribe(), |frame| match frame {
+            Response::UsageSample { timestamp_ms, .. } => timestamps.push(timestamp_ms),
+            other => panic!("unexpected frame: {other:?}"),
+        })
+        .await
+        .expect("subscribe should succeed");
+    assert_eq!(timestamps, vec![1, 2, 3]);
+    assert!(matches!(last, Response::JobUsageEnded { .. }), "{last:?}");
+
+    let last = client.call(subscribe()).await.expect("call should succeed");
+    assert!(matches!(last, Response::JobUsageEnded { .. }), "{last:?}");
+
+    let multiplexed = client.into_multiplexed();
+    let last = multiplexed
+        .call(subscribe())
+        .await
+        .expect("multiplexed call should succeed");
+    assert!(matches!(last, Response::JobUsageEnded { .. }), "{last:?}");
+
+    server.abort();
+}
+
+/// Handler mimicking a PTY session whose reads block until output arrives.
+struct AttachHandler;
+
+#[async_trait]
+impl RequestHandler for AttachHandler {
+    /// Holds `PtyRead` briefly and acknowledges input and resize immediately.
+    async fn handle(&self, req: Request) -> Response {
+        match req {
+            Request::PtyRead { session_id, .. } => {
+                sleep(Duration::from_millis(300)).await;
+                Response::PtyChunk {
+                    session_id,
+                    offset: 0,
+                    data: b"prompt$ ".to_vec(),
+                    eof: false,
+                    complete: false,
+                    exit_code: None,
+                    next_offset: None,
+                }
+            }
+            Request::PtyInput { session_id, .. } => Response::PtyAck {
+                session_id,
+                action: PtyAction::Input,
+            },
+            Request::PtyResize { session_id, .. } => Response::PtyAck {
+                session_id,
+                action: PtyAction::Resize,
+            },
+            other => TestHandler.handle(other).await,
+        }
+    }
+}
+
+#[tokio::test]
+/// Verifies input and resize complete on one connection while a read is still pending.
+async fn multiplexed_calls_do_not_wait_behind_pending_read() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("attach.sock");
+    let server_socket = socket_path.clone();
+    let server =
+        tokio::spawn(async move { serve_unix(&server_socket, Arc::new(AttachHandler)).await });
+
+    let client = PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+        .await
+        .expect("client should connect")
+        .into_multiplexed();
+    let session_id = SessionId(7);
+
+    let reader = client.clone();
+    let read = tokio::spawn(async move {
+        reader
+            .call(Request::PtyRead {
+                session_id,
+                offset: 0,
+                max_bytes: 1024,
+                follow: true,
+                wait_ms: 1000,
+                from_end: None,
+                utf8_boundary: false,
+                strip_ansi: false,
+            })
+            .await
+    });
+    sleep(Duration::from_millis(20)).await;
+
+    let input = client
+        .call(Request::PtyInput {
+            session_id,
+            data: b"ls\n".to_vec(),
+        })
+        .await
+        .expect("input should complete");
+    assert!(matches!(
+        input,
+        Response::PtyAck {
+            action: PtyAction::Input,
+            ..
+        }
+    ));
+    let resize = client
+        .call(Request::PtyResize {
+            session_id,
+            cols: 120,
+            rows: 40,
+        })
+        .await
+        .expect("resize should complete");
+    assert!(matches!(
+        resize,
+        Response::PtyAck {
+            action: PtyAction::Resize,
+            ..
+        }
+    ));
+    assert!(
+        !read.is_finished(),
+        "read should still be waiting for output"
+    );
+
+    let chunk = read
+        .await
+        .expect("read task should join")
+        .expect("read should complete");
+    match chunk {
+        Response::PtyChunk { data, .. } => assert_eq!(data, b"prompt$ "),
+        other => panic!("unexpected read response: {other:?}"),
+    }
+
+    server.abort();
+}
+
+/// Handler mimicking a shell that echoes its input back as PTY output.
+#[derive(Default)]
+struct EchoShellHandler {
+    /// Output the fake shell has produced so far.
+    output: Mutex<Vec<u8>>,
+    /// Terminal sizes requested through resizes.
+    resizes: Mutex<Vec<(u16, u16)>>,
+    /// Closed sessions with whether each close was forced.
+    closed: Mutex<Vec<(SessionId, bool)>>,
+}
+
+#[async_trait]
+impl RequestHandler for EchoShellHandler {
+    /// Opens one session with a prompt, echoes input, and records resizes and closes.
+    async fn handle(&self, req: Request) -> Response {
+        match req {
+            Request::PtyOpen { .. } => {
+                self.output.lock().expect("output lock").extend(b"$ ");
+                Response::PtyOpened {
+                    session_id: SessionId(3),
+                    pid: Some(42),
+                }
+            }
+            Request::PtyInput { session_id, data } => {
+                self.output.lock().expect("output lock").extend(data);
+                Response::PtyAck {
+                    session_id,
+                    action: PtyAction::Input,
+                }
+            }
+            Request::PtyRead {
+                session_id,
+                offset,
+                wait_ms,
+                from_end,
+                ..
+            } => {
+                let deadline = Instant::now() + Duration::from_millis(wait_ms);
+                let (offset, data) = loop {
+                    let output = self.output.lock().expect("output lock").clone();
+                    let start = match from_end {
+                        Some(tail) => output.len().saturating_sub(tail as usize),
+                        None => usize::try_from(offset)
+                            .expect("offset fits")
+                            .min(output.len()),
+                    };
+                    if start < output.len() || Instant::now() >= deadline {
+                        break (start as u64, output[start..].to_vec());
+                    }
+                    sleep(Duration::from_millis(10)).await;
+                };
+                Response::PtyChunk {
+                    session_id,
+                    offset,
+                    eof: data.is_empty(),
+                    data,
+                    complete: false,
+                    exit_code: None,
+                    next_offset: None,
+                }
+            }
+            Request::PtyResize {
+                session_id,
+                cols,
+                rows,
+            } => {
+                self.resizes
+                    .lock()
+                    .expect("resizes lock")
+                    .push((cols, rows));
+                Response::PtyAck {
+                    session_id,
+                    action: PtyAction::Resize,
+                }
+            }
+            Request::PtyClose { session_id, force } => {
+                self.closed
+                    .lock()
+                    .expect("closed lock")
+                    .push((session_id, force));
+                Response::PtyAck {
+                    session_id,
+                    action: PtyAction::Closed,
+                }
+            }
+            other => TestHandler.handle(other).await,
+        }
+    }
+}
+
+#[tokio::test]
+/// Verifies the PTY duplex handle writes input, reads the echoed output, resizes, and closes
+/// the session when dropped.
+async fn pty_session_handle_drives_echoing_shell() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("pty.sock");
+    let server_socket = socket_path.clone();
+    let handler = Arc::new(EchoShellHandler::default());
+    let server_handler: Arc<dyn RequestHandler> = handler.clone();
+    let server = tokio::spawn(async move { serve_unix(&server_socket, server_handler).await });
+
+    let client = PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+        .await
+        .expect("client should connect");
+    let mut session = client
+        .open_pty(PtyOptions::new("/bin/sh"))
+        .await
+        .expect("pty should open");
+    assert_eq!(session.session_id(), SessionId(3));
+    assert_eq!(session.pid(), Some(42));
+
+    session
+        .write_all(b"echo hi\n")
+        .await
+        .expect("input should be written");
+    session.flush().await.expect("input should be acknowledged");
+    let mut echoed = Vec::new();
+    timeout(Duration::from_secs(5), async {
+        let mut buf = [0_u8; 64];
+        while !echoed.ends_with(b"echo hi\n") {
+            let read = session.read(&mut buf).await.expect("output should read");
+            assert_ne!(read, 0, "output ended early");
+            echoed.extend_from_slice(&buf[..read]);
+        }
+    })
+    .await
+    .expect("echo should arrive");
+    assert_eq!(echoed, b"$ echo hi\n");
+
+    session
+        .resize(100, 30)
+        .await
+        .expect("resize should succeed");
+    assert_eq!(*handler.resizes.lock().expect("resizes lock"), [(100, 30)]);
+
+    drop(session);
+    timeout(Duration::from_secs(5), async {
+        while handler.closed.lock().expect("closed lock").is_empty() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("dropping the handle should close the session");
+    assert_eq!(
+        *handler.closed.lock().expect("closed lock"),
+        [(SessionId(3), false)]
+    );
+
+    server.abort();
+}
+
+#[tokio::test]
+/// Verifies an attached viewer replays the requested tail, sends input, and leaves the session
+/// open when dropped.
+async fn attached_pty_handle_replays_tail_and_stays_open() {
+    let tmp = tempdir().expect("tempdir should be created");
+    let socket_path = tmp.path().join("pty.sock");
+    let server_socket = socket_path.clone();
+    let handler = Arc::new(EchoShellHandler::default());
+    handler
+        .output
+        .lock()
+        .expect("output lock")
+        .extend(b"$ echo hi\nhi\n");
+    let server_handler: Arc<dyn RequestHandler> = handler.clone();
+    let server = tokio::spawn(async move { serve_unix(&server_socket, server_handler).await });
+
+    let client = PlanterClient::connect_with_retry(&socket_path, 200, Duration::from_millis(10))
+        .await
+        .expect("client should connect")
+        .into_multiplexed();
+    let mut viewer = client.attach_pty(SessionId(3), Some(3));
+    assert_eq!(viewer.session_id(), SessionId(3));
+
+    let mut buf = [0_u8; 64];
+    let read = timeout(Duration::from_secs(5), viewer.read(&mut buf))
+        .await
+        .expect("tail should arrive")
+        .expect("output should read");
+    assert_eq!(&buf[..read], b"hi\n");
+
+    viewer
+        .write_all(b"ls\n")
+        .await
+        .expect("input should be written");
+    viewer.flush().await.expect("input should be acknowledged");
+    let read = timeout(Duration::from_secs(5), viewer.read(&mut buf))
+        .await
+        .expect("echo should arrive")
+        .expect("output should read");
+    assert_eq!(&buf[..read], b"ls\n");
+
+    drop(viewer);
+    sleep(Duration::from_millis(100)).await;
+    assert!(handler.closed.lock().expect("closed lock").is_empty());
+
+    server.abort();
+}
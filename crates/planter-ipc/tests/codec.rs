@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use planter_core::{
+    CellId, CellSpec, CommandSpec, ErrorCode, ExitStatus, JobId, JobInfo, LogStream, ReqId,
+    Request, RequestEnvelope, ResourceLimits, Response, ResponseEnvelope, SandboxMode, SessionId,
+};
+use planter_ipc::{
+    IpcError, WireFormat,
+    codec::{describe_frame, encode_into_framed},
+    framing::{MAX_FRAME_SIZE, write_frame},
+};
+
+/// Formats every codec test runs against.
+const FORMATS: [WireFormat; 2] = [WireFormat::Cbor, WireFormat::Json];
+
+/// Representative request envelopes covering nested specs, bytes, and options.
+fn requests() -> Vec<RequestEnvelope<Request>> {
+    vec![
+        RequestEnvelope {
+            req_id: ReqId(1),
+            body: Request::Version {},
+            deadline_ms: None,
+        },
+        RequestEnvelope {
+            req_id: ReqId(2),
+            body: Request::CellCreate {
+                spec: CellSpec {
+                    name: "demo".to_string(),
+                    env: BTreeMap::from([("FOO".to_string(), "bar".to_string())]),
+                    sandbox_mode: Some(SandboxMode::Enforced),
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            },
+            deadline_ms: None,
+        },
+        RequestEnvelope {
+            req_id: ReqId(3),
+            body: Request::JobRun {
+                cell_id: CellId("cell-1".to_string()),
+                cmd: CommandSpec {
+                    argv: vec!["/bin/echo".to_string(), "hi".to_string()],
+                    cwd: Some("/tmp".to_string()),
+                    env: BTreeMap::new(),
+                    limits: Some(ResourceLimits {
+                        timeout_ms: Some(1000),
+                        max_rss_bytes: None,
+                        max_log_bytes: Some(4096),
+                        max_open_files: None,
+                        max_processes: None,
+                        max_cpu_seconds: None,
+                        nice: Some(-5),
+                        cpu_affinity: None,
+                    }),
+                },
+                deterministic_id: true,
+                process_title: false,
+                system_tmp: false,
+            },
+            deadline_ms: None,
+        },
+        RequestEnvelope {
+            req_id: ReqId(4),
+            body: Request::PtyInput {
+                session_id: SessionId(9),
+                data: vec![0, 27, 255, b'\n'],
+            },
+            deadline_ms: None,
+        },
+        RequestEnvelope {
+            req_id: ReqId(u64::MAX),
+            body: Request::LogsRead {
+                job_id: JobId("job-1".to_string()),
+                stream: LogStream::Stderr,
+                offset: 12,
+                max_bytes: 1024,
+                follow: false,
+                wait_ms: 0,
+                strip_ansi: false,
+                reverse: true,
+                generation: None,
+            },
+            deadline_ms: Some(250),
+        },
+    ]
+}
+
+/// Representative response envelopes covering job records, chunks, and errors.
+fn responses() -> Vec<ResponseEnvelope<Response>> {
+    vec![
+        ResponseEnvelope {
+            req_id: ReqId(1),
+            body: Response::JobStatus {
+                job: JobInfo {
+                    id: JobId("job-1".to_string()),
+                    cell_id: CellId("cell-1".to_string()),
+                    command: CommandSpec {
+                        argv: vec!["true".to_string()],
+                        cwd: None,
+                        env: BTreeMap::new(),
+                        limits: None,
+                    },
+                    requested_at_ms: 1,
+                    started_at_ms: 1,
+                    finished_at_ms: Some(2),
+                    pid: Some(100),
+                    status: ExitStatus::Exited { code: Some(0) },
+                    termination_reason: None,
+                    sandbox_mode: Some(SandboxMode::Permissive),
+                    sandboxed: false,
+                    sandbox_fallback_reason: None,
+                    spawn_latency_ms: Some(3),
+                    effective_env: None,
+                    cell_exists: true,
+                    stdout_bytes: 12,
+                    stderr_bytes: 0,
+                },
+            },
+            more: false,
+        },
+        ResponseEnvelope {
+            req_id: ReqId(2),
+            body: Response::LogsChunk {
+                job_id: JobId("job-1".to_string()),
+                stream: LogStream::Stdout,
+                offset: 5,
+                data: b"hello\xff".to_vec(),
+                eof: true,
+                complete: false,
+                reset: false,
+                more_expected: true,
+                next_offset: None,
+                generation: 0,
+            },
+            more: false,
+        },
+        ResponseEnvelope {
+            req_id: ReqId(3),
+            body: Response::Error {
+                code: ErrorCode::NotFound,
+                message: "job not found".to_string(),
+                detail: Some("job-9".to_string()),
+            },
+            more: false,
+        },
+    ]
+}
+
+#[test]
+/// Verifies the same requests roundtrip through both wire formats.
+fn requests_roundtrip_in_both_formats() {
+    for format in FORMATS {
+        for request in requests() {
+            let encoded = format.encode(&request).expect("encode request");
+            assert_eq!(WireFormat::detect(&encoded), format);
+            let decoded: RequestEnvelope<Request> =
+                format.decode(&encoded).expect("decode request");
+            assert_eq!(decoded, request, "{format:?}");
+        }
+    }
+}
+
+#[test]
+/// Verifies the same responses roundtrip through both wire formats.
+fn responses_roundtrip_in_both_formats() {
+    for format in FORMATS {
+        for response in responses() {
+            let encoded = format.encode(&response).expect("encode response");
+            assert_eq!(WireFormat::detect(&encoded), format);
+            let decoded: ResponseEnvelope<Response> =
+                format.decode(&encoded).expect("decode response");
+            assert_eq!(decoded, response, "{format:?}");
+        }
+    }
+}
+
+#[test]
+/// Verifies JSON payloads are plain text and still get an oversized-frame breakdown.
+fn json_frames_are_readable_and_described() {
+    let request = RequestEnvelope {
+        req_id: ReqId(7),
+        body: Request::Health { deep: true },
+        deadline_ms: None,
+    };
+    let encoded = WireFormat::Json.encode(&request).expect("encode request");
+    let text = std::str::from_utf8(&encoded).expect("json is utf-8");
+    assert!(text.contains("\"type\":\"health\""), "{text}");
+
+    let breakdown = describe_frame(&encoded);
+    assert_eq!(breakdown.variant, "health");
+    assert_eq!(breakdown.fields, vec![("deep".to_string(), 4)]);
+}
+
+#[tokio::test]
+/// Verifies single-pass framed encoding through one reused buffer matches encode followed by
+/// write_frame byte-for-byte, in both wire formats.
+async fn encode_into_framed_matches_two_step_framing() {
+    let mut envelopes = responses();
+    envelopes.push(ResponseEnvelope {
+        req_id: ReqId(4),
+        body: Response::LogsChunk {
+            job_id: JobId("job-2".to_string()),
+            stream: LogStream::Stderr,
+            offset: 0,
+            data: vec![b'x'; 256 * 1024],
+            eof: false,
+            complete: false,
+            reset: false,
+            more_expected: true,
+            next_offset: None,
+            generation: 0,
+        },
+        more: false,
+    });
+
+    let mut frame = Vec::new();
+    for format in FORMATS {
+        for envelope in &envelopes {
+            let mut direct = Vec::new();
+            format
+                .encode_into_framed(&mut direct, &mut frame, envelope)
+                .await
+                .expect("encode into frame");
+            let mut two_step = Vec::new();
+            let payload = format.encode(envelope).expect("encode response");
+            write_frame(&mut two_step, &payload)
+                .await
+                .expect("write frame");
+            assert_eq!(direct, two_step);
+        }
+    }
+}
+
+#[tokio::test]
+/// Verifies oversized values are rejected before anything is written.
+async fn encode_into_framed_rejects_oversized_values() {
+    let envelope = ResponseEnvelope {
+        req_id: ReqId(5),
+        body: Response::LogsChunk {
+            job_id: JobId("job-3".to_string()),
+            stream: LogStream::Stdout,
+            offset: 0,
+            data: vec![0; MAX_FRAME_SIZE as usize + 1],
+            eof: false,
+            complete: false,
+            reset: false,
+            more_expected: false,
+            next_offset: None,
+            generation: 0,
+        },
+        more: false,
+    };
+
+    let mut out = Vec::new();
+    let err = encode_into_framed(&mut out, &mut Vec::new(), &envelope)
+        .await
+        .expect_err("oversized frame");
+    assert!(matches!(err, IpcError::FrameTooLarge { .. }), "{err:?}");
+    assert!(out.is_empty());
+}
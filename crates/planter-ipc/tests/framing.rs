@@ -1,8 +1,92 @@
+use std::{
+    io::IoSlice,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
 use planter_ipc::{
     IpcError,
     framing::{MAX_FRAME_SIZE, read_frame, write_frame},
 };
-use tokio::io::{AsyncWriteExt, duplex, sink};
+use tokio::io::{AsyncWrite, AsyncWriteExt, duplex, sink};
+
+/// Writer that records bytes and counts write calls, optionally accepting short writes.
+struct RecordingWriter {
+    /// Bytes accepted so far.
+    data: Vec<u8>,
+    /// Number of write calls observed.
+    calls: usize,
+    /// Maximum bytes accepted per call.
+    max_per_call: usize,
+    /// Whether the writer advertises efficient vectored writes.
+    vectored: bool,
+}
+
+impl RecordingWriter {
+    /// Builds a writer with the given per-call cap and vectored support.
+    fn new(max_per_call: usize, vectored: bool) -> Self {
+        Self {
+            data: Vec::new(),
+            calls: 0,
+            max_per_call,
+            vectored,
+        }
+    }
+}
+
+impl AsyncWrite for RecordingWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.calls += 1;
+        let n = buf.len().min(self.max_per_call);
+        self.data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        if !self.vectored {
+            let first = bufs
+                .iter()
+                .find(|buf| !buf.is_empty())
+                .map_or(&[][..], |buf| buf);
+            return self.poll_write(cx, first);
+        }
+        self.calls += 1;
+        let mut budget = self.max_per_call;
+        for buf in bufs {
+            let n = buf.len().min(budget);
+            self.data.extend_from_slice(&buf[..n]);
+            budget -= n;
+        }
+        Poll::Ready(Ok(self.max_per_call - budget))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.vectored
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Returns the expected wire bytes for one frame.
+fn framed(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(payload);
+    bytes
+}
 
 #[tokio::test]
 /// Verifies one frame can be written and read back unchanged.
@@ -60,3 +144,43 @@ async fn detect_truncated_frame_payload() {
         other => panic!("unexpected error: {other}"),
     }
 }
+
+#[tokio::test]
+/// Verifies a vectored writer receives header and payload in one write call.
+async fn write_frame_uses_single_vectored_write() {
+    let mut writer = RecordingWriter::new(usize::MAX, true);
+    let payload = vec![7_u8; 64 * 1024];
+
+    write_frame(&mut writer, &payload)
+        .await
+        .expect("write should succeed");
+
+    assert_eq!(writer.calls, 1);
+    assert_eq!(writer.data, framed(&payload));
+}
+
+#[tokio::test]
+/// Verifies short vectored writes resume correctly across the header/payload boundary.
+async fn write_frame_resumes_after_partial_vectored_writes() {
+    let mut writer = RecordingWriter::new(3, true);
+
+    write_frame(&mut writer, b"hello-frame")
+        .await
+        .expect("write should succeed");
+
+    assert_eq!(writer.data, framed(b"hello-frame"));
+    assert_eq!(writer.calls, 5);
+}
+
+#[tokio::test]
+/// Verifies writers without vectored support still receive an intact frame.
+async fn write_frame_falls_back_without_vectored_support() {
+    let mut writer = RecordingWriter::new(usize::MAX, false);
+
+    write_frame(&mut writer, b"hello-frame")
+        .await
+        .expect("write should succeed");
+
+    assert_eq!(writer.data, framed(b"hello-frame"));
+    assert_eq!(writer.calls, 2);
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "tls")]
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use planter_core::{ErrorCode, PROTOCOL_VERSION, Request, Response};
+use planter_ipc::{
+    PlanterClient, RequestHandler, client_config_from_pem, serve_tcp_listener,
+    server_config_from_pem,
+};
+use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+use tokio::net::TcpListener;
+
+/// Minimal request handler that only answers version requests.
+struct VersionHandler;
+
+#[async_trait]
+impl RequestHandler for VersionHandler {
+    /// Returns a canned version response and rejects everything else.
+    async fn handle(&self, req: Request) -> Response {
+        match req {
+            Request::Version {} => Response::Version {
+                daemon: "0.1.0".to_string(),
+                protocol: PROTOCOL_VERSION,
+            },
+            _ => Response::Error {
+                code: ErrorCode::InvalidRequest,
+                message: "unsupported in test".to_string(),
+                detail: None,
+            },
+        }
+    }
+}
+
+/// PEM-encoded certificate and private key pair.
+struct PemPair {
+    /// Certificate PEM.
+    cert: String,
+    /// Private key PEM.
+    key: String,
+}
+
+/// Generates a self-signed CA plus server and client leaf certificates.
+fn generate_pki() -> (String, PemPair, PemPair) {
+    let ca_key = KeyPair::generate().expect("ca key");
+    let mut ca_params = CertificateParams::new(Vec::new()).expect("ca params");
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key).expect("ca cert");
+
+    let server_key = KeyPair::generate().expect("server key");
+    let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+        .expect("server params")
+        .signed_by(&server_key, &ca_cert, &ca_key)
+        .expect("server cert");
+
+    let client_key = KeyPair::generate().expect("client key");
+    let client_cert = CertificateParams::new(vec!["planter-client".to_string()])
+        .expect("client params")
+        .signed_by(&client_key, &ca_cert, &ca_key)
+        .expect("client cert");
+
+    (
+        ca_cert.pem(),
+        PemPair {
+            cert: server_cert.pem(),
+            key: server_key.serialize_pem(),
+        },
+        PemPair {
+            cert: client_cert.pem(),
+            key: client_key.serialize_pem(),
+        },
+    )
+}
+
+#[tokio::test]
+/// Verifies a mutually authenticated TLS client can complete a version round trip.
+async fn tls_version_roundtrip_with_client_cert() {
+    let (ca_pem, server, client) = generate_pki();
+    let server_config = server_config_from_pem(
+        server.cert.as_bytes(),
+        server.key.as_bytes(),
+        ca_pem.as_bytes(),
+    )
+    .expect("server config should build");
+    let client_config = client_config_from_pem(
+        ca_pem.as_bytes(),
+        client.cert.as_bytes(),
+        client.key.as_bytes(),
+    )
+    .expect("client config should build");
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("listener should bind");
+    let addr = listener.local_addr().expect("listener address");
+    let server = tokio::spawn(async move {
+        serve_tcp_listener(listener, server_config, Arc::new(VersionHandler)).await
+    });
+
+    let mut client = PlanterClient::connect_tls(addr, "localhost", client_config)
+        .await
+        .expect("tls client should connect");
+    let version = client
+        .call(Request::Version {})
+        .await
+        .expect("version call should succeed");
+    match version {
+        Response::Version { protocol, .. } => assert_eq!(protocol, PROTOCOL_VERSION),
+        other => panic!("unexpected response: {other:?}"),
+    }
+
+    server.abort();
+}
@@ -1,14 +1,25 @@
 use std::{
     collections::BTreeMap,
-    fs, io,
+    ffi::CString,
+    fs,
+    io::{self, Read},
+    os::{
+        fd::AsRawFd,
+        unix::{ffi::OsStrExt, fs::OpenOptionsExt},
+    },
     path::{Path, PathBuf},
-    process::{Command as StdCommand, Stdio},
+    process::{Child as StdChild, Command as StdCommand, ExitStatus, Stdio},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use planter_core::{CellId, CommandSpec, JobId, JobInfo};
-use planter_platform::{CellPaths, JobHandle, JobUsage, PlatformError, PlatformOps};
+pub use planter_core::SandboxMode;
+
+use planter_core::{CellId, CommandSpec, JobId, JobInfo, SANDBOX_EXEC_PATH, SandboxOperation};
+use planter_platform::{
+    CellPaths, JobHandle, JobUsage, PlatformError, PlatformOps, SandboxDecision, SandboxProfile,
+    apply_rlimits, fd_path,
+};
 use tokio::process::{Child, Command};
 
 /// Ordered sandbox profile fragments merged into a generated profile file.
@@ -22,18 +33,26 @@ const PROFILE_FRAGMENTS: &[(&str, &str)] = &[
     ("30-network", include_str!("../profiles/30-network.sb")),
 ];
 
-/// System path for the macOS sandbox runner.
-const SANDBOX_EXEC_PATH: &str = "/usr/bin/sandbox-exec";
-
-/// Sandboxing policy to apply for launched jobs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SandboxMode {
-    /// Launch without sandbox restrictions.
-    Disabled,
-    /// Attempt sandboxing and fall back to unsandboxed execution on failure.
-    Permissive,
-    /// Require sandboxing; fail if sandbox launch cannot be applied.
-    Enforced,
+/// Longest a sandbox check probe may run before it is killed.
+const SANDBOX_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Poll interval while waiting for a sandbox check probe.
+const SANDBOX_PROBE_POLL: Duration = Duration::from_millis(10);
+/// Probe script that reads `$1`, listing it when it is a directory.
+const READ_PROBE: &str =
+    r#"if [ -d "$1" ]; then /bin/ls "$1" >/dev/null; else /bin/cat "$1" >/dev/null; fi"#;
+/// Probe script that checks writing `$1` without changing anything the client named.
+///
+/// Existing files are opened for append. For a directory, or the parent of a missing file,
+/// a private probe file is created there and removed again; `$1` itself is never created.
+const WRITE_PROBE: &str = r#"set -C; if [ -d "$1" ]; then dir="$1"; elif [ -e "$1" ]; then exec 3>>"$1"; exit; else dir="$(/usr/bin/dirname "$1")"; fi; probe="$dir/.planter-sandbox-probe.$$"; : >"$probe" && /bin/rm -f "$probe""#;
+
+/// Cell directory resolved through an opened handle, so its profile grants what was opened.
+#[derive(Debug)]
+struct CellDir {
+    /// Path the directory was opened through.
+    path: PathBuf,
+    /// Real path derived from the open handle rather than by re-resolving `path`.
+    real_path: PathBuf,
 }
 
 /// macOS implementation of [`PlatformOps`].
@@ -43,12 +62,18 @@ pub struct MacosOps {
     root: PathBuf,
     /// Runtime sandbox mode.
     sandbox_mode: SandboxMode,
+    /// Optional directory of extra `.sb` fragments appended after the built-ins.
+    profile_dir: Option<PathBuf>,
 }
 
 impl MacosOps {
     /// Creates a new macOS platform backend for a state root.
-    pub fn new(root: PathBuf, sandbox_mode: SandboxMode) -> Self {
-        Self { root, sandbox_mode }
+    pub fn new(root: PathBuf, sandbox_mode: SandboxMode, profile_dir: Option<PathBuf>) -> Self {
+        Self {
+            root,
+            sandbox_mode,
+            profile_dir,
+        }
     }
 
     /// Returns the root directory containing all cell workspaces.
@@ -73,29 +98,78 @@ impl MacosOps {
 
     /// Renders and writes a sandbox profile file for a cell.
     pub fn compile_sandbox_profile(&self, cell_id: &CellId) -> Result<PathBuf, PlatformError> {
+        let cell_dir = self.open_cell_dir(cell_id)?;
+        self.write_sandbox_profile(cell_id, &cell_dir)
+    }
+
+    /// Writes a cell's sandbox profile rendered against an already opened cell directory.
+    fn write_sandbox_profile(
+        &self,
+        cell_id: &CellId,
+        cell_dir: &CellDir,
+    ) -> Result<PathBuf, PlatformError> {
         let sandbox_dir = self.sandbox_dir();
         fs::create_dir_all(&sandbox_dir)?;
 
         let profile_path = sandbox_dir.join(format!("{}.sb", cell_id.0));
-        let cell_dir = self.cells_dir().join(&cell_id.0);
-        let profile = self.render_sandbox_profile(cell_id, &cell_dir);
+        let profile = self.render_sandbox_profile(cell_id, &cell_dir.path, &cell_dir.real_path)?;
         fs::write(&profile_path, profile)?;
 
         Ok(profile_path)
     }
 
+    /// Loads extra `.sb` fragments from the configured directory in file name order.
+    fn extra_profile_fragments(&self) -> Result<Vec<(String, String)>, PlatformError> {
+        let Some(dir) = &self.profile_dir else {
+            return Ok(Vec::new());
+        };
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "sb") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                Ok((name, fs::read_to_string(&path)?))
+            })
+            .collect()
+    }
+
     /// Renders the final sandbox profile by applying placeholder substitutions.
-    fn render_sandbox_profile(&self, cell_id: &CellId, cell_dir: &Path) -> String {
+    fn render_sandbox_profile(
+        &self,
+        cell_id: &CellId,
+        cell_dir: &Path,
+        cell_dir_real: &Path,
+    ) -> Result<String, PlatformError> {
         let mut output = String::new();
         let state_root = self.root.to_string_lossy().to_string();
         let state_root_real = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
         let state_root_real = state_root_real.to_string_lossy().to_string();
         let cell_dir = cell_dir.to_string_lossy().to_string();
-        let cell_dir_real = fs::canonicalize(cell_dir.as_str())
-            .unwrap_or_else(|_| PathBuf::from(cell_dir.as_str()));
         let cell_dir_real = cell_dir_real.to_string_lossy().to_string();
 
-        for (name, fragment) in PROFILE_FRAGMENTS {
+        let extra = self.extra_profile_fragments()?;
+        let fragments = PROFILE_FRAGMENTS
+            .iter()
+            .map(|(name, fragment)| (*name, *fragment))
+            .chain(
+                extra
+                    .iter()
+                    .map(|(name, fragment)| (name.as_str(), fragment.as_str())),
+            );
+
+        for (name, fragment) in fragments {
             if !output.is_empty() {
                 output.push('\n');
             }
@@ -114,7 +188,7 @@ impl MacosOps {
             output.push('\n');
         }
 
-        output
+        Ok(output)
     }
 
     /// Resolves the active local user used for lease metadata.
@@ -164,6 +238,7 @@ impl MacosOps {
 
         command.current_dir(cwd);
         command.envs(env.clone());
+        apply_rlimits(&mut command, cmd.limits.as_ref());
         command.stdout(Stdio::from(stdout_file));
         command.stderr(Stdio::from(stderr_file));
         command.spawn().map_err(PlatformError::from)
@@ -193,21 +268,35 @@ impl MacosOps {
 
         command.current_dir(cwd);
         command.envs(env.clone());
+        apply_rlimits(&mut command, cmd.limits.as_ref());
         command.stdout(Stdio::from(stdout_file));
         command.stderr(Stdio::from(stderr_file));
         command.spawn().map_err(PlatformError::from)
     }
 
-    /// Ensures the target cell directory exists before launching work.
-    fn ensure_cell_exists(&self, cell_id: &CellId) -> Result<PathBuf, PlatformError> {
-        let cell_dir = self.cells_dir().join(&cell_id.0);
-        if !cell_dir.exists() {
-            return Err(PlatformError::InvalidInput(format!(
-                "cell {} does not exist",
-                cell_id.0
-            )));
-        }
-        Ok(cell_dir)
+    /// Opens the target cell directory, refusing a symlink in its place.
+    fn open_cell_dir(&self, cell_id: &CellId) -> Result<CellDir, PlatformError> {
+        let path = self.cells_dir().join(&cell_id.0);
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW)
+            .open(&path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => {
+                    PlatformError::InvalidInput(format!("cell {} does not exist", cell_id.0))
+                }
+                _ if err.raw_os_error() == Some(libc::ELOOP)
+                    || err.raw_os_error() == Some(libc::ENOTDIR) =>
+                {
+                    PlatformError::InvalidInput(format!(
+                        "cell {} directory is not a real directory",
+                        cell_id.0
+                    ))
+                }
+                _ => PlatformError::Io(err),
+            })?;
+        let real_path = fd_path(file.as_raw_fd())?;
+        Ok(CellDir { path, real_path })
     }
 
     /// Ensures the log directory exists and returns its path.
@@ -312,6 +401,7 @@ impl PlatformOps for MacosOps {
         cell_id: &CellId,
         cmd: &CommandSpec,
         env: &BTreeMap<String, String>,
+        sandbox_mode: SandboxMode,
     ) -> Result<JobHandle, PlatformError> {
         if cmd.argv.is_empty() {
             return Err(PlatformError::InvalidInput(
@@ -319,19 +409,18 @@ impl PlatformOps for MacosOps {
             ));
         }
 
-        let cell_dir = self.ensure_cell_exists(cell_id)?;
+        let cell_dir = self.open_cell_dir(cell_id)?;
         let logs_dir = self.ensure_logs_dir()?;
         let leased_user = self.lease_user()?;
-        let sandbox_profile = self.compile_sandbox_profile(cell_id)?;
+        let sandbox_profile = self.write_sandbox_profile(cell_id, &cell_dir)?;
 
         let stdout_path = logs_dir.join(format!("{}.stdout.log", job_id.0));
         let stderr_path = logs_dir.join(format!("{}.stderr.log", job_id.0));
 
         let cwd = cmd
             .cwd
-            .as_ref()
-            .map(PathBuf::from)
-            .unwrap_or_else(|| cell_dir.clone());
+            .as_deref()
+            .map_or(cell_dir.path.as_path(), Path::new);
 
         let mut merged_env = env.clone();
         merged_env.insert("PLANTER_LEASED_USER".to_string(), leased_user);
@@ -342,15 +431,15 @@ impl PlatformOps for MacosOps {
 
         let (stdout_file, stderr_file) = self.open_log_files(&stdout_path, &stderr_path, false)?;
 
-        let child = match self.sandbox_mode {
+        let child = match sandbox_mode {
             SandboxMode::Disabled => {
-                self.spawn_plain(cmd, &cwd, &merged_env, stdout_file, stderr_file)?
+                self.spawn_plain(cmd, cwd, &merged_env, stdout_file, stderr_file)?
             }
             SandboxMode::Permissive => {
                 if self.sandbox_exec_available() {
                     match self.spawn_sandboxed(
                         cmd,
-                        &cwd,
+                        cwd,
                         &merged_env,
                         &sandbox_profile,
                         stdout_file,
@@ -365,7 +454,7 @@ impl PlatformOps for MacosOps {
                             );
                             let (stdout_file, stderr_file) =
                                 self.open_log_files(&stdout_path, &stderr_path, true)?;
-                            self.spawn_plain(cmd, &cwd, &merged_env, stdout_file, stderr_file)?
+                            self.spawn_plain(cmd, cwd, &merged_env, stdout_file, stderr_file)?
                         }
                     }
                 } else {
@@ -374,12 +463,12 @@ impl PlatformOps for MacosOps {
                         job_id = %job_id.0,
                         "sandbox runtime missing in permissive mode; falling back to plain spawn"
                     );
-                    self.spawn_plain(cmd, &cwd, &merged_env, stdout_file, stderr_file)?
+                    self.spawn_plain(cmd, cwd, &merged_env, stdout_file, stderr_file)?
                 }
             }
             SandboxMode::Enforced => self.spawn_sandboxed(
                 cmd,
-                &cwd,
+                cwd,
                 &merged_env,
                 &sandbox_profile,
                 stdout_file,
@@ -392,11 +481,17 @@ impl PlatformOps for MacosOps {
             stdout_path,
             stderr_path,
             child,
+            sandbox_mode,
         })
     }
 
     /// Stops a job process tree gracefully, then forcefully if needed.
-    fn kill_job_tree(&self, job_id: &JobId, force: bool) -> Result<(), PlatformError> {
+    fn kill_job_tree(
+        &self,
+        job_id: &JobId,
+        force: bool,
+        grace: Duration,
+    ) -> Result<(), PlatformError> {
         let job = self.load_job(job_id)?;
         let Some(pid) = job.pid else {
             return Ok(());
@@ -411,7 +506,7 @@ impl PlatformOps for MacosOps {
         self.signal_children(pid, "TERM")?;
         self.signal_pid(pid, "TERM")?;
 
-        thread::sleep(Duration::from_millis(250));
+        thread::sleep(grace);
 
         if self.process_alive(pid)? {
             self.signal_children(pid, "KILL")?;
@@ -449,13 +544,141 @@ impl PlatformOps for MacosOps {
             cpu_nanos: None,
         }))
     }
+
+    /// Runs a shell probe attempting the operation under the cell's profile and reports its exit.
+    fn sandbox_check(
+        &self,
+        cell_id: &CellId,
+        path: &Path,
+        operation: SandboxOperation,
+        sandbox_mode: SandboxMode,
+    ) -> Result<SandboxDecision, PlatformError> {
+        if !path.is_absolute() {
+            return Err(PlatformError::InvalidInput(
+                "sandbox check path must be absolute".to_string(),
+            ));
+        }
+
+        let cell_dir = self.open_cell_dir(cell_id)?;
+        let sandboxed = match sandbox_mode {
+            SandboxMode::Disabled => false,
+            SandboxMode::Permissive => self.sandbox_exec_available(),
+            SandboxMode::Enforced => {
+                if !self.sandbox_exec_available() {
+                    return Err(PlatformError::Unsupported(format!(
+                        "sandbox runtime not available at {SANDBOX_EXEC_PATH}"
+                    )));
+                }
+                true
+            }
+        };
+
+        // Without a sandbox only the daemon user's own permissions apply, so check those
+        // rather than touching the path.
+        if !sandboxed {
+            return unsandboxed_access(path, operation);
+        }
+
+        let sandbox_profile = self.write_sandbox_profile(cell_id, &cell_dir)?;
+        let mut command = StdCommand::new(SANDBOX_EXEC_PATH);
+        command.arg("-f").arg(&sandbox_profile).arg("/bin/sh");
+        let script = match operation {
+            SandboxOperation::Read => READ_PROBE,
+            SandboxOperation::Write => WRITE_PROBE,
+        };
+        command
+            .arg("-c")
+            .arg(script)
+            .arg("planter-sandbox-probe")
+            .arg(path)
+            .current_dir(&cell_dir.path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn()?;
+        let Some(status) = wait_with_timeout(&mut child, SANDBOX_PROBE_TIMEOUT)? else {
+            return Err(PlatformError::Timeout(format!(
+                "sandbox probe exceeded {}ms",
+                SANDBOX_PROBE_TIMEOUT.as_millis()
+            )));
+        };
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            pipe.read_to_string(&mut stderr)?;
+        }
+        let stderr = stderr.trim();
+        Ok(SandboxDecision {
+            allowed: status.success(),
+            sandboxed,
+            detail: (!stderr.is_empty()).then(|| stderr.to_string()),
+        })
+    }
+
+    /// Returns the sandbox mode configured for this backend.
+    fn sandbox_mode(&self) -> SandboxMode {
+        self.sandbox_mode
+    }
+
+    /// Renders the cell's profile under the backend's sandbox directory.
+    fn sandbox_profile(&self, cell_id: &CellId) -> Result<Option<SandboxProfile>, PlatformError> {
+        let cell_dir = self.open_cell_dir(cell_id)?;
+        let path = self.write_sandbox_profile(cell_id, &cell_dir)?;
+        Ok(Some(SandboxProfile {
+            path,
+            cell_dir: cell_dir.real_path,
+        }))
+    }
+}
+
+/// Decides a check outside any sandbox with `access(2)`, creating nothing.
+///
+/// Writing a missing path is allowed when its parent directory is writable.
+fn unsandboxed_access(
+    path: &Path,
+    operation: SandboxOperation,
+) -> Result<SandboxDecision, PlatformError> {
+    let (target, mode) = match operation {
+        SandboxOperation::Read => (path, libc::R_OK),
+        SandboxOperation::Write if fs::symlink_metadata(path).is_ok() => (path, libc::W_OK),
+        SandboxOperation::Write => (
+            path.parent().unwrap_or(Path::new("/")),
+            libc::W_OK | libc::X_OK,
+        ),
+    };
+    let target = CString::new(target.as_os_str().as_bytes()).map_err(|_| {
+        PlatformError::InvalidInput("sandbox check path contains a NUL byte".to_string())
+    })?;
+    // SAFETY: `target` is a valid NUL-terminated path and access only reads it.
+    let allowed = unsafe { libc::access(target.as_ptr(), mode) } == 0;
+    Ok(SandboxDecision {
+        allowed,
+        sandboxed: false,
+        detail: (!allowed).then(|| io::Error::last_os_error().to_string()),
+    })
+}
+
+/// Waits for `child` to exit, killing it and returning `None` once `timeout` elapses.
+fn wait_with_timeout(child: &mut StdChild, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(SANDBOX_PROBE_POLL);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{MacosOps, SANDBOX_EXEC_PATH, SandboxMode};
-    use planter_core::{CellId, CommandSpec, JobId};
-    use planter_platform::PlatformOps;
+    use planter_core::{CellId, CommandSpec, JobId, SandboxOperation};
+    use planter_platform::{PlatformError, PlatformOps};
     use std::{
         collections::BTreeMap,
         path::{Path, PathBuf},
@@ -468,9 +691,12 @@ mod tests {
         let ops = MacosOps::new(
             PathBuf::from("/tmp/planter-test-state"),
             SandboxMode::Permissive,
+            None,
         );
         let cell_dir = PathBuf::from("/tmp/planter-test-state/cells/cell-123");
-        let profile = ops.render_sandbox_profile(&CellId("cell-123".to_string()), &cell_dir);
+        let profile = ops
+            .render_sandbox_profile(&CellId("cell-123".to_string()), &cell_dir, &cell_dir)
+            .expect("render profile");
 
         assert!(profile.contains("cell-123"));
         assert!(profile.contains("/tmp/planter-test-state"));
@@ -479,6 +705,149 @@ mod tests {
         assert!(profile.contains("(allow network*)"));
     }
 
+    #[test]
+    /// Verifies extra fragments are appended after the built-ins with placeholders resolved.
+    fn sandbox_profile_appends_extra_fragments() {
+        let tmp = tempdir().expect("tempdir");
+        let profile_dir = tmp.path().join("profiles");
+        std::fs::create_dir_all(&profile_dir).expect("create profile dir");
+        std::fs::write(
+            profile_dir.join("50-corp-cache.sb"),
+            "(allow file-read* (subpath \"/opt/corp-cache/{{CELL_ID}}\"))\n",
+        )
+        .expect("write fragment");
+        std::fs::write(profile_dir.join("README.txt"), "ignored").expect("write readme");
+
+        let ops = MacosOps::new(
+            PathBuf::from("/tmp/planter-test-state"),
+            SandboxMode::Permissive,
+            Some(profile_dir),
+        );
+        let cell_dir = PathBuf::from("/tmp/planter-test-state/cells/cell-123");
+        let profile = ops
+            .render_sandbox_profile(&CellId("cell-123".to_string()), &cell_dir, &cell_dir)
+            .expect("render profile");
+
+        let extra = profile
+            .find("(allow file-read* (subpath \"/opt/corp-cache/cell-123\"))")
+            .expect("extra fragment rendered");
+        let network = profile.find("; ---- 30-network ----").expect("built-in");
+        assert!(network < extra);
+        assert!(profile.contains("; ---- 50-corp-cache ----"));
+        assert!(!profile.contains("ignored"));
+    }
+
+    #[tokio::test]
+    /// Verifies spawned job handles report the sandbox mode they were launched with.
+    async fn spawn_job_reports_sandbox_decision() {
+        let tmp = tempdir().expect("tempdir");
+        let cell_id = CellId("cell-test".to_string());
+        let command = CommandSpec {
+            argv: vec!["/bin/sh".to_string(), "-c".to_string(), "true".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+
+        let disabled = MacosOps::new(tmp.path().join("disabled"), SandboxMode::Disabled, None);
+        disabled
+            .create_cell_dirs(&cell_id)
+            .expect("cell dirs should be created");
+        let mut handle = disabled
+            .spawn_job(
+                &JobId("job-plain".to_string()),
+                &cell_id,
+                &command,
+                &BTreeMap::new(),
+                disabled.sandbox_mode(),
+            )
+            .expect("spawn should succeed");
+        assert_eq!(handle.sandbox_mode, SandboxMode::Disabled);
+        assert_eq!(handle.sandbox_mode, disabled.sandbox_mode());
+        let _ = handle.child.wait().await;
+
+        if Path::new(SANDBOX_EXEC_PATH).exists() {
+            return;
+        }
+        let permissive =
+            MacosOps::new(tmp.path().join("permissive"), SandboxMode::Permissive, None);
+        permissive
+            .create_cell_dirs(&cell_id)
+            .expect("cell dirs should be created");
+        let mut handle = permissive
+            .spawn_job(
+                &JobId("job-fallback".to_string()),
+                &cell_id,
+                &command,
+                &BTreeMap::new(),
+                permissive.sandbox_mode(),
+            )
+            .expect("permissive spawn should fall back");
+        assert_eq!(handle.sandbox_mode, SandboxMode::Permissive);
+        let _ = handle.child.wait().await;
+    }
+
+    #[tokio::test]
+    /// Verifies a per-launch disabled mode overrides an enforced backend default.
+    async fn spawn_job_honors_sandbox_mode_override() {
+        let tmp = tempdir().expect("tempdir");
+        let ops = MacosOps::new(tmp.path().join("state"), SandboxMode::Enforced, None);
+        let cell_id = CellId("cell-open".to_string());
+        ops.create_cell_dirs(&cell_id)
+            .expect("cell dirs should be created");
+        let command = CommandSpec {
+            argv: vec!["/bin/sh".to_string(), "-c".to_string(), "true".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+
+        let mut handle = ops
+            .spawn_job(
+                &JobId("job-open".to_string()),
+                &cell_id,
+                &command,
+                &BTreeMap::new(),
+                SandboxMode::Disabled,
+            )
+            .expect("disabled override should spawn without a sandbox");
+        assert_eq!(handle.sandbox_mode, SandboxMode::Disabled);
+        assert_eq!(ops.sandbox_mode(), SandboxMode::Enforced);
+        assert!(handle.child.wait().await.expect("wait").success());
+    }
+
+    #[test]
+    /// Verifies a profile names the cell directory that was opened, and a cell directory swapped
+    /// for a symlink is refused instead of granting the symlink's target.
+    fn sandbox_profile_refuses_swapped_cell_dir() {
+        let tmp = tempdir().expect("tempdir");
+        let ops = MacosOps::new(tmp.path().join("state"), SandboxMode::Enforced, None);
+        let cell_id = CellId("cell-swap".to_string());
+        let paths = ops
+            .create_cell_dirs(&cell_id)
+            .expect("cell dirs should be created");
+
+        let profile = ops
+            .sandbox_profile(&cell_id)
+            .expect("compile profile")
+            .expect("profile");
+        let original = std::fs::canonicalize(&paths.cell_dir).expect("canonical cell dir");
+        assert_eq!(profile.cell_dir, original);
+
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(&outside).expect("create outside dir");
+        std::fs::rename(&paths.cell_dir, tmp.path().join("moved")).expect("move cell dir");
+        std::os::unix::fs::symlink(&outside, &paths.cell_dir).expect("swap in symlink");
+
+        let err = ops
+            .sandbox_profile(&cell_id)
+            .expect_err("symlinked cell dir should be refused");
+        assert!(
+            matches!(err, PlatformError::InvalidInput(ref message) if message.contains("not a real directory")),
+            "{err}"
+        );
+    }
+
     #[tokio::test]
     /// Verifies enforced sandbox permits writes under the configured state root.
     async fn enforced_sandbox_allows_write_under_state_root() {
@@ -488,7 +857,7 @@ mod tests {
 
         let tmp = tempdir().expect("tempdir");
         let state_root = tmp.path().join("state");
-        let ops = MacosOps::new(state_root.clone(), SandboxMode::Enforced);
+        let ops = MacosOps::new(state_root.clone(), SandboxMode::Enforced, None);
         let cell_id = CellId("cell-test".to_string());
         ops.create_cell_dirs(&cell_id)
             .expect("cell dirs should be created");
@@ -513,6 +882,7 @@ mod tests {
                 &cell_id,
                 &command,
                 &BTreeMap::new(),
+                ops.sandbox_mode(),
             )
             .expect("spawn should succeed");
         let status = handle.child.wait().await.expect("wait should succeed");
@@ -536,7 +906,7 @@ mod tests {
         let blocked = outside_root.join("blocked.txt");
         std::fs::create_dir_all(&outside_root).expect("create outside dir");
 
-        let ops = MacosOps::new(state_root, SandboxMode::Enforced);
+        let ops = MacosOps::new(state_root, SandboxMode::Enforced, None);
         let cell_id = CellId("cell-test".to_string());
         ops.create_cell_dirs(&cell_id)
             .expect("cell dirs should be created");
@@ -558,6 +928,7 @@ mod tests {
                 &cell_id,
                 &command,
                 &BTreeMap::new(),
+                ops.sandbox_mode(),
             )
             .expect("spawn should succeed");
         let status = handle.child.wait().await.expect("wait should succeed");
@@ -565,4 +936,92 @@ mod tests {
         assert!(!status.success());
         assert!(!blocked.exists());
     }
+
+    #[test]
+    /// Verifies unsandboxed checks use the daemon's own permissions without creating anything.
+    fn sandbox_check_touches_nothing_when_disabled() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let ops = MacosOps::new(state_root.clone(), SandboxMode::Disabled, None);
+        let cell_id = CellId("cell-probe".to_string());
+        let paths = ops.create_cell_dirs(&cell_id).expect("cell dirs");
+        let target = paths.cell_dir.join("new.txt");
+
+        let write = ops
+            .sandbox_check(
+                &cell_id,
+                &target,
+                SandboxOperation::Write,
+                SandboxMode::Disabled,
+            )
+            .expect("write probe");
+        assert!(write.allowed, "{:?}", write.detail);
+        assert!(!write.sandboxed);
+        assert_eq!(
+            std::fs::read_dir(&paths.cell_dir)
+                .expect("read cell dir")
+                .count(),
+            0
+        );
+
+        let read = ops
+            .sandbox_check(
+                &cell_id,
+                &target,
+                SandboxOperation::Read,
+                SandboxMode::Disabled,
+            )
+            .expect("read probe");
+        assert!(!read.allowed);
+        assert!(read.detail.is_some());
+
+        let err = ops
+            .sandbox_check(
+                &cell_id,
+                Path::new("relative.txt"),
+                SandboxOperation::Read,
+                SandboxMode::Disabled,
+            )
+            .expect_err("relative path");
+        assert!(matches!(err, PlatformError::InvalidInput(_)), "{err}");
+    }
+
+    #[test]
+    /// Verifies an enforced sandbox check allows writes in the cell and denies them outside.
+    fn enforced_sandbox_check_distinguishes_cell_dir() {
+        if !Path::new(SANDBOX_EXEC_PATH).exists() {
+            return;
+        }
+
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let outside_root = tmp.path().join("outside");
+        std::fs::create_dir_all(&outside_root).expect("create outside dir");
+        let ops = MacosOps::new(state_root, SandboxMode::Enforced, None);
+        let cell_id = CellId("cell-probe".to_string());
+        let paths = ops.create_cell_dirs(&cell_id).expect("cell dirs");
+
+        let inside = ops
+            .sandbox_check(
+                &cell_id,
+                &paths.cell_dir.join("inside.txt"),
+                SandboxOperation::Write,
+                SandboxMode::Enforced,
+            )
+            .expect("inside probe");
+        assert!(inside.allowed, "{:?}", inside.detail);
+        assert!(inside.sandboxed);
+
+        let outside = ops
+            .sandbox_check(
+                &cell_id,
+                &outside_root.join("outside.txt"),
+                SandboxOperation::Write,
+                SandboxMode::Enforced,
+            )
+            .expect("outside probe");
+        assert!(!outside.allowed);
+        assert!(outside.sandboxed);
+        assert!(!outside_root.join("outside.txt").exists());
+    }
 }
@@ -0,0 +1,28 @@
+use std::{io, os::fd::RawFd, path::PathBuf};
+
+/// Returns the path the kernel currently associates with an open descriptor.
+///
+/// Unlike re-resolving the path it was opened through, this names what the descriptor refers
+/// to even if that path has since been swapped for a symlink.
+#[cfg(target_os = "macos")]
+pub fn fd_path(fd: RawFd) -> io::Result<PathBuf> {
+    use std::{ffi::CStr, os::unix::ffi::OsStrExt};
+
+    let mut buf = [0 as libc::c_char; libc::PATH_MAX as usize];
+    // SAFETY: F_GETPATH writes a NUL-terminated path of at most MAXPATHLEN bytes into `buf`.
+    if unsafe { libc::fcntl(fd, libc::F_GETPATH, buf.as_mut_ptr()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: on success the kernel NUL-terminated `buf`.
+    let path = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    Ok(PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes())))
+}
+
+/// Returns the path the kernel currently associates with an open descriptor.
+///
+/// Unlike re-resolving the path it was opened through, this names what the descriptor refers
+/// to even if that path has since been swapped for a symlink.
+#[cfg(not(target_os = "macos"))]
+pub fn fd_path(fd: RawFd) -> io::Result<PathBuf> {
+    std::fs::read_link(format!("/proc/self/fd/{fd}"))
+}
@@ -1,28 +1,67 @@
-use std::{collections::BTreeMap, path::PathBuf};
+mod fd;
+mod privilege;
+mod rlimits;
 
-use planter_core::{CellId, CommandSpec, JobId};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use planter_core::{CellId, CommandSpec, JobId, SandboxMode, SandboxOperation};
 use thiserror::Error;
 use tokio::process::Child;
 
+pub use fd::fd_path;
+pub use privilege::{RootPolicy, current_euid, root_policy};
+pub use rlimits::{NICE_RANGE, apply_rlimits, check_cpu_affinity, check_rlimits};
+
 /// Platform abstraction for filesystem/process operations used by workers.
 pub trait PlatformOps: Send + Sync {
     /// Creates per-cell directories and returns resolved paths.
     fn create_cell_dirs(&self, cell_id: &CellId) -> Result<CellPaths, PlatformError>;
 
-    /// Launches a job process for a cell with merged environment variables.
+    /// Launches a job process for a cell with merged environment variables under `sandbox_mode`.
     fn spawn_job(
         &self,
         job_id: &JobId,
         cell_id: &CellId,
         cmd: &CommandSpec,
         env: &BTreeMap<String, String>,
+        sandbox_mode: SandboxMode,
     ) -> Result<JobHandle, PlatformError>;
 
-    /// Terminates a job and any descendants.
-    fn kill_job_tree(&self, job_id: &JobId, force: bool) -> Result<(), PlatformError>;
+    /// Terminates a job and any descendants, waiting `grace` between TERM and KILL.
+    fn kill_job_tree(
+        &self,
+        job_id: &JobId,
+        force: bool,
+        grace: Duration,
+    ) -> Result<(), PlatformError>;
 
     /// Returns a point-in-time resource usage sample for a job, if available.
     fn probe_usage(&self, job_id: &JobId) -> Result<Option<JobUsage>, PlatformError>;
+
+    /// Returns the default sandbox policy for cells without their own override.
+    fn sandbox_mode(&self) -> SandboxMode;
+
+    /// Compiles the cell's sandbox profile for workers to launch jobs under.
+    ///
+    /// Returns `None` on platforms without a sandbox runtime.
+    fn sandbox_profile(&self, _cell_id: &CellId) -> Result<Option<SandboxProfile>, PlatformError> {
+        Ok(None)
+    }
+
+    /// Attempts `operation` on `path` the way a job in the cell would under `sandbox_mode`.
+    fn sandbox_check(
+        &self,
+        _cell_id: &CellId,
+        _path: &Path,
+        _operation: SandboxOperation,
+        _sandbox_mode: SandboxMode,
+    ) -> Result<SandboxDecision, PlatformError> {
+        Err(PlatformError::Unsupported("sandbox_check".to_string()))
+    }
 }
 
 /// Paths created for a logical execution cell.
@@ -32,6 +71,15 @@ pub struct CellPaths {
     pub cell_dir: PathBuf,
 }
 
+/// Sandbox profile compiled for a cell's jobs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxProfile {
+    /// Compiled profile file.
+    pub path: PathBuf,
+    /// Real path of the cell directory the profile grants, read from the opened directory.
+    pub cell_dir: PathBuf,
+}
+
 /// Handle to a newly spawned job process and its log files.
 pub struct JobHandle {
     /// Spawned process id when available.
@@ -42,6 +90,8 @@ pub struct JobHandle {
     pub stderr_path: PathBuf,
     /// Tokio child handle for lifecycle management.
     pub child: Child,
+    /// Sandbox policy in effect for this launch.
+    pub sandbox_mode: SandboxMode,
 }
 
 /// Process resource metrics sampled by a platform backend.
@@ -53,6 +103,17 @@ pub struct JobUsage {
     pub cpu_nanos: Option<u64>,
 }
 
+/// Outcome of a sandbox check probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxDecision {
+    /// True when the probe performed the operation successfully.
+    pub allowed: bool,
+    /// True when the probe ran under the cell's sandbox profile.
+    pub sandboxed: bool,
+    /// Probe error output explaining a denial, when any.
+    pub detail: Option<String>,
+}
+
 /// Errors emitted by platform backends.
 #[derive(Debug, Error)]
 pub enum PlatformError {
@@ -65,4 +126,7 @@ pub enum PlatformError {
     /// Operation is not supported on this backend.
     #[error("unsupported operation: {0}")]
     Unsupported(String),
+    /// Operation did not finish within its deadline.
+    #[error("timed out: {0}")]
+    Timeout(String),
 }
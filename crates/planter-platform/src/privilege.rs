@@ -0,0 +1,39 @@
+/// Startup decision for a process given its effective uid and `--allow-root`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootPolicy {
+    /// Not running as root; start normally.
+    Unprivileged,
+    /// Running as root because the operator passed `--allow-root`; start with a warning.
+    AllowedRoot,
+    /// Running as root without `--allow-root`; refuse to start.
+    RefusedRoot,
+}
+
+/// Decides whether a process running with effective uid `euid` may start.
+pub fn root_policy(euid: u32, allow_root: bool) -> RootPolicy {
+    match (euid, allow_root) {
+        (0, true) => RootPolicy::AllowedRoot,
+        (0, false) => RootPolicy::RefusedRoot,
+        _ => RootPolicy::Unprivileged,
+    }
+}
+
+/// Returns the current process's effective uid.
+pub fn current_euid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RootPolicy, root_policy};
+
+    #[test]
+    /// Verifies root is refused unless explicitly allowed and other uids always start.
+    fn root_policy_requires_explicit_opt_in() {
+        assert_eq!(root_policy(0, false), RootPolicy::RefusedRoot);
+        assert_eq!(root_policy(0, true), RootPolicy::AllowedRoot);
+        assert_eq!(root_policy(501, false), RootPolicy::Unprivileged);
+        assert_eq!(root_policy(501, true), RootPolicy::Unprivileged);
+    }
+}
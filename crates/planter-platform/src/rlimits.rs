@@ -0,0 +1,321 @@
+use std::{io, ops::RangeInclusive};
+
+use planter_core::ResourceLimits;
+use tokio::process::Command;
+
+use crate::PlatformError;
+
+/// Niceness values accepted for job launches.
+pub const NICE_RANGE: RangeInclusive<i32> = -20..=19;
+
+/// Checks that `cores` names CPUs this host has and that pinning is supported here.
+pub fn check_cpu_affinity(cores: &[usize]) -> Result<(), PlatformError> {
+    if !cfg!(target_os = "linux") {
+        return Err(PlatformError::Unsupported(
+            "cpu_affinity is only supported on Linux".to_string(),
+        ));
+    }
+    if cores.is_empty() {
+        return Err(PlatformError::InvalidInput(
+            "cpu_affinity must name at least one core".to_string(),
+        ));
+    }
+    // SAFETY: sysconf only reads a system configuration value.
+    let configured = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    let available = usize::try_from(configured).unwrap_or(1).max(1);
+    if let Some(core) = cores.iter().find(|core| **core >= available) {
+        return Err(PlatformError::InvalidInput(format!(
+            "cpu_affinity core {core} is out of range; this host has {available} cpu(s)"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that no kernel limit in `limits` asks for more than this process's hard limit.
+///
+/// Without privileges `setrlimit` refuses to raise a hard limit, which would otherwise only
+/// surface as an opaque `EPERM` when the job is spawned.
+pub fn check_rlimits(limits: &ResourceLimits) -> Result<(), PlatformError> {
+    // SAFETY: geteuid only reads process credentials.
+    if unsafe { libc::geteuid() } == 0 {
+        return Ok(());
+    }
+    let plan = [
+        ("max_open_files", libc::RLIMIT_NOFILE, limits.max_open_files),
+        ("max_processes", libc::RLIMIT_NPROC, limits.max_processes),
+        ("max_cpu_seconds", libc::RLIMIT_CPU, limits.max_cpu_seconds),
+    ];
+    for (name, resource, value) in plan {
+        let Some(value) = value else {
+            continue;
+        };
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit only writes the provided struct.
+        if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+            return Err(PlatformError::Io(io::Error::last_os_error()));
+        }
+        let hard = current.rlim_max;
+        if hard != libc::RLIM_INFINITY && value > hard {
+            return Err(PlatformError::InvalidInput(format!(
+                "{name} {value} exceeds the hard limit of {hard}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Installs a `pre_exec` hook applying kernel rlimits, niceness, and CPU affinity from `limits`.
+pub fn apply_rlimits(command: &mut Command, limits: Option<&ResourceLimits>) {
+    let Some(limits) = limits else {
+        return;
+    };
+    let plan = [
+        (libc::RLIMIT_NOFILE, limits.max_open_files),
+        (libc::RLIMIT_NPROC, limits.max_processes),
+        (libc::RLIMIT_CPU, limits.max_cpu_seconds),
+    ];
+    let nice = limits.nice.map(effective_nice);
+    #[cfg(target_os = "linux")]
+    let affinity = limits.cpu_affinity.as_deref().map(cpu_set);
+    #[cfg(not(target_os = "linux"))]
+    let affinity: Option<()> = None;
+    if plan.iter().all(|(_, value)| value.is_none()) && nice.is_none() && affinity.is_none() {
+        return;
+    }
+
+    // SAFETY: the hook runs between fork and exec and only calls async-signal-safe
+    // setrlimit/setpriority/sched_setaffinity.
+    unsafe {
+        command.pre_exec(move || {
+            #[cfg(target_os = "linux")]
+            if let Some(set) = &affinity
+                && libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            for (resource, value) in plan {
+                let Some(value) = value else {
+                    continue;
+                };
+                let limit = libc::rlimit {
+                    rlim_cur: value as libc::rlim_t,
+                    rlim_max: value as libc::rlim_t,
+                };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(nice) = nice
+                && libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) != 0
+            {
+                let err = io::Error::last_os_error();
+                // Raising priority can still be refused (e.g. RLIMIT_NICE); run at the inherited level.
+                if err.raw_os_error() != Some(libc::EPERM)
+                    && err.raw_os_error() != Some(libc::EACCES)
+                {
+                    return Err(err);
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Builds the kernel CPU mask for `cores`, ignoring indices beyond `CPU_SETSIZE`.
+#[cfg(target_os = "linux")]
+fn cpu_set(cores: &[usize]) -> libc::cpu_set_t {
+    // SAFETY: an all-zero `cpu_set_t` is the empty mask, and CPU_SET bounds-checks the index.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for core in cores {
+            libc::CPU_SET(*core, &mut set);
+        }
+        set
+    }
+}
+
+/// Clamps `nice` into range and, without root, to no higher priority than this process has.
+fn effective_nice(nice: i32) -> i32 {
+    let nice = nice.clamp(*NICE_RANGE.start(), *NICE_RANGE.end());
+    // SAFETY: geteuid/getpriority only read process credentials and scheduling state.
+    let (euid, current) = unsafe {
+        (
+            libc::geteuid(),
+            libc::getpriority(libc::PRIO_PROCESS as _, 0),
+        )
+    };
+    if euid != 0 && nice < current {
+        tracing::warn!(
+            requested = nice,
+            applied = current,
+            "raising job priority requires root; clamping niceness"
+        );
+        return current;
+    }
+    nice
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Stdio;
+
+    use planter_core::ResourceLimits;
+    use tokio::process::Command;
+
+    use super::{apply_rlimits, check_cpu_affinity, check_rlimits};
+    use crate::PlatformError;
+
+    /// Builds limits with only the open-file cap set.
+    fn open_files_limit(max_open_files: u64) -> ResourceLimits {
+        ResourceLimits {
+            timeout_ms: None,
+            max_rss_bytes: None,
+            max_log_bytes: None,
+            max_open_files: Some(max_open_files),
+            max_processes: None,
+            max_cpu_seconds: None,
+            nice: None,
+            cpu_affinity: None,
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a tiny `max_open_files` prevents the child from opening many fds.
+    async fn max_open_files_blocks_extra_descriptors() {
+        let mut command = Command::new("/bin/sh");
+        command
+            .arg("-c")
+            .arg("ulimit -n; for fd in 3 4 5 6 7 8 9; do eval \"exec $fd</dev/null\" || exit 7; done")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        apply_rlimits(&mut command, Some(&open_files_limit(5)));
+
+        let output = command.output().await.expect("spawn limited child");
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "5");
+        assert!(!output.status.success());
+    }
+
+    #[tokio::test]
+    /// Verifies absent limits leave the child unrestricted.
+    async fn no_limits_leaves_child_unrestricted() {
+        let mut command = Command::new("/bin/sh");
+        command
+            .arg("-c")
+            .arg("for fd in 3 4 5 6 7 8 9; do eval \"exec $fd</dev/null\" || exit 7; done")
+            .stderr(Stdio::null());
+        apply_rlimits(&mut command, None);
+
+        let status = command.status().await.expect("spawn child");
+
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    /// Verifies `nice` lowers the child's scheduling priority.
+    async fn nice_lowers_child_priority() {
+        let mut command = Command::new("/bin/sh");
+        command
+            .arg("-c")
+            .arg("command -v ps >/dev/null || { echo skip; exit 0; }; ps -o nice= -p $$")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        let limits = ResourceLimits {
+            max_open_files: None,
+            nice: Some(19),
+            ..open_files_limit(0)
+        };
+        apply_rlimits(&mut command, Some(&limits));
+
+        let output = command.output().await.expect("spawn niced child");
+        let reported = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if reported == "skip" {
+            return;
+        }
+
+        assert_eq!(reported, "19");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    /// Verifies `cpu_affinity` pins the child to the requested core.
+    async fn cpu_affinity_pins_child_to_core() {
+        let mut command = Command::new("/bin/sh");
+        command
+            .arg("-c")
+            .arg("cut -d' ' -f39 /proc/self/stat; grep Cpus_allowed_list /proc/self/status")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        let limits = ResourceLimits {
+            max_open_files: None,
+            cpu_affinity: Some(vec![0]),
+            ..open_files_limit(0)
+        };
+        apply_rlimits(&mut command, Some(&limits));
+
+        let output = command.output().await.expect("spawn pinned child");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+
+        assert_eq!(lines.next(), Some("0"));
+        assert_eq!(
+            lines
+                .next()
+                .map(|line| line.split_whitespace().collect::<Vec<_>>()),
+            Some(vec!["Cpus_allowed_list:", "0"])
+        );
+    }
+
+    #[test]
+    /// Verifies affinity checks reject empty lists and cores the host does not have.
+    fn check_cpu_affinity_validates_core_indices() {
+        if !cfg!(target_os = "linux") {
+            assert!(matches!(
+                check_cpu_affinity(&[0]),
+                Err(PlatformError::Unsupported(_))
+            ));
+            return;
+        }
+        check_cpu_affinity(&[0]).expect("core 0 exists");
+        assert!(matches!(
+            check_cpu_affinity(&[]),
+            Err(PlatformError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            check_cpu_affinity(&[usize::MAX]),
+            Err(PlatformError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    /// Verifies limits above the hard limit are rejected by name and lower ones pass.
+    fn check_rlimits_rejects_values_above_hard_limit() {
+        check_rlimits(&open_files_limit(5)).expect("low limit fits");
+        // SAFETY: geteuid only reads process credentials.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit only writes the provided struct.
+        assert_eq!(
+            unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut current) },
+            0
+        );
+        if current.rlim_max == libc::RLIM_INFINITY {
+            return;
+        }
+
+        let Err(PlatformError::InvalidInput(message)) =
+            check_rlimits(&open_files_limit(current.rlim_max + 1))
+        else {
+            panic!("limit above the hard limit should be rejected");
+        };
+        assert!(message.starts_with("max_open_files "), "{message}");
+    }
+}
@@ -0,0 +1,262 @@
+//! Client-side environment self-checks for `planter doctor`.
+
+use std::{fs, io::Write, path::Path};
+
+use planter_core::{HealthStatus, PROTOCOL_VERSION, Request, Response, SANDBOX_EXEC_PATH};
+use planter_ipc::PlanterClient;
+
+/// Outcome category for one doctor check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// Check passed.
+    Pass,
+    /// Check found a degraded but usable setup.
+    Warn,
+    /// Check found a blocking problem.
+    Fail,
+}
+
+/// Result of one doctor check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// Short check label.
+    pub name: &'static str,
+    /// Check outcome.
+    pub status: CheckStatus,
+    /// What was observed.
+    pub detail: String,
+    /// Suggested remediation when the check did not pass.
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    /// Builds a passing result.
+    fn pass(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail,
+            hint: None,
+        }
+    }
+
+    /// Builds a non-passing result with a remediation hint.
+    fn problem(name: &'static str, status: CheckStatus, detail: String, hint: String) -> Self {
+        Self {
+            name,
+            status,
+            detail,
+            hint: Some(hint),
+        }
+    }
+}
+
+/// Runs all doctor checks in report order.
+pub async fn run_checks(socket: &Path, state_dir: &Path) -> Vec<CheckResult> {
+    let mut results = check_daemon(socket).await;
+    results.push(check_state_dir(state_dir));
+    results.push(check_sandbox_exec(Path::new(SANDBOX_EXEC_PATH)));
+    results
+}
+
+/// Checks socket reachability, protocol version, and daemon health.
+pub async fn check_daemon(socket: &Path) -> Vec<CheckResult> {
+    let mut client = match PlanterClient::connect(socket).await {
+        Ok(client) => client,
+        Err(err) => {
+            let hint = if socket.exists() {
+                format!(
+                    "socket exists but nothing is listening; remove the stale {} and restart planterd",
+                    socket.display()
+                )
+            } else {
+                format!(
+                    "start planterd listening on {} or pass the daemon's --socket path",
+                    socket.display()
+                )
+            };
+            return vec![CheckResult::problem(
+                "socket",
+                CheckStatus::Fail,
+                format!("{}: {err}", socket.display()),
+                hint,
+            )];
+        }
+    };
+
+    let mut results = vec![CheckResult::pass(
+        "socket",
+        format!("{} is reachable", socket.display()),
+    )];
+
+    results.push(match client.call(Request::Version {}).await {
+        Ok(Response::Version { daemon, protocol }) if protocol == PROTOCOL_VERSION => {
+            CheckResult::pass(
+                "version",
+                format!("planterd {daemon} (protocol {protocol})"),
+            )
+        }
+        Ok(Response::Version { daemon, protocol }) => CheckResult::problem(
+            "version",
+            CheckStatus::Fail,
+            format!(
+                "planterd {daemon} speaks protocol {protocol}, client speaks {PROTOCOL_VERSION}"
+            ),
+            "upgrade planter and planterd to matching builds".to_string(),
+        ),
+        Ok(other) => unexpected_result("version", &other),
+        Err(err) => ipc_failure("version", &err),
+    });
+
+    results.push(match client.call(Request::Health { deep: true }).await {
+        Ok(Response::Health {
+            status: HealthStatus::Ok,
+            ..
+        }) => CheckResult::pass("health", "ok".to_string()),
+        Ok(Response::Health {
+            status: HealthStatus::Degraded,
+            detail,
+        }) => CheckResult::problem(
+            "health",
+            CheckStatus::Warn,
+            format!("degraded: {}", detail.unwrap_or_default()),
+            "planterd is serving requests but workers are slow or partly unresponsive".to_string(),
+        ),
+        Ok(Response::Health {
+            status: HealthStatus::Unhealthy,
+            detail,
+        }) => CheckResult::problem(
+            "health",
+            CheckStatus::Fail,
+            format!("unhealthy: {}", detail.unwrap_or_default()),
+            "check planterd logs for worker or state errors".to_string(),
+        ),
+        Ok(other) => unexpected_result("health", &other),
+        Err(err) => ipc_failure("health", &err),
+    });
+
+    results
+}
+
+/// Checks that the state directory can be created and written.
+pub fn check_state_dir(state_dir: &Path) -> CheckResult {
+    let probe = state_dir.join(".doctor-probe");
+    let outcome = fs::create_dir_all(state_dir)
+        .and_then(|()| fs::File::create(&probe))
+        .and_then(|mut file| file.write_all(b"ok"))
+        .and_then(|()| fs::remove_file(&probe));
+
+    match outcome {
+        Ok(()) => CheckResult::pass("state dir", format!("{} is writable", state_dir.display())),
+        Err(err) => CheckResult::problem(
+            "state dir",
+            CheckStatus::Fail,
+            format!("{}: {err}", state_dir.display()),
+            "fix permissions or set PLANTER_STATE_DIR to a writable directory".to_string(),
+        ),
+    }
+}
+
+/// Checks whether the sandbox runner is installed.
+pub fn check_sandbox_exec(path: &Path) -> CheckResult {
+    if path.exists() {
+        CheckResult::pass("sandbox", format!("{} found", path.display()))
+    } else {
+        CheckResult::problem(
+            "sandbox",
+            CheckStatus::Warn,
+            format!("{} not found", path.display()),
+            "jobs run unsandboxed in permissive mode; enforced mode will refuse to launch"
+                .to_string(),
+        )
+    }
+}
+
+/// Builds a failing result for an unexpected daemon response.
+fn unexpected_result(name: &'static str, response: &Response) -> CheckResult {
+    CheckResult::problem(
+        name,
+        CheckStatus::Fail,
+        format!("unexpected response: {response:?}"),
+        "upgrade planter and planterd to matching builds".to_string(),
+    )
+}
+
+/// Builds a failing result for a transport error after connecting.
+fn ipc_failure(name: &'static str, err: &planter_ipc::IpcError) -> CheckResult {
+    CheckResult::problem(
+        name,
+        CheckStatus::Fail,
+        err.to_string(),
+        "check planterd logs; the daemon closed the connection".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::{CheckStatus, check_daemon, check_sandbox_exec, check_state_dir};
+
+    #[test]
+    /// Verifies a writable state directory passes and leaves no probe behind.
+    fn state_dir_check_passes_for_writable_dir() {
+        let tmp = tempdir().expect("tempdir");
+        let state_dir = tmp.path().join("state");
+
+        let result = check_state_dir(&state_dir);
+
+        assert_eq!(result.status, CheckStatus::Pass);
+        assert!(state_dir.is_dir());
+        assert!(!state_dir.join(".doctor-probe").exists());
+    }
+
+    #[test]
+    /// Verifies a state path blocked by a regular file fails with a hint.
+    fn state_dir_check_fails_when_path_is_file() {
+        let tmp = tempdir().expect("tempdir");
+        let blocker = tmp.path().join("state");
+        std::fs::write(&blocker, b"not a dir").expect("write blocker");
+
+        let result = check_state_dir(&blocker);
+
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.is_some());
+    }
+
+    #[test]
+    /// Verifies a missing sandbox runner is reported as a warning.
+    fn sandbox_check_warns_when_missing() {
+        let result = check_sandbox_exec(Path::new("/nonexistent/sandbox-exec"));
+        assert_eq!(result.status, CheckStatus::Warn);
+    }
+
+    #[tokio::test]
+    /// Verifies an unreachable socket fails and distinguishes stale sockets.
+    async fn daemon_check_fails_for_unreachable_socket() {
+        let tmp = tempdir().expect("tempdir");
+        let missing = tmp.path().join("missing.sock");
+        let results = check_daemon(&missing).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(
+            results[0]
+                .hint
+                .as_deref()
+                .is_some_and(|hint| hint.contains("start planterd"))
+        );
+
+        let stale = tmp.path().join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&stale).expect("bind stale socket"));
+        let results = check_daemon(&stale).await;
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(
+            results[0]
+                .hint
+                .as_deref()
+                .is_some_and(|hint| hint.contains("stale"))
+        );
+    }
+}
@@ -1,24 +1,59 @@
+mod doctor;
+
 use std::{
     collections::BTreeMap,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     mem::MaybeUninit,
     os::fd::AsRawFd,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::ExitCode,
+    time::{Duration, Instant},
 };
 
-use clap::{Parser, Subcommand};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use clap::{Parser, Subcommand, ValueEnum};
+use doctor::CheckStatus;
 use planter_core::{
-    CellId, CellSpec, CommandSpec, ErrorCode, ExitStatus, JobId, LogStream, Request, Response,
-    SessionId,
+    CellId, CellSpec, CommandSpec, DiagnosticsReport, ErrorCode, EventKind, ExitStatus,
+    FollowChunk, JobId, JobInfo, LogStream, PlanterError, Request, ResourceLimits, Response,
+    SandboxMode, SandboxOperation, SessionId, VERSION_FILE_NAME, VersionFile, default_state_dir,
+    drive_follow, schema::protocol_schema,
 };
-use planter_ipc::PlanterClient;
+use planter_ipc::{IpcError, MultiplexedClient, PlanterClient, WireFormat};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
+    signal::unix::{SignalKind, signal},
     task::JoinError,
 };
 
+/// Default longest time followed output waits in the stdout buffer before being flushed.
+const DEFAULT_FLUSH_MS: u64 = 50;
+/// Buffered follow output that is flushed regardless of the flush interval.
+const MAX_PENDING_OUTPUT_BYTES: usize = 64 * 1024;
+/// Chunk size used for cell file uploads and downloads.
+const CELL_FILE_CHUNK_BYTES: usize = 1024 * 1024;
+/// Longest single `JobWait` request, kept under the client call timeout.
+const JOB_WAIT_SLICE_MS: u64 = 4000;
+/// Marker printed before each stderr line when reading both log streams.
+const STDERR_LINE_PREFIX: &[u8] = b"stderr| ";
+/// Marker ending a line cut at the maximum line length; the rest of the line is dropped.
+const TRUNCATED_LINE_MARKER: &[u8] = b" [line truncated]\n";
+/// Name given to the throwaway cells created by `exec`.
+const EXEC_CELL_NAME: &str = "exec";
+/// Maximum bytes per log read while `exec` follows a job.
+const EXEC_LOG_MAX_BYTES: u32 = 65536;
+/// Follow wait timeout in milliseconds while `exec` follows a job.
+const EXEC_LOG_WAIT_MS: u64 = 1000;
+/// Connection attempts per reconnect after a followed daemon connection drops.
+const RECONNECT_ATTEMPTS: u32 = 10;
+/// Initial delay between reconnect attempts; doubles up to the client's backoff cap.
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+/// Time a kill call allows beyond its grace period for the daemon to answer.
+const KILL_CALL_MARGIN: Duration = Duration::from_secs(5);
+
 /// Root CLI arguments for the planter client binary.
 #[derive(Debug, Parser)]
 #[command(name = "planter", about = "Planter CLI")]
@@ -26,18 +61,84 @@ struct Cli {
     /// Path to daemon unix socket.
     #[arg(long, default_value = "/tmp/planterd.sock")]
     socket: PathBuf,
+    /// Connection attempts before giving up on an unreachable daemon.
+    #[arg(long, default_value_t = 1)]
+    connect_attempts: u32,
+    /// Initial delay between connection attempts in milliseconds; doubles per retry.
+    #[arg(long, default_value_t = 100)]
+    connect_backoff_ms: u64,
+    /// Payload encoding for daemon traffic; json keeps captures readable.
+    #[arg(long, value_enum, default_value_t = WireFormatArg::Cbor)]
+    wire_format: WireFormatArg,
     /// Selected top-level command.
     #[command(subcommand)]
     command: Command,
 }
 
+/// CLI-facing wire format values.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum WireFormatArg {
+    /// Compact CBOR payloads.
+    Cbor,
+    /// Plain JSON payloads.
+    Json,
+}
+
+/// CLI-facing output formats for structured reports and streamed output.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormatArg {
+    /// Human-readable lines, or raw bytes for streamed output.
+    Text,
+    /// Pretty-printed JSON, or one JSON object per line for streamed output.
+    Json,
+}
+
+impl From<WireFormatArg> for WireFormat {
+    fn from(format: WireFormatArg) -> Self {
+        match format {
+            WireFormatArg::Cbor => WireFormat::Cbor,
+            WireFormatArg::Json => WireFormat::Json,
+        }
+    }
+}
+
 /// Top-level CLI command variants.
 #[derive(Debug, Subcommand)]
 enum Command {
+    /// Commands sent to the daemon.
+    #[command(flatten)]
+    Daemon(DaemonCommand),
+    /// Commands that run without a daemon connection.
+    #[command(flatten)]
+    Local(LocalCommand),
+}
+
+/// Commands that run without a daemon connection.
+#[derive(Debug, Subcommand)]
+enum LocalCommand {
+    /// Checks the local environment and daemon connectivity.
+    Doctor,
+    /// Prints the protocol JSON Schema for client code generation.
+    Schema,
+}
+
+/// Commands sent to the daemon.
+#[derive(Debug, Subcommand)]
+enum DaemonCommand {
     /// Prints daemon and protocol versions.
-    Version,
+    Version {
+        /// Read the version planterd recorded in the state dir instead of asking over the socket.
+        #[arg(long)]
+        local: bool,
+    },
     /// Prints daemon health status.
-    Health,
+    Health {
+        /// Also start or ping the shared PTY worker and ping cached cell workers.
+        #[arg(long)]
+        deep: bool,
+    },
+    /// Lists the signal names the daemon accepts.
+    Signals,
     /// Creates a new cell.
     Create {
         /// Friendly cell name.
@@ -46,11 +147,56 @@ enum Command {
         /// Repeated `KEY=VALUE` env values.
         #[arg(long = "env", value_name = "KEY=VALUE")]
         env: Vec<String>,
+        /// Sandbox policy for this cell's jobs; defaults to the daemon's mode.
+        #[arg(long, value_enum)]
+        sandbox_mode: Option<SandboxMode>,
+        /// Repeated daemon env var names whose values jobs inherit.
+        #[arg(long = "inherit-env", value_name = "NAME")]
+        inherit_env: Vec<String>,
+        /// `planter-execd` binary for this cell's worker, from the daemon's `--allow-worker-bin` list.
+        #[arg(long = "worker-bin", value_name = "PATH")]
+        worker_bin_override: Option<String>,
     },
     /// Runs a command in a cell.
     Run {
         /// Target cell id.
         cell_id: String,
+        /// Optional working directory.
+        #[arg(long)]
+        cwd: Option<String>,
+        /// Repeated `KEY=VALUE` env overrides.
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// Hard cap on open file descriptors.
+        #[arg(long)]
+        max_open_files: Option<u64>,
+        /// Hard cap on processes for the job's user.
+        #[arg(long)]
+        max_processes: Option<u64>,
+        /// Hard cap on CPU time in seconds.
+        #[arg(long)]
+        max_cpu_seconds: Option<u64>,
+        /// Scheduling niceness from -20 (highest priority) to 19 (lowest).
+        #[arg(long, allow_negative_numbers = true)]
+        nice: Option<i32>,
+        /// Comma-separated CPU core indices to pin the job to (Linux only).
+        #[arg(long, value_name = "CORES", value_delimiter = ',')]
+        cpu_affinity: Option<Vec<usize>>,
+        /// Derive the job id from the launch spec and reuse a matching job.
+        #[arg(long)]
+        deterministic_id: bool,
+        /// Name the job process after its job id in `ps` output.
+        #[arg(long)]
+        process_title: bool,
+        /// Keep the system temp directory instead of a per-job one inside the cell.
+        #[arg(long)]
+        system_tmp: bool,
+        /// Command argv.
+        #[arg(last = true, required = true, num_args = 1..)]
+        argv: Vec<String>,
+    },
+    /// Runs one command in a throwaway cell, streams its output, and removes the cell.
+    Exec {
         /// Optional working directory.
         #[arg(long)]
         cwd: Option<String>,
@@ -71,12 +217,24 @@ enum Command {
         /// Read stderr instead of stdout.
         #[arg(long)]
         stderr: bool,
+        /// Read stdout and stderr together, marking stderr lines.
+        #[arg(long, conflicts_with = "stderr")]
+        both: bool,
+        /// Longest line kept whole with `--both`; longer lines are cut and marked.
+        #[arg(long, default_value_t = 1024 * 1024)]
+        max_line_bytes: u32,
         /// Maximum bytes per read.
         #[arg(long, default_value_t = 65536)]
         max_bytes: u32,
         /// Follow wait timeout in milliseconds.
         #[arg(long, default_value_t = 1000)]
         wait_ms: u64,
+        /// Output format; json prints one object per chunk with base64 data.
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormatArg,
+        /// Longest milliseconds output waits in the buffer; a terminal also flushes at line ends.
+        #[arg(long, default_value_t = DEFAULT_FLUSH_MS)]
+        flush_ms: u64,
     },
     /// Nested job commands.
     Job {
@@ -96,6 +254,27 @@ enum Command {
         #[command(subcommand)]
         command: SessionCommand,
     },
+    /// Prints a read-only daemon diagnostics bundle to attach to bug reports.
+    Diagnostics {
+        /// Output format.
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormatArg,
+    },
+    /// Backs up or restores daemon cell and job metadata.
+    State {
+        /// State subcommand.
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+    /// Prints recent lifecycle events from the daemon journal.
+    Events {
+        /// Only show events at or after this UNIX millisecond timestamp.
+        #[arg(long, default_value_t = 0)]
+        since_ms: u64,
+        /// Maximum number of most recent events to show.
+        #[arg(long, default_value_t = 100)]
+        limit: u32,
+    },
 }
 
 /// Subcommands for existing jobs.
@@ -106,6 +285,14 @@ enum JobCommand {
         /// Target job id.
         job_id: String,
     },
+    /// Blocks until a job finishes and prints its final status.
+    Wait {
+        /// Target job id.
+        job_id: String,
+        /// Give up and print the running status after this many milliseconds.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+    },
     /// Terminates a running job.
     Kill {
         /// Target job id.
@@ -113,12 +300,55 @@ enum JobCommand {
         /// Force kill instead of graceful terminate.
         #[arg(long)]
         force: bool,
+        /// Milliseconds to wait between TERM and KILL; defaults to the daemon setting.
+        #[arg(long, conflicts_with = "force")]
+        grace_ms: Option<u64>,
+    },
+    /// Clears a job's logs without stopping it.
+    TruncateLogs {
+        /// Target job id.
+        job_id: String,
+        /// Only truncate stdout.
+        #[arg(long, conflicts_with = "stderr")]
+        stdout: bool,
+        /// Only truncate stderr.
+        #[arg(long)]
+        stderr: bool,
+    },
+    /// Flushes a job's logs to disk.
+    SyncLogs {
+        /// Target job id.
+        job_id: String,
+    },
+    /// Prints a job's log sizes and log quota.
+    LogsInfo {
+        /// Target job id.
+        job_id: String,
+    },
+}
+
+/// Subcommands for daemon state backups.
+#[derive(Debug, Subcommand)]
+enum StateCommand {
+    /// Prints a JSON snapshot of every cell and job record.
+    Snapshot,
+    /// Recreates the cell and job records from a snapshot file.
+    Restore {
+        /// Snapshot file written by `state snapshot`.
+        file: PathBuf,
     },
 }
 
 /// Subcommands for cells.
 #[derive(Debug, Subcommand)]
 enum CellCommand {
+    /// Changes a cell's name without touching its id or files.
+    Rename {
+        /// Target cell id.
+        cell_id: String,
+        /// New friendly name.
+        new_name: String,
+    },
     /// Removes a cell.
     Rm {
         /// Target cell id.
@@ -127,6 +357,54 @@ enum CellCommand {
         #[arg(long)]
         force: bool,
     },
+    /// Stops new jobs, waits for running jobs, then removes a cell.
+    Drain {
+        /// Target cell id.
+        cell_id: String,
+        /// Maximum time to wait for running jobs in milliseconds.
+        #[arg(long, default_value_t = 30000)]
+        timeout_ms: u64,
+    },
+    /// Replaces a cell's worker, marking jobs it was running as terminated.
+    RestartWorker {
+        /// Target cell id.
+        cell_id: String,
+    },
+    /// Checks whether the cell's sandbox lets jobs read or write a path.
+    SandboxCheck {
+        /// Target cell id.
+        cell_id: String,
+        /// Absolute path to probe.
+        path: String,
+        /// Check writing instead of reading.
+        #[arg(long)]
+        write: bool,
+    },
+    /// Uploads a local file into a cell directory.
+    Put {
+        /// Target cell id.
+        cell_id: String,
+        /// Local file to upload.
+        local_path: PathBuf,
+        /// Destination path relative to the cell directory.
+        relative_path: String,
+    },
+    /// Downloads a file from a cell directory.
+    Get {
+        /// Source cell id.
+        cell_id: String,
+        /// File path relative to the cell directory.
+        relative_path: String,
+        /// Local destination file.
+        local_path: PathBuf,
+    },
+    /// Lists files in a cell directory.
+    Ls {
+        /// Source cell id.
+        cell_id: String,
+        /// Directory relative to the cell directory; the cell root when omitted.
+        subpath: Option<String>,
+    },
 }
 
 /// Subcommands for interactive PTY sessions.
@@ -149,6 +427,12 @@ enum SessionCommand {
         /// Initial terminal rows.
         #[arg(long, default_value_t = 40)]
         rows: u16,
+        /// Allow `cd` outside the build cell and keep the shell's own prompt hooks.
+        #[arg(long)]
+        no_jail_cwd: bool,
+        /// Keep full session output on disk so old scrollback stays readable.
+        #[arg(long)]
+        persist_scrollback: bool,
         /// Additional shell args.
         #[arg(last = true)]
         args: Vec<String>,
@@ -169,6 +453,15 @@ enum SessionCommand {
         /// Follow wait timeout in milliseconds.
         #[arg(long, default_value_t = 250)]
         wait_ms: u64,
+        /// Start from the last N buffered bytes instead of `--offset`.
+        #[arg(long)]
+        tail: Option<u32>,
+        /// Output format; json prints one object per chunk with base64 data.
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormatArg,
+        /// Longest milliseconds output waits in the buffer; a terminal also flushes at line ends.
+        #[arg(long, default_value_t = DEFAULT_FLUSH_MS)]
+        flush_ms: u64,
     },
     /// Writes a string to PTY input.
     Write {
@@ -194,6 +487,17 @@ enum SessionCommand {
         #[arg(long)]
         force: bool,
     },
+    /// Closes every PTY session idle longer than a threshold.
+    Reap {
+        /// Idle threshold such as `500ms`, `30s`, `10m`, or `2h`.
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_ms)]
+        idle: u64,
+    },
+    /// Prints the macOS sandbox profile a PTY session runs under.
+    SandboxProfile {
+        /// Session id; renders the profile for the next session to open when omitted.
+        session_id: Option<u64>,
+    },
     /// Attaches local terminal I/O to a PTY session.
     Attach {
         /// Session id.
@@ -204,6 +508,12 @@ enum SessionCommand {
         /// Terminal rows.
         #[arg(long, default_value_t = 40)]
         rows: u16,
+        /// Replay only the last N buffered bytes on attach.
+        #[arg(long)]
+        tail: Option<u32>,
+        /// Close the session on stdin EOF or Ctrl-C instead of detaching and leaving it running.
+        #[arg(long)]
+        kill_on_exit: bool,
     },
 }
 
@@ -229,6 +539,26 @@ enum CliError {
         /// Optional formatted detail string.
         detail: String,
     },
+    /// Recorded daemon version could not be read.
+    #[error("cannot read {path}: {source}; has planterd started with this state dir?")]
+    LocalVersion {
+        /// Version file path.
+        path: String,
+        /// Underlying read or parse failure.
+        source: io::Error,
+    },
+    /// Daemon connection dropped while attached; PTY sessions do not survive a restart.
+    #[error("lost connection to planterd; pty session {session_id} is gone")]
+    SessionLost {
+        /// Attached session id.
+        session_id: u64,
+    },
+    /// One or more doctor checks failed.
+    #[error("doctor found {failed} failing check(s)")]
+    DoctorFailed { failed: usize },
+    /// A job run by `exec` finished unsuccessfully; the CLI exits with its code.
+    #[error("job exited with {}", code.map_or_else(|| "no exit code".to_string(), |code| format!("code {code}")))]
+    JobFailed { code: Option<i32> },
     /// Env flag failed `KEY=VALUE` parsing.
     #[error("invalid env var '{value}': expected KEY=VALUE")]
     InvalidEnv { value: String },
@@ -247,6 +577,13 @@ enum CliError {
 async fn main() -> ExitCode {
     match run().await {
         Ok(()) => ExitCode::SUCCESS,
+        // The job's own output already explains the failure; just pass its code through.
+        Err(CliError::JobFailed { code: Some(code) }) => ExitCode::from(
+            u8::try_from(code)
+                .ok()
+                .filter(|code| *code != 0)
+                .unwrap_or(1),
+        ),
         Err(err) => {
             eprintln!("{err}");
             ExitCode::from(1)
@@ -257,10 +594,25 @@ async fn main() -> ExitCode {
 /// Parses CLI args, executes selected command, and prints command output.
 async fn run() -> Result<(), CliError> {
     let cli = Cli::parse();
-    let mut client = PlanterClient::connect(&cli.socket).await?;
+    let command = match cli.command {
+        Command::Local(LocalCommand::Doctor) => return run_doctor(&cli.socket).await,
+        Command::Local(LocalCommand::Schema) => return print_schema(),
+        Command::Daemon(DaemonCommand::Version { local: true }) => {
+            return print_local_version(&default_state_dir());
+        }
+        Command::Daemon(command) => command,
+    };
+    let wire_format = WireFormat::from(cli.wire_format);
+    let mut client = PlanterClient::connect_with_retry(
+        &cli.socket,
+        cli.connect_attempts,
+        Duration::from_millis(cli.connect_backoff_ms),
+    )
+    .await?
+    .with_wire_format(wire_format);
 
-    match cli.command {
-        Command::Version => {
+    match command {
+        DaemonCommand::Version { .. } => {
             let response = client.call(Request::Version {}).await?;
             match response {
                 Response::Version { daemon, protocol } => {
@@ -282,11 +634,14 @@ async fn run() -> Result<(), CliError> {
                 }),
             }
         }
-        Command::Health => {
-            let response = client.call(Request::Health {}).await?;
+        DaemonCommand::Health { deep } => {
+            let response = client.call(Request::Health { deep }).await?;
             match response {
-                Response::Health { status } => {
-                    println!("{status}");
+                Response::Health { status, detail } => {
+                    match detail {
+                        Some(detail) => println!("{}: {detail}", serde_label(&status)),
+                        None => println!("{}", serde_label(&status)),
+                    }
                     Ok(())
                 }
                 Response::Error {
@@ -304,12 +659,45 @@ async fn run() -> Result<(), CliError> {
                 }),
             }
         }
-        Command::Create { name, env } => {
+        DaemonCommand::Signals => {
+            let response = client.call(Request::SupportedSignals {}).await?;
+            match response {
+                Response::SupportedSignals { signals } => {
+                    for signal in signals {
+                        println!("{} {}", signal.name, signal.number);
+                    }
+                    Ok(())
+                }
+                Response::Error {
+                    code,
+                    message,
+                    detail,
+                } => Err(CliError::Daemon {
+                    code,
+                    message,
+                    detail: format_detail(detail),
+                }),
+                other => Err(CliError::Unexpected {
+                    command: "signals",
+                    response: Box::new(other),
+                }),
+            }
+        }
+        DaemonCommand::Create {
+            name,
+            env,
+            sandbox_mode,
+            inherit_env,
+            worker_bin_override,
+        } => {
             let response = client
                 .call(Request::CellCreate {
                     spec: CellSpec {
                         name,
                         env: parse_env_pairs(env)?,
+                        sandbox_mode,
+                        inherit_host_env: inherit_env,
+                        worker_bin_override,
                     },
                 })
                 .await?;
@@ -334,12 +722,35 @@ async fn run() -> Result<(), CliError> {
                 }),
             }
         }
-        Command::Run {
+        DaemonCommand::Run {
             cell_id,
             cwd,
             env,
+            max_open_files,
+            max_processes,
+            max_cpu_seconds,
+            nice,
+            cpu_affinity,
+            deterministic_id,
+            process_title,
+            system_tmp,
             argv,
         } => {
+            let limits = (max_open_files.is_some()
+                || max_processes.is_some()
+                || max_cpu_seconds.is_some()
+                || nice.is_some()
+                || cpu_affinity.is_some())
+            .then_some(ResourceLimits {
+                timeout_ms: None,
+                max_rss_bytes: None,
+                max_log_bytes: None,
+                max_open_files,
+                max_processes,
+                max_cpu_seconds,
+                nice,
+                cpu_affinity,
+            });
             let response = client
                 .call(Request::JobRun {
                     cell_id: CellId(cell_id),
@@ -347,8 +758,11 @@ async fn run() -> Result<(), CliError> {
                         argv,
                         cwd,
                         env: parse_env_pairs(env)?,
-                        limits: None,
+                        limits,
                     },
+                    deterministic_id,
+                    process_title,
+                    system_tmp,
                 })
                 .await?;
 
@@ -372,28 +786,110 @@ async fn run() -> Result<(), CliError> {
                 }),
             }
         }
-        Command::Logs {
+        DaemonCommand::Exec { cwd, env, argv } => {
+            let cmd = CommandSpec {
+                argv,
+                cwd,
+                env: parse_env_pairs(env)?,
+                limits: None,
+            };
+            exec_ephemeral(
+                &mut client,
+                &cli.socket,
+                wire_format,
+                cmd,
+                tokio::signal::ctrl_c(),
+            )
+            .await
+        }
+        DaemonCommand::Logs {
             job_id,
             follow,
             stderr,
+            both,
+            max_line_bytes,
             max_bytes,
             wait_ms,
+            output,
+            flush_ms,
         } => {
-            stream_logs(
-                &mut client,
-                &JobId(job_id),
-                if stderr {
-                    LogStream::Stderr
+            let job_id = JobId(job_id);
+            let reconnect = follow.then_some((cli.socket.as_path(), wire_format));
+            let output = FollowOutput {
+                format: output,
+                flush: FlushPolicy::for_stdout(Duration::from_millis(flush_ms)),
+            };
+            // Owned out here so output still buffered when Ctrl-C drops the stream is written.
+            let mut buffers = [
+                FollowStdout::new(output.flush),
+                FollowStdout::new(output.flush),
+            ];
+            let [stdout_buffer, stderr_buffer] = &mut buffers;
+            let stream = async {
+                if both {
+                    let stderr_client = PlanterClient::connect(&cli.socket)
+                        .await?
+                        .with_wire_format(wire_format);
+                    stream_both_logs(
+                        [
+                            (LogConnection::new(client, reconnect), stdout_buffer),
+                            (LogConnection::new(stderr_client, reconnect), stderr_buffer),
+                        ],
+                        &job_id,
+                        follow,
+                        max_bytes,
+                        wait_ms,
+                        max_line_bytes,
+                        output,
+                    )
+                    .await
                 } else {
-                    LogStream::Stdout
-                },
-                follow,
-                max_bytes,
-                wait_ms,
-            )
-            .await
+                    let stream = if stderr {
+                        LogStream::Stderr
+                    } else {
+                        LogStream::Stdout
+                    };
+                    let stdout = stdout_buffer;
+                    stream_logs(
+                        &mut LogConnection::new(client, reconnect),
+                        &job_id,
+                        stream,
+                        follow,
+                        max_bytes,
+                        wait_ms,
+                        &mut |chunk| match output.format {
+                            OutputFormatArg::Text => stdout.write(&chunk.data, chunk),
+                            OutputFormatArg::Json => stdout.write(
+                                &chunk_record_line(
+                                    ChunkSource::Log {
+                                        job_id: &job_id,
+                                        stream,
+                                    },
+                                    chunk,
+                                )?,
+                                chunk,
+                            ),
+                        },
+                    )
+                    .await?;
+                    stdout.flush()?;
+                    Ok(())
+                }
+            };
+            let result = if follow {
+                let mut killer = SocketJobKiller {
+                    socket: &cli.socket,
+                    wire_format,
+                };
+                kill_on_interrupt(stream, tokio::signal::ctrl_c(), &mut killer, &job_id).await
+            } else {
+                stream.await
+            };
+            let flushed = buffers.iter_mut().try_for_each(FollowStdout::flush);
+            result?;
+            Ok(flushed?)
         }
-        Command::Job { command } => match command {
+        DaemonCommand::Job { command } => match command {
             JobCommand::Status { job_id } => {
                 let response = client
                     .call(Request::JobStatus {
@@ -402,16 +898,7 @@ async fn run() -> Result<(), CliError> {
                     .await?;
                 match response {
                     Response::JobStatus { job } => {
-                        let status = match job.status {
-                            ExitStatus::Running => "running".to_string(),
-                            ExitStatus::Exited { code } => {
-                                format!(
-                                    "exited({})",
-                                    code.map_or_else(|| "none".to_string(), |c| c.to_string())
-                                )
-                            }
-                        };
-                        println!("{} {}", job.id.0, status);
+                        print_job_status(&job);
                         Ok(())
                     }
                     Response::Error {
@@ -429,18 +916,39 @@ async fn run() -> Result<(), CliError> {
                     }),
                 }
             }
-            JobCommand::Kill { job_id, force } => {
+            JobCommand::Wait { job_id, timeout_ms } => {
+                let job = wait_for_job(&mut client, &JobId(job_id), timeout_ms).await?;
+                print_job_status(&job);
+                Ok(())
+            }
+            JobCommand::Kill {
+                job_id,
+                force,
+                grace_ms,
+            } => {
                 let response = client
+                    .with_timeout(kill_call_timeout(grace_ms))
                     .call(Request::JobKill {
                         job_id: JobId(job_id),
                         force,
+                        grace_ms,
                     })
                     .await?;
                 match response {
+                    Response::JobKilled {
+                        job_id,
+                        status,
+                        delivered: false,
+                        ..
+                    } => {
+                        println!("{} already exited {:?}", job_id.0, status);
+                        Ok(())
+                    }
                     Response::JobKilled {
                         job_id,
                         signal,
                         status,
+                        ..
                     } => {
                         println!("{} {} {:?}", job_id.0, signal, status);
                         Ok(())
@@ -460,18 +968,25 @@ async fn run() -> Result<(), CliError> {
                     }),
                 }
             }
-        },
-        Command::Cell { command } => match command {
-            CellCommand::Rm { cell_id, force } => {
+            JobCommand::TruncateLogs {
+                job_id,
+                stdout,
+                stderr,
+            } => {
+                let stream = match (stdout, stderr) {
+                    (true, _) => Some(LogStream::Stdout),
+                    (_, true) => Some(LogStream::Stderr),
+                    _ => None,
+                };
                 let response = client
-                    .call(Request::CellRemove {
-                        cell_id: CellId(cell_id),
-                        force,
+                    .call(Request::LogsTruncate {
+                        job_id: JobId(job_id),
+                        stream,
                     })
                     .await?;
                 match response {
-                    Response::CellRemoved { cell_id } => {
-                        println!("{}", cell_id.0);
+                    Response::LogsTruncated { job_id, .. } => {
+                        println!("{}", job_id.0);
                         Ok(())
                     }
                     Response::Error {
@@ -484,37 +999,20 @@ async fn run() -> Result<(), CliError> {
                         detail: format_detail(detail),
                     }),
                     other => Err(CliError::Unexpected {
-                        command: "cell rm",
+                        command: "job truncate-logs",
                         response: Box::new(other),
                     }),
                 }
             }
-        },
-        Command::Session { command } => match command {
-            SessionCommand::Open {
-                shell,
-                cwd,
-                env,
-                cols,
-                rows,
-                args,
-            } => {
+            JobCommand::SyncLogs { job_id } => {
                 let response = client
-                    .call(Request::PtyOpen {
-                        shell,
-                        args,
-                        cwd,
-                        env: parse_env_pairs(env)?,
-                        cols,
-                        rows,
+                    .call(Request::LogsSync {
+                        job_id: JobId(job_id),
                     })
                     .await?;
                 match response {
-                    Response::PtyOpened { session_id, pid } => {
-                        match pid {
-                            Some(pid) => println!("{} {}", session_id.0, pid),
-                            None => println!("{}", session_id.0),
-                        }
+                    Response::LogsSynced { job_id } => {
+                        println!("{}", job_id.0);
                         Ok(())
                     }
                     Response::Error {
@@ -527,37 +1025,34 @@ async fn run() -> Result<(), CliError> {
                         detail: format_detail(detail),
                     }),
                     other => Err(CliError::Unexpected {
-                        command: "session open",
+                        command: "job sync-logs",
                         response: Box::new(other),
                     }),
                 }
             }
-            SessionCommand::Read {
-                session_id,
-                offset,
-                max_bytes,
-                follow,
-                wait_ms,
-            } => {
-                stream_pty(
-                    &mut client,
-                    SessionId(session_id),
-                    offset,
-                    max_bytes,
-                    follow,
-                    wait_ms,
-                )
-                .await
-            }
-            SessionCommand::Write { session_id, data } => {
+            JobCommand::LogsInfo { job_id } => {
                 let response = client
-                    .call(Request::PtyInput {
-                        session_id: SessionId(session_id),
-                        data: data.into_bytes(),
+                    .call(Request::LogsInfo {
+                        job_id: JobId(job_id),
                     })
                     .await?;
                 match response {
-                    Response::PtyAck { .. } => Ok(()),
+                    Response::LogsInfo {
+                        stdout,
+                        stderr,
+                        total_bytes,
+                        max_log_bytes,
+                        ..
+                    } => {
+                        for (name, info) in [("stdout", stdout), ("stderr", stderr)] {
+                            println!("{name}: {} bytes", info.current_bytes);
+                        }
+                        match max_log_bytes {
+                            Some(max) => println!("total: {total_bytes} of {max} bytes"),
+                            None => println!("total: {total_bytes} bytes"),
+                        }
+                        Ok(())
+                    }
                     Response::Error {
                         code,
                         message,
@@ -568,25 +1063,25 @@ async fn run() -> Result<(), CliError> {
                         detail: format_detail(detail),
                     }),
                     other => Err(CliError::Unexpected {
-                        command: "session write",
+                        command: "job logs-info",
                         response: Box::new(other),
                     }),
                 }
             }
-            SessionCommand::Resize {
-                session_id,
-                cols,
-                rows,
-            } => {
+        },
+        DaemonCommand::Cell { command } => match command {
+            CellCommand::Rename { cell_id, new_name } => {
                 let response = client
-                    .call(Request::PtyResize {
-                        session_id: SessionId(session_id),
-                        cols,
-                        rows,
+                    .call(Request::CellRename {
+                        cell_id: CellId(cell_id),
+                        new_name,
                     })
                     .await?;
                 match response {
-                    Response::PtyAck { .. } => Ok(()),
+                    Response::CellRenamed { cell } => {
+                        println!("{} {}", cell.id.0, cell.spec.name);
+                        Ok(())
+                    }
                     Response::Error {
                         code,
                         message,
@@ -597,20 +1092,23 @@ async fn run() -> Result<(), CliError> {
                         detail: format_detail(detail),
                     }),
                     other => Err(CliError::Unexpected {
-                        command: "session resize",
+                        command: "cell rename",
                         response: Box::new(other),
                     }),
                 }
             }
-            SessionCommand::Close { session_id, force } => {
+            CellCommand::Rm { cell_id, force } => {
                 let response = client
-                    .call(Request::PtyClose {
-                        session_id: SessionId(session_id),
+                    .call(Request::CellRemove {
+                        cell_id: CellId(cell_id),
                         force,
                     })
                     .await?;
                 match response {
-                    Response::PtyAck { .. } => Ok(()),
+                    Response::CellRemoved { cell_id } => {
+                        println!("{}", cell_id.0);
+                        Ok(())
+                    }
                     Response::Error {
                         code,
                         message,
@@ -621,59 +1119,1187 @@ async fn run() -> Result<(), CliError> {
                         detail: format_detail(detail),
                     }),
                     other => Err(CliError::Unexpected {
-                        command: "session close",
+                        command: "cell rm",
                         response: Box::new(other),
                     }),
                 }
             }
-            SessionCommand::Attach {
-                session_id,
-                cols,
-                rows,
-            } => attach_session(&cli.socket, SessionId(session_id), cols, rows).await,
-        },
-    }
-}
-
-/// Streams log chunks until completion (or once when not following).
-async fn stream_logs(
-    client: &mut PlanterClient,
-    job_id: &JobId,
-    stream: LogStream,
-    follow: bool,
-    max_bytes: u32,
-    wait_ms: u64,
-) -> Result<(), CliError> {
-    let mut offset: u64 = 0;
-
-    loop {
-        let response = client
-            .call(Request::LogsRead {
-                job_id: job_id.clone(),
-                stream,
-                offset,
+            CellCommand::Drain {
+                cell_id,
+                timeout_ms,
+            } => {
+                let response = client
+                    .call(Request::CellDrain {
+                        cell_id: CellId(cell_id),
+                        timeout_ms,
+                    })
+                    .await?;
+                match response {
+                    Response::CellDrained {
+                        cell_id,
+                        removed,
+                        pending_jobs,
+                    } => {
+                        if removed {
+                            println!("{}", cell_id.0);
+                        } else {
+                            let pending = pending_jobs
+                                .iter()
+                                .map(|job_id| job_id.0.as_str())
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            println!(
+                                "{} drain timed out and the cell accepts jobs again; still running: {pending}",
+                                cell_id.0
+                            );
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "cell drain",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            CellCommand::RestartWorker { cell_id } => {
+                let response = client
+                    .call(Request::WorkerRestart {
+                        cell_id: CellId(cell_id),
+                    })
+                    .await?;
+                match response {
+                    Response::WorkerRestarted {
+                        cell_id,
+                        terminated_jobs,
+                    } => {
+                        if terminated_jobs.is_empty() {
+                            println!("{}", cell_id.0);
+                        } else {
+                            let terminated = terminated_jobs
+                                .iter()
+                                .map(|job_id| job_id.0.as_str())
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            println!("{} restarted; terminated: {terminated}", cell_id.0);
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "cell restart-worker",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            CellCommand::SandboxCheck {
+                cell_id,
+                path,
+                write,
+            } => {
+                let response = client
+                    .call(Request::SandboxCheck {
+                        cell_id: CellId(cell_id),
+                        path,
+                        operation: if write {
+                            SandboxOperation::Write
+                        } else {
+                            SandboxOperation::Read
+                        },
+                    })
+                    .await?;
+                match response {
+                    Response::SandboxChecked {
+                        allowed,
+                        sandboxed,
+                        detail,
+                        ..
+                    } => {
+                        let decision = if allowed { "allowed" } else { "denied" };
+                        if sandboxed {
+                            println!("{decision}");
+                        } else {
+                            println!("{decision} (cell jobs are not sandboxed)");
+                        }
+                        if let Some(detail) = detail {
+                            println!("  {detail}");
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "cell sandbox-check",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            CellCommand::Put {
+                cell_id,
+                local_path,
+                relative_path,
+            } => put_cell_file(&mut client, CellId(cell_id), &local_path, relative_path).await,
+            CellCommand::Get {
+                cell_id,
+                relative_path,
+                local_path,
+            } => get_cell_file(&mut client, CellId(cell_id), relative_path, &local_path).await,
+            CellCommand::Ls { cell_id, subpath } => {
+                let response = client
+                    .call(Request::CellListFiles {
+                        cell_id: CellId(cell_id),
+                        subpath,
+                    })
+                    .await?;
+                match response {
+                    Response::CellFiles {
+                        entries, truncated, ..
+                    } => {
+                        for entry in entries {
+                            let kind = if entry.is_dir { 'd' } else { '-' };
+                            println!("{kind} {:>12} {}", entry.size, entry.name);
+                        }
+                        if truncated {
+                            eprintln!("listing truncated; list a subdirectory to see more");
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "cell ls",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+        },
+        DaemonCommand::Session { command } => match command {
+            SessionCommand::Open {
+                shell,
+                cwd,
+                env,
+                cols,
+                rows,
+                no_jail_cwd,
+                persist_scrollback,
+                args,
+            } => {
+                let response = client
+                    .call(Request::PtyOpen {
+                        shell,
+                        args,
+                        cwd,
+                        env: parse_env_pairs(env)?,
+                        cols,
+                        rows,
+                        jail_cwd: !no_jail_cwd,
+                        persist_scrollback,
+                    })
+                    .await?;
+                match response {
+                    Response::PtyOpened { session_id, pid } => {
+                        match pid {
+                            Some(pid) => println!("{} {}", session_id.0, pid),
+                            None => println!("{}", session_id.0),
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session open",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::Read {
+                session_id,
+                offset,
                 max_bytes,
                 follow,
                 wait_ms,
+                tail,
+                output,
+                flush_ms,
+            } => {
+                stream_pty(
+                    &mut client,
+                    SessionId(session_id),
+                    SessionReadOptions {
+                        offset,
+                        max_bytes,
+                        follow,
+                        wait_ms,
+                        tail,
+                        output: FollowOutput {
+                            format: output,
+                            flush: FlushPolicy::for_stdout(Duration::from_millis(flush_ms)),
+                        },
+                    },
+                )
+                .await
+            }
+            SessionCommand::Write { session_id, data } => {
+                let response = client
+                    .call(Request::PtyInput {
+                        session_id: SessionId(session_id),
+                        data: data.into_bytes(),
+                    })
+                    .await?;
+                match response {
+                    Response::PtyAck { .. } => Ok(()),
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session write",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::Resize {
+                session_id,
+                cols,
+                rows,
+            } => {
+                let response = client
+                    .call(Request::PtyResize {
+                        session_id: SessionId(session_id),
+                        cols,
+                        rows,
+                    })
+                    .await?;
+                match response {
+                    Response::PtyAck { .. } => Ok(()),
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session resize",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::Close { session_id, force } => {
+                let response = client
+                    .call(Request::PtyClose {
+                        session_id: SessionId(session_id),
+                        force,
+                    })
+                    .await?;
+                match response {
+                    Response::PtyAck { .. } => Ok(()),
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session close",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::Reap { idle } => {
+                let response = client.call(Request::PtyReap { idle_ms: idle }).await?;
+                match response {
+                    Response::PtyReaped { session_ids } => {
+                        for session_id in session_ids {
+                            println!("{}", session_id.0);
+                        }
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session reap",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::SandboxProfile { session_id } => {
+                let response = client
+                    .call(Request::PtySandboxProfile {
+                        session_id: session_id.map(SessionId),
+                    })
+                    .await?;
+                match response {
+                    Response::PtySandboxProfileRendered { profile, .. } => {
+                        print!("{profile}");
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "session sandbox-profile",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            SessionCommand::Attach {
+                session_id,
+                cols,
+                rows,
+                tail,
+                kill_on_exit,
+            } => {
+                attach_session(
+                    &cli.socket,
+                    wire_format,
+                    SessionId(session_id),
+                    AttachOptions {
+                        cols,
+                        rows,
+                        tail,
+                        kill_on_exit,
+                    },
+                )
+                .await
+            }
+        },
+        DaemonCommand::Diagnostics { output } => {
+            let response = client.call(Request::Diagnostics {}).await?;
+            match response {
+                Response::Diagnostics { report } => print_diagnostics(&report, output),
+                Response::Error {
+                    code,
+                    message,
+                    detail,
+                } => Err(CliError::Daemon {
+                    code,
+                    message,
+                    detail: format_detail(detail),
+                }),
+                other => Err(CliError::Unexpected {
+                    command: "diagnostics",
+                    response: Box::new(other),
+                }),
+            }
+        }
+        DaemonCommand::State { command } => match command {
+            StateCommand::Snapshot => {
+                let response = client.call(Request::StateSnapshot {}).await?;
+                match response {
+                    Response::StateSnapshot { snapshot } => {
+                        let mut stdout = io::stdout().lock();
+                        serde_json::to_writer_pretty(&mut stdout, &snapshot)
+                            .map_err(io::Error::from)?;
+                        writeln!(stdout)?;
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "state snapshot",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+            StateCommand::Restore { file } => {
+                let snapshot =
+                    serde_json::from_slice(&std::fs::read(&file)?).map_err(io::Error::from)?;
+                let response = client.call(Request::StateRestore { snapshot }).await?;
+                match response {
+                    Response::StateRestored { cells, jobs } => {
+                        println!("restored cells={cells} jobs={jobs}");
+                        Ok(())
+                    }
+                    Response::Error {
+                        code,
+                        message,
+                        detail,
+                    } => Err(CliError::Daemon {
+                        code,
+                        message,
+                        detail: format_detail(detail),
+                    }),
+                    other => Err(CliError::Unexpected {
+                        command: "state restore",
+                        response: Box::new(other),
+                    }),
+                }
+            }
+        },
+        DaemonCommand::Events { since_ms, limit } => {
+            let response = client.call(Request::Events { since_ms, limit }).await?;
+            match response {
+                Response::Events { events } => {
+                    for event in events {
+                        println!(
+                            "{} {} cell={} job={} {}",
+                            event.ts_ms,
+                            event_kind_label(event.kind),
+                            event.cell_id.map_or_else(|| "-".to_string(), |id| id.0),
+                            event.job_id.map_or_else(|| "-".to_string(), |id| id.0),
+                            event.detail.unwrap_or_default()
+                        );
+                    }
+                    Ok(())
+                }
+                Response::Error {
+                    code,
+                    message,
+                    detail,
+                } => Err(CliError::Daemon {
+                    code,
+                    message,
+                    detail: format_detail(detail),
+                }),
+                other => Err(CliError::Unexpected {
+                    command: "events",
+                    response: Box::new(other),
+                }),
+            }
+        }
+    }
+}
+
+/// Prints a diagnostics bundle as text lines or pretty JSON.
+fn print_diagnostics(report: &DiagnosticsReport, output: OutputFormatArg) -> Result<(), CliError> {
+    let mut stdout = io::stdout().lock();
+    if let OutputFormatArg::Json = output {
+        serde_json::to_writer_pretty(&mut stdout, report).map_err(io::Error::from)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    let daemon = &report.daemon;
+    writeln!(
+        stdout,
+        "daemon: planterd {} protocol={} exec_protocol={} state_root={}",
+        daemon.version, daemon.protocol, daemon.exec_protocol, daemon.state_root
+    )?;
+    writeln!(
+        stdout,
+        "config: default_kill_grace_ms={} max_running_jobs={} max_pty_input_bytes={} eager_worker_shutdown={}",
+        daemon.default_kill_grace_ms,
+        daemon
+            .max_running_jobs
+            .map_or_else(|| "-".to_string(), |max| max.to_string()),
+        daemon.max_pty_input_bytes,
+        daemon.eager_worker_shutdown
+    )?;
+    writeln!(
+        stdout,
+        "ipc: largest_frame_bytes={}",
+        daemon.largest_frame_bytes
+    )?;
+    let resources = &report.resources;
+    writeln!(
+        stdout,
+        "resources: cells={} jobs={} running_jobs={} pty_sessions={}",
+        resources.cells, resources.jobs, resources.running_jobs, resources.pty_sessions
+    )?;
+    writeln!(
+        stdout,
+        "sandbox: mode={} sandbox_exec_available={}",
+        serde_label(&report.sandbox.mode),
+        report.sandbox.sandbox_exec_available
+    )?;
+    for worker in &report.workers {
+        writeln!(
+            stdout,
+            "worker: {} {}",
+            worker.cell_id.0,
+            serde_label(&worker.mode)
+        )?;
+    }
+    if report.errors.is_empty() {
+        writeln!(stdout, "errors: none")?;
+    }
+    for error in &report.errors {
+        writeln!(
+            stdout,
+            "errors: {}={}",
+            serde_label(&error.code),
+            error.count
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the wire name of a unit enum value, e.g. `in_process`.
+fn serde_label(value: &impl serde::Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(label)) => label,
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Follows stdout and stderr over separate connections, marking stderr lines.
+///
+/// Lines keep their order within a stream; lines from different streams interleave
+/// in the order their chunks arrive. JSON records carry their stream instead of a marker.
+/// Each stream batches its output in the caller-owned buffer paired with its connection.
+async fn stream_both_logs<'a, 'b>(
+    [stdout_stream, stderr_stream]: [(LogConnection<'a>, &'b mut FollowStdout); 2],
+    job_id: &JobId,
+    follow: bool,
+    max_bytes: u32,
+    wait_ms: u64,
+    max_line_bytes: u32,
+    output: FollowOutput,
+) -> Result<(), CliError> {
+    let max_line_bytes = usize::try_from(max_line_bytes).unwrap_or(usize::MAX);
+    let follow_stream = |(mut client, stdout): (LogConnection<'a>, &'b mut FollowStdout),
+                         stream,
+                         prefix| async move {
+        let mut lines = LinePrefixer::new(prefix, max_line_bytes);
+        // Each stream buffers only the whole lines its prefixer emits, so batches never split a line.
+        stream_logs(
+            &mut client,
+            job_id,
+            stream,
+            follow,
+            max_bytes,
+            wait_ms,
+            &mut |chunk| match output.format {
+                OutputFormatArg::Text => stdout.write(&lines.push(&chunk.data), chunk),
+                OutputFormatArg::Json => stdout.write(
+                    &chunk_record_line(ChunkSource::Log { job_id, stream }, chunk)?,
+                    chunk,
+                ),
+            },
+        )
+        .await?;
+        stdout.flush()?;
+        write_stdout(&lines.finish())?;
+        Ok::<(), CliError>(())
+    };
+    tokio::try_join!(
+        follow_stream(stdout_stream, LogStream::Stdout, b""),
+        follow_stream(stderr_stream, LogStream::Stderr, STDERR_LINE_PREFIX),
+    )?;
+    Ok(())
+}
+
+/// What a streamed chunk was read from, as named in its JSON Lines record.
+#[derive(Debug, Clone, Copy)]
+enum ChunkSource<'a> {
+    /// One stream of a job's logs.
+    Log {
+        /// Job whose logs were read.
+        job_id: &'a JobId,
+        /// Stream the chunk came from.
+        stream: LogStream,
+    },
+    /// A PTY session's output.
+    Session(SessionId),
+}
+
+/// One streamed chunk as printed by `--output json`.
+#[derive(Debug, Serialize)]
+struct ChunkRecord<'a> {
+    /// Job whose logs were read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<&'a str>,
+    /// Log stream the chunk came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<LogStream>,
+    /// PTY session whose output was read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<u64>,
+    /// Absolute offset where the data starts.
+    offset: u64,
+    /// Chunk bytes, base64-encoded.
+    data_base64: String,
+    /// True when no more bytes were available.
+    eof: bool,
+    /// True when the source finished and no more bytes will arrive.
+    complete: bool,
+}
+
+/// Encodes a chunk as one newline-terminated JSON record.
+///
+/// Empty chunks from follow waits are skipped, except the one reporting completion.
+fn chunk_record_line(source: ChunkSource<'_>, chunk: &FollowChunk) -> io::Result<Vec<u8>> {
+    if chunk.data.is_empty() && !chunk.complete {
+        return Ok(Vec::new());
+    }
+    let (job_id, stream, session_id) = match source {
+        ChunkSource::Log { job_id, stream } => (Some(job_id.0.as_str()), Some(stream), None),
+        ChunkSource::Session(session_id) => (None, None, Some(session_id.0)),
+    };
+    let mut line = serde_json::to_vec(&ChunkRecord {
+        job_id,
+        stream,
+        session_id,
+        offset: chunk.offset,
+        data_base64: BASE64.encode(&chunk.data),
+        eof: chunk.eof,
+        complete: chunk.complete,
+    })?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Writes bytes to stderr and flushes them.
+fn write_stderr(data: &[u8]) -> io::Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let mut stderr = io::stderr().lock();
+    stderr.write_all(data)?;
+    stderr.flush()
+}
+
+/// Writes bytes to stdout and flushes them.
+fn write_stdout(data: &[u8]) -> io::Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(data)?;
+    stdout.flush()
+}
+
+/// How a follow loop renders chunks to stdout.
+#[derive(Debug, Clone, Copy)]
+struct FollowOutput {
+    /// Raw bytes or JSON Lines records.
+    format: OutputFormatArg,
+    /// When buffered output is written out.
+    flush: FlushPolicy,
+}
+
+/// Decides when follow output buffered for stdout is flushed.
+///
+/// Piped output is batched to save syscalls on streams of small chunks; a terminal also
+/// flushes at each line end so it stays responsive.
+#[derive(Debug, Clone, Copy)]
+struct FlushPolicy {
+    /// Flush whenever buffered output ends a line.
+    line_flush: bool,
+    /// Longest time output stays buffered.
+    interval: Duration,
+}
+
+impl FlushPolicy {
+    /// Policy for this process's stdout, line flushing only when it is a terminal.
+    fn for_stdout(interval: Duration) -> Self {
+        Self {
+            line_flush: io::stdout().is_terminal(),
+            interval,
+        }
+    }
+
+    /// Returns whether `pending` buffered bytes should be flushed after `written` was buffered
+    /// for `chunk`, `since_flush` after the last flush.
+    fn should_flush(
+        &self,
+        pending: usize,
+        written: &[u8],
+        chunk: &FollowChunk,
+        since_flush: Duration,
+    ) -> bool {
+        if pending == 0 {
+            return false;
+        }
+        // An empty chunk means the follow wait found nothing new, so holding output only delays it.
+        chunk.complete
+            || chunk.data.is_empty()
+            || pending >= MAX_PENDING_OUTPUT_BYTES
+            || since_flush >= self.interval
+            || (self.line_flush && written.contains(&b'\n'))
+    }
+}
+
+/// Stdout writer for follow loops that batches output under a [`FlushPolicy`].
+///
+/// Anything still buffered when it is dropped is flushed on a best-effort basis.
+struct FollowStdout {
+    /// When buffered output is written out.
+    policy: FlushPolicy,
+    /// Output not yet written to stdout.
+    pending: Vec<u8>,
+    /// When output was last flushed.
+    last_flush: Instant,
+}
+
+impl FollowStdout {
+    /// Creates an empty writer following `policy`.
+    fn new(policy: FlushPolicy) -> Self {
+        Self {
+            policy,
+            pending: Vec::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Buffers `data` rendered from `chunk`, flushing when the policy says so.
+    fn write(&mut self, data: &[u8], chunk: &FollowChunk) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+        if self
+            .policy
+            .should_flush(self.pending.len(), data, chunk, self.last_flush.elapsed())
+        {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out and flushes everything buffered.
+    fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        write_stdout(&std::mem::take(&mut self.pending))
+    }
+}
+
+impl Drop for FollowStdout {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Regroups one stream's chunks into whole lines, each starting with a fixed prefix.
+///
+/// Lines longer than the maximum are emitted once, cut and marked, so buffering stays
+/// bounded however long a line runs without a newline.
+struct LinePrefixer {
+    /// Bytes written before every line.
+    prefix: &'static [u8],
+    /// Longest line content kept, excluding its newline.
+    max_line_bytes: usize,
+    /// Trailing bytes of a line whose newline has not arrived yet.
+    pending: Vec<u8>,
+    /// True while dropping the rest of a line that was already emitted truncated.
+    discarding: bool,
+}
+
+impl LinePrefixer {
+    /// Creates a prefixer with no buffered bytes.
+    fn new(prefix: &'static [u8], max_line_bytes: usize) -> Self {
+        Self {
+            prefix,
+            max_line_bytes,
+            pending: Vec::new(),
+            discarding: false,
+        }
+    }
+
+    /// Accepts a chunk and returns every line it completes, prefixed.
+    fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for segment in data.split_inclusive(|byte| *byte == b'\n') {
+            let terminated = segment.ends_with(b"\n");
+            if self.discarding {
+                self.discarding = !terminated;
+                continue;
+            }
+            let room = self.max_line_bytes.saturating_sub(self.pending.len());
+            if segment.len() - usize::from(terminated) > room {
+                self.pending.extend_from_slice(&segment[..room]);
+                out.extend_from_slice(self.prefix);
+                out.append(&mut self.pending);
+                out.extend_from_slice(TRUNCATED_LINE_MARKER);
+                self.discarding = !terminated;
+                continue;
+            }
+            self.pending.extend_from_slice(segment);
+            if terminated {
+                out.extend_from_slice(self.prefix);
+                out.append(&mut self.pending);
+            }
+        }
+        out
+    }
+
+    /// Returns any unterminated final line, prefixed and newline-terminated.
+    fn finish(&mut self) -> Vec<u8> {
+        self.discarding = false;
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let mut out = self.prefix.to_vec();
+        out.append(&mut self.pending);
+        out.push(b'\n');
+        out
+    }
+}
+
+/// Prints a one-line job status, noting when the job's cell is gone.
+fn print_job_status(job: &JobInfo) {
+    let status = match job.status {
+        ExitStatus::Running => "running".to_string(),
+        ExitStatus::Exited { code } => {
+            format!(
+                "exited({})",
+                code.map_or_else(|| "none".to_string(), |c| c.to_string())
+            )
+        }
+    };
+    if job.cell_exists {
+        println!("{} {}", job.id.0, status);
+    } else {
+        println!("{} {} (cell {} removed)", job.id.0, status, job.cell_id.0);
+    }
+    if let Some(reason) = &job.sandbox_fallback_reason {
+        println!("unsandboxed: {reason}");
+    }
+}
+
+/// Log-reading daemon connection that reconnects when the daemon drops it mid-follow.
+///
+/// Logs are files that outlive a daemon restart, so retrying the read at the same
+/// offset on a fresh connection resumes exactly where the lost one stopped.
+struct LogConnection<'a> {
+    /// Current daemon connection.
+    client: PlanterClient,
+    /// Socket and wire format to reconnect with; `None` surfaces connection loss.
+    reconnect: Option<(&'a Path, WireFormat)>,
+}
+
+impl<'a> LogConnection<'a> {
+    /// Wraps a connected client, optionally allowing reconnects.
+    fn new(client: PlanterClient, reconnect: Option<(&'a Path, WireFormat)>) -> Self {
+        Self { client, reconnect }
+    }
+
+    /// Sends one request, reconnecting and resending it after a transport failure.
+    async fn call(&mut self, req: Request) -> Result<Response, CliError> {
+        let mut reconnects = 0;
+        loop {
+            let err = match self.client.call(req.clone()).await {
+                Err(IpcError::Io(err)) => err,
+                result => return Ok(result?),
+            };
+            let Some((socket, wire_format)) = self.reconnect else {
+                return Err(IpcError::Io(err).into());
+            };
+            if reconnects == RECONNECT_ATTEMPTS {
+                return Err(IpcError::Io(err).into());
+            }
+            reconnects += 1;
+            eprintln!("daemon connection lost ({err}); reconnecting");
+            self.client =
+                PlanterClient::connect_with_retry(socket, RECONNECT_ATTEMPTS, RECONNECT_BACKOFF)
+                    .await?
+                    .with_wire_format(wire_format);
+        }
+    }
+}
+
+/// Streams log chunks until completion (or once when not following).
+async fn stream_logs(
+    client: &mut LogConnection<'_>,
+    job_id: &JobId,
+    stream: LogStream,
+    follow: bool,
+    max_bytes: u32,
+    wait_ms: u64,
+    output: &mut impl FnMut(&FollowChunk) -> io::Result<()>,
+) -> Result<(), CliError> {
+    // Lets the daemon report truncations the file length alone would hide.
+    let mut generation = None;
+    drive_follow(
+        0,
+        follow,
+        async |offset| {
+            let response = client
+                .call(Request::LogsRead {
+                    job_id: job_id.clone(),
+                    stream,
+                    offset,
+                    max_bytes,
+                    follow,
+                    wait_ms,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation,
+                })
+                .await?;
+            if let Response::LogsChunk {
+                generation: current,
+                ..
+            } = &response
+            {
+                generation = Some(*current);
+            }
+            follow_chunk("logs", response)
+        },
+        async |chunk| {
+            if chunk.reset {
+                eprintln!("log truncated; continuing from the start");
+            }
+            output(&chunk)?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Converts a log or PTY chunk response into a follow chunk, surfacing daemon errors.
+fn follow_chunk(command: &'static str, response: Response) -> Result<FollowChunk, CliError> {
+    match response {
+        Response::LogsChunk {
+            offset,
+            data,
+            eof,
+            complete,
+            reset,
+            next_offset,
+            ..
+        } => Ok(FollowChunk {
+            offset,
+            data,
+            eof,
+            complete,
+            reset,
+            next_offset,
+        }),
+        Response::PtyChunk {
+            offset,
+            data,
+            eof,
+            complete,
+            next_offset,
+            ..
+        } => Ok(FollowChunk {
+            offset,
+            data,
+            eof,
+            complete,
+            reset: false,
+            next_offset,
+        }),
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => Err(CliError::Daemon {
+            code,
+            message,
+            detail: format_detail(detail),
+        }),
+        other => Err(CliError::Unexpected {
+            command,
+            response: Box::new(other),
+        }),
+    }
+}
+
+/// Prints a doctor report and fails when any check fails.
+async fn run_doctor(socket: &Path) -> Result<(), CliError> {
+    let results = doctor::run_checks(socket, &default_state_dir()).await;
+    let mut failed = 0;
+    for result in &results {
+        let label = match result.status {
+            CheckStatus::Pass => "ok",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => {
+                failed += 1;
+                "FAIL"
+            }
+        };
+        println!("[{label}] {}: {}", result.name, result.detail);
+        if let Some(hint) = &result.hint {
+            println!("       hint: {hint}");
+        }
+    }
+
+    if failed > 0 {
+        return Err(CliError::DoctorFailed { failed });
+    }
+    Ok(())
+}
+
+/// Prints the version planterd recorded at startup without contacting the daemon.
+fn print_local_version(state_dir: &Path) -> Result<(), CliError> {
+    let version = VersionFile::read(state_dir).map_err(|source| CliError::LocalVersion {
+        path: state_dir.join(VERSION_FILE_NAME).display().to_string(),
+        source,
+    })?;
+    println!(
+        "planterd {} (protocol {})",
+        version.daemon, version.protocol
+    );
+    Ok(())
+}
+
+/// Prints the protocol JSON Schema as pretty JSON.
+fn print_schema() -> Result<(), CliError> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &protocol_schema()).map_err(io::Error::from)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+/// Returns the call timeout for a kill that waits `grace_ms` before escalating to KILL.
+fn kill_call_timeout(grace_ms: Option<u64>) -> Duration {
+    Duration::from_millis(grace_ms.unwrap_or(0)).saturating_add(KILL_CALL_MARGIN)
+}
+
+/// Terminates a job whose output the CLI was following when interrupted.
+trait JobKiller {
+    /// Requests termination of the given job.
+    async fn kill(&mut self, job_id: &JobId) -> Result<(), CliError>;
+}
+
+/// Job killer that opens a fresh daemon connection on demand.
+struct SocketJobKiller<'a> {
+    /// Daemon socket path.
+    socket: &'a Path,
+    /// Payload encoding for the kill connection.
+    wire_format: WireFormat,
+}
+
+impl JobKiller for SocketJobKiller<'_> {
+    async fn kill(&mut self, job_id: &JobId) -> Result<(), CliError> {
+        let mut client = PlanterClient::connect(self.socket)
+            .await?
+            .with_wire_format(self.wire_format)
+            .with_timeout(kill_call_timeout(None));
+        let response = client
+            .call(Request::JobKill {
+                job_id: job_id.clone(),
+                force: false,
+                grace_ms: None,
             })
             .await?;
-
         match response {
-            Response::LogsChunk {
-                data,
-                eof,
-                complete,
+            Response::JobKilled { .. }
+            | Response::Error {
+                code: ErrorCode::NotFound,
                 ..
-            } => {
-                if !data.is_empty() {
-                    let mut stdout = io::stdout().lock();
-                    stdout.write_all(&data)?;
-                    stdout.flush()?;
-                    offset = offset.saturating_add(data.len() as u64);
-                }
+            } => Ok(()),
+            Response::Error {
+                code,
+                message,
+                detail,
+            } => Err(CliError::Daemon {
+                code,
+                message,
+                detail: format_detail(detail),
+            }),
+            other => Err(CliError::Unexpected {
+                command: "job kill",
+                response: Box::new(other),
+            }),
+        }
+    }
+}
 
-                if complete || (!follow && eof && data.is_empty()) {
-                    return Ok(());
+/// Drives `stream` to completion, killing `job_id` if `interrupt` fires first.
+async fn kill_on_interrupt<S, I, K>(
+    stream: S,
+    interrupt: I,
+    killer: &mut K,
+    job_id: &JobId,
+) -> Result<(), CliError>
+where
+    S: Future<Output = Result<(), CliError>>,
+    I: Future<Output = io::Result<()>>,
+    K: JobKiller,
+{
+    tokio::select! {
+        result = stream => result,
+        signal = interrupt => {
+            signal?;
+            killer.kill(job_id).await
+        }
+    }
+}
+
+/// Waits until a job finishes, or until `timeout_ms` elapses, and returns its status.
+async fn wait_for_job(
+    client: &mut PlanterClient,
+    job_id: &JobId,
+    timeout_ms: Option<u64>,
+) -> Result<JobInfo, CliError> {
+    let deadline = timeout_ms.map(|ms| std::time::Instant::now() + Duration::from_millis(ms));
+    loop {
+        // Wait in slices shorter than the client call timeout.
+        let mut slice = JOB_WAIT_SLICE_MS;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            slice = slice.min(u64::try_from(remaining.as_millis()).unwrap_or(u64::MAX));
+        }
+        let response = client
+            .call(Request::JobWait {
+                job_id: job_id.clone(),
+                timeout_ms: Some(slice),
+            })
+            .await?;
+        match response {
+            Response::JobStatus { job } => {
+                let expired =
+                    deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline);
+                if !matches!(job.status, ExitStatus::Running) || expired {
+                    return Ok(job);
                 }
             }
             Response::Error {
@@ -689,7 +2315,7 @@ async fn stream_logs(
             }
             other => {
                 return Err(CliError::Unexpected {
-                    command: "logs",
+                    command: "job wait",
                     response: Box::new(other),
                 });
             }
@@ -697,41 +2323,272 @@ async fn stream_logs(
     }
 }
 
-/// Streams PTY chunks until completion (or once when not following).
-async fn stream_pty(
+/// Runs a command in a fresh cell, following its output, and removes the cell afterward.
+///
+/// The cell is removed however the run ends: on success, on a failed job or request,
+/// and after `interrupt` fires and the job is killed.
+async fn exec_ephemeral(
     client: &mut PlanterClient,
-    session_id: SessionId,
-    mut offset: u64,
-    max_bytes: u32,
-    follow: bool,
-    wait_ms: u64,
+    socket: &Path,
+    wire_format: WireFormat,
+    cmd: CommandSpec,
+    interrupt: impl Future<Output = io::Result<()>>,
 ) -> Result<(), CliError> {
-    loop {
+    let response = client
+        .call(Request::CellCreate {
+            spec: CellSpec {
+                name: EXEC_CELL_NAME.to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            },
+        })
+        .await?;
+    let cell_id = match response {
+        Response::CellCreated { cell } => cell.id,
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => {
+            return Err(CliError::Daemon {
+                code,
+                message,
+                detail: format_detail(detail),
+            });
+        }
+        other => {
+            return Err(CliError::Unexpected {
+                command: "exec",
+                response: Box::new(other),
+            });
+        }
+    };
+
+    let result = exec_in_cell(client, socket, wire_format, &cell_id, cmd, interrupt).await;
+    let removed = remove_exec_cell(socket, wire_format, &cell_id).await;
+    if let (Err(_), Err(err)) = (&result, &removed) {
+        eprintln!("failed to remove cell {}: {err}", cell_id.0);
+    }
+    result.and(removed)
+}
+
+/// Force-removes the throwaway `exec` cell over a fresh connection.
+///
+/// The run may have failed or been interrupted mid-call on the original connection, which
+/// leaves it unusable for another request.
+async fn remove_exec_cell(
+    socket: &Path,
+    wire_format: WireFormat,
+    cell_id: &CellId,
+) -> Result<(), CliError> {
+    let mut client = PlanterClient::connect(socket)
+        .await?
+        .with_wire_format(wire_format);
+    match client
+        .call(Request::CellRemove {
+            cell_id: cell_id.clone(),
+            force: true,
+        })
+        .await?
+    {
+        Response::CellRemoved { .. } => Ok(()),
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => Err(CliError::Daemon {
+            code,
+            message,
+            detail: format_detail(detail),
+        }),
+        other => Err(CliError::Unexpected {
+            command: "exec",
+            response: Box::new(other),
+        }),
+    }
+}
+
+/// Starts `cmd` in a cell, streams both log streams until it finishes, and checks its exit.
+async fn exec_in_cell(
+    client: &mut PlanterClient,
+    socket: &Path,
+    wire_format: WireFormat,
+    cell_id: &CellId,
+    cmd: CommandSpec,
+    interrupt: impl Future<Output = io::Result<()>>,
+) -> Result<(), CliError> {
+    let response = client
+        .call(Request::JobRun {
+            cell_id: cell_id.clone(),
+            cmd,
+            deterministic_id: false,
+            process_title: false,
+            system_tmp: false,
+        })
+        .await?;
+    let job_id = match response {
+        Response::JobStarted { job } => job.id,
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => {
+            return Err(CliError::Daemon {
+                code,
+                message,
+                detail: format_detail(detail),
+            });
+        }
+        other => {
+            return Err(CliError::Unexpected {
+                command: "exec",
+                response: Box::new(other),
+            });
+        }
+    };
+
+    let reconnect = Some((socket, wire_format));
+    let mut stdout_client = LogConnection::new(
+        PlanterClient::connect(socket)
+            .await?
+            .with_wire_format(wire_format),
+        reconnect,
+    );
+    let mut stderr_client = LogConnection::new(
+        PlanterClient::connect(socket)
+            .await?
+            .with_wire_format(wire_format),
+        reconnect,
+    );
+    let mut stdout_output = |chunk: &FollowChunk| write_stdout(&chunk.data);
+    let mut stderr_output = |chunk: &FollowChunk| write_stderr(&chunk.data);
+    let streams = async {
+        tokio::try_join!(
+            stream_logs(
+                &mut stdout_client,
+                &job_id,
+                LogStream::Stdout,
+                true,
+                EXEC_LOG_MAX_BYTES,
+                EXEC_LOG_WAIT_MS,
+                &mut stdout_output,
+            ),
+            stream_logs(
+                &mut stderr_client,
+                &job_id,
+                LogStream::Stderr,
+                true,
+                EXEC_LOG_MAX_BYTES,
+                EXEC_LOG_WAIT_MS,
+                &mut stderr_output,
+            ),
+        )?;
+        Ok(())
+    };
+    let mut killer = SocketJobKiller {
+        socket,
+        wire_format,
+    };
+    kill_on_interrupt(streams, interrupt, &mut killer, &job_id).await?;
+
+    let job = wait_for_job(client, &job_id, None).await?;
+    match job.status {
+        ExitStatus::Exited { code: Some(0) } => Ok(()),
+        ExitStatus::Exited { code } => Err(CliError::JobFailed { code }),
+        ExitStatus::Running => Err(CliError::JobFailed { code: None }),
+    }
+}
+
+/// Uploads a local file into a cell in fixed-size staged chunks, then commits it by checksum.
+async fn put_cell_file(
+    client: &mut PlanterClient,
+    cell_id: CellId,
+    local_path: &Path,
+    relative_path: String,
+) -> Result<(), CliError> {
+    let bytes = std::fs::read(local_path)?;
+    let mut chunks = bytes.chunks(CELL_FILE_CHUNK_BYTES).collect::<Vec<_>>();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+
+    let mut offset = 0_u64;
+    for data in chunks {
         let response = client
-            .call(Request::PtyRead {
-                session_id,
+            .call(Request::CellPut {
+                cell_id: cell_id.clone(),
+                relative_path: relative_path.clone(),
                 offset,
-                max_bytes,
-                follow,
-                wait_ms,
+                data: data.to_vec(),
+                staged: true,
             })
             .await?;
+        offset = cell_put_ack(response)?;
+    }
 
-        match response {
-            Response::PtyChunk {
-                data,
-                eof,
-                complete,
-                ..
-            } => {
-                if !data.is_empty() {
-                    let mut stdout = io::stdout().lock();
-                    stdout.write_all(&data)?;
-                    stdout.flush()?;
-                    offset = offset.saturating_add(data.len() as u64);
-                }
+    let sha256 = Sha256::digest(&bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let response = client
+        .call(Request::CellPutFinalize {
+            cell_id,
+            relative_path,
+            sha256,
+        })
+        .await?;
+    println!("{}", cell_put_ack(response)?);
+    Ok(())
+}
+
+/// Extracts the acknowledged file size from a cell put response.
+fn cell_put_ack(response: Response) -> Result<u64, CliError> {
+    match response {
+        Response::CellPutAck { size, .. } => Ok(size),
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => Err(CliError::Daemon {
+            code,
+            message,
+            detail: format_detail(detail),
+        }),
+        other => Err(CliError::Unexpected {
+            command: "cell put",
+            response: Box::new(other),
+        }),
+    }
+}
+
+/// Downloads a cell file into a local path in fixed-size chunks.
+async fn get_cell_file(
+    client: &mut PlanterClient,
+    cell_id: CellId,
+    relative_path: String,
+    local_path: &Path,
+) -> Result<(), CliError> {
+    let mut file = std::fs::File::create(local_path)?;
+    let mut offset = 0_u64;
 
-                if complete || (!follow && eof && data.is_empty()) {
+    loop {
+        let response = client
+            .call(Request::CellGet {
+                cell_id: cell_id.clone(),
+                relative_path: relative_path.clone(),
+                offset,
+                max_bytes: CELL_FILE_CHUNK_BYTES as u32,
+            })
+            .await?;
+        match response {
+            Response::CellFileChunk { data, eof, .. } => {
+                file.write_all(&data)?;
+                offset = offset.saturating_add(data.len() as u64);
+                if eof || data.is_empty() {
+                    file.flush()?;
+                    println!("{offset}");
                     return Ok(());
                 }
             }
@@ -748,101 +2605,155 @@ async fn stream_pty(
             }
             other => {
                 return Err(CliError::Unexpected {
-                    command: "session read",
+                    command: "cell get",
                     response: Box::new(other),
                 });
             }
-        }
-    }
+        }
+    }
+}
+
+/// Read position, batching, and output format for `session read`.
+struct SessionReadOptions {
+    /// Offset to start reading from.
+    offset: u64,
+    /// Maximum bytes per read.
+    max_bytes: u32,
+    /// Keep reading until the session completes.
+    follow: bool,
+    /// Follow wait timeout in milliseconds.
+    wait_ms: u64,
+    /// Start from the last N buffered bytes instead of `offset`.
+    tail: Option<u32>,
+    /// Output format and flush cadence.
+    output: FollowOutput,
+}
+
+/// Streams PTY chunks until completion (or once when not following).
+async fn stream_pty(
+    client: &mut PlanterClient,
+    session_id: SessionId,
+    options: SessionReadOptions,
+) -> Result<(), CliError> {
+    let SessionReadOptions {
+        offset,
+        max_bytes,
+        follow,
+        wait_ms,
+        tail,
+        output,
+    } = options;
+    let mut from_end = tail;
+    let mut stdout = FollowStdout::new(output.flush);
+    drive_follow(
+        offset,
+        follow,
+        async |offset| {
+            let response = client
+                .call(Request::PtyRead {
+                    session_id,
+                    offset,
+                    max_bytes,
+                    follow,
+                    wait_ms,
+                    from_end: from_end.take(),
+                    utf8_boundary: false,
+                    strip_ansi: false,
+                })
+                .await?;
+            follow_chunk("session read", response)
+        },
+        async |chunk| {
+            match output.format {
+                OutputFormatArg::Text => stdout.write(&chunk.data, &chunk)?,
+                OutputFormatArg::Json => {
+                    stdout.write(
+                        &chunk_record_line(ChunkSource::Session(session_id), &chunk)?,
+                        &chunk,
+                    )?;
+                }
+            }
+            Ok(())
+        },
+    )
+    .await?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Terminal and exit behavior for `session attach`.
+struct AttachOptions {
+    /// Initial terminal columns.
+    cols: u16,
+    /// Initial terminal rows.
+    rows: u16,
+    /// Replay only the last N buffered bytes on attach.
+    tail: Option<u32>,
+    /// Close the session when this viewer exits rather than detaching.
+    kill_on_exit: bool,
 }
 
 /// Attaches local stdin/stdout to a remote PTY session.
+///
+/// On stdin EOF or Ctrl-C the viewer detaches and leaves the session to other viewers,
+/// unless `kill_on_exit` asks it to close the session.
 async fn attach_session(
     socket: &PathBuf,
+    wire_format: WireFormat,
     session_id: SessionId,
-    cols: u16,
-    rows: u16,
+    options: AttachOptions,
 ) -> Result<(), CliError> {
+    let AttachOptions {
+        cols,
+        rows,
+        tail,
+        kill_on_exit,
+    } = options;
     print_planter_banner()?;
     let _terminal_mode = TerminalModeGuard::enter_raw()?;
 
-    let mut control = PlanterClient::connect(socket).await?;
-    let resize = control
-        .call(Request::PtyResize {
-            session_id,
-            cols,
-            rows,
-        })
-        .await?;
-    match resize {
-        Response::PtyAck { .. } => {}
-        Response::Error {
-            code,
-            message,
-            detail,
-        } => {
-            return Err(CliError::Daemon {
-                code,
-                message,
-                detail: format_detail(detail),
-            });
-        }
-        other => {
-            return Err(CliError::Unexpected {
-                command: "session attach resize",
-                response: Box::new(other),
-            });
+    // One pipelined connection carries resize, output reads, and input writes together.
+    let client = PlanterClient::connect(socket)
+        .await?
+        .with_wire_format(wire_format)
+        .into_multiplexed();
+    resize_session(&client, session_id, cols, rows).await?;
+    let control = client.clone();
+    let mut window_changes = signal(SignalKind::window_change())?;
+    let resize_task = tokio::spawn(async move {
+        let mut current = (cols, rows);
+        while window_changes.recv().await.is_some() {
+            let Some(size) = read_winsize(io::stdout().as_raw_fd()) else {
+                continue;
+            };
+            if size == current {
+                continue;
+            }
+            current = size;
+            resize_session(&control, session_id, size.0, size.1).await?;
         }
-    }
+        Ok::<(), CliError>(())
+    });
 
-    let mut read_client = PlanterClient::connect(socket).await?;
-    let mut write_client = PlanterClient::connect(socket).await?;
+    let (mut output, mut input) = tokio::io::split(client.attach_pty(session_id, tail));
+    let write_client = client.clone();
 
-    let mut read_task = tokio::spawn(async move {
-        let mut offset = 0_u64;
+    let read_loop = async move {
         let mut stdout = tokio::io::stdout();
+        let mut buf = vec![0_u8; 65536];
         loop {
-            let response = read_client
-                .call(Request::PtyRead {
-                    session_id,
-                    offset,
-                    max_bytes: 65536,
-                    follow: true,
-                    wait_ms: 200,
-                })
-                .await?;
-
-            match response {
-                Response::PtyChunk { data, complete, .. } => {
-                    if !data.is_empty() {
-                        stdout.write_all(&data).await?;
-                        stdout.flush().await?;
-                        offset = offset.saturating_add(data.len() as u64);
-                    }
-                    if complete {
-                        return Ok::<(), CliError>(());
-                    }
-                }
-                Response::Error {
-                    code,
-                    message,
-                    detail,
-                } => {
-                    return Err(CliError::Daemon {
-                        code,
-                        message,
-                        detail: format_detail(detail),
-                    });
-                }
-                other => {
-                    return Err(CliError::Unexpected {
-                        command: "session attach read",
-                        response: Box::new(other),
-                    });
-                }
-            }
+            let read = match output.read(&mut buf).await {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                // The session was closed, by this viewer or another one.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(err) => return Err(attach_io_error(err, session_id)),
+            };
+            stdout.write_all(&buf[..read]).await?;
+            stdout.flush().await?;
         }
-    });
+    };
+    tokio::pin!(read_loop);
 
     let mut write_task = tokio::spawn(async move {
         let mut stdin = tokio::io::stdin();
@@ -850,105 +2761,155 @@ async fn attach_session(
         loop {
             let read = stdin.read(&mut buf).await?;
             if read == 0 {
-                match write_client
-                    .call(Request::PtyClose {
-                        session_id,
-                        force: false,
-                    })
+                input
+                    .flush()
                     .await
-                {
-                    Ok(Response::PtyAck { .. }) => {}
-                    Ok(Response::Error {
-                        code: ErrorCode::NotFound,
-                        ..
-                    }) => {}
-                    Ok(Response::Error {
-                        code,
-                        message,
-                        detail,
-                    }) => {
-                        return Err(CliError::Daemon {
-                            code,
-                            message,
-                            detail: format_detail(detail),
-                        });
-                    }
-                    Ok(other) => {
-                        return Err(CliError::Unexpected {
-                            command: "session attach close",
-                            response: Box::new(other),
-                        });
-                    }
-                    Err(err) => return Err(CliError::Ipc(err)),
-                }
-                return Ok::<(), CliError>(());
-            }
-
-            let response = write_client
-                .call(Request::PtyInput {
-                    session_id,
-                    data: buf[..read].to_vec(),
-                })
-                .await?;
-            match response {
-                Response::PtyAck { .. } => {}
-                Response::Error {
-                    code,
-                    message,
-                    detail,
-                } => {
-                    return Err(CliError::Daemon {
-                        code,
-                        message,
-                        detail: format_detail(detail),
-                    });
-                }
-                other => {
-                    return Err(CliError::Unexpected {
-                        command: "session attach write",
-                        response: Box::new(other),
-                    });
-                }
+                    .map_err(|err| attach_io_error(err, session_id))?;
+                return leave_attached_session(&write_client, session_id, kill_on_exit).await;
             }
+            input
+                .write_all(&buf[..read])
+                .await
+                .map_err(|err| attach_io_error(err, session_id))?;
         }
     });
 
     tokio::select! {
-        result = &mut read_task => {
+        result = &mut read_loop => {
             write_task.abort();
-            match result {
-                Ok(Ok(())) => {}
-                Ok(Err(err)) => {
-                    if !matches!(err, CliError::Daemon { code: ErrorCode::NotFound, .. }) {
-                        return Err(err);
-                    }
-                }
-                Err(err) => return Err(CliError::Join(err)),
-            }
+            result?;
         }
         result = &mut write_task => {
             result??;
-            match read_task.await {
-                Ok(Ok(())) => {}
-                Ok(Err(err)) => {
-                    if !matches!(err, CliError::Daemon { code: ErrorCode::NotFound, .. }) {
-                        return Err(err);
-                    }
-                }
-                Err(err) => return Err(CliError::Join(err)),
+            // After closing, drain the session's final output; a detached viewer just stops.
+            if kill_on_exit {
+                read_loop.await?;
             }
         }
         _ = tokio::signal::ctrl_c() => {
-            let mut close_client = PlanterClient::connect(socket).await?;
-            let _ = close_client.call(Request::PtyClose { session_id, force: false }).await;
-            read_task.abort();
+            let _ = leave_attached_session(&client, session_id, kill_on_exit).await;
             write_task.abort();
         }
     }
+    resize_task.abort();
 
     Ok(())
 }
 
+/// Maps a failed attached-session read or write: daemon errors keep their detail, while a
+/// dropped connection means the session was lost with the daemon.
+fn attach_io_error(err: io::Error, session_id: SessionId) -> CliError {
+    match err
+        .into_inner()
+        .and_then(|inner| inner.downcast::<IpcError>().ok())
+    {
+        Some(err) => match *err {
+            IpcError::Daemon(PlanterError {
+                code,
+                message,
+                detail,
+            }) => CliError::Daemon {
+                code,
+                message,
+                detail: format_detail(detail),
+            },
+            err => CliError::Ipc(err),
+        },
+        None => CliError::SessionLost {
+            session_id: session_id.0,
+        },
+    }
+}
+
+/// Ends one viewer's attachment, closing the session only when `kill_on_exit` is set.
+async fn leave_attached_session(
+    client: &MultiplexedClient,
+    session_id: SessionId,
+    kill_on_exit: bool,
+) -> Result<(), CliError> {
+    if !kill_on_exit {
+        return Ok(());
+    }
+    match client
+        .call(Request::PtyClose {
+            session_id,
+            force: false,
+        })
+        .await?
+    {
+        Response::PtyAck { .. }
+        | Response::Error {
+            code: ErrorCode::NotFound,
+            ..
+        } => Ok(()),
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => Err(CliError::Daemon {
+            code,
+            message,
+            detail: format_detail(detail),
+        }),
+        other => Err(CliError::Unexpected {
+            command: "session attach close",
+            response: Box::new(other),
+        }),
+    }
+}
+
+/// Sends one PTY resize request for an attached session.
+async fn resize_session(
+    client: &MultiplexedClient,
+    session_id: SessionId,
+    cols: u16,
+    rows: u16,
+) -> Result<(), CliError> {
+    let response = client
+        .call(Request::PtyResize {
+            session_id,
+            cols,
+            rows,
+        })
+        .await?;
+    match response {
+        Response::PtyAck { .. } => Ok(()),
+        Response::Error {
+            code,
+            message,
+            detail,
+        } => Err(CliError::Daemon {
+            code,
+            message,
+            detail: format_detail(detail),
+        }),
+        other => Err(CliError::Unexpected {
+            command: "session attach resize",
+            response: Box::new(other),
+        }),
+    }
+}
+
+/// Reads the terminal size of `fd` as `(cols, rows)`, or `None` when it is not a sized TTY.
+fn read_winsize(fd: i32) -> Option<(u16, u16)> {
+    let mut size = MaybeUninit::<libc::winsize>::zeroed();
+    // SAFETY: TIOCGWINSZ writes one winsize into valid writable storage.
+    let rc = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, size.as_mut_ptr()) };
+    if rc != 0 {
+        return None;
+    }
+    // SAFETY: storage was zero-initialized and ioctl succeeded.
+    winsize_dimensions(unsafe { size.assume_init() })
+}
+
+/// Converts a kernel winsize into `(cols, rows)`, rejecting unset dimensions.
+fn winsize_dimensions(size: libc::winsize) -> Option<(u16, u16)> {
+    if size.ws_col == 0 || size.ws_row == 0 {
+        return None;
+    }
+    Some((size.ws_col, size.ws_row))
+}
+
 /// Prints the CLI attach banner.
 fn print_planter_banner() -> Result<(), CliError> {
     const BANNER: &str = r#"
@@ -990,6 +2951,41 @@ fn parse_env_pairs(pairs: Vec<String>) -> Result<BTreeMap<String, String>, CliEr
     Ok(env)
 }
 
+/// Parses a duration with an `ms`, `s`, `m`, or `h` suffix into milliseconds.
+fn parse_duration_ms(value: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid duration '{value}': expected e.g. 500ms, 30s, 10m, or 2h");
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let scale = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return Err(invalid()),
+    };
+    amount.checked_mul(scale).ok_or_else(invalid)
+}
+
+/// Returns the wire name for a journal event kind.
+fn event_kind_label(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::CellCreated => "cell_created",
+        EventKind::CellRenamed => "cell_renamed",
+        EventKind::CellRemoved => "cell_removed",
+        EventKind::CellDrained => "cell_drained",
+        EventKind::WorkerRestarted => "worker_restarted",
+        EventKind::JobStarted => "job_started",
+        EventKind::JobKilled => "job_killed",
+        EventKind::PtyOpened => "pty_opened",
+        EventKind::PtyClosed => "pty_closed",
+        EventKind::PtyReaped => "pty_reaped",
+        EventKind::StateRestored => "state_restored",
+    }
+}
+
 /// Formats optional daemon detail strings for CLI errors.
 fn format_detail(detail: Option<String>) -> String {
     detail
@@ -1050,3 +3046,625 @@ impl Drop for TerminalModeGuard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        future,
+        os::fd::AsRawFd,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use planter_core::{
+        CellId, CellInfo, CommandSpec, ErrorCode, ExitStatus, FollowChunk, JobId, JobInfo,
+        LogStream, PtyAction, Request, RequestEnvelope, Response, ResponseEnvelope, SessionId,
+    };
+    use planter_ipc::{
+        PlanterClient, WireFormat,
+        codec::{decode, encode},
+        framing::{read_frame, write_frame},
+    };
+    use tempfile::tempdir;
+    use tokio::{
+        net::{UnixListener, UnixStream},
+        time::sleep,
+    };
+
+    use super::{
+        ChunkSource, CliError, FlushPolicy, JobKiller, LinePrefixer, LogConnection,
+        MAX_PENDING_OUTPUT_BYTES, STDERR_LINE_PREFIX, chunk_record_line, exec_ephemeral,
+        kill_on_interrupt, leave_attached_session, parse_duration_ms, read_winsize, stream_logs,
+        winsize_dimensions,
+    };
+
+    /// Job killer that records requested job ids.
+    #[derive(Default)]
+    struct RecordingKiller {
+        /// Jobs passed to `kill`.
+        killed: Vec<JobId>,
+    }
+
+    impl JobKiller for RecordingKiller {
+        async fn kill(&mut self, job_id: &JobId) -> Result<(), CliError> {
+            self.killed.push(job_id.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies an interrupt during a follow kills the active job.
+    async fn interrupt_kills_followed_job() {
+        let mut killer = RecordingKiller::default();
+        let job_id = JobId("job-1".to_string());
+
+        kill_on_interrupt(
+            future::pending(),
+            future::ready(Ok(())),
+            &mut killer,
+            &job_id,
+        )
+        .await
+        .expect("interrupt cleanup");
+
+        assert_eq!(killer.killed, vec![job_id]);
+    }
+
+    #[tokio::test]
+    /// Verifies a stream that completes on its own leaves the job alone.
+    async fn completed_stream_does_not_kill() {
+        let mut killer = RecordingKiller::default();
+        let job_id = JobId("job-1".to_string());
+
+        kill_on_interrupt(
+            future::ready(Ok(())),
+            future::pending(),
+            &mut killer,
+            &job_id,
+        )
+        .await
+        .expect("stream completes");
+
+        assert!(killer.killed.is_empty());
+    }
+
+    #[test]
+    /// Verifies chunks are regrouped into whole prefixed lines and a trailing partial is flushed.
+    fn line_prefixer_marks_whole_lines() {
+        let mut lines = LinePrefixer::new(STDERR_LINE_PREFIX, 1024);
+
+        assert_eq!(lines.push(b"warn: a"), b"");
+        assert_eq!(lines.push(b"bc\nerr"), b"stderr| warn: abc\n");
+        assert_eq!(lines.push(b"or\n\nlast"), b"stderr| error\nstderr| \n");
+        assert_eq!(lines.finish(), b"stderr| last\n");
+        assert_eq!(lines.finish(), b"");
+    }
+
+    #[test]
+    /// Verifies durations accept unit suffixes and reject bare or unknown units.
+    fn parse_duration_ms_handles_units() {
+        assert_eq!(parse_duration_ms("500ms"), Ok(500));
+        assert_eq!(parse_duration_ms("30s"), Ok(30_000));
+        assert_eq!(parse_duration_ms("10m"), Ok(600_000));
+        assert_eq!(parse_duration_ms("2h"), Ok(7_200_000));
+        assert!(parse_duration_ms("30").is_err());
+        assert!(parse_duration_ms("s").is_err());
+        assert!(parse_duration_ms("5d").is_err());
+    }
+
+    #[test]
+    /// Verifies a giant line without newlines is emitted once, truncated, with bounded buffering.
+    fn line_prefixer_truncates_overlong_lines() {
+        let mut lines = LinePrefixer::new(STDERR_LINE_PREFIX, 8);
+        let chunk = vec![b'x'; 64 * 1024];
+
+        let mut first = lines.push(b"abc");
+        for _ in 0..256 {
+            first.extend(lines.push(&chunk));
+            assert!(lines.pending.len() <= 8);
+        }
+        assert_eq!(first, b"stderr| abcxxxxx [line truncated]\n");
+        assert_eq!(lines.push(b"xx\nnext\n"), b"stderr| next\n");
+        assert_eq!(
+            lines.push(b"12345678\n123456789\n"),
+            b"stderr| 12345678\nstderr| 12345678 [line truncated]\n"
+        );
+        assert_eq!(lines.finish(), b"");
+    }
+
+    #[test]
+    /// Verifies piped output batches until the interval, size cap, idle, or completion, and a
+    /// terminal also flushes at line ends.
+    fn flush_policy_batches_pipes_and_flushes_terminal_lines() {
+        let chunk = |data: &[u8], complete| FollowChunk {
+            offset: 0,
+            data: data.to_vec(),
+            eof: true,
+            complete,
+            reset: false,
+            next_offset: None,
+        };
+        let interval = Duration::from_millis(50);
+        let piped = FlushPolicy {
+            line_flush: false,
+            interval,
+        };
+        let soon = Duration::from_millis(1);
+
+        assert!(!piped.should_flush(3, b"a\n", &chunk(b"a\n", false), soon));
+        assert!(piped.should_flush(3, b"a\n", &chunk(b"a\n", false), interval));
+        assert!(piped.should_flush(
+            MAX_PENDING_OUTPUT_BYTES,
+            b"a\n",
+            &chunk(b"a\n", false),
+            soon
+        ));
+        assert!(piped.should_flush(3, b"a\n", &chunk(b"a\n", true), soon));
+        assert!(piped.should_flush(3, b"", &chunk(b"", false), soon));
+        assert!(!piped.should_flush(0, b"", &chunk(b"", true), interval));
+
+        let terminal = FlushPolicy {
+            line_flush: true,
+            interval,
+        };
+        assert!(terminal.should_flush(3, b"a\n", &chunk(b"a\n", false), soon));
+        assert!(!terminal.should_flush(7, b"partial", &chunk(b"partial", false), soon));
+    }
+
+    #[test]
+    /// Verifies interleaved chunks from two streams keep each stream's line order.
+    fn merged_streams_preserve_per_stream_order() {
+        let mut stdout = LinePrefixer::new(b"", 1024);
+        let mut stderr = LinePrefixer::new(STDERR_LINE_PREFIX, 1024);
+        let mut merged = Vec::new();
+
+        merged.extend(stdout.push(b"out-1\nout-"));
+        merged.extend(stderr.push(b"err-1\n"));
+        merged.extend(stdout.push(b"2\n"));
+        merged.extend(stderr.push(b"err-2"));
+        merged.extend(stdout.finish());
+        merged.extend(stderr.finish());
+
+        let merged = String::from_utf8(merged).expect("utf8 output");
+        assert_eq!(
+            merged.lines().collect::<Vec<_>>(),
+            ["out-1", "stderr| err-1", "out-2", "stderr| err-2"]
+        );
+        let stderr_lines: Vec<_> = merged
+            .lines()
+            .filter_map(|line| line.strip_prefix("stderr| "))
+            .collect();
+        assert_eq!(stderr_lines, ["err-1", "err-2"]);
+    }
+
+    #[test]
+    /// Verifies winsize conversion keeps populated sizes and rejects unset ones.
+    fn winsize_dimensions_require_both_axes() {
+        let size = |cols, rows| libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+
+        assert_eq!(winsize_dimensions(size(132, 43)), Some((132, 43)));
+        assert_eq!(winsize_dimensions(size(0, 43)), None);
+        assert_eq!(winsize_dimensions(size(132, 0)), None);
+    }
+
+    #[test]
+    /// Verifies non-terminal descriptors report no window size.
+    fn read_winsize_ignores_non_tty() {
+        let file = tempfile::tempfile().expect("tempfile");
+        assert_eq!(read_winsize(file.as_raw_fd()), None);
+    }
+
+    /// Answers one `LogsRead` on a raw daemon connection, returning the requested offset.
+    async fn answer_log_read(conn: &mut UnixStream, data: &[u8], complete: bool) -> u64 {
+        let frame = read_frame(conn).await.expect("request frame");
+        let request: RequestEnvelope<Request> = decode(&frame).expect("decode request");
+        let Request::LogsRead {
+            job_id,
+            stream,
+            offset,
+            ..
+        } = request.body
+        else {
+            panic!("unexpected request: {:?}", request.body);
+        };
+        let response = ResponseEnvelope {
+            req_id: request.req_id,
+            body: Response::LogsChunk {
+                job_id,
+                stream,
+                offset,
+                data: data.to_vec(),
+                eof: true,
+                complete,
+                reset: false,
+                more_expected: !complete,
+                next_offset: None,
+                generation: 0,
+            },
+            more: false,
+        };
+        write_frame(conn, &encode(&response).expect("encode response"))
+            .await
+            .expect("write response");
+        offset
+    }
+
+    #[tokio::test]
+    /// Verifies a log follow reconnects after the daemon restarts and resumes at its offset.
+    async fn log_follow_resumes_after_daemon_restart() {
+        let tmp = tempdir().expect("tempdir");
+        let socket = tmp.path().join("planterd.sock");
+        let listener = UnixListener::bind(&socket).expect("bind socket");
+        let server_socket = socket.clone();
+        let daemon = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.expect("accept");
+            let first = answer_log_read(&mut conn, b"hello ", false).await;
+            drop(conn);
+            drop(listener);
+            std::fs::remove_file(&server_socket).expect("remove socket");
+            sleep(Duration::from_millis(150)).await;
+
+            // The restarted daemon still has the log file, so it serves the rest.
+            let listener = UnixListener::bind(&server_socket).expect("rebind socket");
+            let (mut conn, _) = listener.accept().await.expect("accept after restart");
+            let second = answer_log_read(&mut conn, b"world", true).await;
+            [first, second]
+        });
+
+        let client = PlanterClient::connect(&socket).await.expect("connect");
+        let mut output = Vec::new();
+        stream_logs(
+            &mut LogConnection::new(client, Some((&socket, WireFormat::Cbor))),
+            &JobId("job-1".to_string()),
+            LogStream::Stdout,
+            true,
+            1024,
+            10,
+            &mut |chunk| {
+                output.extend_from_slice(&chunk.data);
+                Ok(())
+            },
+        )
+        .await
+        .expect("follow should survive the restart");
+
+        assert_eq!(output, b"hello world");
+        assert_eq!(daemon.await.expect("daemon task"), [0, 6]);
+    }
+
+    #[tokio::test]
+    /// Verifies JSON log output is one parseable record per chunk whose data decodes back.
+    async fn json_log_output_emits_one_record_per_chunk() {
+        let tmp = tempdir().expect("tempdir");
+        let socket = tmp.path().join("planterd.sock");
+        let listener = UnixListener::bind(&socket).expect("bind socket");
+        let daemon = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.expect("accept");
+            answer_log_read(&mut conn, b"line one\n", false).await;
+            answer_log_read(&mut conn, b"", false).await;
+            answer_log_read(&mut conn, b"\xffbinary", true).await;
+        });
+
+        let client = PlanterClient::connect(&socket).await.expect("connect");
+        let job_id = JobId("job-1".to_string());
+        let mut output = Vec::new();
+        stream_logs(
+            &mut LogConnection::new(client, None),
+            &job_id,
+            LogStream::Stdout,
+            true,
+            1024,
+            10,
+            &mut |chunk| {
+                let source = ChunkSource::Log {
+                    job_id: &job_id,
+                    stream: LogStream::Stdout,
+                };
+                output.extend(chunk_record_line(source, chunk)?);
+                Ok(())
+            },
+        )
+        .await
+        .expect("follow logs");
+        daemon.await.expect("daemon task");
+
+        let output = String::from_utf8(output).expect("utf8 output");
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+            .collect();
+        assert_eq!(records.len(), 2, "{output}");
+        let mut data = Vec::new();
+        for record in &records {
+            assert_eq!(record["job_id"], "job-1");
+            assert_eq!(record["stream"], "stdout");
+            data.extend(
+                STANDARD
+                    .decode(record["data_base64"].as_str().expect("data string"))
+                    .expect("base64 data"),
+            );
+        }
+        assert_eq!(data, b"line one\n\xffbinary");
+        assert_eq!(records[1]["offset"], 9);
+        assert_eq!(records[0]["complete"], false);
+        assert_eq!(records[1]["complete"], true);
+    }
+
+    /// Serves every connection on `listener`, recording each request before answering it.
+    fn spawn_fake_daemon(
+        listener: UnixListener,
+        respond: fn(&Request) -> Response,
+    ) -> Arc<Mutex<Vec<Request>>> {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let recorded = Arc::clone(&recorded);
+                tokio::spawn(async move {
+                    while let Ok(frame) = read_frame(&mut conn).await {
+                        let request: RequestEnvelope<Request> =
+                            decode(&frame).expect("decode request");
+                        let body = respond(&request.body);
+                        recorded.lock().expect("requests lock").push(request.body);
+                        let response = ResponseEnvelope {
+                            req_id: request.req_id,
+                            body,
+                            more: false,
+                        };
+                        write_frame(&mut conn, &encode(&response).expect("encode response"))
+                            .await
+                            .expect("write response");
+                    }
+                });
+            }
+        });
+        requests
+    }
+
+    /// Answers `exec` requests for a job that exits with `exit_code`, or fails to start on `None`.
+    fn exec_response(request: &Request, exit_code: Option<i32>) -> Response {
+        let job = |status| JobInfo {
+            id: JobId("job-exec".to_string()),
+            cell_id: CellId("cell-exec".to_string()),
+            command: CommandSpec {
+                argv: vec!["false".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                limits: None,
+            },
+            requested_at_ms: 0,
+            started_at_ms: 0,
+            finished_at_ms: None,
+            pid: None,
+            status,
+            termination_reason: None,
+            sandbox_mode: None,
+            sandboxed: false,
+            sandbox_fallback_reason: None,
+            spawn_latency_ms: None,
+            effective_env: None,
+            cell_exists: true,
+            stdout_bytes: 0,
+            stderr_bytes: 0,
+        };
+        match (request, exit_code) {
+            (Request::CellCreate { spec }, _) => Response::CellCreated {
+                cell: CellInfo {
+                    id: CellId("cell-exec".to_string()),
+                    spec: spec.clone(),
+                    created_at_ms: 0,
+                    dir: "/tmp/cell-exec".to_string(),
+                    draining: false,
+                },
+            },
+            (Request::JobRun { .. }, Some(_)) => Response::JobStarted {
+                job: job(ExitStatus::Running),
+            },
+            (Request::JobRun { .. }, None) => Response::Error {
+                code: ErrorCode::InvalidRequest,
+                message: "spawn failed".to_string(),
+                detail: None,
+            },
+            (
+                Request::LogsRead {
+                    job_id,
+                    stream,
+                    offset,
+                    ..
+                },
+                _,
+            ) => Response::LogsChunk {
+                job_id: job_id.clone(),
+                stream: *stream,
+                offset: *offset,
+                data: Vec::new(),
+                eof: true,
+                complete: true,
+                reset: false,
+                more_expected: false,
+                next_offset: None,
+                generation: 0,
+            },
+            (Request::JobWait { .. }, Some(code)) => Response::JobStatus {
+                job: job(ExitStatus::Exited { code: Some(code) }),
+            },
+            (Request::CellRemove { cell_id, .. }, _) => Response::CellRemoved {
+                cell_id: cell_id.clone(),
+            },
+            (other, _) => panic!("unexpected request: {other:?}"),
+        }
+    }
+
+    /// Runs `exec` against a fake daemon and returns its result and the requests it sent.
+    async fn run_exec(respond: fn(&Request) -> Response) -> (Result<(), CliError>, Vec<Request>) {
+        let tmp = tempdir().expect("tempdir");
+        let socket = tmp.path().join("planterd.sock");
+        let requests =
+            spawn_fake_daemon(UnixListener::bind(&socket).expect("bind socket"), respond);
+        let mut client = PlanterClient::connect(&socket).await.expect("connect");
+        let result = exec_ephemeral(
+            &mut client,
+            &socket,
+            WireFormat::Cbor,
+            CommandSpec {
+                argv: vec!["false".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                limits: None,
+            },
+            future::pending(),
+        )
+        .await;
+        let requests = requests.lock().expect("requests lock").clone();
+        (result, requests)
+    }
+
+    /// Returns true when `requests` force-removed the throwaway cell.
+    fn removed_exec_cell(requests: &[Request]) -> bool {
+        requests.iter().any(|request| {
+            matches!(
+                request,
+                Request::CellRemove { cell_id, force: true } if cell_id.0 == "cell-exec"
+            )
+        })
+    }
+
+    #[tokio::test]
+    /// Verifies `exec` passes a failing job's exit code through and still removes its cell.
+    async fn exec_removes_cell_after_failed_job() {
+        let (result, requests) = run_exec(|request| exec_response(request, Some(3))).await;
+
+        assert!(
+            matches!(result, Err(CliError::JobFailed { code: Some(3) })),
+            "{result:?}"
+        );
+        assert!(removed_exec_cell(&requests), "{requests:?}");
+    }
+
+    #[tokio::test]
+    /// Verifies `exec` removes its cell over a fresh connection when the original one breaks.
+    async fn exec_removes_cell_after_connection_breaks() {
+        let tmp = tempdir().expect("tempdir");
+        let broken_socket = tmp.path().join("broken.sock");
+        let listener = UnixListener::bind(&broken_socket).expect("bind broken socket");
+        tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.expect("accept");
+            let frame = read_frame(&mut conn).await.expect("read cell create");
+            let request: RequestEnvelope<Request> = decode(&frame).expect("decode request");
+            let response = ResponseEnvelope {
+                req_id: request.req_id,
+                body: exec_response(&request.body, Some(0)),
+                more: false,
+            };
+            write_frame(&mut conn, &encode(&response).expect("encode response"))
+                .await
+                .expect("write response");
+            // Hang up on the job run so the original connection is unusable afterwards.
+            let _ = read_frame(&mut conn).await;
+        });
+        let socket = tmp.path().join("planterd.sock");
+        let requests = spawn_fake_daemon(
+            UnixListener::bind(&socket).expect("bind socket"),
+            |request| exec_response(request, Some(0)),
+        );
+
+        let mut client = PlanterClient::connect(&broken_socket)
+            .await
+            .expect("connect");
+        let result = exec_ephemeral(
+            &mut client,
+            &socket,
+            WireFormat::Cbor,
+            CommandSpec {
+                argv: vec!["false".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                limits: None,
+            },
+            future::pending(),
+        )
+        .await;
+
+        assert!(result.is_err(), "{result:?}");
+        let requests = requests.lock().expect("requests lock").clone();
+        assert!(removed_exec_cell(&requests), "{requests:?}");
+    }
+
+    #[tokio::test]
+    /// Verifies `exec` removes its cell when the job cannot be started.
+    async fn exec_removes_cell_when_run_fails() {
+        let (result, requests) = run_exec(|request| exec_response(request, None)).await;
+
+        assert!(matches!(result, Err(CliError::Daemon { .. })), "{result:?}");
+        assert!(removed_exec_cell(&requests), "{requests:?}");
+    }
+
+    #[tokio::test]
+    /// Verifies a detaching viewer leaves the session open while the owner closes it on exit.
+    async fn detaching_viewer_keeps_shared_session_open() {
+        let tmp = tempdir().expect("tempdir");
+        let socket = tmp.path().join("planterd.sock");
+        let requests = spawn_fake_daemon(
+            UnixListener::bind(&socket).expect("bind socket"),
+            |request| match request {
+                Request::PtyInput { session_id, .. } => Response::PtyAck {
+                    session_id: *session_id,
+                    action: PtyAction::Input,
+                },
+                Request::PtyClose { session_id, .. } => Response::PtyAck {
+                    session_id: *session_id,
+                    action: PtyAction::Closed,
+                },
+                other => panic!("unexpected request: {other:?}"),
+            },
+        );
+        let session_id = SessionId(7);
+        let viewer = PlanterClient::connect(&socket)
+            .await
+            .expect("connect viewer")
+            .into_multiplexed();
+        let owner = PlanterClient::connect(&socket)
+            .await
+            .expect("connect owner")
+            .into_multiplexed();
+
+        leave_attached_session(&viewer, session_id, false)
+            .await
+            .expect("viewer detaches");
+        owner
+            .call(Request::PtyInput {
+                session_id,
+                data: b"still here\n".to_vec(),
+            })
+            .await
+            .expect("owner input");
+        assert!(
+            !requests
+                .lock()
+                .expect("requests lock")
+                .iter()
+                .any(|request| matches!(request, Request::PtyClose { .. })),
+            "detaching must not close the session"
+        );
+
+        leave_attached_session(&owner, session_id, true)
+            .await
+            .expect("owner closes");
+        let requests = requests.lock().expect("requests lock").clone();
+        assert!(
+            matches!(
+                requests.as_slice(),
+                [Request::PtyInput { .. }, Request::PtyClose { session_id: closed, force: false }]
+                    if *closed == session_id
+            ),
+            "{requests:?}"
+        );
+    }
+}
@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use planter_core::{Request, Response};
 use planter_ipc::RequestHandler;
+use tokio::sync::mpsc;
 
 use crate::handlers::Handler;
 
@@ -25,6 +26,21 @@ impl RequestHandler for DaemonDispatcher {
     async fn handle(&self, req: Request) -> Response {
         self.handler.handle(req).await
     }
+
+    /// Routes one request through the daemon handler, bounding its waits by `deadline`.
+    async fn handle_with_deadline(&self, req: Request, deadline: Option<Instant>) -> Response {
+        self.handler.handle_with_deadline(req, deadline).await
+    }
+
+    /// Routes one request through the daemon handler, forwarding its streamed frames.
+    async fn handle_streaming(
+        &self,
+        req: Request,
+        deadline: Option<Instant>,
+        frames: mpsc::Sender<Response>,
+    ) -> Response {
+        self.handler.handle_streaming(req, deadline, frames).await
+    }
 }
 
 impl From<Arc<crate::state::StateStore>> for DaemonDispatcher {
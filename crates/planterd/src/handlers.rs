@@ -1,59 +1,243 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use planter_core::{PROTOCOL_VERSION, PlanterError, PtyAction, Request, Response};
+use planter_core::{
+    ErrorCode, ErrorCount, EventKind, ExitStatus, HealthStatus, JobId, JournalEvent,
+    PROTOCOL_VERSION, PlanterError, PtyAction, Request, Response, now_ms,
+};
+use tokio::sync::mpsc;
 
-use crate::state::StateStore;
+use crate::{
+    journal::Journal,
+    signals,
+    state::{LogsReadSpec, PtyOpenSpec, PtyReadSpec, RunJobOptions, StateStore, UsageSampleResult},
+    worker_manager::lock_recovering,
+};
+
+/// Journal file name under the state root.
+const JOURNAL_FILE: &str = "events.jsonl";
+/// Shortest interval between streamed usage samples.
+const MIN_USAGE_INTERVAL_MS: u64 = 50;
 
 /// Request handler that maps protocol messages to state-store operations.
 #[derive(Clone)]
 pub struct Handler {
     /// Shared mutable daemon state.
     state: Arc<StateStore>,
+    /// Append-only lifecycle event journal.
+    journal: Arc<Journal>,
+    /// Error responses returned since startup, by code.
+    errors: Arc<Mutex<Vec<ErrorCount>>>,
 }
 
 impl Handler {
     /// Creates a handler from a shared state store.
     pub fn new(state: Arc<StateStore>) -> Self {
-        Self { state }
+        let journal = Arc::new(Journal::open(state.root().join(JOURNAL_FILE)));
+        Self {
+            state,
+            journal,
+            errors: Arc::new(Mutex::new(Vec::new())),
+        }
     }
 
     /// Executes one protocol request and returns a protocol response.
     pub async fn handle(&self, request: Request) -> Response {
+        self.handle_with_deadline(request, None).await
+    }
+
+    /// Executes one protocol request, ending follow reads and job waits by `deadline`.
+    ///
+    /// A wait cut short by the deadline rather than the caller's own timeout fails with
+    /// `timeout`.
+    pub async fn handle_with_deadline(
+        &self,
+        request: Request,
+        deadline: Option<Instant>,
+    ) -> Response {
         let result = match request {
             Request::Version {} => Ok(Response::Version {
                 daemon: env!("CARGO_PKG_VERSION").to_string(),
                 protocol: PROTOCOL_VERSION,
             }),
-            Request::Health {} => Ok(Response::Health {
-                status: "ok".to_string(),
+            Request::Health { deep: false } => Ok(Response::Health {
+                status: HealthStatus::Ok,
+                detail: None,
+            }),
+            Request::Health { deep: true } => {
+                let (status, detail) = self.state.deep_health().await;
+                Ok(Response::Health { status, detail })
+            }
+            Request::SupportedSignals {} => Ok(Response::SupportedSignals {
+                signals: signals::supported_signals(),
+            }),
+            Request::Diagnostics {} => self.state.diagnostics().map(|mut report| {
+                report.errors = self.errors_guard().clone();
+                Response::Diagnostics { report }
             }),
             Request::CellCreate { spec } => self
                 .state
                 .create_cell(spec)
                 .map(|cell| Response::CellCreated { cell }),
-            Request::JobRun { cell_id, cmd } => self
+            Request::CellRename { cell_id, new_name } => self
+                .state
+                .rename_cell(&cell_id, new_name)
+                .map(|cell| Response::CellRenamed { cell }),
+            Request::JobRun {
+                cell_id,
+                cmd,
+                deterministic_id,
+                process_title,
+                system_tmp,
+            } => self
                 .state
-                .run_job(cell_id, cmd)
+                .run_job(
+                    cell_id,
+                    cmd,
+                    RunJobOptions {
+                        deterministic_id,
+                        process_title,
+                        system_tmp,
+                    },
+                    deadline,
+                )
                 .await
                 .map(|job| Response::JobStarted { job }),
             Request::JobStatus { job_id } => self
                 .state
                 .load_job(&job_id)
                 .map(|job| Response::JobStatus { job }),
-            Request::JobKill { job_id, force } => {
+            Request::JobWait { job_id, timeout_ms } => {
+                let (timeout_ms, capped) = cap_wait_ms(timeout_ms, deadline);
                 self.state
-                    .kill_job(&job_id, force)
+                    .wait_job(&job_id, timeout_ms)
                     .await
-                    .map(|result| Response::JobKilled {
-                        job_id,
-                        signal: result.signal,
-                        status: result.job.status,
+                    .and_then(|job| {
+                        if capped && matches!(job.status, ExitStatus::Running) {
+                            return Err(deadline_exceeded());
+                        }
+                        Ok(Response::JobStatus { job })
                     })
             }
+            Request::JobUsageSubscribe { .. } => Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "usage subscriptions stream frames and cannot run inside a batch"
+                    .to_string(),
+                detail: None,
+            }),
+            Request::JobKill {
+                job_id,
+                force,
+                grace_ms,
+            } => self
+                .state
+                .kill_job(&job_id, force, grace_ms)
+                .await
+                .map(|result| Response::JobKilled {
+                    job_id,
+                    signal: result.signal,
+                    status: result.job.status,
+                    delivered: result.delivered,
+                }),
             Request::CellRemove { cell_id, force } => self
                 .state
                 .remove_cell(&cell_id, force)
                 .map(|()| Response::CellRemoved { cell_id }),
+            Request::CellDrain {
+                cell_id,
+                timeout_ms,
+            } => self
+                .state
+                .drain_cell(&cell_id, timeout_ms)
+                .await
+                .map(|result| Response::CellDrained {
+                    cell_id,
+                    removed: result.removed,
+                    pending_jobs: result.pending_jobs,
+                }),
+            Request::WorkerRestart { cell_id } => {
+                self.state
+                    .restart_worker(&cell_id)
+                    .await
+                    .map(|terminated_jobs| Response::WorkerRestarted {
+                        cell_id,
+                        terminated_jobs,
+                    })
+            }
+            Request::SandboxCheck {
+                cell_id,
+                path,
+                operation,
+            } => self
+                .state
+                .sandbox_check(&cell_id, &path, operation)
+                .await
+                .map(|decision| Response::SandboxChecked {
+                    cell_id,
+                    path,
+                    operation,
+                    allowed: decision.allowed,
+                    sandboxed: decision.sandboxed,
+                    detail: decision.detail,
+                }),
+            Request::CellPut {
+                cell_id,
+                relative_path,
+                offset,
+                data,
+                staged,
+            } => {
+                let written = if staged {
+                    self.state
+                        .cell_put_staged(&cell_id, &relative_path, offset, &data)
+                } else {
+                    self.state.cell_put(&cell_id, &relative_path, offset, &data)
+                };
+                written.map(|size| Response::CellPutAck {
+                    cell_id,
+                    relative_path,
+                    size,
+                })
+            }
+            Request::CellPutFinalize {
+                cell_id,
+                relative_path,
+                sha256,
+            } => self
+                .state
+                .cell_put_finalize(&cell_id, &relative_path, &sha256)
+                .await
+                .map(|size| Response::CellPutAck {
+                    cell_id,
+                    relative_path,
+                    size,
+                }),
+            Request::CellGet {
+                cell_id,
+                relative_path,
+                offset,
+                max_bytes,
+            } => self
+                .state
+                .cell_get(&cell_id, &relative_path, offset, max_bytes)
+                .map(|chunk| Response::CellFileChunk {
+                    cell_id,
+                    relative_path,
+                    offset: chunk.offset,
+                    data: chunk.data,
+                    eof: chunk.eof,
+                }),
+            Request::CellListFiles { cell_id, subpath } => self
+                .state
+                .cell_list_files(&cell_id, subpath.as_deref())
+                .map(|listing| Response::CellFiles {
+                    cell_id,
+                    subpath,
+                    entries: listing.entries,
+                    truncated: listing.truncated,
+                }),
             Request::LogsRead {
                 job_id,
                 stream,
@@ -61,18 +245,90 @@ impl Handler {
                 max_bytes,
                 follow,
                 wait_ms,
+                strip_ansi,
+                reverse,
+                generation,
+            } => {
+                let (wait_ms, capped) = cap_wait_ms(Some(wait_ms), deadline);
+                self.state
+                    .read_logs(
+                        &job_id,
+                        stream,
+                        LogsReadSpec {
+                            offset,
+                            max_bytes,
+                            follow,
+                            wait_ms: wait_ms.unwrap_or_default(),
+                            strip_ansi,
+                            reverse,
+                            generation,
+                        },
+                    )
+                    .await
+                    .and_then(|chunk| {
+                        if capped
+                            && follow
+                            && chunk.data.is_empty()
+                            && chunk.next_offset.is_none()
+                            && !chunk.complete
+                        {
+                            return Err(deadline_exceeded());
+                        }
+                        Ok(Response::LogsChunk {
+                            job_id,
+                            stream,
+                            offset: chunk.offset,
+                            data: chunk.data,
+                            eof: chunk.eof,
+                            complete: chunk.complete,
+                            reset: chunk.reset,
+                            more_expected: chunk.more_expected,
+                            next_offset: chunk.next_offset,
+                            generation: chunk.generation,
+                        })
+                    })
+            }
+            Request::LogsPeek {
+                job_id,
+                stream,
+                offset,
+                max_bytes,
             } => self
                 .state
-                .read_logs(&job_id, stream, offset, max_bytes, follow, wait_ms)
-                .await
-                .map(|chunk| Response::LogsChunk {
+                .peek_logs(&job_id, stream, offset, max_bytes)
+                .map(|peeked| Response::LogsPeeked {
                     job_id,
                     stream,
-                    offset: chunk.offset,
-                    data: chunk.data,
-                    eof: chunk.eof,
-                    complete: chunk.complete,
+                    offset: peeked.offset,
+                    data: peeked.data,
+                    length: peeked.length,
+                    complete: peeked.complete,
                 }),
+            Request::LogsTruncate { job_id, stream } => self
+                .state
+                .truncate_logs(&job_id, stream)
+                .await
+                .map(|()| Response::LogsTruncated { job_id, stream }),
+            Request::LogsSync { job_id } => self
+                .state
+                .sync_logs(&job_id)
+                .await
+                .map(|()| Response::LogsSynced { job_id }),
+            Request::LogsInfo { job_id } => {
+                self.state
+                    .logs_info(&job_id)
+                    .map(|info| Response::LogsInfo {
+                        job_id,
+                        stdout: info.stdout,
+                        stderr: info.stderr,
+                        total_bytes: info.total_bytes,
+                        max_log_bytes: info.max_log_bytes,
+                    })
+            }
+            Request::CancelFollow { job_id } => self
+                .state
+                .cancel_follows(&job_id)
+                .map(|cancelled| Response::FollowCancelled { job_id, cancelled }),
             Request::PtyOpen {
                 shell,
                 args,
@@ -80,9 +336,20 @@ impl Handler {
                 env,
                 cols,
                 rows,
+                jail_cwd,
+                persist_scrollback,
             } => self
                 .state
-                .open_pty(shell, args, cwd, env, cols, rows)
+                .open_pty(PtyOpenSpec {
+                    shell,
+                    args,
+                    cwd,
+                    env,
+                    cols,
+                    rows,
+                    jail_cwd,
+                    persist_scrollback,
+                })
                 .await
                 .map(|opened| Response::PtyOpened {
                     session_id: opened.session_id,
@@ -102,17 +369,59 @@ impl Handler {
                 max_bytes,
                 follow,
                 wait_ms,
+                from_end,
+                utf8_boundary,
+                strip_ansi,
+            } => {
+                let (wait_ms, capped) = cap_wait_ms(Some(wait_ms), deadline);
+                self.state
+                    .pty_read(
+                        session_id,
+                        PtyReadSpec {
+                            offset,
+                            max_bytes,
+                            follow,
+                            wait_ms: wait_ms.unwrap_or_default(),
+                            from_end,
+                            utf8_boundary,
+                            strip_ansi,
+                        },
+                    )
+                    .await
+                    .and_then(|chunk| {
+                        if capped
+                            && follow
+                            && chunk.data.is_empty()
+                            && chunk.next_offset.is_none()
+                            && !chunk.complete
+                        {
+                            return Err(deadline_exceeded());
+                        }
+                        Ok(Response::PtyChunk {
+                            session_id,
+                            offset: chunk.offset,
+                            data: chunk.data,
+                            eof: chunk.eof,
+                            complete: chunk.complete,
+                            exit_code: chunk.exit_code,
+                            next_offset: chunk.next_offset,
+                        })
+                    })
+            }
+            Request::PtyPeek {
+                session_id,
+                offset,
+                max_bytes,
             } => self
                 .state
-                .pty_read(session_id, offset, max_bytes, follow, wait_ms)
+                .pty_peek(session_id, offset, max_bytes)
                 .await
-                .map(|chunk| Response::PtyChunk {
+                .map(|peeked| Response::PtyPeeked {
                     session_id,
-                    offset: chunk.offset,
-                    data: chunk.data,
-                    eof: chunk.eof,
-                    complete: chunk.complete,
-                    exit_code: chunk.exit_code,
+                    offset: peeked.offset,
+                    data: peeked.data,
+                    length: peeked.length,
+                    complete: peeked.complete,
                 }),
             Request::PtyResize {
                 session_id,
@@ -134,12 +443,306 @@ impl Handler {
                     session_id,
                     action: PtyAction::Closed,
                 }),
+            Request::PtyReap { idle_ms } => self
+                .state
+                .pty_reap(idle_ms)
+                .await
+                .map(|session_ids| Response::PtyReaped { session_ids }),
+            Request::PtyCancelFollow { session_id } => {
+                let cancelled = self.state.cancel_pty_follows(session_id);
+                Ok(Response::PtyFollowCancelled {
+                    session_id,
+                    cancelled,
+                })
+            }
+            Request::PtySandboxProfile { session_id } => self
+                .state
+                .pty_sandbox_profile(session_id)
+                .await
+                .map(
+                    |(session_id, profile)| Response::PtySandboxProfileRendered {
+                        session_id,
+                        profile,
+                    },
+                ),
+            Request::StateSnapshot {} => self
+                .state
+                .snapshot()
+                .map(|snapshot| Response::StateSnapshot { snapshot }),
+            Request::StateRestore { snapshot } => {
+                self.state
+                    .restore(snapshot)
+                    .map(|(cells, jobs)| Response::StateRestored {
+                        cells: u32::try_from(cells).unwrap_or(u32::MAX),
+                        jobs: u32::try_from(jobs).unwrap_or(u32::MAX),
+                    })
+            }
+            Request::Events { since_ms, limit } => self
+                .journal
+                .read_recent(since_ms, limit)
+                .await
+                .map(|events| Response::Events { events }),
+            Request::Batch {
+                requests,
+                stop_on_error,
+            } => self.handle_batch(requests, stop_on_error, deadline).await,
         };
 
         match result {
+            Ok(response) => {
+                if let Some(event) = journal_event(&response) {
+                    self.journal.append(event);
+                }
+                response
+            }
+            Err(err) => {
+                self.record_error(err.code);
+                to_error_response(err)
+            }
+        }
+    }
+
+    /// Executes one protocol request, sending intermediate frames of streaming requests to
+    /// `frames`.
+    ///
+    /// Only `JobUsageSubscribe` streams; other requests behave like `handle_with_deadline`.
+    pub async fn handle_streaming(
+        &self,
+        request: Request,
+        deadline: Option<Instant>,
+        frames: mpsc::Sender<Response>,
+    ) -> Response {
+        let Request::JobUsageSubscribe {
+            job_id,
+            interval_ms,
+        } = request
+        else {
+            return self.handle_with_deadline(request, deadline).await;
+        };
+        match self.stream_usage(job_id, interval_ms, &frames).await {
             Ok(response) => response,
-            Err(err) => to_error_response(err),
+            Err(err) => {
+                self.record_error(err.code);
+                to_error_response(err)
+            }
+        }
+    }
+
+    /// Sends a usage sample every `interval_ms` while the job runs, then a final sample.
+    ///
+    /// Waiting between samples wakes as soon as the job exits. Answers `JobUsageEnded` once
+    /// the job has finished or the client stops reading.
+    async fn stream_usage(
+        &self,
+        job_id: JobId,
+        interval_ms: u64,
+        frames: &mpsc::Sender<Response>,
+    ) -> Result<Response, PlanterError> {
+        let interval_ms = interval_ms.max(MIN_USAGE_INTERVAL_MS);
+        let mut job = self.state.wait_job(&job_id, Some(0)).await?;
+        loop {
+            let sample = self.state.usage_sample(&job_id).await;
+            if !matches!(job.status, ExitStatus::Running) {
+                if let Ok(sample) = sample {
+                    let _ = frames.send(usage_sample(&job_id, sample)).await;
+                }
+                return Ok(Response::JobUsageEnded {
+                    job_id,
+                    status: job.status,
+                });
+            }
+            if frames.send(usage_sample(&job_id, sample?)).await.is_err() {
+                return Ok(Response::JobUsageEnded {
+                    job_id,
+                    status: job.status,
+                });
+            }
+            job = self.state.wait_job(&job_id, Some(interval_ms)).await?;
+        }
+    }
+
+    /// Counts one error response toward the diagnostics totals.
+    fn record_error(&self, code: ErrorCode) {
+        let mut errors = self.errors_guard();
+        match errors.iter_mut().find(|entry| entry.code == code) {
+            Some(entry) => entry.count += 1,
+            None => errors.push(ErrorCount { code, count: 1 }),
+        }
+    }
+
+    /// Locks the error totals, recovering from poisoning since counts stay consistent.
+    fn errors_guard(&self) -> std::sync::MutexGuard<'_, Vec<ErrorCount>> {
+        lock_recovering(&self.errors, "errors")
+    }
+
+    /// Runs batch sub-requests in order, optionally stopping at the first error.
+    async fn handle_batch(
+        &self,
+        requests: Vec<Request>,
+        stop_on_error: bool,
+        deadline: Option<Instant>,
+    ) -> Result<Response, PlanterError> {
+        if requests
+            .iter()
+            .any(|request| matches!(request, Request::Batch { .. }))
+        {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "nested batch requests are not supported".to_string(),
+                detail: None,
+            });
+        }
+
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            let response = Box::pin(self.handle_with_deadline(request, deadline)).await;
+            let failed = matches!(response, Response::Error { .. });
+            responses.push(response);
+            if failed && stop_on_error {
+                break;
+            }
+        }
+        Ok(Response::Batch { responses })
+    }
+}
+
+/// Caps a blocking wait at the time left before `deadline`, where `None` waits forever.
+///
+/// Also returns whether the deadline, not the caller's own wait, now bounds it.
+fn cap_wait_ms(wait_ms: Option<u64>, deadline: Option<Instant>) -> (Option<u64>, bool) {
+    let Some(deadline) = deadline else {
+        return (wait_ms, false);
+    };
+    let remaining = u64::try_from(
+        deadline
+            .saturating_duration_since(Instant::now())
+            .as_millis(),
+    )
+    .unwrap_or(u64::MAX);
+    match wait_ms {
+        Some(wait_ms) if wait_ms <= remaining => (Some(wait_ms), false),
+        _ => (Some(remaining), true),
+    }
+}
+
+/// Error for a blocking request whose wait ended at its envelope deadline.
+fn deadline_exceeded() -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Timeout,
+        message: "request deadline exceeded".to_string(),
+        detail: None,
+    }
+}
+
+/// Derives a journal event from a successful mutating response.
+fn journal_event(response: &Response) -> Option<JournalEvent> {
+    let (kind, cell_id, job_id, detail) = match response {
+        Response::CellCreated { cell } => (
+            EventKind::CellCreated,
+            Some(cell.id.clone()),
+            None,
+            Some(format!("name={}", cell.spec.name)),
+        ),
+        Response::CellRenamed { cell } => (
+            EventKind::CellRenamed,
+            Some(cell.id.clone()),
+            None,
+            Some(format!("name={}", cell.spec.name)),
+        ),
+        Response::CellRemoved { cell_id } => {
+            (EventKind::CellRemoved, Some(cell_id.clone()), None, None)
         }
+        Response::CellDrained {
+            cell_id,
+            removed,
+            pending_jobs,
+        } => (
+            EventKind::CellDrained,
+            Some(cell_id.clone()),
+            None,
+            Some(format!("removed={removed} pending={}", pending_jobs.len())),
+        ),
+        Response::WorkerRestarted {
+            cell_id,
+            terminated_jobs,
+        } => (
+            EventKind::WorkerRestarted,
+            Some(cell_id.clone()),
+            None,
+            Some(format!("terminated={}", terminated_jobs.len())),
+        ),
+        Response::JobStarted { job } => (
+            EventKind::JobStarted,
+            Some(job.cell_id.clone()),
+            Some(job.id.clone()),
+            Some(job.command.argv.join(" ")),
+        ),
+        Response::JobKilled {
+            delivered: false, ..
+        } => return None,
+        Response::JobKilled { job_id, signal, .. } => (
+            EventKind::JobKilled,
+            None,
+            Some(job_id.clone()),
+            Some(format!("signal={signal}")),
+        ),
+        Response::PtyOpened { session_id, pid } => (
+            EventKind::PtyOpened,
+            None,
+            None,
+            Some(match pid {
+                Some(pid) => format!("session={} pid={pid}", session_id.0),
+                None => format!("session={}", session_id.0),
+            }),
+        ),
+        Response::PtyAck {
+            session_id,
+            action: PtyAction::Closed,
+        } => (
+            EventKind::PtyClosed,
+            None,
+            None,
+            Some(format!("session={}", session_id.0)),
+        ),
+        Response::PtyReaped { session_ids } if session_ids.is_empty() => return None,
+        Response::PtyReaped { session_ids } => (
+            EventKind::PtyReaped,
+            None,
+            None,
+            Some(format!(
+                "sessions={}",
+                session_ids
+                    .iter()
+                    .map(|session_id| session_id.0.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+        ),
+        Response::StateRestored { cells, jobs } => (
+            EventKind::StateRestored,
+            None,
+            None,
+            Some(format!("cells={cells} jobs={jobs}")),
+        ),
+        _ => return None,
+    };
+
+    Some(JournalEvent {
+        ts_ms: now_ms(),
+        kind,
+        cell_id,
+        job_id,
+        detail,
+    })
+}
+
+/// Builds the usage sample frame for a job.
+fn usage_sample(job_id: &JobId, sample: UsageSampleResult) -> Response {
+    Response::UsageSample {
+        job_id: job_id.clone(),
+        rss_bytes: sample.rss_bytes,
+        cpu_nanos: sample.cpu_nanos,
+        timestamp_ms: sample.timestamp_ms,
     }
 }
 
@@ -154,178 +757,1432 @@ fn to_error_response(err: PlanterError) -> Response {
 
 #[cfg(all(test, target_os = "macos"))]
 mod tests {
-    use std::{collections::BTreeMap, sync::Arc, time::Duration};
+    use std::{
+        collections::BTreeMap,
+        path::Path,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
-    use super::Handler;
-    use planter_core::{CellSpec, CommandSpec, ErrorCode, JobId, LogStream, Request, Response};
+    use super::{Handler, journal_event};
+    use planter_core::{
+        CellId, CellInfo, CellSpec, CommandSpec, ErrorCode, ErrorCount, EventKind, ExitStatus,
+        JobId, LogStream, Request, Response, SessionId,
+    };
     use planter_platform_macos::{MacosOps, SandboxMode};
     use tempfile::tempdir;
-    use tokio::time::sleep;
+    use tokio::{sync::mpsc, time::sleep};
 
     use crate::state::StateStore;
 
     /// Constructs a handler backed by a temporary local state store.
     fn test_handler(state_root: std::path::PathBuf) -> Handler {
-        let platform = Arc::new(MacosOps::new(state_root.clone(), SandboxMode::Disabled));
+        let platform = Arc::new(MacosOps::new(
+            state_root.clone(),
+            SandboxMode::Disabled,
+            None,
+        ));
         let state =
             Arc::new(StateStore::new(state_root, platform).expect("state should initialize"));
         Handler::new(state)
     }
 
     #[tokio::test]
-    /// Exercises create/run/logs/status/kill/remove lifecycle through handler API.
-    async fn lifecycle_and_logs_flow() {
+    /// Verifies job status reports when the job's cell directory was removed out of band.
+    async fn job_status_reports_missing_cell() {
         let tmp = tempdir().expect("tempdir");
         let state_root = tmp.path().join("state");
-        let handler = test_handler(state_root);
-
-        let created = handler
+        let handler = test_handler(state_root.clone());
+        let cell_id = match handler
             .handle(Request::CellCreate {
                 spec: CellSpec {
-                    name: "demo".to_string(),
+                    name: "orphan".to_string(),
                     env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
                 },
             })
-            .await;
-        let cell_id = match created {
+            .await
+        {
             Response::CellCreated { cell } => cell.id,
             other => panic!("unexpected response: {other:?}"),
         };
-
-        let started = handler
+        let job = match handler
             .handle(Request::JobRun {
                 cell_id: cell_id.clone(),
                 cmd: CommandSpec {
-                    argv: vec![
-                        "/bin/sh".to_string(),
-                        "-c".to_string(),
-                        "echo hello-from-job; echo err-line >&2".to_string(),
-                    ],
+                    argv: vec!["/bin/echo".to_string(), "hi".to_string()],
                     cwd: None,
                     env: BTreeMap::new(),
                     limits: None,
                 },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
             })
-            .await;
-        let job_id = match started {
-            Response::JobStarted { job } => job.id,
+            .await
+        {
+            Response::JobStarted { job } => job,
             other => panic!("unexpected response: {other:?}"),
         };
+        assert!(job.cell_exists);
 
-        let mut saw_hello = false;
-        for _ in 0..20 {
-            let chunk = handler
-                .handle(Request::LogsRead {
-                    job_id: job_id.clone(),
-                    stream: LogStream::Stdout,
-                    offset: 0,
-                    max_bytes: 4096,
-                    follow: true,
-                    wait_ms: 100,
-                })
-                .await;
-
-            if let Response::LogsChunk { data, .. } = chunk
-                && String::from_utf8_lossy(&data).contains("hello-from-job")
-            {
-                saw_hello = true;
-                break;
-            }
-            sleep(Duration::from_millis(50)).await;
-        }
-        assert!(saw_hello, "expected stdout logs via LogsRead");
+        std::fs::remove_dir_all(state_root.join("cells").join(&cell_id.0))
+            .expect("remove cell dir");
 
-        let status = handler
-            .handle(Request::JobStatus {
-                job_id: job_id.clone(),
-            })
-            .await;
-        match status {
-            Response::JobStatus { job } => {
-                assert_eq!(job.id, job_id);
-            }
+        match handler.handle(Request::JobStatus { job_id: job.id }).await {
+            Response::JobStatus { job } => assert!(!job.cell_exists),
             other => panic!("unexpected response: {other:?}"),
         }
+    }
 
-        let kill = handler
-            .handle(Request::JobKill {
+    /// Reads stdout from `offset` once, returning `(offset, data, reset)`.
+    async fn read_stdout(handler: &Handler, job_id: &JobId, offset: u64) -> (u64, Vec<u8>, bool) {
+        match handler
+            .handle(Request::LogsRead {
                 job_id: job_id.clone(),
-                force: true,
-            })
-            .await;
-        match kill {
-            Response::JobKilled { job_id: id, .. } => assert_eq!(id, job_id),
-            other => panic!("unexpected response: {other:?}"),
-        }
-
-        let removed = handler
-            .handle(Request::CellRemove {
-                cell_id: cell_id.clone(),
-                force: true,
+                stream: LogStream::Stdout,
+                offset,
+                max_bytes: 64 * 1024,
+                follow: false,
+                wait_ms: 0,
+                strip_ansi: false,
+                reverse: false,
+                generation: None,
             })
-            .await;
-        match removed {
-            Response::CellRemoved { cell_id: id } => assert_eq!(id, cell_id),
+            .await
+        {
+            Response::LogsChunk {
+                offset,
+                data,
+                reset,
+                ..
+            } => (offset, data, reset),
             other => panic!("unexpected response: {other:?}"),
         }
     }
 
     #[tokio::test]
-    /// Ensures removing a cell with active jobs requires `force=true`.
-    async fn remove_cell_without_force_fails_when_job_running() {
+    /// Verifies truncating a running job's logs resets followers and leaves no sparse gap.
+    async fn truncate_logs_mid_run_resets_followers() {
         let tmp = tempdir().expect("tempdir");
-        let state_root = tmp.path().join("state");
-        let handler = test_handler(state_root);
-
-        let created = handler
+        let handler = test_handler(tmp.path().join("state"));
+        let cell_id = match handler
             .handle(Request::CellCreate {
                 spec: CellSpec {
-                    name: "demo".to_string(),
+                    name: "truncate".to_string(),
                     env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
                 },
             })
-            .await;
-        let cell_id = match created {
+            .await
+        {
             Response::CellCreated { cell } => cell.id,
             other => panic!("unexpected response: {other:?}"),
         };
-
-        let started = handler
+        let job_id = match handler
             .handle(Request::JobRun {
-                cell_id: cell_id.clone(),
+                cell_id,
                 cmd: CommandSpec {
                     argv: vec![
                         "/bin/sh".to_string(),
                         "-c".to_string(),
-                        "sleep 10".to_string(),
+                        "i=0; while :; do echo line-$i; i=$((i+1)); sleep 0.02; done".to_string(),
                     ],
                     cwd: None,
                     env: BTreeMap::new(),
                     limits: None,
                 },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
             })
-            .await;
-        let job_id = match started {
+            .await
+        {
             Response::JobStarted { job } => job.id,
             other => panic!("unexpected response: {other:?}"),
         };
 
-        let removed = handler
-            .handle(Request::CellRemove {
-                cell_id,
-                force: false,
+        let mut follower_offset = 0;
+        for _ in 0..100 {
+            let (_, data, _) = read_stdout(&handler, &job_id, follower_offset).await;
+            follower_offset += data.len() as u64;
+            if follower_offset > 200 {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        assert!(follower_offset > 200, "job should produce output");
+
+        match handler
+            .handle(Request::LogsTruncate {
+                job_id: job_id.clone(),
+                stream: None,
             })
-            .await;
-        match removed {
-            Response::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidRequest),
+            .await
+        {
+            Response::LogsTruncated { job_id: id, .. } => assert_eq!(id, job_id),
             other => panic!("unexpected response: {other:?}"),
         }
 
+        let (offset, data, reset) = read_stdout(&handler, &job_id, follower_offset).await;
+        assert!(reset, "follower past the new end should be told to reset");
+        assert_eq!(offset, 0);
+        assert!(!String::from_utf8_lossy(&data).contains("line-0\n"));
+
+        sleep(Duration::from_millis(100)).await;
+        let (_, data, reset) = read_stdout(&handler, &job_id, 0).await;
+        assert!(!reset);
+        assert!(!data.is_empty(), "job should keep logging after truncation");
+        assert!(!data.contains(&0), "appends must not leave a sparse gap");
+
         let _ = handler
             .handle(Request::JobKill {
-                job_id: JobId(job_id.0),
+                job_id,
                 force: true,
+                grace_ms: None,
             })
             .await;
     }
+
+    #[tokio::test]
+    /// Verifies the daemon-wide running-job cap rejects launches until a slot frees up.
+    async fn run_job_rejected_at_global_running_cap() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let platform = Arc::new(MacosOps::new(
+            state_root.clone(),
+            SandboxMode::Disabled,
+            None,
+        ));
+        let state = StateStore::new(state_root, platform)
+            .expect("state should initialize")
+            .with_max_running_jobs(Some(2));
+        let handler = Handler::new(Arc::new(state));
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "capped".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let run = |cell_id: CellId| Request::JobRun {
+            cell_id,
+            cmd: CommandSpec {
+                argv: vec!["/bin/sleep".to_string(), "30".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                limits: None,
+            },
+            deterministic_id: false,
+            process_title: false,
+            system_tmp: false,
+        };
+
+        let mut running = Vec::new();
+        for _ in 0..2 {
+            match handler.handle(run(cell_id.clone())).await {
+                Response::JobStarted { job } => running.push(job.id),
+                other => panic!("unexpected response: {other:?}"),
+            }
+        }
+        match handler.handle(run(cell_id.clone())).await {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::Unavailable),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let killed = running.remove(0);
+        let _ = handler
+            .handle(Request::JobKill {
+                job_id: killed,
+                force: true,
+                grace_ms: None,
+            })
+            .await;
+        match handler.handle(run(cell_id)).await {
+            Response::JobStarted { job } => running.push(job.id),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        for job_id in running {
+            let _ = handler
+                .handle(Request::JobKill {
+                    job_id,
+                    force: true,
+                    grace_ms: None,
+                })
+                .await;
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a short envelope deadline ends a long follow read promptly with a timeout.
+    async fn follow_read_returns_at_request_deadline() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "deadline".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let job_id = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/bin/sleep".to_string(), "5".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = Instant::now();
+        let response = handler
+            .handle_with_deadline(
+                Request::LogsRead {
+                    job_id: job_id.clone(),
+                    stream: LogStream::Stdout,
+                    offset: 0,
+                    max_bytes: 4096,
+                    follow: true,
+                    wait_ms: 10_000,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+                Some(started + Duration::from_millis(150)),
+            )
+            .await;
+        let elapsed = started.elapsed();
+        match response {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::Timeout),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+
+        let _ = handler
+            .handle(Request::JobKill {
+                job_id,
+                force: true,
+                grace_ms: None,
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    /// Exercises create/run/logs/status/kill/remove lifecycle through handler API.
+    async fn lifecycle_and_logs_flow() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let handler = test_handler(state_root);
+
+        let created = handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "demo".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await;
+        let cell_id = match created {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = handler
+            .handle(Request::JobRun {
+                cell_id: cell_id.clone(),
+                cmd: CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "echo hello-from-job; echo err-line >&2".to_string(),
+                    ],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await;
+        let job_id = match started {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let mut saw_hello = false;
+        for _ in 0..20 {
+            let chunk = handler
+                .handle(Request::LogsRead {
+                    job_id: job_id.clone(),
+                    stream: LogStream::Stdout,
+                    offset: 0,
+                    max_bytes: 4096,
+                    follow: true,
+                    wait_ms: 100,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                })
+                .await;
+
+            if let Response::LogsChunk { data, .. } = chunk
+                && String::from_utf8_lossy(&data).contains("hello-from-job")
+            {
+                saw_hello = true;
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        assert!(saw_hello, "expected stdout logs via LogsRead");
+
+        let status = handler
+            .handle(Request::JobStatus {
+                job_id: job_id.clone(),
+            })
+            .await;
+        match status {
+            Response::JobStatus { job } => {
+                assert_eq!(job.id, job_id);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let kill = handler
+            .handle(Request::JobKill {
+                job_id: job_id.clone(),
+                force: true,
+                grace_ms: None,
+            })
+            .await;
+        match kill {
+            Response::JobKilled { job_id: id, .. } => assert_eq!(id, job_id),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let removed = handler
+            .handle(Request::CellRemove {
+                cell_id: cell_id.clone(),
+                force: true,
+            })
+            .await;
+        match removed {
+            Response::CellRemoved { cell_id: id } => assert_eq!(id, cell_id),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Ensures removing a cell with active jobs requires `force=true`.
+    async fn remove_cell_without_force_fails_when_job_running() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let handler = test_handler(state_root);
+
+        let created = handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "demo".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await;
+        let cell_id = match created {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = handler
+            .handle(Request::JobRun {
+                cell_id: cell_id.clone(),
+                cmd: CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "sleep 10".to_string(),
+                    ],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await;
+        let job_id = match started {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let removed = handler
+            .handle(Request::CellRemove {
+                cell_id,
+                force: false,
+            })
+            .await;
+        match removed {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidRequest),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let _ = handler
+            .handle(Request::JobKill {
+                job_id: JobId(job_id.0),
+                force: true,
+                grace_ms: None,
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    /// Drains a cell with a short-lived job and verifies it is removed cleanly.
+    async fn drain_cell_waits_for_jobs_then_removes() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let handler = test_handler(state_root);
+
+        let created = handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "demo".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await;
+        let cell_id = match created {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = handler
+            .handle(Request::JobRun {
+                cell_id: cell_id.clone(),
+                cmd: CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "sleep 0.2".to_string(),
+                    ],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await;
+        assert!(matches!(started, Response::JobStarted { .. }));
+
+        let drained = handler
+            .handle(Request::CellDrain {
+                cell_id: cell_id.clone(),
+                timeout_ms: 5_000,
+            })
+            .await;
+        match drained {
+            Response::CellDrained {
+                cell_id: id,
+                removed,
+                pending_jobs,
+            } => {
+                assert_eq!(id, cell_id);
+                assert!(removed);
+                assert!(pending_jobs.is_empty());
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let rerun = handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/usr/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await;
+        match rerun {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::NotFound),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies creating a cell and running a job journals two events.
+    async fn create_and_run_are_journaled() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let created = handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "journal".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await;
+        let cell_id = match created {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = handler
+            .handle(Request::JobRun {
+                cell_id: cell_id.clone(),
+                cmd: CommandSpec {
+                    argv: vec!["/usr/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await;
+        let job_id = match started {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let events = match handler
+            .handle(Request::Events {
+                since_ms: 0,
+                limit: 100,
+            })
+            .await
+        {
+            Response::Events { events } => events,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::CellCreated);
+        assert_eq!(events[0].cell_id.as_ref(), Some(&cell_id));
+        assert_eq!(events[1].kind, EventKind::JobStarted);
+        assert_eq!(events[1].job_id.as_ref(), Some(&job_id));
+    }
+
+    #[test]
+    /// Verifies renames, reaps that closed sessions, and restores are journaled.
+    fn rename_reap_and_restore_are_journaled() {
+        let renamed = journal_event(&Response::CellRenamed {
+            cell: CellInfo {
+                id: CellId("cell-1".to_string()),
+                spec: CellSpec {
+                    name: "renamed".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+                created_at_ms: 0,
+                dir: "/tmp/cell-1".to_string(),
+                draining: false,
+            },
+        })
+        .expect("rename event");
+        assert_eq!(renamed.kind, EventKind::CellRenamed);
+        assert_eq!(renamed.cell_id, Some(CellId("cell-1".to_string())));
+        assert_eq!(renamed.detail.as_deref(), Some("name=renamed"));
+
+        let reaped = journal_event(&Response::PtyReaped {
+            session_ids: vec![SessionId(3), SessionId(5)],
+        })
+        .expect("reap event");
+        assert_eq!(reaped.kind, EventKind::PtyReaped);
+        assert_eq!(reaped.detail.as_deref(), Some("sessions=3,5"));
+        assert!(
+            journal_event(&Response::PtyReaped {
+                session_ids: Vec::new()
+            })
+            .is_none()
+        );
+
+        let restored =
+            journal_event(&Response::StateRestored { cells: 1, jobs: 2 }).expect("restore event");
+        assert_eq!(restored.kind, EventKind::StateRestored);
+        assert_eq!(restored.detail.as_deref(), Some("cells=1 jobs=2"));
+    }
+
+    #[tokio::test]
+    /// Verifies started jobs record the configured sandbox mode.
+    async fn job_records_configured_sandbox_mode() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "sandbox".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let job_id = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/usr/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => {
+                assert_eq!(job.sandbox_mode, Some(SandboxMode::Disabled));
+                assert!(!job.sandboxed);
+                job.id
+            }
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        match handler.handle(Request::JobStatus { job_id }).await {
+            Response::JobStatus { job } => {
+                assert_eq!(job.sandbox_mode, Some(SandboxMode::Disabled));
+                assert!(!job.sandboxed);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a cell's disabled sandbox override applies on an enforced daemon, and that the
+    /// daemon default is enforced: jobs run sandboxed, or are refused without a sandbox runtime.
+    async fn cell_sandbox_mode_overrides_daemon_default() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let platform = Arc::new(MacosOps::new(
+            state_root.clone(),
+            SandboxMode::Enforced,
+            None,
+        ));
+        let state = StateStore::new(state_root, platform).expect("state should initialize");
+        let handler = Handler::new(Arc::new(state));
+
+        let mut outcomes = Vec::new();
+        for sandbox_mode in [Some(SandboxMode::Disabled), None] {
+            let cell_id = match handler
+                .handle(Request::CellCreate {
+                    spec: CellSpec {
+                        name: "override".to_string(),
+                        env: BTreeMap::new(),
+                        sandbox_mode,
+                        inherit_host_env: Vec::new(),
+                        worker_bin_override: None,
+                    },
+                })
+                .await
+            {
+                Response::CellCreated { cell } => cell.id,
+                other => panic!("unexpected response: {other:?}"),
+            };
+            outcomes.push(
+                handler
+                    .handle(Request::JobRun {
+                        cell_id,
+                        cmd: CommandSpec {
+                            argv: vec!["/usr/bin/true".to_string()],
+                            cwd: None,
+                            env: BTreeMap::new(),
+                            limits: None,
+                        },
+                        deterministic_id: false,
+                        process_title: false,
+                        system_tmp: false,
+                    })
+                    .await,
+            );
+        }
+
+        match &outcomes[0] {
+            Response::JobStarted { job } => {
+                assert_eq!(job.sandbox_mode, Some(SandboxMode::Disabled));
+                assert!(!job.sandboxed);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match &outcomes[1] {
+            Response::JobStarted { job } if Path::new("/usr/bin/sandbox-exec").exists() => {
+                assert_eq!(job.sandbox_mode, Some(SandboxMode::Enforced));
+                assert!(job.sandboxed);
+                assert_eq!(job.sandbox_fallback_reason, None);
+            }
+            Response::Error { code, message, .. }
+                if !Path::new("/usr/bin/sandbox-exec").exists() =>
+            {
+                assert_eq!(*code, ErrorCode::Unavailable);
+                assert_eq!(message, "sandbox unavailable for enforced job");
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies eager shutdown stops a cell's worker shortly after its last job exits.
+    async fn eager_shutdown_stops_worker_after_last_job_exits() {
+        let tmp = tempdir().expect("tempdir");
+        let state_root = tmp.path().join("state");
+        let platform = Arc::new(MacosOps::new(
+            state_root.clone(),
+            SandboxMode::Disabled,
+            None,
+        ));
+        let state = Arc::new(
+            StateStore::new(state_root, platform)
+                .expect("state should initialize")
+                .with_eager_worker_shutdown(true),
+        );
+        let watcher = state.spawn_exit_watcher(Duration::from_millis(50));
+        let handler = Handler::new(Arc::clone(&state));
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "eager".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let job_id = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/bin/sleep".to_string(), "0.3".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let mut exited = false;
+        for _ in 0..60 {
+            sleep(Duration::from_millis(50)).await;
+            match handler
+                .handle(Request::JobStatus {
+                    job_id: job_id.clone(),
+                })
+                .await
+            {
+                Response::JobStatus { job } => {
+                    exited = matches!(job.status, ExitStatus::Exited { .. });
+                }
+                other => panic!("unexpected response: {other:?}"),
+            }
+            if exited {
+                break;
+            }
+        }
+        assert!(exited, "watcher should observe the natural exit");
+        assert_eq!(
+            state.worker_liveness(None).await.expect("liveness").1,
+            0,
+            "worker should stop once the cell is idle"
+        );
+        watcher.abort();
+    }
+
+    /// Creates a cell and starts `script` in it, returning the job id.
+    async fn start_shell_job(handler: &Handler, script: &str) -> JobId {
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "wait".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies job wait times out with the running status and returns the exit once done.
+    async fn job_wait_returns_exit_status() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+        let job_id = start_shell_job(&handler, "sleep 0.4; exit 3").await;
+
+        match handler
+            .handle(Request::JobWait {
+                job_id: job_id.clone(),
+                timeout_ms: Some(50),
+            })
+            .await
+        {
+            Response::JobStatus { job } => assert_eq!(job.status, ExitStatus::Running),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match handler
+            .handle(Request::JobWait {
+                job_id,
+                timeout_ms: Some(5000),
+            })
+            .await
+        {
+            Response::JobStatus { job } => {
+                assert_eq!(job.status, ExitStatus::Exited { code: Some(3) });
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies job status reports how many bytes each log stream holds.
+    async fn job_status_reports_log_byte_counts() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+        let job_id = start_shell_job(&handler, "printf 0123456789; printf oops >&2").await;
+
+        match handler
+            .handle(Request::JobWait {
+                job_id: job_id.clone(),
+                timeout_ms: Some(5000),
+            })
+            .await
+        {
+            Response::JobStatus { job } => {
+                assert_eq!(job.status, ExitStatus::Exited { code: Some(0) });
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        match handler.handle(Request::JobStatus { job_id }).await {
+            Response::JobStatus { job } => {
+                assert_eq!(job.stdout_bytes, 10);
+                assert_eq!(job.stderr_bytes, 4);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a synced log reads back in full right away and unknown jobs are `NotFound`.
+    async fn logs_sync_makes_output_readable() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+        let job_id = start_shell_job(&handler, "printf synced-output; sleep 30").await;
+
+        let mut delivered = Vec::new();
+        for _ in 0..50 {
+            match handler
+                .handle(Request::LogsSync {
+                    job_id: job_id.clone(),
+                })
+                .await
+            {
+                Response::LogsSynced { job_id: id } => assert_eq!(id, job_id),
+                other => panic!("unexpected response: {other:?}"),
+            }
+            delivered = read_stdout(&handler, &job_id, 0).await.1;
+            if !delivered.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(delivered, b"synced-output");
+
+        match handler
+            .handle(Request::LogsSync {
+                job_id: JobId("job-missing".to_string()),
+            })
+            .await
+        {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::NotFound),
+            other => panic!("unexpected response: {other:?}"),
+        }
+        let _ = handler
+            .handle(Request::JobKill {
+                job_id,
+                force: true,
+                grace_ms: None,
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    /// Verifies removing a waited-on job's cell ends the wait promptly with a clear `NotFound`.
+    async fn job_wait_fails_promptly_when_cell_removed() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = Arc::new(test_handler(tmp.path().join("state")));
+        let job_id = start_shell_job(&handler, "sleep 30").await;
+        let cell_id = match handler
+            .handle(Request::JobStatus {
+                job_id: job_id.clone(),
+            })
+            .await
+        {
+            Response::JobStatus { job } => job.cell_id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let waiter = {
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                handler
+                    .handle(Request::JobWait {
+                        job_id,
+                        timeout_ms: None,
+                    })
+                    .await
+            })
+        };
+        sleep(Duration::from_millis(250)).await;
+        assert!(
+            !waiter.is_finished(),
+            "wait should block while the job runs"
+        );
+
+        let removed_at = std::time::Instant::now();
+        match handler
+            .handle(Request::CellRemove {
+                cell_id,
+                force: true,
+            })
+            .await
+        {
+            Response::CellRemoved { .. } => {}
+            other => panic!("unexpected response: {other:?}"),
+        }
+        let response = tokio::time::timeout(Duration::from_secs(2), waiter)
+            .await
+            .expect("wait should end")
+            .expect("waiter should join");
+        let elapsed = removed_at.elapsed();
+
+        match response {
+            Response::Error { code, message, .. } => {
+                assert_eq!(code, ErrorCode::NotFound);
+                assert_eq!(message, "job removed while waiting");
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        assert!(
+            elapsed < Duration::from_millis(80),
+            "wait should end without another poll: {elapsed:?}"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies launched jobs record spawn latency and report it through status.
+    async fn job_records_spawn_latency() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "latency".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/usr/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let latency = started.spawn_latency_ms.expect("spawn latency recorded");
+        assert!(latency < 60_000, "latency={latency}");
+
+        match handler
+            .handle(Request::JobStatus { job_id: started.id })
+            .await
+        {
+            Response::JobStatus { job } => assert_eq!(job.spawn_latency_ms, Some(latency)),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a usage subscription streams samples while the job runs and ends once it exits.
+    async fn usage_subscription_streams_samples_until_job_exits() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "usage".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let job_id = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "sleep 0.4".to_string(),
+                    ],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let (frames, mut received) = mpsc::channel(64);
+        let started = Instant::now();
+        let last = handler
+            .handle_streaming(
+                Request::JobUsageSubscribe {
+                    job_id: job_id.clone(),
+                    interval_ms: 50,
+                },
+                None,
+                frames,
+            )
+            .await;
+        let elapsed = started.elapsed();
+
+        let mut samples = 0;
+        while let Ok(frame) = received.try_recv() {
+            match frame {
+                Response::UsageSample {
+                    job_id: sampled, ..
+                } => {
+                    assert_eq!(sampled, job_id);
+                    samples += 1;
+                }
+                other => panic!("unexpected frame: {other:?}"),
+            }
+        }
+        assert!(samples >= 1, "no usage samples streamed");
+        match last {
+            Response::JobUsageEnded {
+                job_id: ended,
+                status,
+            } => {
+                assert_eq!(ended, job_id);
+                assert_eq!(status, ExitStatus::Exited { code: Some(0) });
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+        assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    /// Verifies launched jobs persist both the request and worker-ack timestamps, in order.
+    async fn job_records_requested_and_started_timestamps() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "timestamps".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+
+        let started = match handler
+            .handle(Request::JobRun {
+                cell_id,
+                cmd: CommandSpec {
+                    argv: vec!["/usr/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                deterministic_id: false,
+                process_title: false,
+                system_tmp: false,
+            })
+            .await
+        {
+            Response::JobStarted { job } => job,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        assert!(started.requested_at_ms > 0);
+        assert!(
+            started.requested_at_ms <= started.started_at_ms,
+            "requested={} started={}",
+            started.requested_at_ms,
+            started.started_at_ms
+        );
+
+        match handler
+            .handle(Request::JobStatus { job_id: started.id })
+            .await
+        {
+            Response::JobStatus { job } => {
+                assert_eq!(job.requested_at_ms, started.requested_at_ms);
+                assert_eq!(job.started_at_ms, started.started_at_ms);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies batched create+run returns both responses in order.
+    async fn batch_runs_requests_in_order() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+
+        let cell_id = match handler
+            .handle(Request::CellCreate {
+                spec: CellSpec {
+                    name: "existing".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: None,
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                },
+            })
+            .await
+        {
+            Response::CellCreated { cell } => cell.id,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        let run = |cell_id| Request::JobRun {
+            cell_id,
+            cmd: CommandSpec {
+                argv: vec!["/usr/bin/true".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                limits: None,
+            },
+            deterministic_id: false,
+            process_title: false,
+            system_tmp: false,
+        };
+
+        let batch = handler
+            .handle(Request::Batch {
+                requests: vec![
+                    Request::CellCreate {
+                        spec: CellSpec {
+                            name: "batched".to_string(),
+                            env: BTreeMap::new(),
+                            sandbox_mode: None,
+                            inherit_host_env: Vec::new(),
+                            worker_bin_override: None,
+                        },
+                    },
+                    run(cell_id.clone()),
+                ],
+                stop_on_error: true,
+            })
+            .await;
+        match batch {
+            Response::Batch { responses } => {
+                assert_eq!(responses.len(), 2);
+                assert!(matches!(responses[0], Response::CellCreated { .. }));
+                match &responses[1] {
+                    Response::JobStarted { job } => assert_eq!(job.cell_id, cell_id),
+                    other => panic!("unexpected response: {other:?}"),
+                }
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let missing = planter_core::CellId("cell-missing".to_string());
+        let stopped = handler
+            .handle(Request::Batch {
+                requests: vec![run(missing), Request::Health { deep: false }],
+                stop_on_error: true,
+            })
+            .await;
+        match stopped {
+            Response::Batch { responses } => {
+                assert_eq!(responses.len(), 1);
+                assert!(matches!(responses[0], Response::Error { .. }));
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        let nested = handler
+            .handle(Request::Batch {
+                requests: vec![Request::Batch {
+                    requests: Vec::new(),
+                    stop_on_error: false,
+                }],
+                stop_on_error: false,
+            })
+            .await;
+        match nested {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidRequest),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies diagnostics count earlier error responses by code without side effects.
+    async fn diagnostics_counts_error_responses() {
+        let tmp = tempdir().expect("tempdir");
+        let handler = test_handler(tmp.path().join("state"));
+        for _ in 0..2 {
+            let response = handler
+                .handle(Request::JobStatus {
+                    job_id: JobId("missing".to_string()),
+                })
+                .await;
+            assert!(matches!(
+                response,
+                Response::Error {
+                    code: ErrorCode::NotFound,
+                    ..
+                }
+            ));
+        }
+
+        let report = match handler.handle(Request::Diagnostics {}).await {
+            Response::Diagnostics { report } => report,
+            other => panic!("unexpected response: {other:?}"),
+        };
+        assert_eq!(
+            report.errors,
+            vec![ErrorCount {
+                code: ErrorCode::NotFound,
+                count: 2,
+            }]
+        );
+        assert_eq!(report.resources.cells, 0);
+        assert!(report.workers.is_empty());
+    }
 }
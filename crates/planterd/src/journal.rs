@@ -0,0 +1,259 @@
+//! Append-only JSONL journal of daemon lifecycle events.
+
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+use planter_core::{ErrorCode, JournalEvent, PlanterError};
+use tokio::sync::oneshot;
+
+/// Bytes read per step while scanning the journal backwards.
+const READ_BACKWARDS_CHUNK: u64 = 64 * 1024;
+
+/// Messages consumed by the journal writer thread.
+enum JournalMsg {
+    /// Appends one event to the journal.
+    Append(JournalEvent),
+    /// Flushes buffered events, then acknowledges.
+    Flush(oneshot::Sender<()>),
+}
+
+/// Buffered event journal backed by a background writer thread.
+pub struct Journal {
+    /// Journal file path.
+    path: PathBuf,
+    /// Channel into the writer thread.
+    sender: mpsc::Sender<JournalMsg>,
+}
+
+impl Journal {
+    /// Opens a journal at `path` and starts its writer thread.
+    pub fn open(path: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let writer_path = path.clone();
+        thread::spawn(move || run_writer(&writer_path, receiver));
+        Self { path, sender }
+    }
+
+    /// Queues an event for appending without blocking the caller.
+    pub fn append(&self, event: JournalEvent) {
+        if self.sender.send(JournalMsg::Append(event)).is_err() {
+            tracing::warn!("journal writer is not running; dropping event");
+        }
+    }
+
+    /// Returns the most recent `limit` events at or after `since_ms`, oldest first.
+    ///
+    /// The journal is read backwards from its end, stopping at the first event older than
+    /// `since_ms` or once `limit` events are found, so the cost follows what is returned
+    /// rather than the journal's size.
+    pub async fn read_recent(
+        &self,
+        since_ms: u64,
+        limit: u32,
+    ) -> Result<Vec<JournalEvent>, PlanterError> {
+        let (ack, flushed) = oneshot::channel();
+        if self.sender.send(JournalMsg::Flush(ack)).is_ok() {
+            let _ = flushed.await;
+        }
+
+        let mut file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(io_to_error("open journal", err)),
+        };
+
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let mut events = Vec::new();
+        if limit > 0 {
+            for_each_line_backwards(&mut file, |line| {
+                if line.trim_ascii().is_empty() {
+                    return true;
+                }
+                match serde_json::from_slice::<JournalEvent>(line) {
+                    Ok(event) if event.ts_ms >= since_ms => {
+                        events.push(event);
+                        events.len() < limit
+                    }
+                    Ok(_) => false,
+                    Err(err) => {
+                        tracing::warn!(error = %err, "skipping malformed journal line");
+                        true
+                    }
+                }
+            })
+            .map_err(|err| io_to_error("read journal", err))?;
+        }
+        events.reverse();
+        Ok(events)
+    }
+}
+
+/// Feeds `file`'s lines to `visit` from last to first until it returns false.
+fn for_each_line_backwards(
+    file: &mut fs::File,
+    mut visit: impl FnMut(&[u8]) -> bool,
+) -> io::Result<()> {
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    // The start of the line the previous chunk began partway through.
+    let mut tail = Vec::new();
+    while pos > 0 {
+        let start = pos.saturating_sub(READ_BACKWARDS_CHUNK);
+        let mut chunk = vec![0; (pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+
+        let mut end = chunk.len();
+        while let Some(newline) = chunk[..end].iter().rposition(|byte| *byte == b'\n') {
+            if !visit(&chunk[newline + 1..end]) {
+                return Ok(());
+            }
+            end = newline;
+        }
+        chunk.truncate(end);
+        tail = chunk;
+        pos = start;
+    }
+    visit(&tail);
+    Ok(())
+}
+
+/// Drains queued messages into a buffered append-only file writer.
+fn run_writer(path: &Path, receiver: mpsc::Receiver<JournalMsg>) {
+    let mut writer = None;
+
+    while let Ok(first) = receiver.recv() {
+        let mut acks = Vec::new();
+        for msg in std::iter::once(first).chain(receiver.try_iter()) {
+            match msg {
+                JournalMsg::Append(event) => {
+                    if let Err(err) = write_event(path, &mut writer, &event) {
+                        tracing::warn!(error = %err, path = %path.display(), "failed to append journal event");
+                    }
+                }
+                JournalMsg::Flush(ack) => acks.push(ack),
+            }
+        }
+
+        if let Some(writer) = writer.as_mut()
+            && let Err(err) = writer.flush()
+        {
+            tracing::warn!(error = %err, path = %path.display(), "failed to flush journal");
+        }
+        for ack in acks {
+            let _ = ack.send(());
+        }
+    }
+}
+
+/// Serializes one event as a JSON line, opening the journal on first use.
+fn write_event(
+    path: &Path,
+    writer: &mut Option<BufWriter<fs::File>>,
+    event: &JournalEvent,
+) -> io::Result<()> {
+    if writer.is_none() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        *writer = Some(BufWriter::new(file));
+    }
+
+    let Some(writer) = writer.as_mut() else {
+        return Ok(());
+    };
+    serde_json::to_writer(&mut *writer, event)?;
+    writer.write_all(b"\n")
+}
+
+/// Converts plain I/O errors to standardized planter errors.
+fn io_to_error(action: &str, err: io::Error) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Internal,
+        message: action.to_string(),
+        detail: Some(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use planter_core::{CellId, EventKind, JournalEvent};
+    use tempfile::tempdir;
+
+    use super::Journal;
+
+    /// Builds a cell-scoped event with a fixed timestamp.
+    fn event(ts_ms: u64, kind: EventKind) -> JournalEvent {
+        JournalEvent {
+            ts_ms,
+            kind,
+            cell_id: Some(CellId("cell-1".to_string())),
+            job_id: None,
+            detail: None,
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies appended events are persisted and filtered by time and limit.
+    async fn append_and_read_recent_events() {
+        let tmp = tempdir().expect("tempdir");
+        let journal = Journal::open(tmp.path().join("events.jsonl"));
+
+        journal.append(event(10, EventKind::CellCreated));
+        journal.append(event(20, EventKind::JobStarted));
+        journal.append(event(30, EventKind::CellRemoved));
+
+        let all = journal.read_recent(0, 100).await.expect("read journal");
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].kind, EventKind::CellCreated);
+
+        let since = journal.read_recent(20, 100).await.expect("read journal");
+        assert_eq!(since.len(), 2);
+
+        let latest = journal.read_recent(0, 1).await.expect("read journal");
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].kind, EventKind::CellRemoved);
+    }
+
+    #[tokio::test]
+    /// Verifies backwards reads return whole events when lines straddle read chunks.
+    async fn read_recent_spans_read_chunks() {
+        let tmp = tempdir().expect("tempdir");
+        let journal = Journal::open(tmp.path().join("events.jsonl"));
+        for ts_ms in 0..3_000 {
+            journal.append(JournalEvent {
+                detail: Some("x".repeat(50)),
+                ..event(ts_ms, EventKind::JobStarted)
+            });
+        }
+
+        let latest = journal.read_recent(0, 3).await.expect("read journal");
+        let stamps: Vec<u64> = latest.iter().map(|event| event.ts_ms).collect();
+        assert_eq!(stamps, [2_997, 2_998, 2_999]);
+
+        let since = journal
+            .read_recent(2_000, 10_000)
+            .await
+            .expect("read journal");
+        assert_eq!(since.len(), 1_000);
+        assert!(
+            since
+                .iter()
+                .zip(2_000..)
+                .all(|(event, ts_ms)| event.ts_ms == ts_ms)
+        );
+
+        let all = journal.read_recent(0, 10_000).await.expect("read journal");
+        assert_eq!(all.len(), 3_000);
+        assert_eq!(all[0].ts_ms, 0);
+    }
+}
@@ -1,5 +1,7 @@
 mod dispatch;
 mod handlers;
+mod journal;
+mod signals;
 mod state;
 mod worker;
 mod worker_manager;
@@ -10,18 +12,26 @@ use std::{
     path::{Path, PathBuf},
     process::ExitCode,
     sync::Arc,
+    time::Duration,
 };
 
-use clap::{Parser, ValueEnum};
+use clap::Parser;
 use dispatch::DaemonDispatcher;
-use planter_core::{PROTOCOL_VERSION, default_state_dir};
+use planter_core::{
+    DEFAULT_MAX_PTY_INPUT_BYTES, PROTOCOL_VERSION, SandboxMode, VersionFile, default_state_dir,
+};
+use planter_execd_proto::DEFAULT_KILL_GRACE_MS;
 use planter_ipc::serve_unix;
-use planter_platform::PlatformOps;
-use state::StateStore;
+use planter_platform::{PlatformOps, RootPolicy, current_euid, root_policy};
+use state::{DEFAULT_HEALTH_TIMEOUT_MS, StateStore};
 use tracing::info;
+use worker_manager::{DEFAULT_CALL_LOCK_TIMEOUT_MS, DEFAULT_PTY_READER_JOIN_TIMEOUT_MS};
 
 #[cfg(target_os = "macos")]
-use planter_platform_macos::{MacosOps, SandboxMode};
+use planter_platform_macos::MacosOps;
+
+/// How often running jobs are polled for exits when eager worker shutdown is on.
+const EXIT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
 
 /// CLI arguments for launching the planter daemon.
 #[derive(Debug, Parser)]
@@ -31,19 +41,66 @@ struct Args {
     #[arg(long, default_value = "/tmp/planterd.sock")]
     socket: PathBuf,
     /// Sandbox mode used by the platform backend.
-    #[arg(long, value_enum, default_value_t = SandboxModeArg::Permissive)]
-    sandbox_mode: SandboxModeArg,
-}
-
-/// CLI-facing sandbox mode values.
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum SandboxModeArg {
-    /// Disable sandbox enforcement.
-    Disabled,
-    /// Use sandbox when available and permit graceful fallback.
-    Permissive,
-    /// Require sandboxing for job launches.
-    Enforced,
+    #[arg(long, value_enum, default_value_t = SandboxMode::Permissive)]
+    sandbox_mode: SandboxMode,
+    /// Directory of extra `.sb` fragments appended to generated sandbox profiles.
+    #[arg(long)]
+    sandbox_profile_dir: Option<PathBuf>,
+    /// Maximum bytes accepted in one PTY input message.
+    #[arg(long, default_value_t = DEFAULT_MAX_PTY_INPUT_BYTES)]
+    max_pty_input_bytes: usize,
+    /// Repeatable shell path `PtyOpen` may launch; any shell is allowed when none are given.
+    #[arg(long = "pty-shell", value_name = "PATH")]
+    pty_shells: Vec<PathBuf>,
+    /// Repeatable `planter-execd` path a cell may name with `create --worker-bin`; cells can't
+    /// pick their worker binary when none are given.
+    #[arg(long = "allow-worker-bin", value_name = "PATH")]
+    allow_worker_bins: Vec<PathBuf>,
+    /// Repeatable daemon environment variable a cell may inherit with `create --inherit-env`;
+    /// cells can't inherit any when none are given.
+    #[arg(long = "allow-inherit-env", value_name = "NAME")]
+    allow_inherit_env: Vec<String>,
+    /// Default milliseconds between TERM and KILL when a kill request sets no grace period.
+    #[arg(long, default_value_t = DEFAULT_KILL_GRACE_MS)]
+    kill_grace_ms: u64,
+    /// Maximum jobs running at once across all cells; unlimited when unset.
+    #[arg(long)]
+    max_running_jobs: Option<usize>,
+    /// Stop a cell's worker as soon as its last running job exits instead of keeping it warm.
+    #[arg(long)]
+    eager_worker_shutdown: bool,
+    /// Milliseconds deep health checks wait on workers before reporting `degraded`.
+    #[arg(long, default_value_t = DEFAULT_HEALTH_TIMEOUT_MS)]
+    health_timeout_ms: u64,
+    /// Milliseconds a request waits behind another call into the same cell before it fails
+    /// with `unavailable`.
+    #[arg(long, default_value_t = DEFAULT_CALL_LOCK_TIMEOUT_MS)]
+    cell_lock_timeout_ms: u64,
+    /// Milliseconds closing a PTY session waits for its output reader before detaching it.
+    #[arg(long, default_value_t = DEFAULT_PTY_READER_JOIN_TIMEOUT_MS)]
+    pty_reader_join_timeout_ms: u64,
+    /// Directory for job logs, e.g. on a larger volume; defaults to `<state dir>/logs`.
+    #[arg(long)]
+    logs_dir: Option<PathBuf>,
+    /// Start even when running as root, which undermines the sandbox and hands jobs root.
+    #[arg(long)]
+    allow_root: bool,
+    /// Optional TCP address for a mutually authenticated TLS listener.
+    #[cfg(feature = "tls")]
+    #[arg(long, requires_all = ["tls_cert", "tls_key", "tls_client_ca"])]
+    tcp_listen: Option<std::net::SocketAddr>,
+    /// PEM certificate chain presented by the TLS listener.
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key for the TLS listener certificate.
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    tls_key: Option<PathBuf>,
+    /// PEM CA bundle used to verify client certificates.
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    tls_client_ca: Option<PathBuf>,
 }
 
 /// Entrypoint that maps startup failures to process exit code.
@@ -63,22 +120,99 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_target(false).init();
 
     let args = Args::parse();
+    match root_policy(current_euid(), args.allow_root) {
+        RootPolicy::Unprivileged => {}
+        RootPolicy::AllowedRoot => tracing::warn!(
+            "running as root because --allow-root was passed; workers and jobs run as root and the sandbox offers little protection"
+        ),
+        RootPolicy::RefusedRoot => {
+            return Err("refusing to run as root: workers and jobs would run as root and the sandbox offers little protection; \
+                 run as an unprivileged user or pass --allow-root"
+                .into());
+        }
+    }
     prepare_socket_path(&args.socket)?;
 
     let state_dir = default_state_dir();
-    let platform = select_platform(state_dir.clone(), args.sandbox_mode)?;
-    let state = Arc::new(StateStore::new(state_dir, platform)?);
+    let platform = select_platform(
+        state_dir.clone(),
+        args.sandbox_mode,
+        args.sandbox_profile_dir.clone(),
+    )?;
+    let state = Arc::new(
+        StateStore::new(state_dir, platform)?
+            .with_max_pty_input_bytes(args.max_pty_input_bytes)
+            .with_pty_shells(args.pty_shells.clone())
+            .with_worker_bins(args.allow_worker_bins.clone())?
+            .with_inheritable_env(args.allow_inherit_env.clone())
+            .with_default_kill_grace_ms(args.kill_grace_ms)
+            .with_max_running_jobs(args.max_running_jobs)
+            .with_eager_worker_shutdown(args.eager_worker_shutdown)
+            .with_health_timeout(Duration::from_millis(args.health_timeout_ms))
+            .with_cell_lock_timeout(Duration::from_millis(args.cell_lock_timeout_ms))
+            .with_pty_reader_join_timeout(Duration::from_millis(args.pty_reader_join_timeout_ms))
+            .with_logs_dir(args.logs_dir.clone())?,
+    );
+    let version = VersionFile {
+        daemon: env!("CARGO_PKG_VERSION").to_string(),
+        protocol: PROTOCOL_VERSION,
+    };
+    if let Err(err) = version.write(state.root()) {
+        tracing::warn!(error = %err, "failed to write version file");
+    }
+    if args.eager_worker_shutdown {
+        state.spawn_exit_watcher(EXIT_WATCH_INTERVAL);
+    }
 
     info!(
         socket = %args.socket.display(),
         state_dir = %state.root().display(),
+        logs_dir = %state.logs_dir().display(),
         sandbox_mode = %args.sandbox_mode.as_str(),
         daemon = env!("CARGO_PKG_VERSION"),
         protocol = PROTOCOL_VERSION,
         "starting planterd"
     );
 
-    serve_unix(&args.socket, Arc::new(DaemonDispatcher::from(state))).await?;
+    let dispatcher = Arc::new(DaemonDispatcher::from(state));
+    #[cfg(feature = "tls")]
+    spawn_tls_listener(&args, Arc::clone(&dispatcher)).await?;
+
+    serve_unix(&args.socket, dispatcher).await?;
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+/// Starts the optional TLS-over-TCP listener alongside the UNIX socket.
+///
+/// The address is bound before returning so a port in use stops the daemon at startup.
+async fn spawn_tls_listener(
+    args: &Args,
+    dispatcher: Arc<DaemonDispatcher>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(addr), Some(cert), Some(key), Some(client_ca)) = (
+        args.tcp_listen,
+        args.tls_cert.as_ref(),
+        args.tls_key.as_ref(),
+        args.tls_client_ca.as_ref(),
+    ) else {
+        return Ok(());
+    };
+
+    let config = planter_ipc::server_config_from_pem(
+        &fs::read(cert)?,
+        &fs::read(key)?,
+        &fs::read(client_ca)?,
+    )?;
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|err| format!("failed to bind tls listener on {addr}: {err}"))?;
+    info!(%addr, "starting tls listener");
+    tokio::spawn(async move {
+        if let Err(err) = planter_ipc::serve_tcp_listener(listener, config, dispatcher).await {
+            tracing::error!(error = %err, "tls listener exited");
+        }
+    });
     Ok(())
 }
 
@@ -102,34 +236,23 @@ fn prepare_socket_path(path: &Path) -> io::Result<()> {
 
 #[cfg(target_os = "macos")]
 /// Selects the macOS platform backend for process and sandbox operations.
-fn select_platform(root: PathBuf, mode: SandboxModeArg) -> Result<Arc<dyn PlatformOps>, io::Error> {
-    let sandbox_mode = match mode {
-        SandboxModeArg::Disabled => SandboxMode::Disabled,
-        SandboxModeArg::Permissive => SandboxMode::Permissive,
-        SandboxModeArg::Enforced => SandboxMode::Enforced,
-    };
-    Ok(Arc::new(MacosOps::new(root, sandbox_mode)))
+fn select_platform(
+    root: PathBuf,
+    mode: SandboxMode,
+    profile_dir: Option<PathBuf>,
+) -> Result<Arc<dyn PlatformOps>, io::Error> {
+    Ok(Arc::new(MacosOps::new(root, mode, profile_dir)))
 }
 
 #[cfg(not(target_os = "macos"))]
 /// Returns unsupported when no backend is compiled for this target OS.
 fn select_platform(
     _root: PathBuf,
-    _mode: SandboxModeArg,
+    _mode: SandboxMode,
+    _profile_dir: Option<PathBuf>,
 ) -> Result<Arc<dyn PlatformOps>, io::Error> {
     Err(io::Error::new(
         io::ErrorKind::Unsupported,
         "no platform backend configured for this target",
     ))
 }
-
-impl SandboxModeArg {
-    /// Returns a stable lowercase string for structured logs.
-    fn as_str(self) -> &'static str {
-        match self {
-            SandboxModeArg::Disabled => "disabled",
-            SandboxModeArg::Permissive => "permissive",
-            SandboxModeArg::Enforced => "enforced",
-        }
-    }
-}
@@ -0,0 +1,64 @@
+use planter_core::SignalInfo;
+
+/// Signal names the daemon accepts, mapped to this platform's signal numbers.
+const SIGNALS: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ABRT", libc::SIGABRT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("WINCH", libc::SIGWINCH),
+];
+
+/// Returns the accepted signals in ascending number order.
+pub fn supported_signals() -> Vec<SignalInfo> {
+    let mut signals: Vec<SignalInfo> = SIGNALS
+        .iter()
+        .map(|(name, number)| SignalInfo {
+            name: (*name).to_string(),
+            number: *number,
+        })
+        .collect();
+    signals.sort_by_key(|signal| signal.number);
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Verifies common termination signals are listed with their platform numbers.
+    fn supported_signals_include_common_signals() {
+        let signals = supported_signals();
+        for (name, number) in [
+            ("TERM", libc::SIGTERM),
+            ("KILL", libc::SIGKILL),
+            ("INT", libc::SIGINT),
+            ("HUP", libc::SIGHUP),
+        ] {
+            assert!(
+                signals
+                    .iter()
+                    .any(|signal| signal.name == name && signal.number == number),
+                "missing {name}"
+            );
+        }
+        assert!(
+            signals
+                .windows(2)
+                .all(|pair| pair[0].number < pair[1].number)
+        );
+    }
+}
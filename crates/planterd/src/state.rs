@@ -1,34 +1,168 @@
 use std::{
-    collections::BTreeMap,
-    fs, io,
-    path::{Path, PathBuf},
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::{CStr, CString},
+    fs,
+    hash::Hash,
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::{ffi::OsStrExt, ffi::OsStringExt, fs::OpenOptionsExt},
+    },
+    path::{Component, Path, PathBuf},
+    pin::Pin,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicU64, Ordering},
     },
     time::{Duration, Instant},
 };
 
 use planter_core::{
-    CellId, CellInfo, CellSpec, CommandSpec, ErrorCode, ExitStatus, JobId, JobInfo, LogStream,
-    PlanterError, SessionId, TerminationReason, now_ms,
+    CellFileEntry, CellId, CellInfo, CellSpec, CommandSpec, DEFAULT_MAX_PTY_INPUT_BYTES,
+    DaemonDiagnostics, DiagnosticsReport, ErrorCode, ExitStatus, HealthStatus, JobId, JobInfo,
+    LogStream, LogStreamInfo, PROTOCOL_VERSION, PlanterError, ResourceCounts, SANDBOX_EXEC_PATH,
+    SandboxDiagnostics, SandboxMode, SandboxOperation, SessionId, SnapshotJob, StateSnapshot,
+    TerminationReason, now_ms,
+};
+use planter_execd_proto::{
+    DEFAULT_KILL_GRACE_MS, EXECD_PROTOCOL_VERSION, ExecPtyAction, ExecRequest, ExecResponse,
+    JobSandbox, MAX_KILL_GRACE_MS,
+};
+use planter_ipc::framing::largest_frame_size;
+use planter_platform::{
+    NICE_RANGE, PlatformError, PlatformOps, SandboxDecision, check_cpu_affinity, check_rlimits,
+};
+use sha2::{Digest, Sha256};
+use tokio::{
+    sync::{Notify, futures::Notified},
+    time::sleep,
 };
-use planter_execd_proto::{ExecPtyAction, ExecRequest, ExecResponse};
-use planter_platform::{PlatformError, PlatformOps};
-use tokio::time::sleep;
 
-use crate::worker_manager::WorkerManager;
+use crate::{
+    worker::unexpected_worker_response,
+    worker_manager::{WorkerManager, lock_recovering},
+};
+
+/// Maximum bytes accepted in one cell file write chunk.
+const MAX_CELL_PUT_CHUNK: usize = 4 * 1024 * 1024;
+/// Maximum bytes returned by one cell file read chunk.
+const MAX_CELL_GET_CHUNK: usize = 4 * 1024 * 1024;
+/// Maximum size of a file written into a cell over the socket.
+const MAX_CELL_FILE_BYTES: u64 = 512 * 1024 * 1024;
+/// Age after which a staged upload that was never finalized is discarded.
+const UPLOAD_STAGING_TTL: Duration = Duration::from_secs(60 * 60);
+/// Maximum entries returned by one cell directory listing.
+const MAX_CELL_LIST_ENTRIES: usize = 4096;
+/// Interval between worker status polls while waiting on a job.
+const JOB_WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Largest terminal width or height a PTY is opened or resized to; bigger requests are clamped.
+const MAX_PTY_DIMENSION: u16 = 1000;
+/// Longest wait a single PTY follow read holds the shared PTY worker for before the daemon re-polls.
+const PTY_FOLLOW_SLICE_MS: u64 = 250;
+/// Default budget for the worker checks behind a deep health request.
+pub const DEFAULT_HEALTH_TIMEOUT_MS: u64 = 1_000;
+/// Time a one-shot log read of a running job waits for a missing log file to appear.
+const MISSING_LOG_GRACE: Duration = Duration::from_millis(500);
+/// Pause between checks for a running job's log file that does not exist yet.
+const MISSING_LOG_RETRY_INTERVAL: Duration = Duration::from_millis(25);
+/// Domain separator mixed into deterministic job id hashes; bump when the input layout changes.
+const JOB_ID_HASH_SALT: &[u8] = b"planter-job-id-v2";
+/// Stand-in recorded for env values whose names look like credentials.
+const REDACTED_ENV_VALUE: &str = "<redacted>";
+/// Name fragments marking an env var as a credential that job metadata must not store.
+const SECRET_ENV_MARKERS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "KEY",
+    "AUTH",
+];
 
 /// Persistent daemon state and orchestration entrypoint for jobs/PTYs.
 pub struct StateStore {
     /// Root state directory.
     root: PathBuf,
+    /// Directory job logs are written to; `<root>/logs` unless overridden.
+    logs_dir: PathBuf,
     /// Monotonic id counter used for generated ids.
     id_counter: AtomicU64,
     /// Platform backend for filesystem/process operations.
     platform: Arc<dyn PlatformOps>,
     /// Worker lifecycle manager.
     workers: Arc<WorkerManager>,
+    /// Maximum bytes accepted in one PTY input message.
+    max_pty_input_bytes: usize,
+    /// Shell paths `PtyOpen` may launch; any executable is accepted when empty.
+    pty_shells: Vec<PathBuf>,
+    /// Worker executables a cell's `worker_bin_override` may name; none are allowed when empty.
+    worker_bins: Vec<PathBuf>,
+    /// Wait between TERM and KILL when a kill request omits `grace_ms`.
+    default_kill_grace_ms: u64,
+    /// Daemon-wide cap on concurrently running jobs, if any.
+    max_running_jobs: Option<usize>,
+    /// Jobs persisted as running, plus launches holding a reserved slot, mapped to their cell.
+    running_jobs: Mutex<HashMap<JobId, CellId>>,
+    /// Whether to stop a cell's worker as soon as its last running job finishes.
+    eager_worker_shutdown: bool,
+    /// Budget for deep health worker checks before reporting `degraded`.
+    health_timeout: Duration,
+    /// Wakes `wait_job` callers when job records change or cells are removed.
+    job_events: Notify,
+    /// Wakes in-progress follow reads of a job's logs when they are cancelled.
+    log_follows: FollowRegistry<JobId>,
+    /// Wakes in-progress follow reads of a PTY session when they are cancelled.
+    pty_follows: FollowRegistry<SessionId>,
+    /// Serializes the stale-upload sweep against staged writes creating their directories.
+    uploads_lock: Mutex<()>,
+    /// Serializes cell and job record writes against snapshots and restores reading them all.
+    records_lock: Mutex<()>,
+    /// Daemon environment variable names cells may list in `inherit_host_env`.
+    inherit_env_allowlist: Vec<String>,
+    /// Allowlisted daemon environment, captured at startup, that cells' `inherit_host_env`
+    /// reads from.
+    host_env: BTreeMap<String, String>,
+}
+
+/// Outcome of claiming a running-job slot for a launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotClaim {
+    /// The slot is now held by the launch.
+    Reserved,
+    /// The daemon is at its running-job cap.
+    AtLimit,
+    /// Another launch of the same job id already holds a slot.
+    InUse,
+}
+
+/// Launch options for [`StateStore::run_job`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunJobOptions {
+    /// Derive the job id from the launch spec, returning an existing job with that id.
+    pub deterministic_id: bool,
+    /// Name the job process after its job id.
+    pub process_title: bool,
+    /// Leave the temp dir env vars alone instead of pointing them inside the cell.
+    pub system_tmp: bool,
+}
+
+/// Read parameters for job logs.
+pub struct LogsReadSpec {
+    /// Byte offset to start reading from.
+    pub offset: u64,
+    /// Maximum bytes to return.
+    pub max_bytes: u32,
+    /// Whether to wait for additional bytes when at EOF.
+    pub follow: bool,
+    /// Follow wait timeout in milliseconds.
+    pub wait_ms: u64,
+    /// Whether to remove ANSI escape sequences from the returned bytes.
+    pub strip_ansi: bool,
+    /// Whether to read the window ending at `offset`, newest bytes first.
+    pub reverse: bool,
+    /// Truncation generation the caller last saw, if any.
+    pub generation: Option<u64>,
 }
 
 /// Result payload for log read operations.
@@ -41,14 +175,98 @@ pub struct LogsReadResult {
     pub eof: bool,
     /// True when stream is complete and closed.
     pub complete: bool,
+    /// True when the log was truncated since the caller's offset and data restarts at zero.
+    pub reset: bool,
+    /// True when polling again may return more bytes.
+    pub more_expected: bool,
+    /// How many times the stream has been truncated.
+    pub generation: u64,
+    /// Offset to resume from when ANSI stripping shortened `data`.
+    pub next_offset: Option<u64>,
+}
+
+/// Result payload for cell drain operations.
+pub struct CellDrainResult {
+    /// True when the cell was removed after its jobs finished.
+    pub removed: bool,
+    /// Jobs still running when the drain timeout elapsed.
+    pub pending_jobs: Vec<JobId>,
+}
+
+/// Result payload for cell directory listings.
+pub struct CellListResult {
+    /// Entries sorted by name.
+    pub entries: Vec<CellFileEntry>,
+    /// True when entries beyond the listing cap were dropped.
+    pub truncated: bool,
+}
+
+/// Result payload for cell file read operations.
+pub struct CellGetResult {
+    /// Requested offset.
+    pub offset: u64,
+    /// Returned file bytes.
+    pub data: Vec<u8>,
+    /// True when the chunk reaches the end of the file.
+    pub eof: bool,
 }
 
 /// Result payload for job kill operations.
 pub struct JobKillResult {
     /// Updated job metadata.
     pub job: JobInfo,
-    /// Signal name applied to the process.
+    /// Signal name applied to the process, or `none` when nothing was sent.
     pub signal: String,
+    /// Whether a signal was sent; false when the job had already finished.
+    pub delivered: bool,
+}
+
+/// Result payload for job usage samples.
+pub struct UsageSampleResult {
+    /// Resident set size in bytes.
+    pub rss_bytes: Option<u64>,
+    /// CPU usage in nanoseconds.
+    pub cpu_nanos: Option<u64>,
+    /// Sample timestamp in UNIX milliseconds.
+    pub timestamp_ms: u64,
+}
+
+/// Launch parameters for a new PTY session.
+pub struct PtyOpenSpec {
+    /// Shell binary path.
+    pub shell: String,
+    /// Shell argument vector.
+    pub args: Vec<String>,
+    /// Optional working directory.
+    pub cwd: Option<String>,
+    /// Environment overrides.
+    pub env: BTreeMap<String, String>,
+    /// Initial terminal columns.
+    pub cols: u16,
+    /// Initial terminal rows.
+    pub rows: u16,
+    /// Whether generated rc files confine `cd` to the build cell.
+    pub jail_cwd: bool,
+    /// Whether output is teed to the session's on-disk scrollback log.
+    pub persist_scrollback: bool,
+}
+
+/// Read parameters for PTY output.
+pub struct PtyReadSpec {
+    /// Byte offset to start reading from.
+    pub offset: u64,
+    /// Maximum bytes to return.
+    pub max_bytes: u32,
+    /// Whether to wait for additional bytes when at EOF.
+    pub follow: bool,
+    /// Follow wait timeout in milliseconds.
+    pub wait_ms: u64,
+    /// When set, read the last N buffered bytes and ignore `offset`.
+    pub from_end: Option<u32>,
+    /// Whether the chunk must end on a UTF-8 codepoint boundary.
+    pub utf8_boundary: bool,
+    /// Whether to remove ANSI escape sequences from the returned bytes.
+    pub strip_ansi: bool,
 }
 
 /// Result payload for PTY open operations.
@@ -61,7 +279,7 @@ pub struct PtyOpenResult {
 
 /// Result payload for PTY read operations.
 pub struct PtyReadResult {
-    /// Requested offset.
+    /// Absolute offset where the returned bytes start.
     pub offset: u64,
     /// Returned PTY bytes.
     pub data: Vec<u8>,
@@ -71,6 +289,32 @@ pub struct PtyReadResult {
     pub complete: bool,
     /// Shell exit code when complete.
     pub exit_code: Option<i32>,
+    /// Offset to resume from when ANSI stripping shortened `data`.
+    pub next_offset: Option<u64>,
+}
+
+/// Result payload for non-blocking peeks at job logs or PTY output.
+pub struct PeekResult {
+    /// Offset where the returned bytes start.
+    pub offset: u64,
+    /// Returned bytes.
+    pub data: Vec<u8>,
+    /// Total bytes currently available from the source.
+    pub length: u64,
+    /// True when the source has finished and will not grow.
+    pub complete: bool,
+}
+
+/// Result payload for job log size reports.
+pub struct LogsInfo {
+    /// Stdout log size.
+    pub stdout: LogStreamInfo,
+    /// Stderr log size.
+    pub stderr: LogStreamInfo,
+    /// Bytes across both streams.
+    pub total_bytes: u64,
+    /// Log quota from the job's limits, when one was set.
+    pub max_log_bytes: Option<u64>,
 }
 
 /// Internal persisted job metadata representation on disk.
@@ -86,7 +330,10 @@ struct StoredJobInfo {
     stdout_path: String,
     /// Stderr log file path.
     stderr_path: String,
-    /// Job start timestamp.
+    /// Launch request timestamp.
+    #[serde(default)]
+    requested_at_ms: u64,
+    /// Worker-acknowledged start timestamp.
     started_at_ms: u64,
     /// Job finish timestamp if complete.
     finished_at_ms: Option<u64>,
@@ -97,20 +344,44 @@ struct StoredJobInfo {
     /// Optional termination cause.
     #[serde(default)]
     termination_reason: Option<TerminationReason>,
+    /// Sandbox policy configured at launch, if recorded.
+    #[serde(default)]
+    sandbox_mode: Option<SandboxMode>,
+    /// Whether the job was actually launched under a sandbox.
+    #[serde(default)]
+    sandboxed: bool,
+    /// Why the job ran unsandboxed although its sandbox mode was not disabled.
+    #[serde(default)]
+    sandbox_fallback_reason: Option<String>,
+    /// Launch-request-to-spawn latency in milliseconds, if recorded.
+    #[serde(default)]
+    spawn_latency_ms: Option<u64>,
+    /// Merged environment the process was launched with, if recorded.
+    #[serde(default)]
+    effective_env: Option<BTreeMap<String, String>>,
 }
 
 impl StoredJobInfo {
     /// Converts internal representation to public protocol job info.
-    fn to_public(&self) -> JobInfo {
+    fn to_public(&self, cell_exists: bool) -> JobInfo {
         JobInfo {
             id: self.id.clone(),
             cell_id: self.cell_id.clone(),
             command: self.command.clone(),
+            requested_at_ms: self.requested_at_ms,
             started_at_ms: self.started_at_ms,
             finished_at_ms: self.finished_at_ms,
             pid: self.pid,
             status: self.status.clone(),
             termination_reason: self.termination_reason,
+            sandbox_mode: self.sandbox_mode,
+            sandboxed: self.sandboxed,
+            sandbox_fallback_reason: self.sandbox_fallback_reason.clone(),
+            spawn_latency_ms: self.spawn_latency_ms,
+            effective_env: self.effective_env.clone(),
+            cell_exists,
+            stdout_bytes: log_size(&self.stdout_path),
+            stderr_bytes: log_size(&self.stderr_path),
         }
     }
 }
@@ -120,19 +391,167 @@ impl StateStore {
     pub fn new(root: PathBuf, platform: Arc<dyn PlatformOps>) -> Result<Self, PlanterError> {
         let store = Self {
             root: root.clone(),
+            logs_dir: root.join("logs"),
             id_counter: AtomicU64::new(now_ms()),
             platform,
             workers: Arc::new(WorkerManager::new(root.clone())),
+            max_pty_input_bytes: DEFAULT_MAX_PTY_INPUT_BYTES,
+            pty_shells: Vec::new(),
+            worker_bins: Vec::new(),
+            default_kill_grace_ms: DEFAULT_KILL_GRACE_MS,
+            max_running_jobs: None,
+            running_jobs: Mutex::new(HashMap::new()),
+            eager_worker_shutdown: false,
+            health_timeout: Duration::from_millis(DEFAULT_HEALTH_TIMEOUT_MS),
+            job_events: Notify::new(),
+            log_follows: Mutex::new(HashMap::new()),
+            pty_follows: Mutex::new(HashMap::new()),
+            uploads_lock: Mutex::new(()),
+            records_lock: Mutex::new(()),
+            inherit_env_allowlist: Vec::new(),
+            host_env: BTreeMap::new(),
         };
         store.ensure_layout()?;
+        match planter_execd::sweep_orphaned_sessions(&store.root) {
+            Ok(0) => {}
+            Ok(removed) => tracing::info!(removed, "removed orphaned pty session directories"),
+            Err(err) => tracing::warn!(error = %err, "orphaned pty session sweep failed"),
+        }
+        match store.sweep_stale_uploads(UPLOAD_STAGING_TTL) {
+            0 => {}
+            removed => tracing::info!(removed, "removed abandoned staged uploads"),
+        }
+        let running = skip_unreadable(store.job_records()?)
+            .into_iter()
+            .filter(|job| matches!(job.status, ExitStatus::Running))
+            .map(|job| (job.id, job.cell_id));
+        store.running_jobs_guard().extend(running);
         Ok(store)
     }
 
+    /// Writes job logs under `logs_dir` instead of `<root>/logs`, creating it and checking it is writable.
+    pub fn with_logs_dir(mut self, logs_dir: Option<PathBuf>) -> Result<Self, PlanterError> {
+        let Some(logs_dir) = logs_dir else {
+            return Ok(self);
+        };
+        fs::create_dir_all(&logs_dir)
+            .map_err(|err| PlanterError::from_io("create logs directory", err))?;
+        let probe = logs_dir.join(format!(".planterd-write-probe-{}", std::process::id()));
+        fs::write(&probe, b"")
+            .and_then(|()| fs::remove_file(&probe))
+            .map_err(|err| PlanterError {
+                code: ErrorCode::Internal,
+                message: format!("logs directory {} is not writable", logs_dir.display()),
+                detail: Some(err.to_string()),
+            })?;
+        self.logs_dir = logs_dir;
+        Ok(self)
+    }
+
+    /// Overrides the per-message PTY input size cap.
+    pub fn with_max_pty_input_bytes(mut self, max_pty_input_bytes: usize) -> Self {
+        self.max_pty_input_bytes = max_pty_input_bytes;
+        self
+    }
+
+    /// Restricts `PtyOpen` to the listed shell paths; an empty list allows any shell.
+    pub fn with_pty_shells(mut self, pty_shells: Vec<PathBuf>) -> Self {
+        self.pty_shells = pty_shells;
+        self
+    }
+
+    /// Lets cells inherit the listed daemon environment variables; with none listed, cells
+    /// inherit nothing and a cell naming any `inherit_host_env` variable is rejected.
+    pub fn with_inheritable_env(mut self, names: Vec<String>) -> Self {
+        self.host_env = inheritable_host_env(std::env::vars(), &names);
+        self.inherit_env_allowlist = names;
+        self
+    }
+
+    /// Lets cells run their worker from one of the listed executables; with none listed,
+    /// `worker_bin_override` is rejected.
+    ///
+    /// Persisted cells naming a listed executable get their override back. Other overrides are
+    /// dropped with a warning rather than spawning a binary the operator never allowed.
+    pub fn with_worker_bins(mut self, worker_bins: Vec<PathBuf>) -> Result<Self, PlanterError> {
+        self.worker_bins = worker_bins;
+        for cell in skip_unreadable(self.cell_records()?) {
+            let Some(worker_bin) = cell.spec.worker_bin_override else {
+                continue;
+            };
+            match self.check_worker_bin_allowed(&worker_bin) {
+                Ok(()) => self
+                    .workers
+                    .set_worker_bin_override(&cell.id, Some(PathBuf::from(worker_bin)))?,
+                Err(err) => tracing::warn!(
+                    cell_id = %cell.id.0,
+                    error = %err.message,
+                    "ignoring disallowed worker binary override"
+                ),
+            }
+        }
+        Ok(self)
+    }
+
+    /// Overrides the TERM-to-KILL grace period used when a kill request omits one, capped at
+    /// the longest grace a request may ask for.
+    pub fn with_default_kill_grace_ms(mut self, default_kill_grace_ms: u64) -> Self {
+        self.default_kill_grace_ms = default_kill_grace_ms.min(MAX_KILL_GRACE_MS);
+        self
+    }
+
+    /// Caps the number of jobs running at once across all cells.
+    pub fn with_max_running_jobs(mut self, max_running_jobs: Option<usize>) -> Self {
+        self.max_running_jobs = max_running_jobs;
+        self
+    }
+
+    /// Stops a cell's worker once it has no running jobs instead of leaving it idle.
+    pub fn with_eager_worker_shutdown(mut self, eager_worker_shutdown: bool) -> Self {
+        self.eager_worker_shutdown = eager_worker_shutdown;
+        self
+    }
+
+    /// Bounds how long a call waits behind another call into the same cell before failing
+    /// with `unavailable`.
+    pub fn with_cell_lock_timeout(self, cell_lock_timeout: Duration) -> Self {
+        self.workers.set_call_lock_timeout(cell_lock_timeout);
+        self
+    }
+
+    /// Bounds how long closing a PTY session waits for its output reader before detaching it.
+    pub fn with_pty_reader_join_timeout(self, timeout: Duration) -> Self {
+        self.workers.set_pty_reader_join_timeout(timeout);
+        self
+    }
+
+    /// Bounds how long deep health checks wait on workers before reporting `degraded`.
+    pub fn with_health_timeout(mut self, health_timeout: Duration) -> Self {
+        self.health_timeout = health_timeout;
+        self
+    }
+
+    /// Spawns a task that polls running jobs every `interval` so natural exits are observed.
+    pub fn spawn_exit_watcher(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let store = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                store.reconcile_running_jobs().await;
+            }
+        })
+    }
+
     /// Returns the configured root state directory.
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    /// Returns the directory job logs are written to.
+    pub fn logs_dir(&self) -> &Path {
+        &self.logs_dir
+    }
+
     /// Creates a new cell and persists its metadata.
     pub fn create_cell(&self, spec: CellSpec) -> Result<CellInfo, PlanterError> {
         if spec.name.trim().is_empty() {
@@ -142,6 +561,20 @@ impl StateStore {
                 detail: None,
             });
         }
+        if let Some(worker_bin) = &spec.worker_bin_override {
+            self.check_worker_bin_allowed(worker_bin)?;
+        }
+        if let Some(name) = spec
+            .inherit_host_env
+            .iter()
+            .find(|name| !self.inherit_env_allowlist.contains(name))
+        {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!("host variable {name} is not in the inherit allowlist"),
+                detail: Some(format!("allowed={}", self.inherit_env_allowlist.join(","))),
+            });
+        }
 
         let cell_id = CellId(format!("cell-{}", self.next_id()));
         let created_at_ms = now_ms();
@@ -155,12 +588,66 @@ impl StateStore {
             spec,
             created_at_ms,
             dir: paths.cell_dir.to_string_lossy().to_string(),
+            draining: false,
         };
 
-        write_json(self.cell_meta_path(&info.id), &info)?;
+        {
+            let _records = self.records_guard();
+            write_json(self.cell_meta_path(&info.id), &info)?;
+        }
+        if let Some(worker_bin) = &info.spec.worker_bin_override {
+            self.workers
+                .set_worker_bin_override(&info.id, Some(PathBuf::from(worker_bin)))?;
+        }
         Ok(info)
     }
 
+    /// Rejects a worker executable the operator did not list with `planterd --allow-worker-bin`.
+    ///
+    /// A cell's worker runs unsandboxed as the daemon user and holds its auth token, so clients
+    /// may only pick among binaries the operator already trusts.
+    fn check_worker_bin_allowed(&self, worker_bin: &str) -> Result<(), PlanterError> {
+        if self
+            .worker_bins
+            .iter()
+            .any(|allowed| allowed == Path::new(worker_bin))
+        {
+            return Ok(());
+        }
+        let allowed = self
+            .worker_bins
+            .iter()
+            .map(|worker_bin| worker_bin.display().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: format!("worker binary {worker_bin} is not in the worker binary allowlist"),
+            detail: Some(format!("allowed={allowed}")),
+        })
+    }
+
+    /// Replaces a cell's friendly name, leaving its id and directory untouched.
+    pub fn rename_cell(
+        &self,
+        cell_id: &CellId,
+        new_name: String,
+    ) -> Result<CellInfo, PlanterError> {
+        if new_name.trim().is_empty() {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "cell name cannot be empty".to_string(),
+                detail: None,
+            });
+        }
+
+        let _records = self.records_guard();
+        let mut cell = self.load_cell(cell_id)?;
+        cell.spec.name = new_name;
+        write_json(self.cell_meta_path(cell_id), &cell)?;
+        Ok(cell)
+    }
+
     /// Loads a cell metadata file by id.
     pub fn load_cell(&self, cell_id: &CellId) -> Result<CellInfo, PlanterError> {
         let path = self.cell_meta_path(cell_id);
@@ -177,7 +664,13 @@ impl StateStore {
 
     /// Loads job metadata by id.
     pub fn load_job(&self, job_id: &JobId) -> Result<JobInfo, PlanterError> {
-        Ok(self.load_job_record(job_id)?.to_public())
+        let job = self.load_job_record(job_id)?;
+        Ok(self.public_job(&job))
+    }
+
+    /// Builds the public job view, checking whether its cell still exists.
+    fn public_job(&self, job: &StoredJobInfo) -> JobInfo {
+        job.to_public(self.cell_meta_path(&job.cell_id).exists())
     }
 
     /// Loads the internal persisted job representation by id.
@@ -195,12 +688,36 @@ impl StateStore {
     }
 
     /// Launches a job in a cell through the worker manager and persists metadata.
+    ///
+    /// With `deterministic_id`, the id is derived from the launch spec and an
+    /// existing job with that id is returned instead of launching again; a concurrent
+    /// launch of the same id that has not started yet fails with `Unavailable`.
+    ///
+    /// Unless `system_tmp` is set, `TMPDIR`, `TMP`, and `TEMP` point at a fresh
+    /// `.tmp/<job id>` directory inside the cell, removed once the job finishes; values the
+    /// caller set explicitly win.
+    ///
+    /// If `deadline` passes before the worker reports the job started, the launch is
+    /// cancelled: a worker still starting is torn down, a job that did start is killed, and
+    /// no job metadata is written.
     pub async fn run_job(
         &self,
         cell_id: CellId,
         cmd: CommandSpec,
+        options: RunJobOptions,
+        deadline: Option<Instant>,
     ) -> Result<JobInfo, PlanterError> {
+        let RunJobOptions {
+            deterministic_id,
+            process_title,
+            system_tmp,
+        } = options;
+        let received_at = Instant::now();
+        let requested_at_ms = now_ms();
         let cell = self.load_cell(&cell_id)?;
+        if cell.draining {
+            return Err(cell_draining(&cell_id));
+        }
 
         if cmd.argv.is_empty() {
             return Err(PlanterError {
@@ -209,35 +726,193 @@ impl StateStore {
                 detail: None,
             });
         }
+        if let Some(nice) = cmd.limits.as_ref().and_then(|limits| limits.nice)
+            && !NICE_RANGE.contains(&nice)
+        {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "nice {nice} is outside {}..={}",
+                    NICE_RANGE.start(),
+                    NICE_RANGE.end()
+                ),
+                detail: None,
+            });
+        }
+        if let Some(cores) = cmd
+            .limits
+            .as_ref()
+            .and_then(|limits| limits.cpu_affinity.as_deref())
+        {
+            check_cpu_affinity(cores).map_err(|err| match err {
+                PlatformError::Unsupported(message) => PlanterError {
+                    code: ErrorCode::InvalidRequest,
+                    message,
+                    detail: None,
+                },
+                other => platform_to_planter_error(other),
+            })?;
+        }
+        if let Some(limits) = &cmd.limits {
+            check_rlimits(limits).map_err(platform_to_planter_error)?;
+        }
 
-        let job_id = JobId(format!("job-{}", self.next_id()));
-
-        let mut env = BTreeMap::new();
+        let sandbox_mode = cell
+            .spec
+            .sandbox_mode
+            .unwrap_or_else(|| self.platform.sandbox_mode());
+        let sandbox_profile = match sandbox_mode {
+            SandboxMode::Disabled => None,
+            SandboxMode::Permissive => self
+                .platform
+                .sandbox_profile(&cell_id)
+                .unwrap_or_else(|err| {
+                    tracing::warn!(cell_id = %cell_id.0, error = %err, "failed to compile sandbox profile");
+                    None
+                }),
+            SandboxMode::Enforced => self
+                .platform
+                .sandbox_profile(&cell_id)
+                .map_err(platform_to_planter_error)?,
+        };
+        let mut env: BTreeMap<String, String> = cell
+            .spec
+            .inherit_host_env
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.host_env.get(name)?.clone())))
+            .collect();
         env.extend(cell.spec.env.clone());
         env.extend(cmd.env.clone());
 
+        let job_id = if deterministic_id {
+            let job_id = content_job_id(&cell_id, &cmd, &env);
+            if self.job_path(&job_id).exists() {
+                return self.load_job(&job_id);
+            }
+            job_id
+        } else {
+            JobId(format!("job-{}", self.next_id()))
+        };
+
+        let mut claim = self.reserve_running_slot(&job_id, &cell_id);
+        if claim == SlotClaim::AtLimit {
+            self.reconcile_running_jobs().await;
+            claim = self.reserve_running_slot(&job_id, &cell_id);
+        }
+        match claim {
+            SlotClaim::Reserved => {}
+            SlotClaim::AtLimit => {
+                return Err(PlanterError {
+                    code: ErrorCode::Unavailable,
+                    message: format!(
+                        "daemon is at its limit of {} running jobs",
+                        self.max_running_jobs.unwrap_or_default()
+                    ),
+                    detail: None,
+                });
+            }
+            // Only a deterministic id can be claimed twice: the same launch is in flight or
+            // running, so hand back its record once there is one.
+            SlotClaim::InUse => {
+                return if self.job_path(&job_id).exists() {
+                    self.load_job(&job_id)
+                } else {
+                    Err(PlanterError {
+                        code: ErrorCode::Unavailable,
+                        message: format!("job {} is already being started", job_id.0),
+                        detail: None,
+                    })
+                };
+            }
+        }
+        // The same launch may have finished and released its slot since the check above.
+        if deterministic_id && self.job_path(&job_id).exists() {
+            self.running_jobs_guard().remove(&job_id);
+            return self.load_job(&job_id);
+        }
+        // A drain that started after the first check either sees this slot or set the flag
+        // before it was reserved; see `set_cell_draining`.
+        match self.load_cell(&cell_id) {
+            Ok(current) if !current.draining => {}
+            Ok(_) => {
+                self.running_jobs_guard().remove(&job_id);
+                return Err(cell_draining(&cell_id));
+            }
+            Err(err) => {
+                self.running_jobs_guard().remove(&job_id);
+                return Err(err);
+            }
+        }
+
+        // Scratch files stay inside the cell, where the sandbox allows writes and cell removal
+        // cleans them up, instead of landing in whatever temp dir the worker inherited.
+        let job_tmp_dir = (!system_tmp).then(|| Path::new(&cell.dir).join(".tmp").join(&job_id.0));
+        if let Some(dir) = &job_tmp_dir {
+            if let Err(err) = fs::create_dir_all(dir) {
+                self.running_jobs_guard().remove(&job_id);
+                return Err(PlanterError::from_io("create job temp directory", err));
+            }
+            for name in ["TMPDIR", "TMP", "TEMP"] {
+                env.entry(name.to_string())
+                    .or_insert_with(|| dir.display().to_string());
+            }
+        }
+
         let stdout_path = self.logs_dir().join(format!("{}.stdout.log", job_id.0));
         let stderr_path = self.logs_dir().join(format!("{}.stderr.log", job_id.0));
-        let response = self
-            .workers
-            .call(
-                &cell_id,
-                ExecRequest::RunJob {
-                    job_id: job_id.clone(),
-                    cmd: cmd.clone(),
-                    env: env.clone(),
-                    stdout_path: stdout_path.display().to_string(),
-                    stderr_path: stderr_path.display().to_string(),
-                },
-            )
-            .await?;
-        let pid = match response {
-            ExecResponse::JobStarted {
-                job_id: started,
-                pid,
-            } if started == job_id => pid,
-            other => return Err(unexpected_worker_response("run job", other)),
+        let launched = async {
+            let response = self
+                .workers
+                .call_with_deadline(
+                    &cell_id,
+                    ExecRequest::RunJob {
+                        job_id: job_id.clone(),
+                        cmd: Box::new(cmd.clone()),
+                        env: env.clone(),
+                        stdout_path: stdout_path.display().to_string(),
+                        stderr_path: stderr_path.display().to_string(),
+                        process_title,
+                        sandbox: Some(JobSandbox {
+                            mode: sandbox_mode,
+                            profile: sandbox_profile
+                                .as_ref()
+                                .map(|profile| profile.path.display().to_string()),
+                            cell_dir: sandbox_profile
+                                .as_ref()
+                                .map(|profile| profile.cell_dir.display().to_string()),
+                        }),
+                    },
+                    deadline,
+                )
+                .await?;
+            match response {
+                ExecResponse::JobStarted {
+                    job_id: started,
+                    pid,
+                    sandboxed,
+                    sandbox_fallback_reason,
+                    env: effective_env,
+                } if started == job_id => {
+                    Ok((pid, sandboxed, sandbox_fallback_reason, effective_env))
+                }
+                other => Err(unexpected_worker_response("run job", other)),
+            }
+        }
+        .await;
+        let (pid, sandboxed, sandbox_fallback_reason, effective_env) = match launched {
+            Ok(started) => started,
+            Err(err) => {
+                self.running_jobs_guard().remove(&job_id);
+                if let Some(dir) = &job_tmp_dir {
+                    let _ = fs::remove_dir_all(dir);
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(job_run_cancelled(&job_id));
+                }
+                return Err(err);
+            }
         };
+        let spawn_latency_ms = u64::try_from(received_at.elapsed().as_millis()).unwrap_or(u64::MAX);
 
         let job = StoredJobInfo {
             id: job_id.clone(),
@@ -245,15 +920,142 @@ impl StateStore {
             command: cmd,
             stdout_path: stdout_path.display().to_string(),
             stderr_path: stderr_path.display().to_string(),
+            requested_at_ms,
             started_at_ms: now_ms(),
             finished_at_ms: None,
             pid,
             status: ExitStatus::Running,
             termination_reason: None,
+            sandbox_mode: Some(sandbox_mode),
+            // Audits need the worker's own account of whether and why it skipped the sandbox.
+            sandboxed,
+            sandbox_fallback_reason,
+            spawn_latency_ms: Some(spawn_latency_ms),
+            // Older workers don't report their env; the daemon's merge is the closest record.
+            effective_env: Some(redact_secret_env(effective_env.unwrap_or(env))),
         };
 
-        write_json(self.job_path(&job_id), &job)?;
-        Ok(job.to_public())
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            // The caller stopped waiting before hearing the job started, so nobody would track it.
+            self.abort_unrecorded_job(&job).await;
+            return Err(job_run_cancelled(&job_id));
+        }
+        if let Err(err) = self.persist_job(&job) {
+            // Without a record nothing could find or stop the job later, so take it down now.
+            self.abort_unrecorded_job(&job).await;
+            return Err(err);
+        }
+        Ok(job.to_public(true))
+    }
+
+    /// Force-kills a just-launched job that will not get a metadata record.
+    async fn abort_unrecorded_job(&self, job: &StoredJobInfo) {
+        let killed = self
+            .workers
+            .call(
+                &job.cell_id,
+                ExecRequest::JobSignal {
+                    job_id: job.id.clone(),
+                    force: true,
+                    grace_ms: 0,
+                },
+            )
+            .await;
+        if let Err(err) = killed {
+            tracing::warn!(
+                job_id = %job.id.0,
+                pid = ?job.pid,
+                error = %err,
+                "failed to kill job that was launched without a record"
+            );
+        }
+        self.running_jobs_guard().remove(&job.id);
+    }
+
+    /// Waits for a job to finish, returning its status once done or when `timeout_ms` elapses.
+    ///
+    /// Fails with `NotFound` as soon as the job's record or cell is removed mid-wait.
+    pub async fn wait_job(
+        &self,
+        job_id: &JobId,
+        timeout_ms: Option<u64>,
+    ) -> Result<JobInfo, PlanterError> {
+        let deadline = timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+        let mut job = self.load_job_record(job_id)?;
+        loop {
+            let changed = self.job_events.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            if !matches!(job.status, ExitStatus::Running) {
+                return Ok(self.public_job(&job));
+            }
+            if !self.cell_meta_path(&job.cell_id).exists() {
+                return Err(job_removed_while_waiting(job_id));
+            }
+            let cell_id = job.cell_id.clone();
+            job = match self.refresh_job_record(job).await {
+                Ok(job) => job,
+                Err(_) if !self.cell_meta_path(&cell_id).exists() => {
+                    return Err(job_removed_while_waiting(job_id));
+                }
+                Err(err) => return Err(err),
+            };
+            if !matches!(job.status, ExitStatus::Running) {
+                return Ok(self.public_job(&job));
+            }
+
+            let mut wait = JOB_WAIT_POLL_INTERVAL;
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(self.public_job(&job));
+                }
+                wait = wait.min(remaining);
+            }
+            tokio::select! {
+                () = &mut changed => {}
+                () = sleep(wait) => {}
+            }
+
+            job = match self.load_job_record(job_id) {
+                Ok(job) => job,
+                Err(err) if err.code == ErrorCode::NotFound => {
+                    return Err(job_removed_while_waiting(job_id));
+                }
+                Err(err) => return Err(err),
+            };
+            if !self.cell_meta_path(&job.cell_id).exists() {
+                return Err(job_removed_while_waiting(job_id));
+            }
+        }
+    }
+
+    /// Samples a job's current resource usage through its cell's worker.
+    pub async fn usage_sample(&self, job_id: &JobId) -> Result<UsageSampleResult, PlanterError> {
+        let job = self.load_job_record(job_id)?;
+        let response = self
+            .workers
+            .call(
+                &job.cell_id,
+                ExecRequest::UsageProbe {
+                    job_id: job_id.clone(),
+                },
+            )
+            .await?;
+        match response {
+            ExecResponse::UsageSample {
+                job_id: sampled_id,
+                rss_bytes,
+                cpu_nanos,
+                timestamp_ms,
+            } if sampled_id == *job_id => Ok(UsageSampleResult {
+                rss_bytes,
+                cpu_nanos,
+                timestamp_ms,
+            }),
+            other => Err(unexpected_worker_response("usage probe", other)),
+        }
     }
 
     /// Signals a running job and updates persisted metadata.
@@ -261,46 +1063,63 @@ impl StateStore {
         &self,
         job_id: &JobId,
         force: bool,
+        grace_ms: Option<u64>,
     ) -> Result<JobKillResult, PlanterError> {
+        if let Some(grace_ms) = grace_ms
+            && grace_ms > MAX_KILL_GRACE_MS
+        {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!("grace_ms {grace_ms} exceeds the limit of {MAX_KILL_GRACE_MS}"),
+                detail: None,
+            });
+        }
         let mut job = self.load_job_record(job_id)?;
-        if matches!(job.status, ExitStatus::Running) {
-            let response = self
-                .workers
-                .call(
-                    &job.cell_id,
-                    ExecRequest::JobSignal {
-                        job_id: job_id.clone(),
-                        force,
-                    },
-                )
-                .await?;
-            match response {
-                ExecResponse::JobStatus {
-                    job_id: returned,
-                    status,
-                    finished_at_ms,
-                    termination_reason,
-                } if returned == *job_id => {
-                    job.status = status;
-                    job.finished_at_ms = finished_at_ms.or(Some(now_ms()));
-                    job.termination_reason = termination_reason.or(Some(if force {
-                        TerminationReason::ForcedKill
-                    } else {
-                        TerminationReason::TerminatedByUser
-                    }));
-                }
-                other => return Err(unexpected_worker_response("job signal", other)),
+        if !matches!(job.status, ExitStatus::Running) {
+            return Ok(JobKillResult {
+                job: self.public_job(&job),
+                signal: "none".to_string(),
+                delivered: false,
+            });
+        }
+        let response = self
+            .workers
+            .call(
+                &job.cell_id,
+                ExecRequest::JobSignal {
+                    job_id: job_id.clone(),
+                    force,
+                    grace_ms: grace_ms.unwrap_or(self.default_kill_grace_ms),
+                },
+            )
+            .await?;
+        match response {
+            ExecResponse::JobStatus {
+                job_id: returned,
+                status,
+                finished_at_ms,
+                termination_reason,
+            } if returned == *job_id => {
+                job.status = status;
+                job.finished_at_ms = finished_at_ms.or(Some(now_ms()));
+                job.termination_reason = termination_reason.or(Some(if force {
+                    TerminationReason::ForcedKill
+                } else {
+                    TerminationReason::TerminatedByUser
+                }));
             }
-            write_json(self.job_path(job_id), &job)?;
+            other => return Err(unexpected_worker_response("job signal", other)),
         }
+        self.persist_job(&job)?;
 
         Ok(JobKillResult {
-            job: job.to_public(),
+            job: self.public_job(&job),
             signal: if force {
                 "KILL".to_string()
             } else {
                 "TERM".to_string()
             },
+            delivered: true,
         })
     }
 
@@ -331,53 +1150,613 @@ impl StateStore {
 
         if force {
             self.workers.stop_worker(cell_id, true)?;
-            for mut job in running_jobs {
-                job.status = ExitStatus::Exited { code: None };
-                job.finished_at_ms = Some(now_ms());
-                job.termination_reason = Some(TerminationReason::ForcedKill);
-                write_json(self.job_path(&job.id), &job)?;
-            }
         }
 
+        // Remove the cell before marking its jobs so waiters never see a killed job in a live cell.
         let cell_dir = self.cells_dir().join(&cell_id.0);
         if cell_dir.exists() {
+            let _records = self.records_guard();
             fs::remove_dir_all(&cell_dir)
-                .map_err(|err| io_to_error("remove cell directory", err))?;
+                .map_err(|err| PlanterError::from_io("remove cell directory", err))?;
+        }
+        let _ = fs::remove_dir_all(self.uploads_dir().join(&cell_id.0));
+
+        for mut job in running_jobs {
+            job.status = ExitStatus::Exited { code: None };
+            job.finished_at_ms = Some(now_ms());
+            job.termination_reason = Some(TerminationReason::ForcedKill);
+            self.persist_job(&job)?;
         }
+        self.workers.set_worker_bin_override(cell_id, None)?;
+        self.job_events.notify_waiters();
 
         Ok(())
     }
 
-    /// Reads a chunk of job logs with optional follow behavior.
-    pub async fn read_logs(
+    /// Marks a cell as draining, waits for running jobs, then removes it.
+    ///
+    /// If jobs are still running when `timeout_ms` elapses, the flag is cleared again so the
+    /// cell goes back to accepting jobs instead of being stuck half-drained.
+    pub async fn drain_cell(
         &self,
-        job_id: &JobId,
-        stream: LogStream,
-        offset: u64,
-        max_bytes: u32,
-        follow: bool,
-        wait_ms: u64,
-    ) -> Result<LogsReadResult, PlanterError> {
-        let start = Instant::now();
-        let max_bytes = usize::try_from(max_bytes.max(1)).unwrap_or(1024 * 64);
+        cell_id: &CellId,
+        timeout_ms: u64,
+    ) -> Result<CellDrainResult, PlanterError> {
+        self.set_cell_draining(cell_id, true)?;
 
+        let start = Instant::now();
         loop {
-            let job = self.load_job_record(job_id)?;
-            let log_path = match stream {
-                LogStream::Stdout => PathBuf::from(&job.stdout_path),
-                LogStream::Stderr => PathBuf::from(&job.stderr_path),
-            };
+            let mut pending_jobs = Vec::new();
+            for job in self.jobs_for_cell(cell_id)? {
+                if matches!(job.status, ExitStatus::Running) {
+                    let job = self.refresh_job_record(job).await?;
+                    if matches!(job.status, ExitStatus::Running) {
+                        pending_jobs.push(job.id);
+                    }
+                }
+            }
+            // Launches that reserved a slot before the flag was set have no record yet.
+            let launching: Vec<JobId> = self
+                .running_jobs_guard()
+                .iter()
+                .filter(|(job_id, owner)| *owner == cell_id && !pending_jobs.contains(job_id))
+                .map(|(job_id, _)| job_id.clone())
+                .collect();
+            pending_jobs.extend(launching);
 
-            let (data, file_len) = read_log_chunk(&log_path, offset, max_bytes)?;
-            let job_running = matches!(job.status, ExitStatus::Running);
-            let eof = offset.saturating_add(data.len() as u64) >= file_len;
+            if pending_jobs.is_empty() {
+                self.workers.stop_worker(cell_id, false)?;
+                self.remove_cell(cell_id, false)?;
+                return Ok(CellDrainResult {
+                    removed: true,
+                    pending_jobs,
+                });
+            }
 
-            if !data.is_empty() {
-                return Ok(LogsReadResult {
-                    offset,
-                    data,
+            if start.elapsed() >= Duration::from_millis(timeout_ms) {
+                self.set_cell_draining(cell_id, false)?;
+                return Ok(CellDrainResult {
+                    removed: false,
+                    pending_jobs,
+                });
+            }
+
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Persists a cell's draining flag.
+    ///
+    /// The write happens under the running-job lock, which launches also take to reserve their
+    /// slot: a launch either reserves first and is waited for by the drain, or re-reads the
+    /// cell after reserving and sees the flag.
+    fn set_cell_draining(&self, cell_id: &CellId, draining: bool) -> Result<(), PlanterError> {
+        let _running = self.running_jobs_guard();
+        let _records = self.records_guard();
+        let mut cell = self.load_cell(cell_id)?;
+        if cell.draining != draining {
+            cell.draining = draining;
+            write_json(self.cell_meta_path(cell_id), &cell)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces a cell's worker and returns the running jobs it took down.
+    ///
+    /// The fresh worker cannot adopt the old one's children, so those jobs are marked
+    /// terminated with an unknown reason instead of being left running forever.
+    pub async fn restart_worker(&self, cell_id: &CellId) -> Result<Vec<JobId>, PlanterError> {
+        self.load_cell(cell_id)?;
+        let worker_alive = self.workers.is_alive(cell_id).await?;
+        let mut running_jobs = Vec::new();
+        for job in self.jobs_for_cell(cell_id)? {
+            if !matches!(job.status, ExitStatus::Running) {
+                continue;
+            }
+            // Pick up jobs that already finished so they keep their real exit status.
+            let job = if worker_alive {
+                self.refresh_job_record(job.clone()).await.unwrap_or(job)
+            } else {
+                job
+            };
+            if matches!(job.status, ExitStatus::Running) {
+                running_jobs.push(job);
+            }
+        }
+
+        self.workers.restart_worker(cell_id).await?;
+
+        let mut terminated_jobs = Vec::with_capacity(running_jobs.len());
+        for mut job in running_jobs {
+            job.status = ExitStatus::Exited { code: None };
+            job.finished_at_ms = Some(now_ms());
+            job.termination_reason = Some(TerminationReason::Unknown);
+            self.persist_job(&job)?;
+            terminated_jobs.push(job.id);
+        }
+        Ok(terminated_jobs)
+    }
+
+    /// Probes whether a job in the cell may perform `operation` on `path` under its sandbox.
+    pub async fn sandbox_check(
+        &self,
+        cell_id: &CellId,
+        path: &str,
+        operation: SandboxOperation,
+    ) -> Result<SandboxDecision, PlanterError> {
+        let cell = self.load_cell(cell_id)?;
+        let sandbox_mode = cell
+            .spec
+            .sandbox_mode
+            .unwrap_or_else(|| self.platform.sandbox_mode());
+        let platform = Arc::clone(&self.platform);
+        let cell_id = cell_id.clone();
+        let path = PathBuf::from(path);
+        // The probe is a short-lived child process; keep its wait off the async runtime.
+        tokio::task::spawn_blocking(move || {
+            platform.sandbox_check(&cell_id, &path, operation, sandbox_mode)
+        })
+        .await
+        .map_err(|err| PlanterError {
+            code: ErrorCode::Internal,
+            message: "sandbox check task failed".to_string(),
+            detail: Some(err.to_string()),
+        })?
+        .map_err(platform_to_planter_error)
+    }
+
+    /// Writes one chunk of a file inside a cell and returns the new file size.
+    pub fn cell_put(
+        &self,
+        cell_id: &CellId,
+        relative_path: &str,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<u64, PlanterError> {
+        let cell = self.load_cell(cell_id)?;
+        let names = cell_file_names(relative_path)?;
+        write_file_chunk(offset, data, |truncate| {
+            let flags = if truncate {
+                libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC
+            } else {
+                libc::O_WRONLY | libc::O_APPEND
+            };
+            let file = open_cell_file(&cell, &names, true, flags)
+                .map_err(|err| cell_io_error("open cell file", relative_path, err))?;
+            check_regular_file(&file, relative_path)?;
+            Ok(file)
+        })
+    }
+
+    /// Writes one chunk of a staged upload and returns the staged size.
+    pub fn cell_put_staged(
+        &self,
+        cell_id: &CellId,
+        relative_path: &str,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<u64, PlanterError> {
+        self.load_cell(cell_id)?;
+        // Symlinks in the target are refused when the upload is committed.
+        cell_file_names(relative_path)?;
+        if offset == 0 {
+            self.sweep_stale_uploads(UPLOAD_STAGING_TTL);
+        }
+        let path = self.upload_path(cell_id, relative_path);
+        write_file_chunk(offset, data, |truncate| {
+            // Held until the file is open so a sweep can't remove the directory in between.
+            let _uploads = self.uploads_guard();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| PlanterError::from_io("create upload staging dir", err))?;
+            }
+            let mut options = fs::OpenOptions::new();
+            if truncate {
+                options.write(true).create(true).truncate(true);
+            } else {
+                options.append(true);
+            }
+            options
+                .open(&path)
+                .map_err(|err| PlanterError::from_io("open staged upload", err))
+        })
+    }
+
+    /// Checks a staged upload against its expected SHA-256 and renames it into the cell.
+    ///
+    /// A mismatched upload is discarded so the client restarts from offset zero.
+    pub async fn cell_put_finalize(
+        &self,
+        cell_id: &CellId,
+        relative_path: &str,
+        sha256: &str,
+    ) -> Result<u64, PlanterError> {
+        let cell = self.load_cell(cell_id)?;
+        let names = cell_file_names(relative_path)?;
+        let staged = self.upload_path(cell_id, relative_path);
+        // Uploads run to hundreds of MiB; hash them off the async runtime.
+        let hashed = tokio::task::spawn_blocking({
+            let staged = staged.clone();
+            move || hash_file(&staged)
+        })
+        .await
+        .map_err(|err| PlanterError {
+            code: ErrorCode::Internal,
+            message: "hash staged upload task failed".to_string(),
+            detail: Some(err.to_string()),
+        })?;
+        let (size, actual) = match hashed {
+            Ok(hashed) => hashed,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(PlanterError {
+                    code: ErrorCode::NotFound,
+                    message: format!("no staged upload for {relative_path} in cell {}", cell_id.0),
+                    detail: None,
+                });
+            }
+            Err(err) => return Err(PlanterError::from_io("hash staged upload", err)),
+        };
+        let expected = sha256.trim().to_ascii_lowercase();
+        if actual != expected {
+            let _ = fs::remove_file(&staged);
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!("checksum mismatch for {relative_path}; upload discarded"),
+                detail: Some(format!("expected={expected} actual={actual} size={size}")),
+            });
+        }
+
+        let (parent, name) = open_cell_parent(&cell, &names, true)
+            .map_err(|err| cell_io_error("open cell file dir", relative_path, err))?;
+        let staged = CString::new(staged.into_os_string().into_vec())
+            .map_err(|err| PlanterError::from_io("commit staged upload", io::Error::other(err)))?;
+        // SAFETY: both names are NUL-terminated and `parent` is an open directory; renaming onto
+        // a symlink replaces the link itself rather than following it.
+        if unsafe {
+            libc::renameat(
+                libc::AT_FDCWD,
+                staged.as_ptr(),
+                parent.as_raw_fd(),
+                name.as_ptr(),
+            )
+        } != 0
+        {
+            return Err(PlanterError::from_io(
+                "commit staged upload",
+                io::Error::last_os_error(),
+            ));
+        }
+        Ok(size)
+    }
+
+    /// Removes staged uploads untouched for longer than `max_age` and returns how many.
+    fn sweep_stale_uploads(&self, max_age: Duration) -> usize {
+        let _uploads = self.uploads_guard();
+        let Ok(cells) = fs::read_dir(self.uploads_dir()) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for cell in cells.flatten() {
+            let Ok(uploads) = fs::read_dir(cell.path()) else {
+                continue;
+            };
+            for upload in uploads.flatten() {
+                let stale = upload
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .is_ok_and(|modified| modified.elapsed().unwrap_or_default() >= max_age);
+                if stale && fs::remove_file(upload.path()).is_ok() {
+                    removed += 1;
+                }
+            }
+            let _ = fs::remove_dir(cell.path());
+        }
+        removed
+    }
+
+    /// Reads one chunk of a file inside a cell.
+    pub fn cell_get(
+        &self,
+        cell_id: &CellId,
+        relative_path: &str,
+        offset: u64,
+        max_bytes: u32,
+    ) -> Result<CellGetResult, PlanterError> {
+        let cell = self.load_cell(cell_id)?;
+        let names = cell_file_names(relative_path)?;
+        let mut file = match open_cell_file(&cell, &names, false, libc::O_RDONLY) {
+            Ok(file) => file,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                ) =>
+            {
+                return Err(PlanterError {
+                    code: ErrorCode::NotFound,
+                    message: format!("file {relative_path} does not exist in cell {}", cell_id.0),
+                    detail: None,
+                });
+            }
+            Err(err) => return Err(cell_io_error("open cell file", relative_path, err)),
+        };
+        let len = check_regular_file(&file, relative_path)?;
+
+        let max_bytes = usize::try_from(max_bytes.max(1))
+            .unwrap_or(MAX_CELL_GET_CHUNK)
+            .min(MAX_CELL_GET_CHUNK);
+        let mut data = Vec::new();
+        if offset < len {
+            file.seek(SeekFrom::Start(offset))
+                .map_err(|err| PlanterError::from_io("seek cell file", err))?;
+            file.take(max_bytes as u64)
+                .read_to_end(&mut data)
+                .map_err(|err| PlanterError::from_io("read cell file", err))?;
+        }
+
+        Ok(CellGetResult {
+            offset,
+            eof: offset.saturating_add(data.len() as u64) >= len,
+            data,
+        })
+    }
+
+    /// Lists a directory inside a cell, omitting symlinks.
+    pub fn cell_list_files(
+        &self,
+        cell_id: &CellId,
+        subpath: Option<&str>,
+    ) -> Result<CellListResult, PlanterError> {
+        let cell = self.load_cell(cell_id)?;
+        let shown = subpath.unwrap_or(".");
+        let names = cell_path_names(shown)?;
+        let listed = open_cell_dir(&cell, &names, false).and_then(|dir| read_dir_entries(&dir));
+        let listed = match listed {
+            Ok(listed) => listed,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(PlanterError {
+                    code: ErrorCode::NotFound,
+                    message: format!("directory {shown} does not exist in cell {}", cell_id.0),
+                    detail: None,
+                });
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotADirectory => {
+                return Err(PlanterError {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!("{shown} is not a directory in cell {}", cell_id.0),
+                    detail: None,
+                });
+            }
+            Err(err) => return Err(cell_io_error("list cell dir", shown, err)),
+        };
+
+        let mut entries: Vec<CellFileEntry> = listed
+            .into_iter()
+            .filter(|entry| !entry.is_symlink)
+            .map(|entry| CellFileEntry {
+                name: entry.name,
+                size: if entry.is_dir { 0 } else { entry.size },
+                is_dir: entry.is_dir,
+                mtime_ms: entry.mtime_ms,
+            })
+            .collect();
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        let truncated = entries.len() > MAX_CELL_LIST_ENTRIES;
+        entries.truncate(MAX_CELL_LIST_ENTRIES);
+
+        Ok(CellListResult { entries, truncated })
+    }
+
+    /// Pings every cached cell worker without spawning, returning `(alive, total)`.
+    ///
+    /// Fails with `Timeout` when a worker's cell is still busy at `deadline`.
+    pub async fn worker_liveness(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<(usize, usize), PlanterError> {
+        let cells = self.workers.cached_cells();
+        let mut alive = 0;
+        for cell_id in &cells {
+            if self
+                .workers
+                .is_alive_with_deadline(cell_id, deadline)
+                .await?
+            {
+                alive += 1;
+            }
+        }
+        Ok((alive, cells.len()))
+    }
+
+    /// Starts or pings the shared PTY worker and pings cached workers within the health budget.
+    ///
+    /// Exceeding the budget means the daemon is busy rather than broken, so it reports `degraded`;
+    /// a worker that cannot start or answer reports `unhealthy`. The budget bounds only waits
+    /// for busy cells and worker startup: a ping that has taken its worker always finishes,
+    /// since abandoning it would leave the worker and its jobs outside the worker map.
+    pub async fn deep_health(&self) -> (HealthStatus, Option<String>) {
+        let deadline = Instant::now() + self.health_timeout;
+        let checks = async {
+            match self
+                .workers
+                .call_with_deadline(&default_pty_cell_id(), ExecRequest::Ping {}, Some(deadline))
+                .await?
+            {
+                ExecResponse::Pong {} => self.worker_liveness(Some(deadline)).await,
+                other => Err(unexpected_worker_response("health ping", other)),
+            }
+        };
+        match checks.await {
+            Ok((alive, total)) if alive == total => (HealthStatus::Ok, None),
+            Ok((alive, total)) => (
+                HealthStatus::Degraded,
+                Some(format!("{} of {total} workers unresponsive", total - alive)),
+            ),
+            Err(err) if err.code != ErrorCode::Timeout => {
+                (HealthStatus::Unhealthy, Some(err.message))
+            }
+            Err(_) => (
+                HealthStatus::Degraded,
+                Some(format!(
+                    "worker checks exceeded {}ms",
+                    self.health_timeout.as_millis()
+                )),
+            ),
+        }
+    }
+
+    /// Builds a diagnostics snapshot without spawning workers or refreshing job records.
+    pub fn diagnostics(&self) -> Result<DiagnosticsReport, PlanterError> {
+        let cells = fs::read_dir(self.cells_dir())
+            .map_err(|err| PlanterError::from_io("read cells directory", err))?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().join("cell.json").exists())
+            .count();
+        let jobs = self.all_jobs()?;
+        let running_jobs = jobs
+            .iter()
+            .filter(|job| matches!(job.status, ExitStatus::Running))
+            .count();
+        let pty_sessions = planter_execd::count_live_sessions(&self.root)
+            .map_err(|err| PlanterError::from_io("count pty sessions", err))?;
+
+        Ok(DiagnosticsReport {
+            daemon: DaemonDiagnostics {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                protocol: PROTOCOL_VERSION,
+                exec_protocol: EXECD_PROTOCOL_VERSION,
+                state_root: self.root.display().to_string(),
+                default_kill_grace_ms: self.default_kill_grace_ms,
+                max_running_jobs: self.max_running_jobs.map(|max| max as u64),
+                max_pty_input_bytes: self.max_pty_input_bytes as u64,
+                eager_worker_shutdown: self.eager_worker_shutdown,
+                largest_frame_bytes: u64::from(largest_frame_size()),
+            },
+            resources: ResourceCounts {
+                cells: cells as u64,
+                jobs: jobs.len() as u64,
+                running_jobs: running_jobs as u64,
+                pty_sessions: pty_sessions as u64,
+            },
+            workers: self.workers.worker_diagnostics(),
+            sandbox: SandboxDiagnostics {
+                mode: self.platform.sandbox_mode(),
+                sandbox_exec_available: Path::new(SANDBOX_EXEC_PATH).exists(),
+            },
+            errors: Vec::new(),
+        })
+    }
+
+    /// Queries the worker for a running job's status and persists any transition.
+    async fn refresh_job_record(
+        &self,
+        mut job: StoredJobInfo,
+    ) -> Result<StoredJobInfo, PlanterError> {
+        let response = self
+            .workers
+            .call(
+                &job.cell_id,
+                ExecRequest::JobStatus {
+                    job_id: job.id.clone(),
+                },
+            )
+            .await?;
+        match response {
+            ExecResponse::JobStatus {
+                job_id,
+                status,
+                finished_at_ms,
+                termination_reason,
+            } if job_id == job.id => {
+                if status != job.status {
+                    job.status = status;
+                    job.finished_at_ms = finished_at_ms;
+                    job.termination_reason = termination_reason;
+                    self.persist_job(&job)?;
+                }
+                Ok(job)
+            }
+            other => Err(unexpected_worker_response("job status", other)),
+        }
+    }
+
+    /// Reads a chunk of job logs with optional follow behavior.
+    pub async fn read_logs(
+        &self,
+        job_id: &JobId,
+        stream: LogStream,
+        spec: LogsReadSpec,
+    ) -> Result<LogsReadResult, PlanterError> {
+        let start = Instant::now();
+        let offset = spec.offset;
+        let max_bytes = usize::try_from(spec.max_bytes.max(1)).unwrap_or(1024 * 64);
+        if spec.reverse {
+            return self.read_logs_reverse(job_id, stream, &spec, max_bytes);
+        }
+        let follow = spec
+            .follow
+            .then(|| FollowRegistration::new(&self.log_follows, job_id.clone()));
+        let cancelled = follow.as_ref().map(|follow| follow.notify.notified());
+        let mut cancelled = std::pin::pin!(cancelled);
+        if let Some(cancelled) = cancelled.as_mut().as_pin_mut() {
+            cancelled.enable();
+        }
+
+        loop {
+            let job = self.load_job_record(job_id)?;
+            let log_path = match stream {
+                LogStream::Stdout => PathBuf::from(&job.stdout_path),
+                LogStream::Stderr => PathBuf::from(&job.stderr_path),
+            };
+
+            let chunk = read_log_chunk(&log_path, offset, max_bytes)?;
+            let job_running = matches!(job.status, ExitStatus::Running);
+            // The worker creates log files around when it acknowledges the launch, so a running
+            // job's missing log is not written yet rather than empty, finished, or truncated.
+            let (data, file_len) = match chunk {
+                Some(chunk) => chunk,
+                None if job_running && !spec.follow && start.elapsed() < MISSING_LOG_GRACE => {
+                    sleep(MISSING_LOG_RETRY_INTERVAL).await;
+                    continue;
+                }
+                None if job_running => (Vec::new(), offset),
+                None => (Vec::new(), 0),
+            };
+            // The file shrinking below `offset` catches most truncations, but output written since
+            // can grow it past `offset` again; the generation catches those.
+            let generation = log_generation(&log_path);
+            let truncated = offset > 0 && spec.generation.is_some_and(|seen| seen != generation);
+            if offset > file_len || truncated {
+                let (data, file_len) = read_log_chunk(&log_path, 0, max_bytes)?.unwrap_or_default();
+                let eof = data.len() as u64 >= file_len;
+                let (data, next_offset) =
+                    strip_log_chunk(data, 0, spec.strip_ansi, eof && !job_running);
+                return Ok(LogsReadResult {
+                    offset: 0,
+                    data,
+                    eof,
+                    complete: eof && !job_running,
+                    reset: true,
+                    more_expected: !eof || job_running,
+                    next_offset,
+                    generation,
+                });
+            }
+            let eof = offset.saturating_add(data.len() as u64) >= file_len;
+            let (data, next_offset) =
+                strip_log_chunk(data, offset, spec.strip_ansi, eof && !job_running);
+
+            // A chunk holding only an unfinished escape waits for the rest like an empty read.
+            if !data.is_empty() || next_offset.is_some_and(|next| next > offset) {
+                return Ok(LogsReadResult {
+                    offset,
+                    data,
                     eof,
                     complete: eof && !job_running,
+                    reset: false,
+                    more_expected: !eof || job_running,
+                    next_offset,
+                    generation,
                 });
             }
 
@@ -387,52 +1766,268 @@ impl StateStore {
                     data: Vec::new(),
                     eof: true,
                     complete: true,
+                    reset: false,
+                    more_expected: false,
+                    next_offset: None,
+                    generation,
                 });
             }
 
-            if !follow {
+            if !spec.follow {
                 return Ok(LogsReadResult {
                     offset,
                     data: Vec::new(),
                     eof,
                     complete: false,
+                    reset: false,
+                    more_expected: true,
+                    next_offset: None,
+                    generation,
                 });
             }
 
-            if start.elapsed() >= Duration::from_millis(wait_ms.max(1)) {
+            let wait_elapsed = start.elapsed() >= Duration::from_millis(spec.wait_ms.max(1));
+            let was_cancelled = !wait_elapsed
+                && match cancelled.as_mut().as_pin_mut() {
+                    Some(cancelled) => tokio::select! {
+                        () = cancelled => true,
+                        () = sleep(Duration::from_millis(75)) => false,
+                    },
+                    None => {
+                        sleep(Duration::from_millis(75)).await;
+                        false
+                    }
+                };
+            if wait_elapsed || was_cancelled {
                 return Ok(LogsReadResult {
                     offset,
                     data: Vec::new(),
                     eof: true,
                     complete: false,
+                    reset: false,
+                    more_expected: true,
+                    next_offset: None,
+                    generation,
                 });
             }
+        }
+    }
+
+    /// Returns the log bytes on disk at `offset` along with the current file length, never waiting.
+    pub fn peek_logs(
+        &self,
+        job_id: &JobId,
+        stream: LogStream,
+        offset: u64,
+        max_bytes: u32,
+    ) -> Result<PeekResult, PlanterError> {
+        let job = self.load_job_record(job_id)?;
+        let log_path = match stream {
+            LogStream::Stdout => PathBuf::from(&job.stdout_path),
+            LogStream::Stderr => PathBuf::from(&job.stderr_path),
+        };
+        let max_bytes = usize::try_from(max_bytes.max(1)).unwrap_or(1024 * 64);
+        let (data, length) = read_log_chunk(&log_path, offset, max_bytes)?.unwrap_or_default();
+        Ok(PeekResult {
+            offset,
+            data,
+            length,
+            complete: !matches!(job.status, ExitStatus::Running),
+        })
+    }
 
-            sleep(Duration::from_millis(75)).await;
+    /// Measures a job's log files against its log quota.
+    pub fn logs_info(&self, job_id: &JobId) -> Result<LogsInfo, PlanterError> {
+        let job = self.load_job_record(job_id)?;
+        let stdout = log_stream_info(Path::new(&job.stdout_path));
+        let stderr = log_stream_info(Path::new(&job.stderr_path));
+        Ok(LogsInfo {
+            stdout,
+            stderr,
+            total_bytes: stdout.current_bytes + stderr.current_bytes,
+            max_log_bytes: job
+                .command
+                .limits
+                .as_ref()
+                .and_then(|limits| limits.max_log_bytes),
+        })
+    }
+
+    /// Ends every in-progress follow read of a job's logs, returning how many were waiting.
+    pub fn cancel_follows(&self, job_id: &JobId) -> Result<u32, PlanterError> {
+        self.load_job_record(job_id)?;
+        Ok(wake_follows(&self.log_follows, job_id))
+    }
+
+    /// Ends every in-progress follow read of a PTY session, returning how many were waiting.
+    ///
+    /// A follow read notices between its waits on the worker, so it ends within one
+    /// [`PTY_FOLLOW_SLICE_MS`] slice.
+    pub fn cancel_pty_follows(&self, session_id: SessionId) -> u32 {
+        wake_follows(&self.pty_follows, &session_id)
+    }
+
+    /// Reads the log window ending at `spec.offset`, returning the chunk's start as its offset.
+    ///
+    /// Passing each returned offset back as the next end walks the log toward the start, and
+    /// prepending the pages reconstructs it.
+    fn read_logs_reverse(
+        &self,
+        job_id: &JobId,
+        stream: LogStream,
+        spec: &LogsReadSpec,
+        max_bytes: usize,
+    ) -> Result<LogsReadResult, PlanterError> {
+        if spec.follow || spec.strip_ansi {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "reverse log reads do not support follow or strip_ansi".to_string(),
+                detail: None,
+            });
         }
+        let job = self.load_job_record(job_id)?;
+        let log_path = match stream {
+            LogStream::Stdout => PathBuf::from(&job.stdout_path),
+            LogStream::Stderr => PathBuf::from(&job.stderr_path),
+        };
+        let (data, chunk_start) = read_log_chunk_before(&log_path, spec.offset, max_bytes)?;
+        let eof = chunk_start == 0;
+        Ok(LogsReadResult {
+            offset: chunk_start,
+            data,
+            eof,
+            complete: eof && !matches!(job.status, ExitStatus::Running),
+            reset: false,
+            more_expected: !eof,
+            next_offset: None,
+            generation: log_generation(&log_path),
+        })
     }
 
-    /// Opens a new PTY session via the PTY worker channel.
-    pub async fn open_pty(
+    /// Truncates a job's logs, going through the worker while the job may still be writing.
+    pub async fn truncate_logs(
         &self,
-        shell: String,
-        args: Vec<String>,
-        cwd: Option<String>,
-        env: BTreeMap<String, String>,
-        cols: u16,
-        rows: u16,
-    ) -> Result<PtyOpenResult, PlanterError> {
+        job_id: &JobId,
+        stream: Option<LogStream>,
+    ) -> Result<(), PlanterError> {
+        let job = self.load_job_record(job_id)?;
+        let paths = match stream {
+            Some(LogStream::Stdout) => vec![Path::new(&job.stdout_path)],
+            Some(LogStream::Stderr) => vec![Path::new(&job.stderr_path)],
+            None => vec![Path::new(&job.stdout_path), Path::new(&job.stderr_path)],
+        };
+
+        let mut truncated_by_worker = false;
+        if matches!(job.status, ExitStatus::Running) {
+            let response = self
+                .workers
+                .call(
+                    &job.cell_id,
+                    ExecRequest::LogsTruncate {
+                        job_id: job_id.clone(),
+                        stream,
+                    },
+                )
+                .await;
+            match response {
+                Ok(ExecResponse::LogsTruncated { job_id: returned }) if returned == *job_id => {
+                    truncated_by_worker = true;
+                }
+                Err(err) if err.code == ErrorCode::NotFound => {}
+                Ok(other) => return Err(unexpected_worker_response("logs truncate", other)),
+                Err(err) => return Err(err),
+            }
+        }
+
+        for path in paths {
+            if !truncated_by_worker {
+                match fs::OpenOptions::new().write(true).open(path) {
+                    Ok(file) => file
+                        .set_len(0)
+                        .map_err(|err| PlanterError::from_io("truncate log file", err))?,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(PlanterError::from_io("open log file", err)),
+                }
+            }
+            // Bumped after truncating, so a follower racing it resets twice rather than never.
+            write_atomic(
+                &log_generation_path(path),
+                (log_generation(path) + 1).to_string().as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Flushes a job's logs to disk, going through the worker while it still tracks the job.
+    pub async fn sync_logs(&self, job_id: &JobId) -> Result<(), PlanterError> {
+        let job = self.load_job_record(job_id)?;
+        if matches!(job.status, ExitStatus::Running) {
+            let response = self
+                .workers
+                .call(
+                    &job.cell_id,
+                    ExecRequest::LogsSync {
+                        job_id: job_id.clone(),
+                    },
+                )
+                .await;
+            match response {
+                Ok(ExecResponse::LogsSynced { job_id: returned }) if returned == *job_id => {
+                    return Ok(());
+                }
+                Err(err) if err.code == ErrorCode::NotFound => {}
+                Ok(other) => return Err(unexpected_worker_response("logs sync", other)),
+                Err(err) => return Err(err),
+            }
+        }
+
+        for path in [&job.stdout_path, &job.stderr_path] {
+            match fs::File::open(path) {
+                Ok(file) => file
+                    .sync_all()
+                    .map_err(|err| PlanterError::from_io("sync log file", err))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(PlanterError::from_io("open log file", err)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a new PTY session via the PTY worker channel.
+    pub async fn open_pty(&self, spec: PtyOpenSpec) -> Result<PtyOpenResult, PlanterError> {
+        let (cols, rows) = pty_dimensions(spec.cols, spec.rows)?;
+        if !self.pty_shells.is_empty()
+            && !self
+                .pty_shells
+                .iter()
+                .any(|allowed| allowed == Path::new(&spec.shell))
+        {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!("shell {} is not in the pty shell allowlist", spec.shell),
+                detail: Some(format!(
+                    "allowed={}",
+                    self.pty_shells
+                        .iter()
+                        .map(|shell| shell.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )),
+            });
+        }
         let response = self
             .workers
             .call(
                 &default_pty_cell_id(),
                 ExecRequest::PtyOpen {
-                    shell,
-                    args,
-                    cwd,
-                    env,
+                    shell: spec.shell,
+                    args: spec.args,
+                    cwd: spec.cwd,
+                    env: spec.env,
                     cols,
                     rows,
+                    jail_cwd: spec.jail_cwd,
+                    persist_scrollback: spec.persist_scrollback,
                 },
             )
             .await?;
@@ -448,6 +2043,22 @@ impl StateStore {
         session_id: SessionId,
         data: Vec<u8>,
     ) -> Result<(), PlanterError> {
+        if data.len() > self.max_pty_input_bytes {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "pty input of {} bytes exceeds the {} byte limit; split it into smaller PtyInput chunks",
+                    data.len(),
+                    self.max_pty_input_bytes
+                ),
+                detail: Some(format!(
+                    "size={} max={}",
+                    data.len(),
+                    self.max_pty_input_bytes
+                )),
+            });
+        }
+
         let response = self
             .workers
             .call(
@@ -465,12 +2076,47 @@ impl StateStore {
     }
 
     /// Reads output bytes from an existing PTY session.
+    ///
+    /// Follow waits are split into short worker calls so one idle follower never holds the
+    /// shared PTY worker's call lock for the whole of its `wait_ms`.
     pub async fn pty_read(
         &self,
         session_id: SessionId,
-        offset: u64,
-        max_bytes: u32,
-        follow: bool,
+        spec: PtyReadSpec,
+    ) -> Result<PtyReadResult, PlanterError> {
+        let deadline = Instant::now() + Duration::from_millis(spec.wait_ms);
+        let follow = spec
+            .follow
+            .then(|| FollowRegistration::new(&self.pty_follows, session_id));
+        let cancelled = follow.as_ref().map(|follow| follow.notify.notified());
+        let mut cancelled = std::pin::pin!(cancelled);
+        if let Some(cancelled) = cancelled.as_mut().as_pin_mut() {
+            cancelled.enable();
+        }
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let wait_ms = u64::try_from(remaining.as_millis())
+                .unwrap_or(u64::MAX)
+                .min(PTY_FOLLOW_SLICE_MS);
+            let chunk = self.pty_read_once(session_id, &spec, wait_ms).await?;
+            let advanced = chunk.next_offset.is_some_and(|next| next > chunk.offset);
+            if !spec.follow
+                || !chunk.data.is_empty()
+                || advanced
+                || chunk.complete
+                || Instant::now() >= deadline
+                || follow_cancelled(cancelled.as_mut()).await
+            {
+                return Ok(chunk);
+            }
+        }
+    }
+
+    /// Issues one PTY read to the worker, waiting at most `wait_ms` for output.
+    async fn pty_read_once(
+        &self,
+        session_id: SessionId,
+        spec: &PtyReadSpec,
         wait_ms: u64,
     ) -> Result<PtyReadResult, PlanterError> {
         let response = self
@@ -479,10 +2125,13 @@ impl StateStore {
                 &default_pty_cell_id(),
                 ExecRequest::PtyRead {
                     session_id,
-                    offset,
-                    max_bytes,
-                    follow,
+                    offset: spec.offset,
+                    max_bytes: spec.max_bytes,
+                    follow: spec.follow,
                     wait_ms,
+                    from_end: spec.from_end,
+                    utf8_boundary: spec.utf8_boundary,
+                    strip_ansi: spec.strip_ansi,
                 },
             )
             .await?;
@@ -494,24 +2143,62 @@ impl StateStore {
                 eof,
                 complete,
                 exit_code,
+                next_offset,
             } if chunk_id == session_id => Ok(PtyReadResult {
                 offset,
                 data,
                 eof,
                 complete,
                 exit_code,
+                next_offset,
             }),
             other => Err(unexpected_worker_response("pty read", other)),
         }
     }
 
-    /// Resizes an existing PTY session.
-    pub async fn pty_resize(
+    /// Returns the PTY output buffered at `offset` along with the total produced, never waiting.
+    pub async fn pty_peek(
         &self,
         session_id: SessionId,
-        cols: u16,
-        rows: u16,
+        offset: u64,
+        max_bytes: u32,
+    ) -> Result<PeekResult, PlanterError> {
+        let response = self
+            .workers
+            .peek(
+                &default_pty_cell_id(),
+                ExecRequest::PtyPeek {
+                    session_id,
+                    offset,
+                    max_bytes,
+                },
+            )
+            .await?;
+        match response {
+            ExecResponse::PtyPeeked {
+                session_id: peeked_id,
+                offset,
+                data,
+                length,
+                complete,
+            } if peeked_id == session_id => Ok(PeekResult {
+                offset,
+                data,
+                length,
+                complete,
+            }),
+            other => Err(unexpected_worker_response("pty peek", other)),
+        }
+    }
+
+    /// Resizes an existing PTY session.
+    pub async fn pty_resize(
+        &self,
+        session_id: SessionId,
+        cols: u16,
+        rows: u16,
     ) -> Result<(), PlanterError> {
+        let (cols, rows) = pty_dimensions(cols, rows)?;
         let response = self
             .workers
             .call(
@@ -550,36 +2237,329 @@ impl StateStore {
         }
     }
 
+    /// Force-closes PTY sessions idle longer than `idle_ms`, returning the closed ids.
+    pub async fn pty_reap(&self, idle_ms: u64) -> Result<Vec<SessionId>, PlanterError> {
+        let response = self
+            .workers
+            .call(&default_pty_cell_id(), ExecRequest::PtyReap { idle_ms })
+            .await?;
+        match response {
+            ExecResponse::PtyReaped { session_ids } => Ok(session_ids),
+            other => Err(unexpected_worker_response("pty reap", other)),
+        }
+    }
+
+    /// Renders the PTY sandbox profile for a session, or for the next session when absent.
+    pub async fn pty_sandbox_profile(
+        &self,
+        session_id: Option<SessionId>,
+    ) -> Result<(SessionId, String), PlanterError> {
+        let response = self
+            .workers
+            .call(
+                &default_pty_cell_id(),
+                ExecRequest::PtySandboxProfile { session_id },
+            )
+            .await?;
+        match response {
+            ExecResponse::PtySandboxProfileRendered {
+                session_id,
+                profile,
+            } => Ok((session_id, profile)),
+            other => Err(unexpected_worker_response("pty sandbox profile", other)),
+        }
+    }
+
+    /// Captures every cell and job record for backup.
+    ///
+    /// Both are read under the records lock, so the snapshot is one consistent view.
+    pub fn snapshot(&self) -> Result<StateSnapshot, PlanterError> {
+        let (mut jobs, mut cells) = {
+            let _records = self.records_guard();
+            (self.all_jobs()?, self.all_cells()?)
+        };
+        jobs.sort_by(|left, right| left.id.0.cmp(&right.id.0));
+        cells.sort_by(|left, right| left.id.0.cmp(&right.id.0));
+
+        let cell_ids: HashSet<&CellId> = cells.iter().map(|cell| &cell.id).collect();
+        let jobs = jobs
+            .into_iter()
+            .map(|job| SnapshotJob {
+                job: job.to_public(cell_ids.contains(&job.cell_id)),
+                stdout_path: job.stdout_path,
+                stderr_path: job.stderr_path,
+            })
+            .collect();
+        Ok(StateSnapshot {
+            taken_at_ms: now_ms(),
+            state_root: self.root.display().to_string(),
+            logs_dir: self.logs_dir.display().to_string(),
+            cells,
+            jobs,
+        })
+    }
+
+    /// Recreates the cell and job records in `snapshot`, returning how many of each.
+    ///
+    /// Only metadata comes back: cell directories are recreated empty, log paths point at
+    /// this daemon's logs directory rather than wherever the snapshot says, and jobs that were
+    /// running are recorded as finished with an unknown exit since their processes are gone.
+    /// Nothing is written if any id already exists or a cell names a worker binary outside
+    /// the operator's allowlist.
+    pub fn restore(&self, snapshot: StateSnapshot) -> Result<(usize, usize), PlanterError> {
+        let records = self.records_guard();
+        for cell in &snapshot.cells {
+            check_record_id("cell", &cell.id.0)?;
+            if self.cell_meta_path(&cell.id).exists() {
+                return Err(restore_conflict("cell", &cell.id.0));
+            }
+            if let Some(worker_bin) = &cell.spec.worker_bin_override {
+                self.check_worker_bin_allowed(worker_bin)?;
+            }
+        }
+        for entry in &snapshot.jobs {
+            check_record_id("job", &entry.job.id.0)?;
+            if self.job_path(&entry.job.id).exists() {
+                return Err(restore_conflict("job", &entry.job.id.0));
+            }
+        }
+
+        let restored_at_ms = now_ms();
+        let (cell_count, job_count) = (snapshot.cells.len(), snapshot.jobs.len());
+        let mut worker_bins = Vec::new();
+        for mut cell in snapshot.cells {
+            let paths = self
+                .platform
+                .create_cell_dirs(&cell.id)
+                .map_err(platform_to_planter_error)?;
+            cell.dir = paths.cell_dir.to_string_lossy().to_string();
+            write_json(self.cell_meta_path(&cell.id), &cell)?;
+            if let Some(worker_bin) = cell.spec.worker_bin_override {
+                worker_bins.push((cell.id, PathBuf::from(worker_bin)));
+            }
+        }
+        for SnapshotJob { job, .. } in snapshot.jobs {
+            let was_running = matches!(job.status, ExitStatus::Running);
+            // A snapshot is client input; trusting its paths would point later log reads and
+            // truncation at arbitrary files.
+            let stdout_path = self.logs_dir().join(format!("{}.stdout.log", job.id.0));
+            let stderr_path = self.logs_dir().join(format!("{}.stderr.log", job.id.0));
+            // Restored jobs are never running, so no running-job bookkeeping is needed.
+            let job_path = self.job_path(&job.id);
+            write_json(
+                job_path,
+                &StoredJobInfo {
+                    id: job.id,
+                    cell_id: job.cell_id,
+                    command: job.command,
+                    stdout_path: stdout_path.display().to_string(),
+                    stderr_path: stderr_path.display().to_string(),
+                    requested_at_ms: job.requested_at_ms,
+                    started_at_ms: job.started_at_ms,
+                    finished_at_ms: if was_running {
+                        Some(restored_at_ms)
+                    } else {
+                        job.finished_at_ms
+                    },
+                    pid: job.pid,
+                    status: if was_running {
+                        ExitStatus::Exited { code: None }
+                    } else {
+                        job.status
+                    },
+                    termination_reason: if was_running {
+                        Some(TerminationReason::Unknown)
+                    } else {
+                        job.termination_reason
+                    },
+                    sandbox_mode: job.sandbox_mode,
+                    sandboxed: job.sandboxed,
+                    sandbox_fallback_reason: job.sandbox_fallback_reason,
+                    spawn_latency_ms: job.spawn_latency_ms,
+                    effective_env: job.effective_env,
+                },
+            )?;
+        }
+        drop(records);
+
+        self.job_events.notify_waiters();
+        for (cell_id, worker_bin) in worker_bins {
+            self.workers
+                .set_worker_bin_override(&cell_id, Some(worker_bin))?;
+        }
+        Ok((cell_count, job_count))
+    }
+
     /// Returns all jobs currently associated with a cell.
     fn jobs_for_cell(&self, cell_id: &CellId) -> Result<Vec<StoredJobInfo>, PlanterError> {
+        let mut jobs = self.all_jobs()?;
+        jobs.retain(|job| job.cell_id == *cell_id);
+        Ok(jobs)
+    }
+
+    /// Returns every persisted cell record.
+    fn all_cells(&self) -> Result<Vec<CellInfo>, PlanterError> {
+        self.cell_records()?.into_iter().collect()
+    }
+
+    /// Reads every persisted cell record, keeping each record's own read result.
+    fn cell_records(&self) -> Result<Vec<Result<CellInfo, PlanterError>>, PlanterError> {
+        let mut cells = Vec::new();
+        let entries = fs::read_dir(self.cells_dir())
+            .map_err(|err| PlanterError::from_io("read cells directory", err))?;
+
+        for entry in entries {
+            let entry =
+                entry.map_err(|err| PlanterError::from_io("read cells directory entry", err))?;
+            let path = entry.path().join("cell.json");
+            if !path.exists() {
+                continue;
+            }
+
+            cells.push(read_json(path));
+        }
+
+        Ok(cells)
+    }
+
+    /// Returns every persisted job record.
+    fn all_jobs(&self) -> Result<Vec<StoredJobInfo>, PlanterError> {
+        self.job_records()?.into_iter().collect()
+    }
+
+    /// Reads every persisted job record, keeping each record's own read result.
+    fn job_records(&self) -> Result<Vec<Result<StoredJobInfo, PlanterError>>, PlanterError> {
         let mut jobs = Vec::new();
-        let entries =
-            fs::read_dir(self.jobs_dir()).map_err(|err| io_to_error("read jobs directory", err))?;
+        let entries = fs::read_dir(self.jobs_dir())
+            .map_err(|err| PlanterError::from_io("read jobs directory", err))?;
 
         for entry in entries {
-            let entry = entry.map_err(|err| io_to_error("read jobs directory entry", err))?;
+            let entry =
+                entry.map_err(|err| PlanterError::from_io("read jobs directory entry", err))?;
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) != Some("json") {
                 continue;
             }
 
-            let job: StoredJobInfo = read_json(path)?;
-            if job.cell_id == *cell_id {
-                jobs.push(job);
-            }
+            jobs.push(read_json(path));
         }
 
         Ok(jobs)
     }
 
+    /// Removes the `.tmp/<job id>` directory a job was given inside its cell, if any.
+    fn remove_job_tmp_dir(&self, job: &StoredJobInfo) {
+        let Ok(cell) = self.load_cell(&job.cell_id) else {
+            return;
+        };
+        let dir = Path::new(&cell.dir).join(".tmp").join(&job.id.0);
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => tracing::warn!(
+                job_id = %job.id.0,
+                path = %dir.display(),
+                error = %err,
+                "failed to remove job temp directory"
+            ),
+        }
+    }
+
+    /// Persists a job record and keeps the running-job set in step with its status.
+    ///
+    /// A finished job's temp directory is removed. With eager worker shutdown, a cell's worker
+    /// is stopped when its last running job finishes.
+    fn persist_job(&self, job: &StoredJobInfo) -> Result<(), PlanterError> {
+        {
+            let _records = self.records_guard();
+            write_json(self.job_path(&job.id), job)?;
+        }
+        if !matches!(job.status, ExitStatus::Running) {
+            self.remove_job_tmp_dir(job);
+        }
+        self.job_events.notify_waiters();
+        let cell_idle = {
+            let mut running = self.running_jobs_guard();
+            if matches!(job.status, ExitStatus::Running) {
+                running.insert(job.id.clone(), job.cell_id.clone());
+                false
+            } else {
+                running.remove(&job.id).is_some()
+                    && !running.values().any(|cell_id| *cell_id == job.cell_id)
+            }
+        };
+
+        if cell_idle && self.eager_worker_shutdown && job.cell_id != default_pty_cell_id() {
+            if let Err(err) = self.workers.stop_worker(&job.cell_id, false) {
+                tracing::warn!(cell_id = %job.cell_id.0, error = %err.message, "idle worker shutdown failed");
+            } else {
+                tracing::debug!(cell_id = %job.cell_id.0, "stopped worker for idle cell");
+            }
+        }
+        Ok(())
+    }
+
+    /// Claims a running-job slot for `job_id` unless the id already holds one or the daemon cap
+    /// is reached.
+    fn reserve_running_slot(&self, job_id: &JobId, cell_id: &CellId) -> SlotClaim {
+        let mut running = self.running_jobs_guard();
+        if running.contains_key(job_id) {
+            return SlotClaim::InUse;
+        }
+        if self
+            .max_running_jobs
+            .is_some_and(|max| running.len() >= max)
+        {
+            return SlotClaim::AtLimit;
+        }
+        running.insert(job_id.clone(), cell_id.clone());
+        SlotClaim::Reserved
+    }
+
+    /// Refreshes jobs counted as running so exits nobody has polled yet free their slots.
+    async fn reconcile_running_jobs(&self) {
+        let tracked: Vec<JobId> = self.running_jobs_guard().keys().cloned().collect();
+        for job_id in tracked {
+            let job = match self.load_job_record(&job_id) {
+                Ok(job) => job,
+                // Launches still in flight have no record yet and keep their slot.
+                Err(_) => continue,
+            };
+            if !matches!(job.status, ExitStatus::Running) {
+                self.running_jobs_guard().remove(&job_id);
+                continue;
+            }
+            if let Err(err) = self.refresh_job_record(job).await {
+                tracing::debug!(job_id = %job_id.0, error = %err.message, "running job refresh failed");
+            }
+        }
+    }
+
+    /// Locks out concurrent upload directory changes, recovering from a poisoned lock.
+    fn uploads_guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        lock_recovering(&self.uploads_lock, "uploads_lock")
+    }
+
+    /// Locks cell and job records, recovering from a poisoned lock.
+    ///
+    /// Always the innermost lock: nothing else is taken while it is held.
+    fn records_guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        lock_recovering(&self.records_lock, "records_lock")
+    }
+
+    /// Locks the running-job set, recovering from a poisoned lock.
+    fn running_jobs_guard(&self) -> std::sync::MutexGuard<'_, HashMap<JobId, CellId>> {
+        lock_recovering(&self.running_jobs, "running_jobs")
+    }
+
     /// Ensures required state directories exist.
     fn ensure_layout(&self) -> Result<(), PlanterError> {
         fs::create_dir_all(self.cells_dir())
-            .map_err(|err| io_to_error("create cells directory", err))?;
+            .map_err(|err| PlanterError::from_io("create cells directory", err))?;
         fs::create_dir_all(self.jobs_dir())
-            .map_err(|err| io_to_error("create jobs directory", err))?;
+            .map_err(|err| PlanterError::from_io("create jobs directory", err))?;
         fs::create_dir_all(self.logs_dir())
-            .map_err(|err| io_to_error("create logs directory", err))?;
+            .map_err(|err| PlanterError::from_io("create logs directory", err))?;
         Ok(())
     }
 
@@ -598,9 +2578,17 @@ impl StateStore {
         self.root.join("jobs")
     }
 
-    /// Returns the logs directory path.
-    fn logs_dir(&self) -> PathBuf {
-        self.root.join("logs")
+    /// Returns the staging directory for chunked uploads awaiting finalize.
+    fn uploads_dir(&self) -> PathBuf {
+        self.root.join("uploads")
+    }
+
+    /// Returns the staging file for an upload, keyed by cell and target path.
+    fn upload_path(&self, cell_id: &CellId, relative_path: &str) -> PathBuf {
+        let key = hex(&Sha256::digest(relative_path.as_bytes()));
+        self.uploads_dir()
+            .join(&cell_id.0)
+            .join(format!("{key}.part"))
     }
 
     /// Returns the metadata file path for a cell.
@@ -614,61 +2602,635 @@ impl StateStore {
     }
 }
 
+/// Derives a stable job id from the cell, argv, effective env, cwd, and limits of a launch.
+fn content_job_id(cell_id: &CellId, cmd: &CommandSpec, env: &BTreeMap<String, String>) -> JobId {
+    /// Feeds one length-prefixed field so adjacent fields cannot alias.
+    fn field(hasher: &mut Sha256, bytes: &[u8]) {
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    }
+
+    let mut hasher = Sha256::new();
+    field(&mut hasher, JOB_ID_HASH_SALT);
+    field(&mut hasher, cell_id.0.as_bytes());
+    hasher.update((cmd.argv.len() as u64).to_be_bytes());
+    for arg in &cmd.argv {
+        field(&mut hasher, arg.as_bytes());
+    }
+    hasher.update((env.len() as u64).to_be_bytes());
+    for (key, value) in env {
+        field(&mut hasher, key.as_bytes());
+        field(&mut hasher, value.as_bytes());
+    }
+    match &cmd.cwd {
+        Some(cwd) => {
+            hasher.update([1]);
+            field(&mut hasher, cwd.as_bytes());
+        }
+        None => hasher.update([0]),
+    }
+    // Limits are plain structs without maps, so their JSON encoding is stable.
+    let limits = serde_json::to_vec(&cmd.limits).unwrap_or_default();
+    field(&mut hasher, &limits);
+
+    let digest = hasher.finalize();
+    JobId(format!("job-{}", hex(&digest[..16])))
+}
+
+/// Formats bytes as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Writes one chunk at `offset` (zero truncates, later chunks must append) and returns the new size.
+///
+/// `open` opens the destination, truncating it when passed `true` and appending otherwise.
+fn write_file_chunk(
+    offset: u64,
+    data: &[u8],
+    open: impl FnOnce(bool) -> Result<fs::File, PlanterError>,
+) -> Result<u64, PlanterError> {
+    if data.len() > MAX_CELL_PUT_CHUNK {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: "cell put chunk too large".to_string(),
+            detail: Some(format!("size={} max={MAX_CELL_PUT_CHUNK}", data.len())),
+        });
+    }
+    let size = offset.saturating_add(data.len() as u64);
+    if size > MAX_CELL_FILE_BYTES {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: "cell file too large".to_string(),
+            detail: Some(format!("size={size} max={MAX_CELL_FILE_BYTES}")),
+        });
+    }
+
+    let mut file = open(offset == 0)?;
+    if offset != 0 {
+        let len = file
+            .metadata()
+            .map_err(|err| PlanterError::from_io("stat cell file", err))?
+            .len();
+        if len != offset {
+            return Err(PlanterError {
+                code: ErrorCode::InvalidRequest,
+                message: "cell put offset does not match file size".to_string(),
+                detail: Some(format!("offset={offset} size={len}")),
+            });
+        }
+    }
+    file.write_all(data)
+        .map_err(|err| PlanterError::from_io("write cell file", err))?;
+    Ok(size)
+}
+
+/// Error returned when a job is launched into a draining cell.
+fn cell_draining(cell_id: &CellId) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::InvalidRequest,
+        message: format!("cell {} is draining and rejects new jobs", cell_id.0),
+        detail: None,
+    }
+}
+
+/// Returns the size and hex SHA-256 of a file.
+fn hash_file(path: &Path) -> io::Result<(u64, String)> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)?;
+    Ok((size, hex(&hasher.finalize())))
+}
+
+/// Splits a cell-relative path into entry names, rejecting anything that could leave the cell.
+///
+/// An empty list names the cell root.
+fn cell_path_names(relative_path: &str) -> Result<Vec<CString>, PlanterError> {
+    let escape = || PlanterError {
+        code: ErrorCode::InvalidRequest,
+        message: "path escapes cell directory".to_string(),
+        detail: Some(relative_path.to_string()),
+    };
+
+    let mut names = Vec::new();
+    for component in Path::new(relative_path).components() {
+        match component {
+            Component::Normal(name) => {
+                names.push(CString::new(name.as_bytes()).map_err(|_| escape())?);
+            }
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(escape());
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Splits a cell-relative file path into entry names; the path must name an entry below the root.
+fn cell_file_names(relative_path: &str) -> Result<Vec<CString>, PlanterError> {
+    let names = cell_path_names(relative_path)?;
+    if names.is_empty() {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: "cell file path cannot be empty".to_string(),
+            detail: None,
+        });
+    }
+    Ok(names)
+}
+
+/// Opens a directory inside a cell one component at a time, never following a symlink.
+///
+/// Each step opens relative to the directory already held, so swapping a component for a
+/// symlink after it was checked can't redirect the walk. With `create`, missing directories
+/// are created along the way.
+fn open_cell_dir(cell: &CellInfo, names: &[CString], create: bool) -> io::Result<fs::File> {
+    let mut dir = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC)
+        .open(&cell.dir)?;
+    for name in names {
+        dir = match openat_nofollow(&dir, name, libc::O_RDONLY | libc::O_DIRECTORY) {
+            Err(err) if create && err.kind() == io::ErrorKind::NotFound => {
+                // SAFETY: `dir` is an open directory and `name` is NUL-terminated.
+                if unsafe { libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), 0o755) } != 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::AlreadyExists {
+                        return Err(err);
+                    }
+                }
+                openat_nofollow(&dir, name, libc::O_RDONLY | libc::O_DIRECTORY)?
+            }
+            // Linux reports a symlink opened with O_DIRECTORY as ENOTDIR; surface it as ELOOP.
+            Err(err) if err.raw_os_error() == Some(libc::ENOTDIR) && is_symlink_at(&dir, name) => {
+                return Err(io::Error::from_raw_os_error(libc::ELOOP));
+            }
+            opened => opened?,
+        };
+    }
+    Ok(dir)
+}
+
+/// Returns whether `name` in `dir` is itself a symlink.
+fn is_symlink_at(dir: &fs::File, name: &CStr) -> bool {
+    let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+    // SAFETY: `dir` is open, `name` is NUL-terminated, and `stat` is only read on success.
+    unsafe {
+        libc::fstatat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            stat.as_mut_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        ) == 0
+            && stat.assume_init().st_mode & libc::S_IFMT == libc::S_IFLNK
+    }
+}
+
+/// Opens the directory holding a cell file and returns it with the file's name.
+fn open_cell_parent(
+    cell: &CellInfo,
+    names: &[CString],
+    create: bool,
+) -> io::Result<(fs::File, CString)> {
+    let (name, parents) = names
+        .split_last()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+    Ok((open_cell_dir(cell, parents, create)?, name.clone()))
+}
+
+/// Opens a cell file with `flags`, refusing symlinks anywhere along its path.
+fn open_cell_file(
+    cell: &CellInfo,
+    names: &[CString],
+    create_parents: bool,
+    flags: libc::c_int,
+) -> io::Result<fs::File> {
+    let (parent, name) = open_cell_parent(cell, names, create_parents)?;
+    // Non-blocking so a FIFO planted in the cell can't stall the daemon on open.
+    openat_nofollow(&parent, &name, flags | libc::O_NONBLOCK)
+}
+
+/// Opens `name` relative to `dir` without following a symlink in its place.
+fn openat_nofollow(dir: &fs::File, name: &CStr, flags: libc::c_int) -> io::Result<fs::File> {
+    // SAFETY: `dir` is an open directory and `name` is NUL-terminated.
+    let fd = unsafe {
+        libc::openat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            flags | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            0o644 as libc::c_uint,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just opened and nothing else owns it.
+    Ok(unsafe { fs::File::from_raw_fd(fd) })
+}
+
+/// Checks an opened cell file is a regular file and returns its length.
+fn check_regular_file(file: &fs::File, relative_path: &str) -> Result<u64, PlanterError> {
+    let meta = file
+        .metadata()
+        .map_err(|err| PlanterError::from_io("stat cell file", err))?;
+    if !meta.is_file() {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: format!("{relative_path} is not a regular file"),
+            detail: None,
+        });
+    }
+    Ok(meta.len())
+}
+
+/// Maps a failed cell path open, reporting a refused symlink as an escape attempt.
+fn cell_io_error(action: &str, relative_path: &str, err: io::Error) -> PlanterError {
+    if err.raw_os_error() == Some(libc::ELOOP) {
+        return PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: "path escapes cell directory".to_string(),
+            detail: Some(format!("{relative_path}: symlinks are not followed")),
+        };
+    }
+    PlanterError::from_io(action, err)
+}
+
+/// One entry of a directory listed through its file descriptor.
+struct DirEntryInfo {
+    /// Entry name.
+    name: String,
+    /// Whether the entry itself is a symlink.
+    is_symlink: bool,
+    /// Whether the entry is a directory.
+    is_dir: bool,
+    /// Size in bytes.
+    size: u64,
+    /// Last modification time in milliseconds since the epoch.
+    mtime_ms: Option<u64>,
+}
+
+/// Lists an opened directory without resolving its path again, skipping `.` and `..`.
+fn read_dir_entries(dir: &fs::File) -> io::Result<Vec<DirEntryInfo>> {
+    // SAFETY: `fdopendir` takes ownership of the duplicated fd, released by `closedir` below.
+    let stream = unsafe {
+        let fd = libc::dup(dir.as_raw_fd());
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let stream = libc::fdopendir(fd);
+        if stream.is_null() {
+            let err = io::Error::last_os_error();
+            libc::close(fd);
+            return Err(err);
+        }
+        stream
+    };
+
+    let mut names = Vec::new();
+    loop {
+        // SAFETY: `stream` stays open until `closedir`; each entry is copied before the next read.
+        let entry = unsafe { libc::readdir(stream) };
+        if entry.is_null() {
+            break;
+        }
+        // SAFETY: `d_name` is NUL-terminated within the entry returned above.
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) }.to_owned();
+        if name.as_bytes() != b"." && name.as_bytes() != b".." {
+            names.push(name);
+        }
+    }
+    // SAFETY: `stream` came from `fdopendir` and is closed exactly once.
+    unsafe { libc::closedir(stream) };
+
+    let mut entries = Vec::with_capacity(names.len());
+    for name in names {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: `dir` is open, `name` is NUL-terminated, and `stat` is written on success.
+        let stat = unsafe {
+            if libc::fstatat(
+                dir.as_raw_fd(),
+                name.as_ptr(),
+                stat.as_mut_ptr(),
+                libc::AT_SYMLINK_NOFOLLOW,
+            ) != 0
+            {
+                let err = io::Error::last_os_error();
+                // Entries removed since the listing are simply gone.
+                if err.kind() == io::ErrorKind::NotFound {
+                    continue;
+                }
+                return Err(err);
+            }
+            stat.assume_init()
+        };
+        let file_type = stat.st_mode & libc::S_IFMT;
+        let mtime_ms = u64::try_from(stat.st_mtime).ok().map(|secs| {
+            secs.saturating_mul(1000)
+                .saturating_add(u64::try_from(stat.st_mtime_nsec).unwrap_or(0) / 1_000_000)
+        });
+        entries.push(DirEntryInfo {
+            name: name.to_string_lossy().into_owned(),
+            is_symlink: file_type == libc::S_IFLNK,
+            is_dir: file_type == libc::S_IFDIR,
+            size: u64::try_from(stat.st_size).unwrap_or(0),
+            mtime_ms,
+        });
+    }
+    Ok(entries)
+}
+
 /// Reads a slice of bytes from a log file using offset and max byte count.
+///
+/// Returns `None` when the file does not exist.
 fn read_log_chunk(
     path: &Path,
     offset: u64,
     max_bytes: usize,
-) -> Result<(Vec<u8>, u64), PlanterError> {
+) -> Result<Option<(Vec<u8>, u64)>, PlanterError> {
     match fs::read(path) {
         Ok(bytes) => {
             let file_len = bytes.len() as u64;
             let start = usize::try_from(offset).unwrap_or(bytes.len());
             if start >= bytes.len() {
-                return Ok((Vec::new(), file_len));
+                return Ok(Some((Vec::new(), file_len)));
             }
             let end = start.saturating_add(max_bytes).min(bytes.len());
-            Ok((bytes[start..end].to_vec(), file_len))
+            Ok(Some((bytes[start..end].to_vec(), file_len)))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(PlanterError::from_io("read log file", err)),
+    }
+}
+
+/// In-progress follow reads by the job or PTY session they follow.
+type FollowRegistry<K> = Mutex<HashMap<K, Arc<Notify>>>;
+
+/// One in-progress follow read, registered so `cancel_follows` or `cancel_pty_follows` can
+/// wake it.
+struct FollowRegistration<'a, K: Eq + Hash> {
+    /// Registry the follow read is listed in.
+    follows: &'a FollowRegistry<K>,
+    /// Job or PTY session being followed.
+    key: K,
+    /// Shared wakeup for every follow read of the same target.
+    notify: Arc<Notify>,
+}
+
+impl<'a, K: Eq + Hash + Clone> FollowRegistration<'a, K> {
+    /// Registers a follow read of `key`.
+    fn new(follows: &'a FollowRegistry<K>, key: K) -> Self {
+        let notify = Arc::clone(
+            lock_recovering(follows, "follows")
+                .entry(key.clone())
+                .or_default(),
+        );
+        Self {
+            follows,
+            key,
+            notify,
+        }
+    }
+}
+
+impl<K: Eq + Hash> Drop for FollowRegistration<'_, K> {
+    fn drop(&mut self) {
+        let mut follows = lock_recovering(self.follows, "follows");
+        // Only the map and this registration still hold the wakeup: no follow reads remain.
+        if Arc::strong_count(&self.notify) <= 2 {
+            follows.remove(&self.key);
         }
-        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok((Vec::new(), 0)),
-        Err(err) => Err(io_to_error("read log file", err)),
     }
 }
 
-/// Serializes a value as pretty JSON to disk.
+/// Wakes every follow read registered for `key`, returning how many there were.
+fn wake_follows<K: Eq + Hash>(follows: &FollowRegistry<K>, key: &K) -> u32 {
+    let follows = lock_recovering(follows, "follows");
+    let Some(notify) = follows.get(key) else {
+        return 0;
+    };
+    notify.notify_waiters();
+    // One reference belongs to the map; the rest are registered follow reads.
+    u32::try_from(Arc::strong_count(notify) - 1).unwrap_or(u32::MAX)
+}
+
+/// Reports whether a follow read's cancellation has fired, without waiting for it.
+async fn follow_cancelled(cancelled: Pin<&mut Option<Notified<'_>>>) -> bool {
+    let Some(cancelled) = cancelled.as_pin_mut() else {
+        return false;
+    };
+    tokio::select! {
+        biased;
+        () = cancelled => true,
+        () = std::future::ready(()) => false,
+    }
+}
+
+/// Reads up to `max_bytes` of log ending at `end`, clamped to the file length.
+///
+/// Only that window is read from disk. Returns the bytes along with the offset they start at.
+fn read_log_chunk_before(
+    path: &Path,
+    end: u64,
+    max_bytes: usize,
+) -> Result<(Vec<u8>, u64), PlanterError> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(err) => return Err(PlanterError::from_io("read log file", err)),
+    };
+    let len = file
+        .metadata()
+        .map_err(|err| PlanterError::from_io("read log file", err))?
+        .len();
+    let end = end.min(len);
+    let start = end.saturating_sub(max_bytes as u64);
+    let mut data = Vec::with_capacity(usize::try_from(end - start).unwrap_or(0));
+    file.seek(SeekFrom::Start(start))
+        .map_err(|err| PlanterError::from_io("seek log file", err))?;
+    file.take(end - start)
+        .read_to_end(&mut data)
+        .map_err(|err| PlanterError::from_io("read log file", err))?;
+    Ok((data, start))
+}
+
+/// Reserved metadata key holding the integrity checksum of a persisted record.
+const CHECKSUM_KEY: &str = "_checksum";
+/// Reserved metadata key holding the schema version a record was written with.
+const SCHEMA_VERSION_KEY: &str = "_schema_version";
+/// Schema version of persisted job and cell metadata; bump when a field changes meaning.
+///
+/// Records written before versioning carry no key and are read as version 1.
+const METADATA_SCHEMA_VERSION: u64 = 1;
+
+/// Serializes a value as pretty JSON with an embedded schema version and checksum and
+/// writes it atomically.
 fn write_json<T: serde::Serialize>(path: PathBuf, value: &T) -> Result<(), PlanterError> {
-    let json = serde_json::to_vec_pretty(value).map_err(|err| PlanterError {
+    let mut record = serde_json::to_value(value).map_err(|err| PlanterError {
         code: ErrorCode::Internal,
         message: "serialize json".to_string(),
         detail: Some(err.to_string()),
     })?;
+    if let serde_json::Value::Object(fields) = &mut record {
+        fields.insert(
+            SCHEMA_VERSION_KEY.to_string(),
+            serde_json::Value::from(METADATA_SCHEMA_VERSION),
+        );
+    }
+    if record.is_object() {
+        let checksum = record_checksum(&record);
+        if let serde_json::Value::Object(fields) = &mut record {
+            fields.insert(
+                CHECKSUM_KEY.to_string(),
+                serde_json::Value::String(checksum),
+            );
+        }
+    }
 
-    fs::write(path, json).map_err(|err| io_to_error("write json file", err))
+    let json = serde_json::to_vec_pretty(&record).map_err(|err| PlanterError {
+        code: ErrorCode::Internal,
+        message: "serialize json".to_string(),
+        detail: Some(err.to_string()),
+    })?;
+    write_atomic(&path, &json)
 }
 
-/// Reads and decodes a JSON file into a typed value.
+/// Reads a JSON file, verifies its embedded checksum and schema version, and decodes it into
+/// a typed value.
+///
+/// Records from a newer schema version are rejected rather than guessed at.
 fn read_json<T: serde::de::DeserializeOwned>(path: PathBuf) -> Result<T, PlanterError> {
-    let bytes = fs::read(path).map_err(|err| io_to_error("read json file", err))?;
-    serde_json::from_slice(&bytes).map_err(|err| PlanterError {
+    let bytes = fs::read(&path).map_err(|err| PlanterError::from_io("read json file", err))?;
+    let mut record: serde_json::Value = serde_json::from_slice(&bytes)
+        .map_err(|err| corrupt_metadata_error(&path, err.to_string()))?;
+
+    let expected = match &mut record {
+        serde_json::Value::Object(fields) => fields.remove(CHECKSUM_KEY),
+        _ => None,
+    };
+    if let Some(expected) = expected {
+        let actual = record_checksum(&record);
+        if expected.as_str() != Some(actual.as_str()) {
+            return Err(corrupt_metadata_error(
+                &path,
+                format!("checksum mismatch: expected={expected} actual={actual}"),
+            ));
+        }
+    }
+
+    let version = match &mut record {
+        serde_json::Value::Object(fields) => fields.remove(SCHEMA_VERSION_KEY),
+        _ => None,
+    };
+    let version = match version {
+        None => 1,
+        Some(version) => version.as_u64().ok_or_else(|| {
+            corrupt_metadata_error(&path, format!("invalid schema version {version}"))
+        })?,
+    };
+    if version > METADATA_SCHEMA_VERSION {
+        return Err(PlanterError {
+            code: ErrorCode::Internal,
+            message: "metadata was written by a newer planterd".to_string(),
+            detail: Some(format!(
+                "{}: schema_version={version} supported={METADATA_SCHEMA_VERSION}",
+                path.display()
+            )),
+        });
+    }
+
+    serde_json::from_value(record).map_err(|err| PlanterError {
         code: ErrorCode::Internal,
         message: "decode json".to_string(),
         detail: Some(err.to_string()),
     })
 }
 
-/// Converts plain I/O errors to standardized planter errors.
-fn io_to_error(action: &str, err: io::Error) -> PlanterError {
-    PlanterError {
-        code: ErrorCode::Internal,
-        message: action.to_string(),
-        detail: Some(err.to_string()),
-    }
+/// Writes bytes to a sibling temp file, syncs it, renames it over the target, then syncs the
+/// directory so the rename itself survives a crash.
+///
+/// Temp names are unique per write, so concurrent writers of one record never share a file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), PlanterError> {
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(
+        ".{file_name}.{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .map_err(|err| PlanterError::from_io("create temp json file", err))?;
+    file.write_all(bytes)
+        .map_err(|err| PlanterError::from_io("write json file", err))?;
+    file.sync_all()
+        .map_err(|err| PlanterError::from_io("sync json file", err))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(|err| {
+        let _ = fs::remove_file(&tmp_path);
+        PlanterError::from_io("rename json file", err)
+    })?;
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|err| PlanterError::from_io("sync json dir", err))
+}
+
+/// Computes an FNV-1a checksum over the canonical JSON encoding of a record.
+fn record_checksum(record: &serde_json::Value) -> String {
+    let canonical = serde_json::to_vec(record).unwrap_or_default();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in canonical {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("fnv1a64:{hash:016x}")
+}
+
+/// Builds a standardized error for truncated or tampered metadata files.
+fn corrupt_metadata_error(path: &Path, detail: String) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Internal,
+        message: "metadata file is corrupt".to_string(),
+        detail: Some(format!("{}: {detail}", path.display())),
+    }
+}
+
+/// Keeps the records that could be read, logging and skipping the rest.
+///
+/// Startup recovery uses this so one corrupt or newer-schema record can't keep the daemon down.
+fn skip_unreadable<T>(records: Vec<Result<T, PlanterError>>) -> Vec<T> {
+    records
+        .into_iter()
+        .filter_map(|record| {
+            record
+                .inspect_err(|err| {
+                    tracing::warn!(
+                        error = %err.message,
+                        detail = err.detail.as_deref().unwrap_or_default(),
+                        "skipping unreadable metadata record"
+                    );
+                })
+                .ok()
+        })
+        .collect()
 }
 
 /// Maps platform backend errors into daemon protocol errors.
 fn platform_to_planter_error(err: PlatformError) -> PlanterError {
     match err {
-        PlatformError::Io(io_err) => io_to_error("platform io", io_err),
+        PlatformError::Io(io_err) => PlanterError::from_io("platform io", io_err),
         PlatformError::InvalidInput(message) => PlanterError {
             code: ErrorCode::InvalidRequest,
             message,
@@ -679,19 +3241,2354 @@ fn platform_to_planter_error(err: PlatformError) -> PlanterError {
             message: "platform unsupported".to_string(),
             detail: Some(message),
         },
+        PlatformError::Timeout(message) => PlanterError {
+            code: ErrorCode::Timeout,
+            message,
+            detail: None,
+        },
     }
 }
 
+/// Keeps the daemon variables named in the inherit allowlist, the only ones cells may inherit.
+fn inheritable_host_env(
+    vars: impl IntoIterator<Item = (String, String)>,
+    allowlist: &[String],
+) -> BTreeMap<String, String> {
+    vars.into_iter()
+        .filter(|(name, _)| allowlist.contains(name))
+        .collect()
+}
+
+/// Replaces the values of credential-looking variables so job metadata never stores them.
+fn redact_secret_env(env: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    env.into_iter()
+        .map(|(name, value)| {
+            let upper = name.to_ascii_uppercase();
+            if SECRET_ENV_MARKERS
+                .iter()
+                .any(|marker| upper.contains(marker))
+            {
+                (name, REDACTED_ENV_VALUE.to_string())
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
+
 /// Returns the dedicated logical cell id used for PTY worker calls.
 fn default_pty_cell_id() -> CellId {
     CellId("cell-pty-default".to_string())
 }
 
-/// Builds a standardized error for unexpected worker response variants.
-fn unexpected_worker_response(action: &str, response: ExecResponse) -> PlanterError {
+/// Builds the error returned when a waited-on job disappears.
+fn job_removed_while_waiting(job_id: &JobId) -> PlanterError {
     PlanterError {
-        code: ErrorCode::Internal,
-        message: format!("unexpected worker response for {action}"),
-        detail: Some(format!("{response:?}")),
+        code: ErrorCode::NotFound,
+        message: "job removed while waiting".to_string(),
+        detail: Some(job_id.0.clone()),
+    }
+}
+
+/// Rejects snapshot ids that are not a single plain path segment.
+fn check_record_id(kind: &str, id: &str) -> Result<(), PlanterError> {
+    let mut components = Path::new(id).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == id => Ok(()),
+        _ => Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: format!("invalid {kind} id in snapshot: {id:?}"),
+            detail: None,
+        }),
+    }
+}
+
+/// Builds the error returned when a restore would overwrite an existing record.
+fn restore_conflict(kind: &str, id: &str) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::InvalidRequest,
+        message: format!("{kind} {id} already exists"),
+        detail: Some("restore only into a daemon without these records".to_string()),
+    }
+}
+
+/// Builds the error returned when a job run's deadline passes before the job started.
+fn job_run_cancelled(job_id: &JobId) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Timeout,
+        message: "job run cancelled: request deadline passed before the job started".to_string(),
+        detail: Some(job_id.0.clone()),
+    }
+}
+
+/// Returns a log file's current size, treating a missing file as empty.
+fn log_size(path: &str) -> u64 {
+    fs::metadata(path).map_or(0, |metadata| metadata.len())
+}
+
+/// Returns the file beside a log that counts how many times it has been truncated.
+fn log_generation_path(log_path: &Path) -> PathBuf {
+    let mut name = log_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".generation");
+    log_path.with_file_name(name)
+}
+
+/// Returns how many times a log has been truncated; zero when it never was.
+fn log_generation(log_path: &Path) -> u64 {
+    fs::read_to_string(log_generation_path(log_path))
+        .ok()
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Measures a log file, treating a missing one as empty.
+fn log_stream_info(path: &Path) -> LogStreamInfo {
+    LogStreamInfo {
+        current_bytes: fs::metadata(path).map_or(0, |metadata| metadata.len()),
+    }
+}
+
+/// Rejects zero-sized terminals and clamps each dimension to [`MAX_PTY_DIMENSION`].
+fn pty_dimensions(cols: u16, rows: u16) -> Result<(u16, u16), PlanterError> {
+    if cols == 0 || rows == 0 {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: format!("pty size {cols}x{rows} is invalid; cols and rows must be at least 1"),
+            detail: None,
+        });
+    }
+    Ok((cols.min(MAX_PTY_DIMENSION), rows.min(MAX_PTY_DIMENSION)))
+}
+
+/// Strips ANSI escapes from a log chunk read at `offset` when requested, returning the resume offset.
+fn strip_log_chunk(
+    data: Vec<u8>,
+    offset: u64,
+    strip_ansi: bool,
+    at_end: bool,
+) -> (Vec<u8>, Option<u64>) {
+    if !strip_ansi {
+        return (data, None);
+    }
+    let (stripped, consumed) = planter_core::strip_ansi(&data, at_end);
+    (stripped, Some(offset + consumed as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        fs,
+        os::unix::fs::PermissionsExt,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use planter_core::{
+        CellId, CellSpec, CommandSpec, ErrorCode, ExitStatus, HealthStatus, JobId, LogStream,
+        LogStreamInfo, ResourceLimits, SandboxMode, SandboxOperation, SessionId, StateSnapshot,
+        TerminationReason, WorkerMode,
+    };
+    use planter_execd_proto::{ExecRequest, ExecResponse};
+    use planter_platform::{CellPaths, JobHandle, JobUsage, PlatformError, PlatformOps};
+    use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+    use tempfile::tempdir;
+    use tokio::time::{sleep, timeout};
+
+    use super::{
+        LogsReadSpec, MAX_PTY_DIMENSION, METADATA_SCHEMA_VERSION, PTY_FOLLOW_SLICE_MS, PtyOpenSpec,
+        PtyReadSpec, REDACTED_ENV_VALUE, RunJobOptions, StateStore, StoredJobInfo, content_job_id,
+        default_pty_cell_id, hex, inheritable_host_env, largest_frame_size, pty_dimensions,
+        read_json, write_json,
+    };
+    use crate::worker_manager::WorkerManager;
+
+    /// Platform stub that only knows how to create cell directories.
+    struct DirOnlyPlatform {
+        /// Root state directory.
+        root: PathBuf,
+    }
+
+    impl PlatformOps for DirOnlyPlatform {
+        fn create_cell_dirs(&self, cell_id: &CellId) -> Result<CellPaths, PlatformError> {
+            let cell_dir = self.root.join("cells").join(&cell_id.0);
+            fs::create_dir_all(&cell_dir)?;
+            Ok(CellPaths { cell_dir })
+        }
+
+        fn spawn_job(
+            &self,
+            _job_id: &JobId,
+            _cell_id: &CellId,
+            _cmd: &CommandSpec,
+            _env: &BTreeMap<String, String>,
+            _sandbox_mode: SandboxMode,
+        ) -> Result<JobHandle, PlatformError> {
+            Err(PlatformError::Unsupported("spawn_job".to_string()))
+        }
+
+        fn kill_job_tree(
+            &self,
+            _job_id: &JobId,
+            _force: bool,
+            _grace: Duration,
+        ) -> Result<(), PlatformError> {
+            Err(PlatformError::Unsupported("kill_job_tree".to_string()))
+        }
+
+        fn probe_usage(&self, _job_id: &JobId) -> Result<Option<JobUsage>, PlatformError> {
+            Ok(None)
+        }
+
+        fn sandbox_mode(&self) -> SandboxMode {
+            SandboxMode::Disabled
+        }
+    }
+
+    /// Builds a state store with one created cell.
+    fn store_with_cell(root: PathBuf) -> (StateStore, CellId) {
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+        let cell = store
+            .create_cell(CellSpec {
+                name: "files".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+        (store, cell.id)
+    }
+
+    /// Minimal record used to exercise metadata persistence helpers.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct Record {
+        /// Record name.
+        name: String,
+        /// Record counter.
+        count: u64,
+    }
+
+    #[test]
+    /// Verifies records roundtrip through the checksummed atomic writer.
+    fn write_and_read_json_roundtrip() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        let record = Record {
+            name: "demo".to_string(),
+            count: 3,
+        };
+
+        write_json(path.clone(), &record).expect("write should succeed");
+        let decoded: Record = read_json(path.clone()).expect("read should succeed");
+
+        assert_eq!(decoded, record);
+        let names: Vec<_> = fs::read_dir(tmp.path())
+            .expect("list dir")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect();
+        assert_eq!(names, ["record.json"]);
+    }
+
+    #[test]
+    /// Verifies concurrent writers of one record each use their own temp file.
+    fn concurrent_json_writes_do_not_collide() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+
+        std::thread::scope(|scope| {
+            for count in 0..8 {
+                let path = path.clone();
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        let record = Record {
+                            name: "demo".to_string(),
+                            count,
+                        };
+                        write_json(path.clone(), &record).expect("write should succeed");
+                    }
+                });
+            }
+        });
+
+        let decoded: Record = read_json(path).expect("read should succeed");
+        assert!(decoded.count < 8);
+        assert_eq!(fs::read_dir(tmp.path()).expect("list dir").count(), 1);
+    }
+
+    #[test]
+    /// Verifies a truncated metadata file surfaces a clean corruption error.
+    fn truncated_json_reports_corruption() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        write_json(
+            path.clone(),
+            &Record {
+                name: "demo".to_string(),
+                count: 3,
+            },
+        )
+        .expect("write should succeed");
+
+        let bytes = fs::read(&path).expect("read bytes");
+        fs::write(&path, &bytes[..bytes.len() / 2]).expect("truncate file");
+
+        let err = read_json::<Record>(path).expect_err("truncated file must fail");
+        assert_eq!(err.code, ErrorCode::Internal);
+        assert_eq!(err.message, "metadata file is corrupt");
+    }
+
+    #[test]
+    /// Verifies edits that keep JSON valid are caught by checksum verification.
+    fn tampered_json_fails_checksum() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        write_json(
+            path.clone(),
+            &Record {
+                name: "demo".to_string(),
+                count: 3,
+            },
+        )
+        .expect("write should succeed");
+
+        let tampered = fs::read_to_string(&path)
+            .expect("read json")
+            .replace("\"count\": 3", "\"count\": 4");
+        fs::write(&path, tampered).expect("write tampered file");
+
+        let err = read_json::<Record>(path).expect_err("tampered file must fail");
+        assert_eq!(err.message, "metadata file is corrupt");
+        assert!(err.detail.unwrap_or_default().contains("checksum mismatch"));
+    }
+
+    #[test]
+    /// Verifies a corrupt job record is skipped at startup instead of failing the daemon.
+    fn startup_skips_corrupt_record() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let (store, cell_id) = store_with_cell(root.clone());
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"");
+        fs::write(store.jobs_dir().join("job-corrupt.json"), b"{\"id\": ").expect("write corrupt");
+        drop(store);
+
+        let store = StateStore::new(root.clone(), Arc::new(DirOnlyPlatform { root }))
+            .expect("state should initialize despite the corrupt record");
+        assert!(store.running_jobs_guard().contains_key(&job_id));
+        assert!(store.load_cell(&cell_id).is_ok());
+    }
+
+    #[test]
+    /// Verifies legacy metadata without an embedded checksum still decodes.
+    fn legacy_json_without_checksum_decodes() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        fs::write(&path, br#"{"name":"legacy","count":1}"#).expect("write legacy file");
+
+        let decoded: Record = read_json(path).expect("legacy read should succeed");
+        assert_eq!(decoded.name, "legacy");
+    }
+
+    #[test]
+    /// Verifies written records carry the current schema version and unversioned ones read as v1.
+    fn json_records_are_schema_versioned() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        write_json(
+            path.clone(),
+            &Record {
+                name: "demo".to_string(),
+                count: 3,
+            },
+        )
+        .expect("write should succeed");
+        let raw: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).expect("read bytes")).expect("parse json");
+        assert_eq!(raw["_schema_version"], METADATA_SCHEMA_VERSION);
+
+        let legacy = tmp.path().join("legacy.json");
+        fs::write(&legacy, br#"{"name":"legacy","count":1}"#).expect("write legacy file");
+        let decoded: Record = read_json(legacy).expect("v1 read should succeed");
+        assert_eq!(
+            decoded,
+            Record {
+                name: "legacy".to_string(),
+                count: 1,
+            }
+        );
+    }
+
+    #[test]
+    /// Verifies a record from a newer schema version fails with a clear error instead of decoding.
+    fn newer_schema_version_is_rejected() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("record.json");
+        let newer = METADATA_SCHEMA_VERSION + 1;
+        fs::write(
+            &path,
+            format!(r#"{{"name":"future","count":1,"_schema_version":{newer}}}"#),
+        )
+        .expect("write newer file");
+
+        let err = read_json::<Record>(path).expect_err("newer schema must fail");
+        assert_eq!(err.message, "metadata was written by a newer planterd");
+        let detail = err.detail.unwrap_or_default();
+        assert!(
+            detail.contains(&format!(
+                "schema_version={newer} supported={METADATA_SCHEMA_VERSION}"
+            )),
+            "{detail}"
+        );
+    }
+
+    #[test]
+    /// Verifies chunked cell file writes read back intact.
+    fn cell_put_get_roundtrip() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        let size = store
+            .cell_put(&cell_id, "src/main.txt", 0, b"hello ")
+            .expect("first chunk");
+        assert_eq!(size, 6);
+        let size = store
+            .cell_put(&cell_id, "src/main.txt", size, b"world")
+            .expect("second chunk");
+        assert_eq!(size, 11);
+
+        let err = store
+            .cell_put(&cell_id, "src/main.txt", 3, b"gap")
+            .expect_err("misaligned chunk should fail");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+        let first = store
+            .cell_get(&cell_id, "src/main.txt", 0, 6)
+            .expect("first read");
+        assert_eq!(first.data, b"hello ");
+        assert!(!first.eof);
+        let rest = store
+            .cell_get(&cell_id, "src/main.txt", 6, 1024)
+            .expect("second read");
+        assert_eq!(rest.data, b"world");
+        assert!(rest.eof);
+    }
+
+    #[tokio::test]
+    /// Verifies a multi-chunk staged upload stays hidden until its checksum commits it.
+    async fn staged_cell_put_commits_on_checksum_match() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        let size = store
+            .cell_put_staged(&cell_id, "out/blob.bin", 0, b"hello ")
+            .expect("first chunk");
+        let size = store
+            .cell_put_staged(&cell_id, "out/blob.bin", size, b"world")
+            .expect("second chunk");
+        assert_eq!(size, 11);
+        assert!(matches!(
+            store.cell_get(&cell_id, "out/blob.bin", 0, 64),
+            Err(err) if err.code == ErrorCode::NotFound
+        ));
+
+        let size = store
+            .cell_put_finalize(
+                &cell_id,
+                "out/blob.bin",
+                &hex(&Sha256::digest(b"hello world")),
+            )
+            .await
+            .expect("finalize");
+        assert_eq!(size, 11);
+        let chunk = store
+            .cell_get(&cell_id, "out/blob.bin", 0, 64)
+            .expect("committed file");
+        assert_eq!(chunk.data, b"hello world");
+        assert!(!store.upload_path(&cell_id, "out/blob.bin").exists());
+    }
+
+    #[tokio::test]
+    /// Verifies a checksum mismatch rejects and discards the staged upload.
+    async fn staged_cell_put_rejects_checksum_mismatch() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        let size = store
+            .cell_put_staged(&cell_id, "blob.bin", 0, b"truncat")
+            .expect("chunk");
+        assert_eq!(size, 7);
+        let err = store
+            .cell_put_finalize(&cell_id, "blob.bin", &hex(&Sha256::digest(b"truncated")))
+            .await
+            .expect_err("mismatch should fail");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("checksum mismatch"), "{}", err.message);
+        assert!(!store.upload_path(&cell_id, "blob.bin").exists());
+        assert!(matches!(
+            store.cell_get(&cell_id, "blob.bin", 0, 64),
+            Err(err) if err.code == ErrorCode::NotFound
+        ));
+
+        store
+            .cell_put_staged(&cell_id, "stale.bin", 0, b"abandoned")
+            .expect("abandoned chunk");
+        assert_eq!(store.sweep_stale_uploads(Duration::ZERO), 1);
+        assert!(!store.upload_path(&cell_id, "stale.bin").exists());
+    }
+
+    #[test]
+    /// Verifies cell file paths cannot escape the cell directory.
+    fn cell_put_rejects_path_escape() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        for path in ["../escape.txt", "/etc/passwd", "a/../../escape.txt", ""] {
+            let err = store
+                .cell_put(&cell_id, path, 0, b"x")
+                .expect_err("escaping path should fail");
+            assert_eq!(err.code, ErrorCode::InvalidRequest, "path={path}");
+        }
+        assert!(!tmp.path().join("state/cells/escape.txt").exists());
+
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(&outside).expect("outside dir");
+        let cell = store.load_cell(&cell_id).expect("load cell");
+        std::os::unix::fs::symlink(&outside, PathBuf::from(&cell.dir).join("link"))
+            .expect("symlink");
+        let err = store
+            .cell_put(&cell_id, "link/escape.txt", 0, b"x")
+            .expect_err("symlinked path should fail");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(!outside.join("escape.txt").exists());
+
+        fs::write(outside.join("secret.txt"), b"secret").expect("outside file");
+        std::os::unix::fs::symlink(
+            outside.join("secret.txt"),
+            PathBuf::from(&cell.dir).join("file-link"),
+        )
+        .expect("file symlink");
+        let err = store
+            .cell_put(&cell_id, "file-link", 0, b"x")
+            .expect_err("symlinked file should not be written");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        let Err(err) = store.cell_get(&cell_id, "file-link", 0, 1024) else {
+            panic!("symlinked file should not be read");
+        };
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert_eq!(
+            fs::read(outside.join("secret.txt")).expect("read outside"),
+            b"secret"
+        );
+    }
+
+    #[test]
+    /// Verifies listings are sorted, skip symlinks, and name files readable via `cell_get`.
+    fn cell_list_files_lists_readable_artifacts() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        store
+            .cell_put(&cell_id, "out/result.txt", 0, b"artifact")
+            .expect("put result");
+        store
+            .cell_put(&cell_id, "out/nested/log.txt", 0, b"log")
+            .expect("put nested");
+        let cell = store.load_cell(&cell_id).expect("load cell");
+        std::os::unix::fs::symlink("/etc", PathBuf::from(&cell.dir).join("out/link"))
+            .expect("symlink");
+
+        let listing = store
+            .cell_list_files(&cell_id, Some("out"))
+            .expect("list out");
+        assert!(!listing.truncated);
+        let summary = listing
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.size, entry.is_dir))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [("nested", 0, true), ("result.txt", 8, false)].as_slice()
+        );
+        assert!(listing.entries.iter().all(|entry| entry.mtime_ms.is_some()));
+
+        let root = store.cell_list_files(&cell_id, None).expect("list root");
+        assert!(
+            root.entries
+                .iter()
+                .any(|entry| entry.name == "out" && entry.is_dir)
+        );
+
+        let chunk = store
+            .cell_get(&cell_id, "out/result.txt", 0, 1024)
+            .expect("read listed file");
+        assert_eq!(chunk.data, b"artifact");
+        assert!(chunk.eof);
+    }
+
+    #[test]
+    /// Verifies listings reject escaping paths, symlinks, files, and missing directories.
+    fn cell_list_files_rejects_path_escape() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        store
+            .cell_put(&cell_id, "file.txt", 0, b"x")
+            .expect("put file");
+        let cell = store.load_cell(&cell_id).expect("load cell");
+        std::os::unix::fs::symlink(tmp.path(), PathBuf::from(&cell.dir).join("link"))
+            .expect("symlink");
+
+        for path in ["..", "/etc", "a/../../..", "link", "file.txt"] {
+            let Err(err) = store.cell_list_files(&cell_id, Some(path)) else {
+                panic!("listing {path} should fail");
+            };
+            assert_eq!(err.code, ErrorCode::InvalidRequest, "path={path}");
+        }
+        let Err(err) = store.cell_list_files(&cell_id, Some("missing")) else {
+            panic!("listing a missing dir should fail");
+        };
+        assert_eq!(err.code, ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    /// Verifies out-of-range niceness is rejected before a worker is involved.
+    async fn run_job_rejects_out_of_range_nice() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let cmd = CommandSpec {
+            argv: vec!["/bin/true".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: Some(ResourceLimits {
+                timeout_ms: None,
+                max_rss_bytes: None,
+                max_log_bytes: None,
+                max_open_files: None,
+                max_processes: None,
+                max_cpu_seconds: None,
+                nice: Some(20),
+                cpu_affinity: None,
+            }),
+        };
+
+        let err = store
+            .run_job(cell_id, cmd, RunJobOptions::default(), None)
+            .await
+            .expect_err("nice 20 should be rejected");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("-20..=19"), "{}", err.message);
+    }
+
+    #[test]
+    /// Verifies identical launch specs share an id and any differing input changes it.
+    fn content_job_id_is_stable_and_distinguishes_specs() {
+        let cell_id = CellId("cell-1".to_string());
+        let cmd = CommandSpec {
+            argv: vec!["/bin/echo".to_string(), "hi".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+        let env = BTreeMap::from([("MODE".to_string(), "fast".to_string())]);
+
+        let id = content_job_id(&cell_id, &cmd, &env);
+        assert_eq!(id, content_job_id(&cell_id, &cmd, &env));
+        assert!(id.0.starts_with("job-"));
+
+        let other_cell = CellId("cell-2".to_string());
+        assert_ne!(id, content_job_id(&other_cell, &cmd, &env));
+
+        let mut split_argv = cmd.clone();
+        split_argv.argv = vec!["/bin/echo".to_string(), "h".to_string(), "i".to_string()];
+        assert_ne!(id, content_job_id(&cell_id, &split_argv, &env));
+
+        let mut with_cwd = cmd.clone();
+        with_cwd.cwd = Some("/tmp".to_string());
+        assert_ne!(id, content_job_id(&cell_id, &with_cwd, &env));
+
+        let other_env = BTreeMap::from([("MODE".to_string(), "slow".to_string())]);
+        assert_ne!(id, content_job_id(&cell_id, &cmd, &other_env));
+
+        let mut with_limits = cmd.clone();
+        with_limits.limits = Some(ResourceLimits {
+            timeout_ms: Some(1_000),
+            max_rss_bytes: None,
+            max_log_bytes: None,
+            max_open_files: None,
+            max_processes: None,
+            max_cpu_seconds: None,
+            nice: None,
+            cpu_affinity: None,
+        });
+        assert_ne!(id, content_job_id(&cell_id, &with_limits, &env));
+    }
+
+    #[tokio::test]
+    /// Verifies oversized PTY input is rejected with chunking guidance before reaching a worker.
+    async fn pty_input_over_cap_is_rejected() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform)
+            .expect("state should initialize")
+            .with_max_pty_input_bytes(8);
+
+        let err = store
+            .pty_input(SessionId(1), vec![b'x'; 9])
+            .await
+            .expect_err("oversized input should fail");
+
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("8 byte limit"), "{}", err.message);
+        assert!(err.message.contains("chunks"), "{}", err.message);
+        assert_eq!(err.detail.as_deref(), Some("size=9 max=8"));
+    }
+
+    #[test]
+    /// Verifies oversized PTY dimensions are clamped and in-range ones pass through.
+    fn pty_dimensions_clamp_to_maximum() {
+        assert_eq!(pty_dimensions(80, 24).expect("valid size"), (80, 24));
+        assert_eq!(
+            pty_dimensions(u16::MAX, u16::MAX).expect("clamped size"),
+            (MAX_PTY_DIMENSION, MAX_PTY_DIMENSION)
+        );
+        assert_eq!(
+            pty_dimensions(2000, 50).expect("clamped size"),
+            (MAX_PTY_DIMENSION, 50)
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies zero-sized PTY open and resize requests fail before reaching a worker.
+    async fn pty_zero_dimensions_are_rejected() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, _) = store_with_cell(tmp.path().join("state"));
+
+        let err = store
+            .pty_resize(SessionId(1), 0, 24)
+            .await
+            .expect_err("zero cols should fail");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("0x24"), "{}", err.message);
+
+        let Err(err) = store
+            .open_pty(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: Vec::new(),
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 0,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .await
+        else {
+            panic!("zero rows should fail");
+        };
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("at least 1"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    /// Verifies an allowlisted shell opens while any other shell is rejected before the worker.
+    async fn pty_open_enforces_shell_allowlist() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform)
+            .expect("state should initialize")
+            .with_pty_shells(vec![PathBuf::from("/bin/sh")]);
+        let spec = |shell: &str| PtyOpenSpec {
+            shell: shell.to_string(),
+            args: vec!["-c".to_string(), "sleep 5".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            cols: 80,
+            rows: 24,
+            jail_cwd: false,
+            persist_scrollback: false,
+        };
+
+        let Err(err) = store.open_pty(spec("/bin/bash")).await else {
+            panic!("non-allowlisted shell should fail");
+        };
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("/bin/bash"), "{}", err.message);
+        assert_eq!(err.detail.as_deref(), Some("allowed=/bin/sh"));
+
+        let opened = match store.open_pty(spec("/bin/sh")).await {
+            Ok(opened) => opened,
+            Err(err) => panic!("allowlisted shell should open: {err:?}"),
+        };
+        store
+            .pty_close(opened.session_id, true)
+            .await
+            .expect("close session");
+    }
+
+    #[tokio::test]
+    /// Verifies a long PTY follow read does not hold the shared PTY worker away from other calls.
+    async fn pty_follow_read_leaves_worker_available() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, _) = store_with_cell(tmp.path().join("state"));
+        let opened = match store
+            .open_pty(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "sleep 5".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .await
+        {
+            Ok(opened) => opened,
+            Err(err) => panic!("open pty: {err:?}"),
+        };
+        let follow = PtyReadSpec {
+            offset: 0,
+            max_bytes: 1024,
+            follow: true,
+            wait_ms: 2_000,
+            from_end: None,
+            utf8_boundary: false,
+            strip_ansi: false,
+        };
+
+        let (followed, resized_in) =
+            tokio::join!(store.pty_read(opened.session_id, follow), async {
+                sleep(Duration::from_millis(100)).await;
+                let started = Instant::now();
+                store
+                    .pty_resize(opened.session_id, 100, 30)
+                    .await
+                    .expect("resize during follow");
+                started.elapsed()
+            });
+
+        assert!(followed.expect("follow read").data.is_empty());
+        assert!(
+            resized_in < Duration::from_millis(PTY_FOLLOW_SLICE_MS * 2),
+            "{resized_in:?}"
+        );
+        store
+            .pty_close(opened.session_id, true)
+            .await
+            .expect("close session");
+    }
+
+    #[tokio::test]
+    /// Verifies cancelling PTY follows ends a long follow read within one worker slice.
+    async fn cancel_pty_follows_ends_pending_follow_read() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, _) = store_with_cell(tmp.path().join("state"));
+        let opened = match store
+            .open_pty(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "sleep 5".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .await
+        {
+            Ok(opened) => opened,
+            Err(err) => panic!("open pty: {err:?}"),
+        };
+        let follow = PtyReadSpec {
+            offset: 0,
+            max_bytes: 1024,
+            follow: true,
+            wait_ms: 10_000,
+            from_end: None,
+            utf8_boundary: false,
+            strip_ansi: false,
+        };
+
+        let started = Instant::now();
+        let (chunk, cancelled) = tokio::join!(store.pty_read(opened.session_id, follow), async {
+            sleep(Duration::from_millis(100)).await;
+            store.cancel_pty_follows(opened.session_id)
+        });
+        let elapsed = started.elapsed();
+
+        assert_eq!(cancelled, 1);
+        let chunk = chunk.expect("follow read");
+        assert!(chunk.data.is_empty());
+        assert!(!chunk.complete);
+        assert!(
+            elapsed < Duration::from_millis(100 + PTY_FOLLOW_SLICE_MS * 2),
+            "{elapsed:?}"
+        );
+        assert_eq!(store.cancel_pty_follows(opened.session_id), 0);
+        store
+            .pty_close(opened.session_id, true)
+            .await
+            .expect("close session");
+    }
+
+    #[tokio::test]
+    /// Verifies a PTY peek is answered while another call holds the PTY worker's call lock.
+    async fn pty_peek_skips_held_call_lock() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, _) = store_with_cell(tmp.path().join("state"));
+        let opened = match store
+            .open_pty(PtyOpenSpec {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "sleep 5".to_string()],
+                cwd: None,
+                env: BTreeMap::new(),
+                cols: 80,
+                rows: 24,
+                jail_cwd: false,
+                persist_scrollback: false,
+            })
+            .await
+        {
+            Ok(opened) => opened,
+            Err(err) => panic!("open pty: {err:?}"),
+        };
+
+        let call_lock = store.workers.get_call_lock(&default_pty_cell_id().0);
+        let held = call_lock.lock().await;
+        let peeked = timeout(
+            Duration::from_millis(500),
+            store.pty_peek(opened.session_id, 0, 1024),
+        )
+        .await
+        .expect("peek waited on the call lock")
+        .expect("peek");
+        drop(held);
+
+        assert_eq!(peeked.offset, 0);
+        store
+            .pty_close(opened.session_id, true)
+            .await
+            .expect("close session");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[tokio::test]
+    /// Verifies PTY sandbox profile requests are rejected where sandbox-exec does not exist.
+    async fn pty_sandbox_profile_is_unsupported_off_macos() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+
+        let err = store
+            .pty_sandbox_profile(None)
+            .await
+            .expect_err("profile rendering should be unsupported");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("macOS"), "{}", err.message);
+    }
+
+    /// Persists a job record with the given status and stdout contents, bypassing the worker.
+    fn store_job(store: &StateStore, cell_id: &CellId, status: ExitStatus, stdout: &[u8]) -> JobId {
+        let id = JobId("job-logs".to_string());
+        let logs_dir = store.logs_dir();
+        fs::create_dir_all(logs_dir).expect("logs dir");
+        let stdout_path = logs_dir.join("job-logs.stdout.log");
+        let stderr_path = logs_dir.join("job-logs.stderr.log");
+        fs::write(&stdout_path, stdout).expect("write stdout");
+        fs::write(&stderr_path, b"").expect("write stderr");
+        store
+            .persist_job(&StoredJobInfo {
+                id: id.clone(),
+                cell_id: cell_id.clone(),
+                command: CommandSpec {
+                    argv: vec!["/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                stdout_path: stdout_path.display().to_string(),
+                stderr_path: stderr_path.display().to_string(),
+                requested_at_ms: 0,
+                started_at_ms: 0,
+                finished_at_ms: None,
+                pid: None,
+                status,
+                termination_reason: None,
+                sandbox_mode: None,
+                sandboxed: false,
+                sandbox_fallback_reason: None,
+                spawn_latency_ms: None,
+                effective_env: None,
+            })
+            .expect("persist job");
+        id
+    }
+
+    #[test]
+    /// Verifies log totals add both streams' file sizes.
+    fn logs_info_totals_both_streams() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"live\n");
+        fs::write(store.logs_dir().join("job-logs.stderr.log"), b"err\n").expect("stderr");
+
+        let info = store.logs_info(&job_id).expect("logs info");
+
+        assert_eq!(info.stdout, LogStreamInfo { current_bytes: 5 });
+        assert_eq!(info.stderr, LogStreamInfo { current_bytes: 4 });
+        assert_eq!(info.total_bytes, 9);
+        assert_eq!(info.max_log_bytes, None);
+    }
+
+    #[test]
+    /// Verifies a snapshot survives JSON and restores its records into a fresh daemon once, with
+    /// log paths rebuilt under the new logs directory.
+    fn snapshot_restores_into_fresh_store() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"live\n");
+
+        let snapshot = store.snapshot().expect("snapshot");
+        assert_eq!(snapshot.cells.len(), 1);
+        assert_eq!(snapshot.jobs.len(), 1);
+        assert!(snapshot.jobs[0].job.cell_exists);
+        let encoded = serde_json::to_vec(&snapshot).expect("encode snapshot");
+        let decoded: StateSnapshot = serde_json::from_slice(&encoded).expect("decode snapshot");
+        assert_eq!(decoded, snapshot);
+
+        let mut tampered = decoded.clone();
+        tampered.jobs[0].stdout_path = "/etc/passwd".to_string();
+        let restored_root = tmp.path().join("restored");
+        let restored = StateStore::new(
+            restored_root.clone(),
+            Arc::new(DirOnlyPlatform {
+                root: restored_root,
+            }),
+        )
+        .expect("state should initialize");
+        let mut with_worker_bin = tampered.clone();
+        with_worker_bin.cells[0].spec.worker_bin_override = Some("/tmp/evil-execd".to_string());
+        let err = restored
+            .restore(with_worker_bin)
+            .expect_err("unlisted worker binary should be rejected");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("allowlist"), "{}", err.message);
+        assert!(restored.cell_records().expect("list cells").is_empty());
+        assert_eq!(restored.restore(tampered).expect("restore"), (1, 1));
+
+        let cell = restored.load_cell(&cell_id).expect("restored cell");
+        assert_eq!(cell.spec.name, "files");
+        assert!(Path::new(&cell.dir).is_dir());
+        let job = restored.load_job(&job_id).expect("restored job");
+        assert_eq!(job.status, ExitStatus::Exited { code: None });
+        assert_eq!(job.termination_reason, Some(TerminationReason::Unknown));
+        assert!(job.finished_at_ms.is_some());
+        let record = restored.load_job_record(&job_id).expect("restored record");
+        assert_eq!(
+            Path::new(&record.stdout_path),
+            restored.logs_dir().join(format!("{}.stdout.log", job_id.0))
+        );
+
+        let err = restored
+            .restore(decoded)
+            .expect_err("second restore conflicts");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+    }
+
+    #[tokio::test]
+    /// Verifies a caught-up one-shot read of a running job says more output may follow.
+    async fn read_logs_running_empty_expects_more() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"abc");
+
+        let chunk = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 3,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        assert!(chunk.data.is_empty());
+        assert!(!chunk.complete);
+        assert!(chunk.more_expected);
+    }
+
+    #[tokio::test]
+    /// Verifies a truncation is reported even after new output grows the log past the old offset.
+    async fn read_logs_resets_after_truncated_log_regrows() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(
+            &store,
+            &cell_id,
+            ExitStatus::Exited { code: Some(0) },
+            b"0123456789",
+        );
+        let spec = |offset, generation| LogsReadSpec {
+            offset,
+            max_bytes: 1024,
+            follow: false,
+            wait_ms: 0,
+            strip_ansi: false,
+            reverse: false,
+            generation,
+        };
+
+        let first = store
+            .read_logs(&job_id, LogStream::Stdout, spec(0, None))
+            .await
+            .expect("first read");
+        assert_eq!(first.data, b"0123456789");
+        assert_eq!(first.generation, 0);
+
+        store
+            .truncate_logs(&job_id, Some(LogStream::Stdout))
+            .await
+            .expect("truncate");
+        fs::write(
+            store.logs_dir().join("job-logs.stdout.log"),
+            b"abcdefghijklmnop",
+        )
+        .expect("regrow stdout");
+
+        let regrown = store
+            .read_logs(&job_id, LogStream::Stdout, spec(10, Some(0)))
+            .await
+            .expect("read after truncation");
+        assert!(regrown.reset);
+        assert_eq!(regrown.offset, 0);
+        assert_eq!(regrown.data, b"abcdefghijklmnop");
+        assert_eq!(regrown.generation, 1);
+
+        let caught_up = store
+            .read_logs(&job_id, LogStream::Stdout, spec(16, Some(1)))
+            .await
+            .expect("caught up read");
+        assert!(!caught_up.reset);
+        assert!(caught_up.data.is_empty());
+    }
+
+    #[tokio::test]
+    /// Reproduces a read landing between the launch being recorded and the worker creating the log.
+    async fn read_logs_waits_for_log_created_after_start() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"");
+        let stdout_path = store.logs_dir().join("job-logs.stdout.log");
+        fs::remove_file(&stdout_path).expect("remove stdout");
+        let writer = tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            fs::write(&stdout_path, b"hello\n").expect("write stdout");
+        });
+
+        let chunk = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 0,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        writer.await.expect("writer");
+        assert_eq!(chunk.data, b"hello\n");
+        assert!(!chunk.complete);
+        assert!(chunk.more_expected);
+    }
+
+    #[tokio::test]
+    /// Verifies a read issued as soon as `run_job` returns sees the job's first output.
+    async fn read_logs_right_after_run_job_sees_output() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        for attempt in 0..10 {
+            let job = store
+                .run_job(
+                    cell_id.clone(),
+                    CommandSpec {
+                        argv: vec!["/bin/echo".to_string(), format!("hello {attempt}")],
+                        cwd: None,
+                        env: BTreeMap::new(),
+                        limits: None,
+                    },
+                    RunJobOptions::default(),
+                    None,
+                )
+                .await
+                .expect("run job");
+            let chunk = store
+                .read_logs(
+                    &job.id,
+                    LogStream::Stdout,
+                    LogsReadSpec {
+                        offset: 0,
+                        max_bytes: 1024,
+                        follow: true,
+                        wait_ms: 5_000,
+                        strip_ansi: false,
+                        reverse: false,
+                        generation: None,
+                    },
+                )
+                .await
+                .expect("read logs");
+            assert_eq!(chunk.data, format!("hello {attempt}\n").into_bytes());
+            assert!(!chunk.reset);
+            store
+                .wait_job(&job.id, Some(5_000))
+                .await
+                .expect("wait job");
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a running job's missing log never reads as a reset or a finished stream.
+    async fn read_logs_missing_log_of_running_job_is_not_ready() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"");
+        fs::remove_file(store.logs_dir().join("job-logs.stdout.log")).expect("remove stdout");
+
+        let chunk = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 5,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        assert!(chunk.data.is_empty());
+        assert!(!chunk.reset);
+        assert_eq!(chunk.offset, 5);
+        assert!(!chunk.complete);
+        assert!(chunk.more_expected);
+    }
+
+    #[tokio::test]
+    /// Verifies a one-shot read returning a running job's data still expects more.
+    async fn read_logs_running_data_expects_more() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"abc");
+
+        let chunk = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 0,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        assert_eq!(chunk.data, b"abc");
+        assert!(chunk.eof);
+        assert!(!chunk.complete);
+        assert!(chunk.more_expected);
+    }
+
+    #[tokio::test]
+    /// Verifies stripped reads drop escapes and resume after an escape split by max_bytes.
+    async fn read_logs_strip_ansi_resumes_at_next_offset() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(
+            &store,
+            &cell_id,
+            ExitStatus::Exited { code: Some(0) },
+            b"\x1b[31mred\x1b[0m plain",
+        );
+        let spec = |offset| LogsReadSpec {
+            offset,
+            max_bytes: 10,
+            follow: false,
+            wait_ms: 0,
+            strip_ansi: true,
+            reverse: false,
+            generation: None,
+        };
+
+        let first = store
+            .read_logs(&job_id, LogStream::Stdout, spec(0))
+            .await
+            .expect("read logs");
+        assert_eq!(first.data, b"red");
+        assert_eq!(first.next_offset, Some(8));
+        assert!(!first.complete);
+
+        let second = store
+            .read_logs(&job_id, LogStream::Stdout, spec(8))
+            .await
+            .expect("read logs");
+        assert_eq!(second.data, b" plain");
+        assert_eq!(second.next_offset, Some(18));
+        assert!(second.complete);
+    }
+
+    #[tokio::test]
+    /// Verifies cancelling follows ends a long follow read promptly with an empty chunk.
+    async fn cancel_follows_ends_pending_follow_read() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"abc");
+
+        let started = Instant::now();
+        let (chunk, cancelled) = tokio::join!(
+            store.read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 3,
+                    max_bytes: 1024,
+                    follow: true,
+                    wait_ms: 10_000,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            ),
+            async {
+                sleep(Duration::from_millis(100)).await;
+                store.cancel_follows(&job_id)
+            }
+        );
+        let elapsed = started.elapsed();
+
+        assert_eq!(cancelled.expect("cancel follows"), 1);
+        let chunk = chunk.expect("read logs");
+        assert!(chunk.data.is_empty());
+        assert!(chunk.more_expected);
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+        assert_eq!(store.cancel_follows(&job_id).expect("cancel follows"), 0);
+    }
+
+    #[test]
+    /// Verifies peeking a running job's log returns immediately and reports the file length.
+    fn peek_logs_never_waits_and_reports_length() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"hello planter");
+
+        let peeked = store
+            .peek_logs(&job_id, LogStream::Stdout, 6, 4)
+            .expect("peek logs");
+        assert_eq!(peeked.offset, 6);
+        assert_eq!(peeked.data, b"plan");
+        assert_eq!(peeked.length, 13);
+        assert!(!peeked.complete);
+
+        let started = Instant::now();
+        let peeked = store
+            .peek_logs(&job_id, LogStream::Stdout, 13, 1024)
+            .expect("peek logs at end");
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(peeked.data.is_empty());
+        assert_eq!(peeked.length, 13);
+    }
+
+    #[tokio::test]
+    /// Verifies reverse reads walk from the end to the start and reconstruct the log.
+    async fn read_logs_reverse_reconstructs_log() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let log = b"line one\nline two\nline three\n";
+        let job_id = store_job(&store, &cell_id, ExitStatus::Exited { code: Some(0) }, log);
+
+        let mut pages = Vec::new();
+        let mut end = u64::MAX;
+        loop {
+            let chunk = store
+                .read_logs(
+                    &job_id,
+                    LogStream::Stdout,
+                    LogsReadSpec {
+                        offset: end,
+                        max_bytes: 7,
+                        follow: false,
+                        wait_ms: 0,
+                        strip_ansi: false,
+                        reverse: true,
+                        generation: None,
+                    },
+                )
+                .await
+                .expect("read logs");
+            assert!(chunk.data.len() <= 7);
+            assert_eq!(
+                chunk.offset + chunk.data.len() as u64,
+                end.min(log.len() as u64)
+            );
+            assert_eq!(chunk.more_expected, !chunk.eof);
+            pages.push(chunk.data);
+            if chunk.eof {
+                assert!(chunk.complete);
+                break;
+            }
+            end = chunk.offset;
+        }
+
+        assert_eq!(pages.len(), log.len().div_ceil(7));
+        let rebuilt: Vec<u8> = pages.into_iter().rev().flatten().collect();
+        assert_eq!(rebuilt, log);
+    }
+
+    #[tokio::test]
+    /// Verifies a finished job's log expects more only until its last bytes are read.
+    async fn read_logs_finished_stops_expecting_more() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let job_id = store_job(
+            &store,
+            &cell_id,
+            ExitStatus::Exited { code: Some(0) },
+            b"abc",
+        );
+
+        let partial = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 0,
+                    max_bytes: 2,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read partial");
+        assert_eq!(partial.data, b"ab");
+        assert!(partial.more_expected);
+
+        let last = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 2,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read last");
+        assert_eq!(last.data, b"c");
+        assert!(last.complete);
+        assert!(!last.more_expected);
+
+        let done = store
+            .read_logs(
+                &job_id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 3,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read done");
+        assert!(done.data.is_empty());
+        assert!(!done.more_expected);
+    }
+
+    #[test]
+    /// Verifies startup removes session dirs of dead shells but keeps live or recoverable ones.
+    fn startup_sweeps_orphaned_pty_sessions() {
+        let tmp = tempdir().expect("tempdir");
+        let sessions = tmp.path().join("sessions");
+        let mut exited = std::process::Command::new("/usr/bin/true")
+            .spawn()
+            .expect("spawn true");
+        let dead_pid = exited.id();
+        exited.wait().expect("wait true");
+        for (name, pid) in [
+            ("pty-1", Some(dead_pid)),
+            ("pty-2", None),
+            ("pty-3", Some(std::process::id())),
+            ("pty-4", Some(dead_pid)),
+        ] {
+            fs::create_dir_all(sessions.join(name).join("home")).expect("seed session");
+            if let Some(pid) = pid {
+                fs::write(sessions.join(name).join("shell.pid"), pid.to_string())
+                    .expect("seed pid");
+            }
+        }
+        fs::write(sessions.join("pty-4").join("output.log"), b"scrollback").expect("seed log");
+
+        let _ = store_with_cell(tmp.path().to_path_buf());
+
+        assert!(!sessions.join("pty-1").exists());
+        assert!(!sessions.join("pty-2").exists());
+        assert!(sessions.join("pty-3").exists());
+        assert!(sessions.join("pty-4").exists());
+    }
+
+    #[tokio::test]
+    /// Verifies a worker spawn slower than the health budget reports `degraded`, not `unhealthy`.
+    async fn deep_health_reports_degraded_on_slow_worker_spawn() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let worker_bin = tmp.path().join("slow-planter-execd");
+        fs::write(&worker_bin, "#!/bin/sh\nsleep 1\n").expect("write worker");
+        fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755)).expect("chmod worker");
+
+        let (store, _) = store_with_cell(root.clone());
+        let mut store = store.with_health_timeout(Duration::from_millis(100));
+        store.workers = Arc::new(WorkerManager::with_worker_bin(root, worker_bin));
+
+        let (status, detail) = store.deep_health().await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert!(
+            detail
+                .as_deref()
+                .is_some_and(|detail| detail.contains("exceeded 100ms")),
+            "{detail:?}"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies a health check that runs out of budget behind a busy cell leaves that cell's
+    /// worker cached and usable.
+    async fn deep_health_timeout_keeps_busy_worker_usable() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, _) = store_with_cell(tmp.path().to_path_buf());
+        let store = store.with_health_timeout(Duration::from_millis(100));
+        let pty_cell = default_pty_cell_id();
+        assert_eq!(store.deep_health().await.0, HealthStatus::Ok);
+
+        let call_lock = store.workers.get_call_lock(&pty_cell.0);
+        let busy = call_lock.lock().await;
+        let (status, detail) = store.deep_health().await;
+        assert_eq!(status, HealthStatus::Degraded, "{detail:?}");
+        drop(busy);
+
+        assert_eq!(store.workers.cached_cells(), vec![pty_cell.clone()]);
+        assert!(
+            store
+                .workers
+                .is_alive(&pty_cell)
+                .await
+                .expect("ping worker")
+        );
+        assert_eq!(store.deep_health().await.0, HealthStatus::Ok);
+    }
+
+    #[test]
+    /// Verifies the diagnostics bundle carries every top-level section and current counts.
+    fn diagnostics_report_has_expected_sections() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().to_path_buf());
+        store_job(&store, &cell_id, ExitStatus::Running, b"");
+
+        let report = store.diagnostics().expect("diagnostics");
+        assert_eq!(report.resources.cells, 1);
+        assert_eq!(report.resources.jobs, 1);
+        assert_eq!(report.resources.running_jobs, 1);
+        assert!(report.workers.is_empty());
+        assert_eq!(
+            report.daemon.largest_frame_bytes,
+            u64::from(largest_frame_size())
+        );
+
+        let json = serde_json::to_value(&report).expect("serialize report");
+        let mut sections: Vec<&str> = json
+            .as_object()
+            .expect("report object")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        sections.sort_unstable();
+        assert_eq!(
+            sections,
+            ["daemon", "errors", "resources", "sandbox", "workers"]
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies an overridden logs directory receives job logs instead of the state root.
+    async fn logs_dir_override_receives_job_logs() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let logs_dir = tmp.path().join("volume").join("logs");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root.clone(), platform)
+            .expect("state should initialize")
+            .with_logs_dir(Some(logs_dir.clone()))
+            .expect("logs dir should be usable");
+        let cell = store
+            .create_cell(CellSpec {
+                name: "logs-volume".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+
+        let job = store
+            .run_job(
+                cell.id,
+                CommandSpec {
+                    argv: vec!["/bin/echo".to_string(), "on-volume".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect("run job");
+        store
+            .wait_job(&job.id, Some(5_000))
+            .await
+            .expect("wait job");
+
+        let stdout = logs_dir.join(format!("{}.stdout.log", job.id.0));
+        assert_eq!(
+            fs::read_to_string(&stdout).expect("read stdout log"),
+            "on-volume\n"
+        );
+        assert!(
+            !root
+                .join("logs")
+                .join(format!("{}.stdout.log", job.id.0))
+                .exists()
+        );
+        assert_eq!(store.logs_dir(), logs_dir);
+    }
+
+    #[tokio::test]
+    /// Verifies the merged launch env, including cell-level variables, is persisted on the job.
+    async fn run_job_records_effective_env() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+        let cell = store
+            .create_cell(CellSpec {
+                name: "merged-env".to_string(),
+                env: BTreeMap::from([
+                    ("CELL_ONLY".to_string(), "cell".to_string()),
+                    ("SHARED".to_string(), "cell".to_string()),
+                    ("DEPLOY_API_TOKEN".to_string(), "hunter2".to_string()),
+                ]),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+
+        let job = store
+            .run_job(
+                cell.id,
+                CommandSpec {
+                    argv: vec!["/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::from([("SHARED".to_string(), "command".to_string())]),
+                    limits: None,
+                },
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect("run job");
+        let env = job.effective_env.expect("effective env recorded");
+        assert_eq!(env.get("CELL_ONLY").map(String::as_str), Some("cell"));
+        assert_eq!(env.get("SHARED").map(String::as_str), Some("command"));
+        assert_eq!(
+            env.get("DEPLOY_API_TOKEN").map(String::as_str),
+            Some(REDACTED_ENV_VALUE)
+        );
+
+        let stored = store.load_job(&job.id).expect("load job");
+        assert_eq!(stored.effective_env, Some(env));
+        let raw = fs::read_to_string(store.job_path(&job.id)).expect("read job metadata");
+        assert!(!raw.contains("hunter2"), "{raw}");
+        store
+            .wait_job(&job.id, Some(5_000))
+            .await
+            .expect("wait job");
+    }
+
+    #[tokio::test]
+    /// Verifies a job whose metadata cannot be saved is killed instead of left running unrecorded.
+    async fn run_job_kills_child_when_metadata_write_fails() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+        let cell = store
+            .create_cell(CellSpec {
+                name: "rollback".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+        let cmd = CommandSpec {
+            argv: vec!["/bin/sleep".to_string(), "30".to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+        let job_id = content_job_id(&cell.id, &cmd, &BTreeMap::new());
+        fs::remove_dir_all(store.jobs_dir()).expect("remove jobs dir");
+        fs::write(store.jobs_dir(), b"").expect("block jobs dir");
+
+        store
+            .run_job(
+                cell.id.clone(),
+                cmd,
+                RunJobOptions {
+                    deterministic_id: true,
+                    ..RunJobOptions::default()
+                },
+                None,
+            )
+            .await
+            .expect_err("persisting the job should fail");
+
+        assert!(store.running_jobs_guard().is_empty());
+        match store
+            .workers
+            .call(&cell.id, ExecRequest::JobStatus { job_id })
+            .await
+            .expect("worker job status")
+        {
+            ExecResponse::JobStatus { status, .. } => {
+                assert!(!matches!(status, ExitStatus::Running), "{status:?}");
+            }
+            other => panic!("unexpected worker response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies a run whose deadline passes during a slow worker spawn tears the worker down
+    /// and leaves no job behind.
+    async fn run_job_cancelled_during_slow_spawn_leaves_no_job() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let worker_bin = tmp.path().join("slow-planter-execd");
+        let store = StateStore::new(root, platform)
+            .and_then(|store| store.with_worker_bins(vec![worker_bin.clone()]))
+            .expect("state should initialize");
+        // Records its pid, then never answers the hello.
+        fs::write(
+            &worker_bin,
+            "#!/bin/sh
+echo $$ > \"$0.pid\"
+exec sleep 30
+",
+        )
+        .expect("write worker");
+        fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755)).expect("chmod worker");
+        let cell = store
+            .create_cell(CellSpec {
+                name: "slow".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: Vec::new(),
+                worker_bin_override: Some(worker_bin.display().to_string()),
+            })
+            .expect("create cell");
+
+        let started = Instant::now();
+        let err = store
+            .run_job(
+                cell.id,
+                CommandSpec {
+                    argv: vec!["/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                RunJobOptions::default(),
+                Some(Instant::now() + Duration::from_millis(200)),
+            )
+            .await
+            .expect_err("run should be cancelled");
+
+        assert_eq!(err.code, ErrorCode::Timeout);
+        assert!(err.message.contains("cancelled"), "{}", err.message);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(store.running_jobs_guard().is_empty());
+        let jobs = fs::read_dir(store.jobs_dir())
+            .expect("read jobs dir")
+            .count();
+        assert_eq!(jobs, 0);
+        let pid: i32 = fs::read_to_string(format!("{}.pid", worker_bin.display()))
+            .expect("worker pid")
+            .trim()
+            .parse()
+            .expect("parse pid");
+        // SAFETY: signal 0 only probes whether the pid still exists.
+        assert_ne!(unsafe { libc::kill(pid, 0) }, 0, "worker should be gone");
+    }
+
+    #[tokio::test]
+    /// Verifies a worker binary the operator did not allow is refused at create and dropped
+    /// from persisted cells when the daemon restarts without it.
+    async fn worker_bin_override_requires_allowlist() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let worker_bin = tmp.path().join("canary-planter-execd");
+        fs::write(&worker_bin, "#!/bin/sh\nexit 1\n").expect("write worker");
+        fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755)).expect("chmod worker");
+        let spec = CellSpec {
+            name: "canary".to_string(),
+            env: BTreeMap::new(),
+            sandbox_mode: None,
+            inherit_host_env: Vec::new(),
+            worker_bin_override: Some(worker_bin.display().to_string()),
+        };
+
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store =
+            StateStore::new(root.clone(), platform.clone()).expect("state should initialize");
+        let err = store
+            .create_cell(spec.clone())
+            .expect_err("override should need the allowlist");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("allowlist"), "{}", err.message);
+        assert!(store.cell_records().expect("list cells").is_empty());
+
+        let cell = StateStore::new(root.clone(), platform.clone())
+            .and_then(|store| store.with_worker_bins(vec![worker_bin.clone()]))
+            .expect("state should initialize")
+            .create_cell(spec)
+            .expect("allowed override");
+
+        let store = StateStore::new(root, platform)
+            .and_then(|store| store.with_worker_bins(Vec::new()))
+            .expect("state should restart");
+        let response = store
+            .workers
+            .call(&cell.id, ExecRequest::Ping {})
+            .await
+            .expect("default worker should answer");
+        assert!(matches!(response, ExecResponse::Pong {}));
+        let workers = store.workers.worker_diagnostics();
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[0].mode, WorkerMode::InProcess);
+    }
+
+    #[test]
+    /// Verifies renaming rewrites only the name, keeping the cell's id, directory, and files.
+    fn rename_cell_keeps_id_and_directory() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().to_path_buf());
+        let before = store.load_cell(&cell_id).expect("load cell");
+        fs::write(PathBuf::from(&before.dir).join("keep.txt"), b"kept").expect("write file");
+
+        let renamed = store
+            .rename_cell(&cell_id, "renamed".to_string())
+            .expect("rename cell");
+        assert_eq!(renamed.id, cell_id);
+
+        let after = store.load_cell(&cell_id).expect("reload cell");
+        assert_eq!(after.spec.name, "renamed");
+        assert_eq!(after.dir, before.dir);
+        assert_eq!(after.created_at_ms, before.created_at_ms);
+        assert_eq!(
+            fs::read(PathBuf::from(&after.dir).join("keep.txt")).expect("read file"),
+            b"kept"
+        );
+
+        let err = store
+            .rename_cell(&cell_id, " ".to_string())
+            .expect_err("blank name should fail");
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+    }
+
+    #[tokio::test]
+    /// Verifies a worker `NotFound` reaches callers with the worker's own message and no debug dump.
+    async fn worker_not_found_surfaces_cleanly() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+
+        let err = store
+            .pty_close(SessionId(404), true)
+            .await
+            .expect_err("unknown session should fail");
+
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert_eq!(err.message, "session 404 does not exist");
+        assert_eq!(err.detail, None);
+    }
+
+    #[tokio::test]
+    /// Verifies an unsandboxed permissive launch records the worker's reason, disabled launches
+    /// record nothing, and an enforced launch is refused for that same reason.
+    async fn permissive_fallback_reason_is_recorded() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform).expect("state should initialize");
+
+        let mut reasons = Vec::new();
+        for sandbox_mode in [SandboxMode::Permissive, SandboxMode::Disabled] {
+            let cell = store
+                .create_cell(CellSpec {
+                    name: "audit".to_string(),
+                    env: BTreeMap::new(),
+                    sandbox_mode: Some(sandbox_mode),
+                    inherit_host_env: Vec::new(),
+                    worker_bin_override: None,
+                })
+                .expect("create cell");
+            let job = store
+                .run_job(
+                    cell.id,
+                    CommandSpec {
+                        argv: vec!["/bin/true".to_string()],
+                        cwd: None,
+                        env: BTreeMap::new(),
+                        limits: None,
+                    },
+                    RunJobOptions::default(),
+                    None,
+                )
+                .await
+                .expect("run job");
+            assert!(!job.sandboxed);
+            let stored = store.load_job(&job.id).expect("load job");
+            assert_eq!(stored.sandbox_fallback_reason, job.sandbox_fallback_reason);
+            reasons.push(stored.sandbox_fallback_reason);
+        }
+
+        assert_eq!(
+            reasons,
+            vec![
+                Some("no sandbox profile is available for this cell".to_string()),
+                None
+            ]
+        );
+
+        let cell = store
+            .create_cell(CellSpec {
+                name: "audit".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: Some(SandboxMode::Enforced),
+                inherit_host_env: Vec::new(),
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+        let err = store
+            .run_job(
+                cell.id,
+                CommandSpec {
+                    argv: vec!["/bin/true".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect_err("enforced job should not run unsandboxed");
+        assert_eq!(err.code, ErrorCode::Unavailable);
+        assert_eq!(
+            err.detail.as_deref(),
+            Some("no sandbox profile is available for this cell")
+        );
+        assert_eq!(store.all_jobs().expect("list jobs").len(), 2);
+    }
+
+    #[tokio::test]
+    /// Verifies jobs see allowlisted daemon env vars beneath cell env, and nothing else from the host.
+    async fn cell_inherits_only_listed_host_env() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let mut store = StateStore::new(root, platform)
+            .expect("state should initialize")
+            .with_inheritable_env(vec![
+                "PLANTER_TEST_CELL_INHERITED".to_string(),
+                "PLANTER_TEST_CELL_OVERRIDDEN".to_string(),
+            ]);
+        store.host_env.extend(
+            [
+                "PLANTER_TEST_CELL_INHERITED",
+                "PLANTER_TEST_CELL_OVERRIDDEN",
+                "PLANTER_TEST_CELL_HIDDEN",
+            ]
+            .map(|name| (name.to_string(), "from-host".to_string())),
+        );
+        let cell = store
+            .create_cell(CellSpec {
+                name: "inherit".to_string(),
+                env: BTreeMap::from([(
+                    "PLANTER_TEST_CELL_OVERRIDDEN".to_string(),
+                    "from-cell".to_string(),
+                )]),
+                sandbox_mode: None,
+                inherit_host_env: vec![
+                    "PLANTER_TEST_CELL_INHERITED".to_string(),
+                    "PLANTER_TEST_CELL_OVERRIDDEN".to_string(),
+                ],
+                worker_bin_override: None,
+            })
+            .expect("create cell");
+
+        let job = store
+            .run_job(
+                cell.id,
+                CommandSpec {
+                    argv: vec![
+                        "/bin/sh".to_string(),
+                        "-c".to_string(),
+                        "echo \"${PLANTER_TEST_CELL_INHERITED:-unset} ${PLANTER_TEST_CELL_OVERRIDDEN:-unset} ${PLANTER_TEST_CELL_HIDDEN:-unset}\"".to_string(),
+                    ],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                RunJobOptions::default(), None,
+            )
+            .await
+            .expect("run job");
+        store
+            .wait_job(&job.id, Some(5_000))
+            .await
+            .expect("wait job");
+
+        let logs = store
+            .read_logs(
+                &job.id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 0,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        assert_eq!(logs.data, b"from-host from-cell unset\n");
+    }
+
+    #[test]
+    /// Verifies a cell can't inherit a daemon env var the operator did not allowlist.
+    fn cell_inheriting_unlisted_host_env_is_rejected() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("state");
+        let platform = Arc::new(DirOnlyPlatform { root: root.clone() });
+        let store = StateStore::new(root, platform)
+            .expect("state should initialize")
+            .with_inheritable_env(vec!["PLANTER_TEST_CELL_INHERITED".to_string()]);
+
+        let err = store
+            .create_cell(CellSpec {
+                name: "inherit".to_string(),
+                env: BTreeMap::new(),
+                sandbox_mode: None,
+                inherit_host_env: vec!["PLANTER_TEST_CELL_SECRET".to_string()],
+                worker_bin_override: None,
+            })
+            .expect_err("unlisted inherit should fail");
+
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("PLANTER_TEST_CELL_SECRET"), "{err:?}");
+    }
+
+    #[test]
+    /// Verifies only allowlisted daemon env vars are captured for cells to inherit.
+    fn host_env_keeps_only_allowlisted_vars() {
+        let vars = [
+            ("PLANTER_TEST_ALLOWED", "allowed"),
+            ("PLANTER_TEST_SECRET", "secret"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let host_env = inheritable_host_env(vars, &["PLANTER_TEST_ALLOWED".to_string()]);
+
+        assert_eq!(
+            host_env,
+            BTreeMap::from([("PLANTER_TEST_ALLOWED".to_string(), "allowed".to_string())])
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies killing an already-finished job reports that no signal was sent.
+    async fn kill_finished_job_reports_no_signal() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().to_path_buf());
+        let job_id = store_job(&store, &cell_id, ExitStatus::Exited { code: Some(0) }, b"");
+
+        let result = store
+            .kill_job(&job_id, true, None)
+            .await
+            .expect("kill finished job");
+        assert!(!result.delivered);
+        assert_eq!(result.signal, "none");
+        assert_eq!(result.job.status, ExitStatus::Exited { code: Some(0) });
+        assert_eq!(result.job.termination_reason, None);
+    }
+
+    #[tokio::test]
+    /// Verifies concurrent deterministic launches of the same spec start the job only once.
+    async fn concurrent_deterministic_launches_start_once() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let launch = || {
+            store.run_job(
+                cell_id.clone(),
+                CommandSpec {
+                    argv: vec!["/bin/sleep".to_string(), "1".to_string()],
+                    cwd: None,
+                    env: BTreeMap::new(),
+                    limits: None,
+                },
+                RunJobOptions {
+                    deterministic_id: true,
+                    ..RunJobOptions::default()
+                },
+                None,
+            )
+        };
+
+        let (first, second) = tokio::join!(launch(), launch());
+        let ids: Vec<JobId> = [first, second]
+            .into_iter()
+            .filter_map(|result| match result {
+                Ok(job) => Some(job.id),
+                Err(err) => {
+                    assert_eq!(err.code, ErrorCode::Unavailable);
+                    None
+                }
+            })
+            .collect();
+        assert!(!ids.is_empty());
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(store.all_jobs().expect("list jobs").len(), 1);
+    }
+
+    #[tokio::test]
+    /// Verifies a drain waits on in-flight launches and clears its flag when it times out.
+    async fn drain_timeout_counts_launches_and_clears_flag() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let launching = JobId("job-launching".to_string());
+        assert_eq!(
+            store.reserve_running_slot(&launching, &cell_id),
+            super::SlotClaim::Reserved
+        );
+
+        let drained = store.drain_cell(&cell_id, 0).await.expect("drain");
+        assert!(!drained.removed);
+        assert_eq!(drained.pending_jobs, vec![launching]);
+        assert!(!store.load_cell(&cell_id).expect("cell kept").draining);
+    }
+
+    #[tokio::test]
+    /// Verifies a kill asking for more than the maximum grace period is rejected.
+    async fn kill_rejects_grace_beyond_limit() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().to_path_buf());
+        let job_id = store_job(&store, &cell_id, ExitStatus::Running, b"");
+
+        let Err(err) = store.kill_job(&job_id, false, Some(u64::MAX)).await else {
+            panic!("huge grace should be rejected");
+        };
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert_eq!(
+            store.load_job(&job_id).expect("load job").status,
+            ExitStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies sandbox checks report unsupported on backends without a probe.
+    async fn sandbox_check_unsupported_without_backend_probe() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+
+        let err = store
+            .sandbox_check(&cell_id, "/tmp", SandboxOperation::Read)
+            .await
+            .expect_err("dir-only platform has no probe");
+        assert_eq!(err.message, "platform unsupported");
+        assert_eq!(err.detail.as_deref(), Some("sandbox_check"));
+    }
+
+    #[tokio::test]
+    /// Verifies restarting a worker marks its running jobs terminated and keeps finished ones.
+    async fn restart_worker_terminates_running_jobs() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let command = |script: &str| CommandSpec {
+            argv: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+        let finished = store
+            .run_job(
+                cell_id.clone(),
+                command("exit 3"),
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect("run finished job");
+        store
+            .wait_job(&finished.id, Some(5_000))
+            .await
+            .expect("wait finished job");
+        let running = store
+            .run_job(
+                cell_id.clone(),
+                command("sleep 30"),
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect("run running job");
+
+        let terminated = store
+            .restart_worker(&cell_id)
+            .await
+            .expect("restart worker");
+        assert_eq!(terminated, vec![running.id.clone()]);
+
+        let running = store.load_job(&running.id).expect("load running job");
+        assert_eq!(running.status, ExitStatus::Exited { code: None });
+        assert_eq!(running.termination_reason, Some(TerminationReason::Unknown));
+        assert!(running.finished_at_ms.is_some());
+        let finished = store.load_job(&finished.id).expect("load finished job");
+        assert_eq!(finished.status, ExitStatus::Exited { code: Some(3) });
+        assert!(store.workers.is_alive(&cell_id).await.expect("ping worker"));
+    }
+
+    #[tokio::test]
+    /// Verifies a job's `mktemp` lands in its own directory inside the cell, removed once the job
+    /// finishes, unless it opts out.
+    async fn job_temp_files_stay_inside_cell() {
+        let tmp = tempdir().expect("tempdir");
+        let (store, cell_id) = store_with_cell(tmp.path().join("state"));
+        let mktemp = CommandSpec {
+            argv: vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                "mktemp".to_string(),
+            ],
+            cwd: None,
+            env: BTreeMap::new(),
+            limits: None,
+        };
+
+        let job = store
+            .run_job(
+                cell_id.clone(),
+                mktemp.clone(),
+                RunJobOptions::default(),
+                None,
+            )
+            .await
+            .expect("run job");
+        store
+            .wait_job(&job.id, Some(5_000))
+            .await
+            .expect("wait job");
+        let logs = store
+            .read_logs(
+                &job.id,
+                LogStream::Stdout,
+                LogsReadSpec {
+                    offset: 0,
+                    max_bytes: 1024,
+                    follow: false,
+                    wait_ms: 0,
+                    strip_ansi: false,
+                    reverse: false,
+                    generation: None,
+                },
+            )
+            .await
+            .expect("read logs");
+        let temp_file = PathBuf::from(String::from_utf8_lossy(&logs.data).trim());
+        let cell_dir = PathBuf::from(store.load_cell(&cell_id).expect("load cell").dir);
+        assert!(
+            temp_file.starts_with(cell_dir.join(".tmp").join(&job.id.0)),
+            "{} is outside {}",
+            temp_file.display(),
+            cell_dir.display()
+        );
+        assert!(!cell_dir.join(".tmp").join(&job.id.0).exists());
+
+        let opted_out = store
+            .run_job(
+                cell_id,
+                mktemp,
+                RunJobOptions {
+                    system_tmp: true,
+                    ..RunJobOptions::default()
+                },
+                None,
+            )
+            .await
+            .expect("run opted-out job");
+        let env = opted_out.effective_env.expect("effective env");
+        assert!(!env.contains_key("TMPDIR"));
+        assert!(!cell_dir.join(".tmp").join(&opted_out.id.0).exists());
     }
 }
@@ -1,9 +1,13 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use planter_core::{ErrorCode, PlanterError};
 use planter_execd_proto::{
-    EXECD_PROTOCOL_VERSION, ExecErrorCode, ExecRequest, ExecRequestEnvelope, ExecResponse,
-    ExecResponseEnvelope,
+    EXECD_PROTOCOL_VERSION, ExecRequest, ExecRequestEnvelope, ExecResponse, ExecResponseEnvelope,
+    UNSUPPORTED_PROTOCOL_MESSAGE,
 };
 use planter_ipc::{
     codec::{decode, encode},
@@ -11,6 +15,9 @@ use planter_ipc::{
 };
 use tokio::net::UnixStream;
 
+/// Message of the error reported when the worker socket itself fails.
+pub const WORKER_IPC_MESSAGE: &str = "worker ipc";
+
 /// Thin RPC client used by `planterd` to talk to one worker process.
 pub struct WorkerClient {
     /// Worker control socket stream.
@@ -29,27 +36,50 @@ impl WorkerClient {
     }
 
     /// Performs protocol/auth handshake with the worker.
-    pub async fn hello(&mut self, auth_token: String, cell_id: String) -> Result<(), PlanterError> {
+    ///
+    /// Fails when the worker reports a state root other than `state_root`.
+    pub async fn hello(
+        &mut self,
+        auth_token: String,
+        cell_id: String,
+        state_root: &Path,
+    ) -> Result<(), PlanterError> {
         let response = self
             .call(ExecRequest::Hello {
                 protocol: EXECD_PROTOCOL_VERSION,
                 auth_token,
                 cell_id,
             })
-            .await?;
+            .await
+            .map_err(|err| {
+                if err.code == ErrorCode::InvalidRequest
+                    && err.message == UNSUPPORTED_PROTOCOL_MESSAGE
+                {
+                    let worker_protocol = err
+                        .detail
+                        .as_deref()
+                        .and_then(rejected_hello_worker_protocol)
+                        .map_or_else(|| "unknown".to_string(), |protocol| protocol.to_string());
+                    protocol_mismatch(worker_protocol)
+                } else {
+                    err
+                }
+            })?;
 
         match response {
-            ExecResponse::HelloAck { protocol, .. } if protocol == EXECD_PROTOCOL_VERSION => Ok(()),
-            ExecResponse::HelloAck { protocol, .. } => Err(PlanterError {
-                code: ErrorCode::ProtocolMismatch,
-                message: "worker protocol mismatch".to_string(),
-                detail: Some(format!("expected={EXECD_PROTOCOL_VERSION} got={protocol}")),
-            }),
-            other => Err(PlanterError {
+            ExecResponse::HelloAck { protocol, .. } if protocol != EXECD_PROTOCOL_VERSION => {
+                Err(protocol_mismatch(protocol))
+            }
+            ExecResponse::HelloAck {
+                state_root: Some(reported),
+                ..
+            } if !same_state_root(Path::new(&reported), state_root) => Err(PlanterError {
                 code: ErrorCode::Internal,
-                message: "unexpected worker hello response".to_string(),
-                detail: Some(format!("{other:?}")),
+                message: "worker state root mismatch".to_string(),
+                detail: Some(format!("expected={} got={reported}", state_root.display())),
             }),
+            ExecResponse::HelloAck { .. } => Ok(()),
+            other => Err(unexpected_worker_response("hello", other)),
         }
     }
 
@@ -58,11 +88,7 @@ impl WorkerClient {
         let response = self.call(ExecRequest::Ping {}).await?;
         match response {
             ExecResponse::Pong {} => Ok(()),
-            other => Err(PlanterError {
-                code: ErrorCode::Internal,
-                message: "unexpected worker ping response".to_string(),
-                detail: Some(format!("{other:?}")),
-            }),
+            other => Err(unexpected_worker_response("ping", other)),
         }
     }
 
@@ -89,18 +115,7 @@ impl WorkerClient {
             });
         }
 
-        match response.body {
-            ExecResponse::ExecError {
-                code,
-                message,
-                detail,
-            } => Err(PlanterError {
-                code: map_exec_error(code),
-                message,
-                detail,
-            }),
-            body => Ok(body),
-        }
+        response.body.into_result()
     }
 }
 
@@ -148,15 +163,47 @@ pub fn new_auth_token() -> String {
     format!("wkr-{}-{ts}", std::process::id())
 }
 
-/// Maps worker-specific error codes to daemon-level error categories.
-fn map_exec_error(code: ExecErrorCode) -> ErrorCode {
-    match code {
-        ExecErrorCode::InvalidRequest => ErrorCode::InvalidRequest,
-        ExecErrorCode::NotFound => ErrorCode::NotFound,
-        ExecErrorCode::Unauthorized => ErrorCode::Unavailable,
-        ExecErrorCode::Unavailable => ErrorCode::Unavailable,
-        ExecErrorCode::Unsupported => ErrorCode::InvalidRequest,
-        ExecErrorCode::Internal => ErrorCode::Internal,
+/// Builds the error for a worker speaking another exec protocol version.
+fn protocol_mismatch(worker_protocol: impl std::fmt::Display) -> PlanterError {
+    PlanterError {
+        code: ErrorCode::ProtocolMismatch,
+        message: "worker protocol mismatch".to_string(),
+        detail: Some(format!(
+            "expected={EXECD_PROTOCOL_VERSION} got={worker_protocol}"
+        )),
+    }
+}
+
+/// Compares state roots by path components, falling back to their canonical forms.
+fn same_state_root(reported: &Path, expected: &Path) -> bool {
+    if reported == expected {
+        return true;
+    }
+    match (fs::canonicalize(reported), fs::canonicalize(expected)) {
+        (Ok(reported), Ok(expected)) => reported == expected,
+        _ => false,
+    }
+}
+
+/// Extracts the worker's own version from its `expected=<worker> got=<daemon>` hello rejection.
+fn rejected_hello_worker_protocol(detail: &str) -> Option<u32> {
+    detail
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("expected="))
+        .and_then(|version| version.parse().ok())
+}
+
+/// Builds the error for a worker reply that does not answer `action`.
+///
+/// A worker error reply surfaces as the worker reported it instead of as an unexpected variant.
+pub fn unexpected_worker_response(action: &str, response: ExecResponse) -> PlanterError {
+    match response.into_result() {
+        Err(err) => err,
+        Ok(other) => PlanterError {
+            code: ErrorCode::Internal,
+            message: format!("unexpected worker response for {action}"),
+            detail: Some(format!("{other:?}")),
+        },
     }
 }
 
@@ -164,14 +211,16 @@ fn map_exec_error(code: ExecErrorCode) -> ErrorCode {
 fn to_ipc_error(err: planter_ipc::IpcError) -> PlanterError {
     PlanterError {
         code: ErrorCode::Internal,
-        message: "worker ipc".to_string(),
+        message: WORKER_IPC_MESSAGE.to_string(),
         detail: Some(err.to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{WorkerClient, make_socket_pair, map_exec_error};
+    use std::path::Path;
+
+    use super::{WorkerClient, make_socket_pair, unexpected_worker_response};
     use planter_core::ErrorCode;
     use planter_execd_proto::{
         ExecErrorCode, ExecRequest, ExecRequestEnvelope, ExecResponse, ExecResponseEnvelope,
@@ -196,6 +245,7 @@ mod tests {
                 ExecRequest::Hello { .. } => ExecResponse::HelloAck {
                     protocol: planter_execd_proto::EXECD_PROTOCOL_VERSION,
                     worker_pid: 123,
+                    state_root: Some("/srv/planter".to_string()),
                 },
                 ExecRequest::Ping {} => ExecResponse::Pong {},
                 _ => ExecResponse::ExecError {
@@ -228,7 +278,11 @@ mod tests {
 
         let mut client = WorkerClient::new(client_stream);
         client
-            .hello("token".to_string(), "cell-1".to_string())
+            .hello(
+                "token".to_string(),
+                "cell-1".to_string(),
+                Path::new("/srv/planter"),
+            )
             .await
             .expect("hello");
         client.ping().await.expect("ping");
@@ -236,16 +290,59 @@ mod tests {
         server.abort();
     }
 
+    #[tokio::test]
+    /// Verifies hello fails when the worker reports a different state root.
+    async fn hello_rejects_mismatched_state_root() {
+        let (client_stream, server_stream) = make_socket_pair().expect("socket pair");
+        let server = tokio::spawn(async move {
+            fake_server(server_stream).await;
+        });
+
+        let mut client = WorkerClient::new(client_stream);
+        let err = client
+            .hello(
+                "token".to_string(),
+                "cell-1".to_string(),
+                Path::new("/srv/other"),
+            )
+            .await
+            .expect_err("mismatched root should fail");
+        assert_eq!(err.message, "worker state root mismatch");
+        assert_eq!(
+            err.detail.as_deref(),
+            Some("expected=/srv/other got=/srv/planter")
+        );
+
+        server.abort();
+    }
+
     #[test]
     /// Verifies unavailable-style exec errors map to daemon unavailable errors.
     fn map_exec_error_unavailable() {
         assert_eq!(
-            map_exec_error(ExecErrorCode::Unavailable),
+            ErrorCode::from(ExecErrorCode::Unavailable),
             ErrorCode::Unavailable
         );
         assert_eq!(
-            map_exec_error(ExecErrorCode::Unauthorized),
+            ErrorCode::from(ExecErrorCode::Unauthorized),
             ErrorCode::Unavailable
         );
     }
+
+    #[test]
+    /// Verifies a worker error reply keeps its code, message, and detail instead of a debug dump.
+    fn unexpected_worker_error_reply_is_passed_through() {
+        let err = unexpected_worker_response(
+            "job status",
+            ExecResponse::ExecError {
+                code: ExecErrorCode::NotFound,
+                message: "job job-1 does not exist".to_string(),
+                detail: Some("cell=cell-1".to_string()),
+            },
+        );
+
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert_eq!(err.message, "job job-1 does not exist");
+        assert_eq!(err.detail.as_deref(), Some("cell=cell-1"));
+    }
 }
@@ -2,44 +2,74 @@
 
 use std::{
     collections::HashMap,
-    os::fd::AsRawFd,
-    path::PathBuf,
-    sync::{Arc, Mutex, MutexGuard},
-    time::Duration,
+    os::{fd::AsRawFd, unix::fs::PermissionsExt},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
-use planter_core::{CellId, ErrorCode, PlanterError, now_ms};
-use planter_execd::WorkerConfig;
+use planter_core::{CellId, ErrorCode, PlanterError, WorkerDiagnostics, WorkerMode, now_ms};
+use planter_execd::{DEFAULT_READER_JOIN_TIMEOUT, WorkerConfig};
 use planter_execd_proto::{ExecRequest, ExecResponse};
+use planter_platform::current_euid;
 use tokio::{
     net::UnixStream,
     process::{Child, Command},
-    sync::Mutex as AsyncMutex,
+    sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard},
     task::JoinHandle,
-    time::timeout,
+    time::{timeout, timeout_at},
 };
 
-use crate::worker::{WorkerClient, new_auth_token};
+use crate::worker::{WORKER_IPC_MESSAGE, WorkerClient, new_auth_token};
 
 /// Default path used when no explicit worker binary override is provided.
 const DEFAULT_WORKER_BIN: &str = "target/debug/planter-execd";
+/// Comma-separated host env var names passed through to jobs.
+const JOB_ENV_ALLOW_VAR: &str = "PLANTER_JOB_ENV_ALLOW";
+/// Truthy value lets jobs keep the worker's controlling terminal.
+const JOB_INHERIT_TTY_VAR: &str = "PLANTER_JOB_INHERIT_TTY";
 /// Maximum handshake wait before considering worker startup failed.
 const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(2_000);
+/// Maximum wait for a worker to answer a shutdown request before it is killed.
+const SHUTDOWN_REQUEST_TIMEOUT: Duration = Duration::from_millis(2_000);
+/// Maximum wait for an in-process worker to finish its own cleanup before it is aborted.
+const IN_PROCESS_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(2_000);
+/// Default milliseconds a call waits behind another call into the same cell before failing.
+pub const DEFAULT_CALL_LOCK_TIMEOUT_MS: u64 = 10_000;
+/// Default milliseconds closing a PTY session waits for its output reader.
+pub const DEFAULT_PTY_READER_JOIN_TIMEOUT_MS: u64 = DEFAULT_READER_JOIN_TIMEOUT.as_millis() as u64;
 /// Per-cell async mutex used to serialize calls into a worker.
 type CallLock = Arc<AsyncMutex<()>>;
 /// Mapping from cell id to call lock.
 type CallLockMap = HashMap<String, CallLock>;
+/// Client for a worker's peek lane, which answers PTY peeks beside its control stream.
+type PeekLane = Arc<AsyncMutex<WorkerClient>>;
 
 /// Lifecycle manager for `planter-execd` worker processes.
 pub struct WorkerManager {
     /// Worker executable path.
     worker_bin: PathBuf,
+    /// Per-cell worker executables used instead of `worker_bin`, keyed by cell id.
+    worker_bin_overrides: Mutex<HashMap<String, PathBuf>>,
     /// Root state directory passed to workers.
     state_root: PathBuf,
+    /// Host env var names workers pass through to jobs.
+    env_allowlist: Vec<String>,
+    /// Whether jobs keep the worker's controlling terminal.
+    inherit_tty: bool,
     /// Active workers keyed by cell id.
     workers: Mutex<HashMap<String, WorkerHandle>>,
     /// Per-cell request serialization locks.
     call_locks: Mutex<CallLockMap>,
+    /// Peek lanes of spawned workers, keyed by cell id.
+    peek_lanes: Mutex<HashMap<String, PeekLane>>,
+    /// Milliseconds a call waits for its cell's lock before reporting the cell busy.
+    call_lock_timeout_ms: AtomicU64,
+    /// Milliseconds closing a PTY session waits for its output reader, passed to new workers.
+    pty_reader_join_timeout_ms: AtomicU64,
 }
 
 /// In-memory handle for one active worker.
@@ -63,16 +93,24 @@ enum WorkerRuntime {
 impl WorkerHandle {
     /// Attempts graceful worker shutdown, then forcefully tears down runtime.
     async fn terminate(&mut self) {
-        let _ = self
-            .client
-            .call(ExecRequest::Shutdown { force: true })
-            .await;
+        // A worker stuck before its handshake never answers, so don't wait on it forever.
+        let _ = timeout(
+            SHUTDOWN_REQUEST_TIMEOUT,
+            self.client.call(ExecRequest::Shutdown { force: true }),
+        )
+        .await;
         match &mut self.runtime {
             WorkerRuntime::Process(child) => {
                 let _ = child.kill().await;
             }
             WorkerRuntime::InProcess(task) => {
-                task.abort();
+                // Aborting skips the worker's cleanup, so let it exit on its own first.
+                if timeout(IN_PROCESS_SHUTDOWN_TIMEOUT, &mut *task)
+                    .await
+                    .is_err()
+                {
+                    task.abort();
+                }
             }
         }
     }
@@ -85,9 +123,15 @@ impl WorkerManager {
             worker_bin: std::env::var("PLANTER_EXECD_BIN")
                 .map(PathBuf::from)
                 .unwrap_or_else(|_| PathBuf::from(DEFAULT_WORKER_BIN)),
+            worker_bin_overrides: Mutex::new(HashMap::new()),
             state_root,
+            env_allowlist: job_env_allowlist(),
+            inherit_tty: job_inherit_tty(),
             workers: Mutex::new(HashMap::new()),
             call_locks: Mutex::new(HashMap::new()),
+            peek_lanes: Mutex::new(HashMap::new()),
+            call_lock_timeout_ms: AtomicU64::new(DEFAULT_CALL_LOCK_TIMEOUT_MS),
+            pty_reader_join_timeout_ms: AtomicU64::new(DEFAULT_PTY_READER_JOIN_TIMEOUT_MS),
         }
     }
 
@@ -95,39 +139,92 @@ impl WorkerManager {
     pub fn with_worker_bin(state_root: PathBuf, worker_bin: PathBuf) -> Self {
         Self {
             worker_bin,
+            worker_bin_overrides: Mutex::new(HashMap::new()),
             state_root,
+            env_allowlist: job_env_allowlist(),
+            inherit_tty: job_inherit_tty(),
             workers: Mutex::new(HashMap::new()),
             call_locks: Mutex::new(HashMap::new()),
+            peek_lanes: Mutex::new(HashMap::new()),
+            call_lock_timeout_ms: AtomicU64::new(DEFAULT_CALL_LOCK_TIMEOUT_MS),
+            pty_reader_join_timeout_ms: AtomicU64::new(DEFAULT_PTY_READER_JOIN_TIMEOUT_MS),
         }
     }
 
+    /// Bounds how long a call waits behind another call into the same cell.
+    ///
+    /// One stuck call, such as a follow read with a huge wait, then fails later callers with
+    /// `Unavailable` instead of wedging the whole cell.
+    pub fn set_call_lock_timeout(&self, timeout: Duration) {
+        let timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self.call_lock_timeout_ms
+            .store(timeout_ms, Ordering::Relaxed);
+    }
+
+    /// Sets how long workers spawned from now on wait for a closing PTY session's reader.
+    pub fn set_pty_reader_join_timeout(&self, timeout: Duration) {
+        let timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self.pty_reader_join_timeout_ms
+            .store(timeout_ms, Ordering::Relaxed);
+    }
+
+    /// Sets or clears the worker executable spawned for one cell.
+    ///
+    /// Takes effect the next time the cell's worker is spawned.
+    pub fn set_worker_bin_override(
+        &self,
+        cell_id: &CellId,
+        worker_bin: Option<PathBuf>,
+    ) -> Result<(), PlanterError> {
+        let mut overrides = self.worker_bin_overrides_lock();
+        match worker_bin {
+            Some(worker_bin) => overrides.insert(cell_id.0.clone(), worker_bin),
+            None => overrides.remove(&cell_id.0),
+        };
+        Ok(())
+    }
+
     /// Sends one request to the worker for the given cell, spawning as needed.
     pub async fn call(
         &self,
         cell_id: &CellId,
         request: ExecRequest,
+    ) -> Result<ExecResponse, PlanterError> {
+        self.call_with_deadline(cell_id, request, None).await
+    }
+
+    /// Sends one request like [`Self::call`], giving up with `Timeout` if the worker is not
+    /// ready by `deadline`.
+    ///
+    /// A worker still starting when the deadline passes is torn down. Once the request has been
+    /// sent its reply is always awaited, so the caller learns about anything it started.
+    pub async fn call_with_deadline(
+        &self,
+        cell_id: &CellId,
+        request: ExecRequest,
+        deadline: Option<Instant>,
     ) -> Result<ExecResponse, PlanterError> {
         let key = cell_id.0.clone();
-        let call_lock = self.get_call_lock(&key)?;
-        let _call_guard = call_lock.lock().await;
+        let call_lock = self.get_call_lock(&key);
+        let _call_guard = self.lock_cell(cell_id, &call_lock, deadline).await?;
 
-        let mut handle = match self.take_worker(&key)? {
+        let mut handle = match self.take_worker(&key) {
             Some(mut existing) => {
                 if existing.client.ping().await.is_ok() {
                     existing
                 } else {
                     existing.terminate().await;
-                    self.spawn_worker(cell_id).await?
+                    self.spawn_worker(cell_id, deadline).await?
                 }
             }
-            None => self.spawn_worker(cell_id).await?,
+            None => self.spawn_worker(cell_id, deadline).await?,
         };
 
         let response = handle.client.call(request).await;
         match response {
             Ok(response) => {
                 handle.last_used_ms = now_ms();
-                self.put_worker(key, handle)?;
+                self.put_worker(key, handle);
                 Ok(response)
             }
             Err(err) => {
@@ -137,10 +234,92 @@ impl WorkerManager {
         }
     }
 
+    /// Sends a PTY peek over the cell's peek lane, without waiting on its call lock.
+    ///
+    /// Falls back to [`Self::call`] when the cell has no live worker to peek.
+    pub async fn peek(
+        &self,
+        cell_id: &CellId,
+        request: ExecRequest,
+    ) -> Result<ExecResponse, PlanterError> {
+        let lane = self.peek_lanes_lock().get(&cell_id.0).cloned();
+        if let Some(lane) = lane {
+            match lane.lock().await.call(request.clone()).await {
+                Err(err) if err.message == WORKER_IPC_MESSAGE => {
+                    let mut lanes = self.peek_lanes_lock();
+                    if lanes
+                        .get(&cell_id.0)
+                        .is_some_and(|current| Arc::ptr_eq(current, &lane))
+                    {
+                        lanes.remove(&cell_id.0);
+                    }
+                }
+                result => return result,
+            }
+        }
+        self.call(cell_id, request).await
+    }
+
+    /// Pings the cached worker for a cell without spawning one; false when none is cached.
+    pub async fn is_alive(&self, cell_id: &CellId) -> Result<bool, PlanterError> {
+        self.is_alive_with_deadline(cell_id, None).await
+    }
+
+    /// Pings like [`Self::is_alive`], giving up with `Timeout` if the cell's call lock is not
+    /// free by `deadline`.
+    ///
+    /// Only the lock wait is bounded: once the worker is taken out of the map the ping runs to
+    /// completion, so a cancelled probe never strands the worker outside the map.
+    pub async fn is_alive_with_deadline(
+        &self,
+        cell_id: &CellId,
+        deadline: Option<Instant>,
+    ) -> Result<bool, PlanterError> {
+        let key = cell_id.0.clone();
+        if !self.workers_lock().contains_key(&key) {
+            return Ok(false);
+        }
+
+        let call_lock = self.get_call_lock(&key);
+        let _call_guard = self.lock_cell(cell_id, &call_lock, deadline).await?;
+        let Some(mut handle) = self.take_worker(&key) else {
+            return Ok(false);
+        };
+        let alive = handle.client.ping().await.is_ok();
+        self.put_worker(key, handle);
+        Ok(alive)
+    }
+
+    /// Returns the cell ids that currently have a cached worker.
+    pub fn cached_cells(&self) -> Vec<CellId> {
+        let mut cells: Vec<CellId> = self.workers_lock().keys().cloned().map(CellId).collect();
+        cells.sort_by(|left, right| left.0.cmp(&right.0));
+        cells
+    }
+
+    /// Describes each cached worker and how it runs, ordered by cell id.
+    pub fn worker_diagnostics(&self) -> Vec<WorkerDiagnostics> {
+        let mut workers: Vec<WorkerDiagnostics> = self
+            .workers_lock()
+            .iter()
+            .map(|(key, handle)| WorkerDiagnostics {
+                cell_id: CellId(key.clone()),
+                mode: match handle.runtime {
+                    WorkerRuntime::Process(_) => WorkerMode::Process,
+                    WorkerRuntime::InProcess(_) => WorkerMode::InProcess,
+                },
+            })
+            .collect();
+        workers.sort_by(|left, right| left.cell_id.0.cmp(&right.cell_id.0));
+        workers
+    }
+
     /// Stops and forgets the worker associated with a cell id.
+    ///
+    /// In-process workers are shut down in the background so they can kill their own jobs.
     pub fn stop_worker(&self, cell_id: &CellId, _force: bool) -> Result<(), PlanterError> {
         let key = cell_id.0.clone();
-        let Some(mut handle) = self.take_worker(&key)? else {
+        let Some(mut handle) = self.take_worker(&key) else {
             return Ok(());
         };
 
@@ -148,92 +327,139 @@ impl WorkerManager {
             WorkerRuntime::Process(child) => {
                 let _ = child.start_kill();
             }
-            WorkerRuntime::InProcess(task) => task.abort(),
+            WorkerRuntime::InProcess(task) => match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(async move { handle.terminate().await });
+                }
+                Err(_) => task.abort(),
+            },
         }
-        let _ = self.call_locks_lock()?.remove(&key);
+        let _ = self.call_locks_lock().remove(&key);
+        let _ = self.peek_lanes_lock().remove(&key);
         Ok(())
     }
 
-    /// Spawns or boots a worker runtime and completes hello handshake.
-    async fn spawn_worker(&self, cell_id: &CellId) -> Result<WorkerHandle, PlanterError> {
-        let (parent_std, child_std) =
-            std::os::unix::net::UnixStream::pair().map_err(|err| PlanterError {
-                code: ErrorCode::Unavailable,
-                message: "create worker socketpair".to_string(),
-                detail: Some(err.to_string()),
-            })?;
-        parent_std
-            .set_nonblocking(true)
-            .map_err(|err| PlanterError {
-                code: ErrorCode::Unavailable,
-                message: "configure worker socketpair".to_string(),
-                detail: Some(err.to_string()),
-            })?;
-        child_std
-            .set_nonblocking(true)
-            .map_err(|err| PlanterError {
-                code: ErrorCode::Unavailable,
-                message: "configure worker socketpair".to_string(),
-                detail: Some(err.to_string()),
-            })?;
+    /// Terminates the cached worker for a cell, if any, and spawns a fresh one in its place.
+    pub async fn restart_worker(&self, cell_id: &CellId) -> Result<(), PlanterError> {
+        let key = cell_id.0.clone();
+        let call_lock = self.get_call_lock(&key);
+        let _call_guard = self.lock_cell(cell_id, &call_lock, None).await?;
 
+        if let Some(mut existing) = self.take_worker(&key) {
+            existing.terminate().await;
+        }
+        let handle = self.spawn_worker(cell_id, None).await?;
+        self.put_worker(key, handle);
+        Ok(())
+    }
+
+    /// Spawns or boots a worker runtime and completes hello handshake.
+    ///
+    /// The handshake is cut short at `deadline`, when that comes before its own timeout.
+    async fn spawn_worker(
+        &self,
+        cell_id: &CellId,
+        deadline: Option<Instant>,
+    ) -> Result<WorkerHandle, PlanterError> {
+        let (parent_stream, child_std) = worker_socket_pair()?;
+        let (peek_parent, peek_child) = worker_socket_pair()?;
         let child_fd = child_std.as_raw_fd();
-        let parent_stream = UnixStream::from_std(parent_std).map_err(|err| PlanterError {
-            code: ErrorCode::Unavailable,
-            message: "convert worker socket".to_string(),
-            detail: Some(err.to_string()),
-        })?;
+        let peek_fd = peek_child.as_raw_fd();
+
+        let override_bin = self.worker_bin_overrides_lock().get(&cell_id.0).cloned();
+        if let Some(worker_bin) = &override_bin {
+            check_worker_bin(worker_bin)?;
+        }
+        // An explicit per-cell binary is always spawned; only the default may run in-process.
+        let worker_bin = override_bin.as_ref().unwrap_or(&self.worker_bin);
 
         let auth_token = new_auth_token();
-        let runtime = if use_inprocess_worker(&self.worker_bin) {
-            let child_stream = UnixStream::from_std(child_std).map_err(|err| PlanterError {
-                code: ErrorCode::Unavailable,
-                message: "convert in-process worker socket".to_string(),
-                detail: Some(err.to_string()),
-            })?;
+        let pty_reader_join_timeout_ms = self.pty_reader_join_timeout_ms.load(Ordering::Relaxed);
+        let runtime = if override_bin.is_none() && use_inprocess_worker(worker_bin) {
+            let convert = |child: std::os::unix::net::UnixStream| {
+                UnixStream::from_std(child).map_err(|err| PlanterError {
+                    code: ErrorCode::Unavailable,
+                    message: "convert in-process worker socket".to_string(),
+                    detail: Some(err.to_string()),
+                })
+            };
+            let child_stream = convert(child_std)?;
+            let peek_stream = convert(peek_child)?;
             let config = WorkerConfig {
                 cell_id: cell_id.0.clone(),
                 auth_token: auth_token.clone(),
                 state_root: self.state_root.clone(),
+                env_allowlist: self.env_allowlist.clone(),
+                inherit_tty: self.inherit_tty,
+                pty_reader_join_timeout: Duration::from_millis(pty_reader_join_timeout_ms),
             };
             let task = tokio::spawn(async move {
-                planter_execd::serve_control_stream(child_stream, config).await
+                planter_execd::serve_control_stream_with_peek_lane(
+                    child_stream,
+                    peek_stream,
+                    config,
+                )
+                .await
             });
             WorkerRuntime::InProcess(task)
         } else {
             clear_close_on_exec(child_fd)?;
-            let mut command = Command::new(&self.worker_bin);
+            clear_close_on_exec(peek_fd)?;
+            let mut command = Command::new(worker_bin);
             command
                 .arg("--control-fd")
                 .arg(child_fd.to_string())
+                .arg("--peek-fd")
+                .arg(peek_fd.to_string())
                 .arg("--auth-token")
                 .arg(&auth_token)
                 .arg("--cell-id")
                 .arg(&cell_id.0)
                 .arg("--state-root")
-                .arg(self.state_root.display().to_string());
+                .arg(self.state_root.display().to_string())
+                .arg("--pty-reader-join-timeout-ms")
+                .arg(pty_reader_join_timeout_ms.to_string());
+            for name in &self.env_allowlist {
+                command.arg("--env-allow").arg(name);
+            }
+            if self.inherit_tty {
+                command.arg("--inherit-tty");
+            }
+            // The daemon only runs as root when its operator passed --allow-root.
+            if current_euid() == 0 {
+                command.arg("--allow-root");
+            }
 
             let child = command.spawn().map_err(|err| PlanterError {
                 code: ErrorCode::Unavailable,
                 message: "spawn planter-execd".to_string(),
-                detail: Some(format!("{}: {err}", self.worker_bin.display())),
+                detail: Some(format!("{}: {err}", worker_bin.display())),
             })?;
             drop(child_std);
+            drop(peek_child);
             WorkerRuntime::Process(child)
         };
 
         let mut client = WorkerClient::new(parent_stream);
-        let hello = timeout(
-            HANDSHAKE_TIMEOUT,
-            client.hello(auth_token, cell_id.0.clone()),
+        let handshake_deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        let cancel_at = deadline.filter(|deadline| *deadline < handshake_deadline);
+        let hello = timeout_at(
+            cancel_at.unwrap_or(handshake_deadline).into(),
+            client.hello(auth_token, cell_id.0.clone(), &self.state_root),
         )
         .await;
         match hello {
-            Ok(Ok(())) => Ok(WorkerHandle {
-                client,
-                runtime,
-                last_used_ms: now_ms(),
-            }),
+            Ok(Ok(())) => {
+                self.peek_lanes_lock().insert(
+                    cell_id.0.clone(),
+                    Arc::new(AsyncMutex::new(WorkerClient::new(peek_parent))),
+                );
+                Ok(WorkerHandle {
+                    client,
+                    runtime,
+                    last_used_ms: now_ms(),
+                })
+            }
             Ok(Err(err)) => {
                 let mut handle = WorkerHandle {
                     client,
@@ -241,7 +467,7 @@ impl WorkerManager {
                     last_used_ms: now_ms(),
                 };
                 handle.terminate().await;
-                Err(err)
+                Err(name_skewed_worker(worker_bin, err))
             }
             Err(_) => {
                 let mut handle = WorkerHandle {
@@ -250,6 +476,9 @@ impl WorkerManager {
                     last_used_ms: now_ms(),
                 };
                 handle.terminate().await;
+                if cancel_at.is_some() {
+                    return Err(worker_not_ready_by_deadline());
+                }
                 Err(PlanterError {
                     code: ErrorCode::Unavailable,
                     message: "worker hello timed out".to_string(),
@@ -260,43 +489,120 @@ impl WorkerManager {
     }
 
     /// Removes and returns a cached worker handle for a key.
-    fn take_worker(&self, key: &str) -> Result<Option<WorkerHandle>, PlanterError> {
-        Ok(self.workers_lock()?.remove(key))
+    fn take_worker(&self, key: &str) -> Option<WorkerHandle> {
+        self.workers_lock().remove(key)
     }
 
     /// Stores a worker handle for a key.
-    fn put_worker(&self, key: String, worker: WorkerHandle) -> Result<(), PlanterError> {
-        self.workers_lock()?.insert(key, worker);
-        Ok(())
+    fn put_worker(&self, key: String, worker: WorkerHandle) {
+        self.workers_lock().insert(key, worker);
     }
 
-    /// Acquires the worker map lock and converts poisoning to planter errors.
-    fn workers_lock(&self) -> Result<MutexGuard<'_, HashMap<String, WorkerHandle>>, PlanterError> {
-        self.workers.lock().map_err(|_| PlanterError {
-            code: ErrorCode::Internal,
-            message: "worker manager lock poisoned".to_string(),
-            detail: None,
-        })
+    /// Acquires the worker map lock, recovering from a poisoned lock.
+    fn workers_lock(&self) -> MutexGuard<'_, HashMap<String, WorkerHandle>> {
+        lock_recovering(&self.workers, "workers")
+    }
+
+    /// Acquires the worker binary override map, recovering from a poisoned lock.
+    fn worker_bin_overrides_lock(&self) -> MutexGuard<'_, HashMap<String, PathBuf>> {
+        lock_recovering(&self.worker_bin_overrides, "worker_bin_overrides")
+    }
+
+    /// Waits for a cell's call lock, failing with `Unavailable` once the lock timeout passes,
+    /// or with `Timeout` when the caller's earlier `deadline` passes first.
+    async fn lock_cell<'a>(
+        &self,
+        cell_id: &CellId,
+        call_lock: &'a CallLock,
+        deadline: Option<Instant>,
+    ) -> Result<AsyncMutexGuard<'a, ()>, PlanterError> {
+        let timeout_ms = self.call_lock_timeout_ms.load(Ordering::Relaxed);
+        // A timeout too large to represent as an instant means waiting without a bound.
+        let busy_at = Instant::now().checked_add(Duration::from_millis(timeout_ms));
+        let give_up_at = match (deadline, busy_at) {
+            (Some(deadline), Some(busy_at)) => deadline.min(busy_at),
+            (Some(give_up_at), None) | (None, Some(give_up_at)) => give_up_at,
+            (None, None) => return Ok(call_lock.lock().await),
+        };
+        match timeout_at(give_up_at.into(), call_lock.lock()).await {
+            Ok(guard) => Ok(guard),
+            Err(_) if busy_at.is_none_or(|busy_at| give_up_at < busy_at) => {
+                Err(worker_not_ready_by_deadline())
+            }
+            Err(_) => Err(PlanterError {
+                code: ErrorCode::Unavailable,
+                message: format!("cell {} busy", cell_id.0),
+                detail: Some(format!(
+                    "another call into this cell has held its worker for over {timeout_ms}ms"
+                )),
+            }),
+        }
     }
 
     /// Returns the per-cell call lock, creating one if absent.
-    fn get_call_lock(&self, key: &str) -> Result<CallLock, PlanterError> {
-        let mut locks = self.call_locks_lock()?;
+    pub(crate) fn get_call_lock(&self, key: &str) -> CallLock {
+        let mut locks = self.call_locks_lock();
         if let Some(lock) = locks.get(key) {
-            return Ok(Arc::clone(lock));
+            return Arc::clone(lock);
         }
         let lock = Arc::new(AsyncMutex::new(()));
         locks.insert(key.to_string(), Arc::clone(&lock));
-        Ok(lock)
+        lock
     }
 
-    /// Acquires the call-lock map and converts poisoning to planter errors.
-    fn call_locks_lock(&self) -> Result<MutexGuard<'_, CallLockMap>, PlanterError> {
-        self.call_locks.lock().map_err(|_| PlanterError {
-            code: ErrorCode::Internal,
-            message: "worker manager call-lock map poisoned".to_string(),
-            detail: None,
-        })
+    /// Acquires the peek-lane map, recovering from a poisoned lock.
+    fn peek_lanes_lock(&self) -> MutexGuard<'_, HashMap<String, PeekLane>> {
+        lock_recovering(&self.peek_lanes, "peek_lanes")
+    }
+
+    /// Acquires the call-lock map, recovering from a poisoned lock.
+    fn call_locks_lock(&self) -> MutexGuard<'_, CallLockMap> {
+        lock_recovering(&self.call_locks, "call_locks")
+    }
+}
+
+/// Creates a nonblocking socketpair, returning the daemon end and the worker end.
+fn worker_socket_pair() -> Result<(UnixStream, std::os::unix::net::UnixStream), PlanterError> {
+    let (parent_std, child_std) =
+        std::os::unix::net::UnixStream::pair().map_err(|err| PlanterError {
+            code: ErrorCode::Unavailable,
+            message: "create worker socketpair".to_string(),
+            detail: Some(err.to_string()),
+        })?;
+    for end in [&parent_std, &child_std] {
+        end.set_nonblocking(true).map_err(|err| PlanterError {
+            code: ErrorCode::Unavailable,
+            message: "configure worker socketpair".to_string(),
+            detail: Some(err.to_string()),
+        })?;
+    }
+    let parent_stream = UnixStream::from_std(parent_std).map_err(|err| PlanterError {
+        code: ErrorCode::Unavailable,
+        message: "convert worker socket".to_string(),
+        detail: Some(err.to_string()),
+    })?;
+    Ok((parent_stream, child_std))
+}
+
+/// Locks `mutex`, taking the data back if a thread panicked while holding it.
+///
+/// The daemon's shared maps only change through single inserts and removes, and worker
+/// handles are taken out of their map while in use, so a panic cannot leave an entry
+/// half-updated. Recovering keeps one panic from failing every later call into the daemon.
+pub(crate) fn lock_recovering<'a, T>(mutex: &'a Mutex<T>, name: &'static str) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!(lock = name, "recovered daemon lock poisoned by a panic");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Error for a call whose deadline passed before its worker was ready to take it.
+fn worker_not_ready_by_deadline() -> PlanterError {
+    PlanterError {
+        code: ErrorCode::Timeout,
+        message: "request deadline passed before the worker was ready".to_string(),
+        detail: None,
     }
 }
 
@@ -311,6 +617,61 @@ fn use_inprocess_worker(worker_bin: &std::path::Path) -> bool {
     }
 }
 
+/// Rejects a worker binary override that is missing or lacks any execute bit.
+fn check_worker_bin(worker_bin: &Path) -> Result<(), PlanterError> {
+    let metadata = std::fs::metadata(worker_bin).map_err(|err| PlanterError {
+        code: ErrorCode::InvalidRequest,
+        message: format!("worker binary {} does not exist", worker_bin.display()),
+        detail: Some(err.to_string()),
+    })?;
+    if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+        return Err(PlanterError {
+            code: ErrorCode::InvalidRequest,
+            message: format!("worker binary {} is not executable", worker_bin.display()),
+            detail: None,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the job env passthrough allowlist from the daemon environment.
+fn job_env_allowlist() -> Vec<String> {
+    let Ok(value) = std::env::var(JOB_ENV_ALLOW_VAR) else {
+        return Vec::new();
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Rewrites a hello protocol mismatch to name the worker binary that needs updating.
+fn name_skewed_worker(worker_bin: &Path, err: PlanterError) -> PlanterError {
+    if err.code != ErrorCode::ProtocolMismatch {
+        return err;
+    }
+    PlanterError {
+        code: ErrorCode::ProtocolMismatch,
+        message: format!(
+            "worker binary {} speaks a different exec protocol; install the planter-execd built with this planterd",
+            worker_bin.display()
+        ),
+        detail: err.detail,
+    }
+}
+
+/// Reads whether jobs should keep the worker's controlling terminal.
+fn job_inherit_tty() -> bool {
+    std::env::var(JOB_INHERIT_TTY_VAR).is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
 /// Clears `FD_CLOEXEC` for an inherited fd passed to the worker process.
 fn clear_close_on_exec(fd: i32) -> Result<(), PlanterError> {
     // SAFETY: fcntl is called with valid command constants and the provided fd.
@@ -333,3 +694,352 @@ fn clear_close_on_exec(fd: i32) -> Result<(), PlanterError> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap, fs, os::unix::fs::PermissionsExt, path::Path, sync::Arc,
+        time::Duration,
+    };
+
+    use planter_core::{CellId, CommandSpec, ErrorCode, JobId};
+    use planter_execd_proto::{
+        EXECD_PROTOCOL_VERSION, ExecErrorCode, ExecRequest, ExecRequestEnvelope, ExecResponse,
+        ExecResponseEnvelope, UNSUPPORTED_PROTOCOL_MESSAGE,
+    };
+    use planter_ipc::{
+        codec::{decode, encode},
+        framing::{read_frame, write_frame},
+    };
+    use tempfile::tempdir;
+    use tokio::time::{Instant, sleep, timeout};
+
+    use super::{WorkerManager, name_skewed_worker};
+    use crate::worker::{WorkerClient, make_socket_pair};
+
+    #[tokio::test]
+    /// Verifies a liveness check on a cell without a worker does not spawn one.
+    async fn is_alive_does_not_spawn_missing_worker() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let cell_id = CellId("cell-idle".to_string());
+
+        assert!(!manager.is_alive(&cell_id).await.expect("liveness"));
+        assert!(manager.cached_cells().is_empty());
+        assert!(manager.call_locks_lock().is_empty());
+    }
+
+    #[tokio::test]
+    /// Verifies a cached worker reports alive and stays cached after the probe.
+    async fn is_alive_pings_cached_worker() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let cell_id = CellId("cell-busy".to_string());
+        let response = manager
+            .call(&cell_id, ExecRequest::Ping {})
+            .await
+            .expect("spawn worker");
+        assert!(matches!(response, ExecResponse::Pong {}));
+
+        assert!(manager.is_alive(&cell_id).await.expect("liveness"));
+        assert_eq!(manager.cached_cells(), vec![cell_id]);
+    }
+
+    #[tokio::test]
+    /// Verifies a panic while holding the manager's locks does not wedge later calls.
+    async fn poisoned_locks_recover() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let cell_id = CellId("cell-poisoned".to_string());
+        manager
+            .call(&cell_id, ExecRequest::Ping {})
+            .await
+            .expect("spawn worker");
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _workers = manager.workers.lock();
+            let _overrides = manager.worker_bin_overrides.lock();
+            let _call_locks = manager.call_locks.lock();
+            panic!("panic while holding worker manager locks");
+        }));
+        assert!(panicked.is_err());
+        assert!(manager.workers.is_poisoned());
+        assert!(manager.worker_bin_overrides.is_poisoned());
+        assert!(manager.call_locks.is_poisoned());
+
+        let response = manager
+            .call(&cell_id, ExecRequest::Ping {})
+            .await
+            .expect("call after poisoning");
+        assert!(matches!(response, ExecResponse::Pong {}));
+        manager
+            .set_worker_bin_override(&cell_id, None)
+            .expect("clear override");
+        assert_eq!(manager.cached_cells(), vec![cell_id.clone()]);
+        manager.stop_worker(&cell_id, false).expect("stop worker");
+        assert!(manager.cached_cells().is_empty());
+        assert!(!manager.workers.is_poisoned());
+        assert!(!manager.worker_bin_overrides.is_poisoned());
+        assert!(!manager.call_locks.is_poisoned());
+    }
+
+    #[tokio::test]
+    /// Verifies stopping an in-process worker lets it kill the jobs it spawned.
+    async fn stop_worker_terminates_in_process_worker_jobs() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let cell_id = CellId("cell-shutdown".to_string());
+        let response = manager
+            .call(
+                &cell_id,
+                ExecRequest::RunJob {
+                    job_id: JobId("job-sleep".to_string()),
+                    cmd: Box::new(CommandSpec {
+                        argv: vec!["sleep".to_string(), "30".to_string()],
+                        cwd: None,
+                        env: BTreeMap::new(),
+                        limits: None,
+                    }),
+                    env: BTreeMap::new(),
+                    stdout_path: tmp.path().join("stdout.log").display().to_string(),
+                    stderr_path: tmp.path().join("stderr.log").display().to_string(),
+                    process_title: false,
+                    sandbox: None,
+                },
+            )
+            .await
+            .expect("run job");
+        let ExecResponse::JobStarted { pid: Some(pid), .. } = response else {
+            panic!("unexpected response: {response:?}");
+        };
+        let pid = i32::try_from(pid).expect("pid fits");
+
+        manager.stop_worker(&cell_id, false).expect("stop worker");
+
+        assert!(manager.cached_cells().is_empty());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        // SAFETY: signal 0 only checks whether the pid exists.
+        while unsafe { libc::kill(pid, 0) } == 0 {
+            assert!(Instant::now() < deadline, "job {pid} outlived its worker");
+            sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies each cell spawns its own override binary and other cells keep the default.
+    async fn worker_bin_override_is_used_per_cell() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let mut bins = Vec::new();
+        for name in ["canary", "stable"] {
+            let worker_bin = tmp.path().join(format!("{name}-planter-execd"));
+            // Records which binary ran, then exits so the hello fails fast.
+            fs::write(&worker_bin, "#!/bin/sh\necho \"$0\" > \"$0.used\"\n").expect("write worker");
+            fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755))
+                .expect("chmod worker");
+            let cell_id = CellId(format!("cell-{name}"));
+            manager
+                .set_worker_bin_override(&cell_id, Some(worker_bin.clone()))
+                .expect("set override");
+            bins.push((cell_id, worker_bin));
+        }
+
+        for (cell_id, worker_bin) in &bins {
+            manager
+                .call(cell_id, ExecRequest::Ping {})
+                .await
+                .expect_err("fake worker never answers hello");
+            let used = fs::read_to_string(format!("{}.used", worker_bin.display()))
+                .expect("override binary ran");
+            assert_eq!(used.trim(), worker_bin.display().to_string());
+        }
+
+        let response = manager
+            .call(&CellId("cell-default".to_string()), ExecRequest::Ping {})
+            .await
+            .expect("default worker");
+        assert!(matches!(response, ExecResponse::Pong {}));
+    }
+
+    #[tokio::test]
+    /// Verifies process workers are told the configured PTY reader join timeout.
+    async fn process_worker_gets_pty_reader_join_timeout() {
+        let tmp = tempdir().expect("tempdir");
+        let worker_bin = tmp.path().join("args-planter-execd");
+        // Records its arguments, then exits so the hello fails fast.
+        fs::write(&worker_bin, "#!/bin/sh\necho \"$@\" > \"$0.args\"\n").expect("write worker");
+        fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755)).expect("chmod worker");
+        let manager = WorkerManager::with_worker_bin(tmp.path().to_path_buf(), worker_bin.clone());
+        manager.set_pty_reader_join_timeout(Duration::from_millis(1_234));
+
+        manager
+            .call(&CellId("cell-args".to_string()), ExecRequest::Ping {})
+            .await
+            .expect_err("fake worker never answers hello");
+
+        let args =
+            fs::read_to_string(format!("{}.args", worker_bin.display())).expect("worker ran");
+        assert!(args.contains("--pty-reader-join-timeout-ms 1234"), "{args}");
+    }
+
+    #[tokio::test]
+    /// Verifies a call stuck on a cell's worker fails later calls as busy instead of hanging them.
+    async fn stuck_call_reports_cell_busy_to_waiting_calls() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = Arc::new(WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        ));
+        manager.set_call_lock_timeout(Duration::from_millis(200));
+        let cell_id = CellId("cell-stuck".to_string());
+        let worker_bin = tmp.path().join("stuck-planter-execd");
+        // Never answers the hello, so the first call holds the cell until the handshake times out.
+        fs::write(&worker_bin, "#!/bin/sh\nexec sleep 30\n").expect("write worker");
+        fs::set_permissions(&worker_bin, fs::Permissions::from_mode(0o755)).expect("chmod worker");
+        manager
+            .set_worker_bin_override(&cell_id, Some(worker_bin))
+            .expect("set override");
+
+        let stuck = tokio::spawn({
+            let manager = Arc::clone(&manager);
+            let cell_id = cell_id.clone();
+            async move { manager.call(&cell_id, ExecRequest::Ping {}).await }
+        });
+        sleep(Duration::from_millis(50)).await;
+
+        let started = Instant::now();
+        let err = timeout(
+            Duration::from_secs(5),
+            manager.call(&cell_id, ExecRequest::Ping {}),
+        )
+        .await
+        .expect("waiting call should not hang")
+        .expect_err("cell should be busy");
+        assert_eq!(err.code, ErrorCode::Unavailable);
+        assert_eq!(err.message, "cell cell-stuck busy");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(
+            !stuck.is_finished(),
+            "stuck call should still hold the cell"
+        );
+
+        let stuck = stuck.await.expect("stuck task");
+        assert_eq!(
+            stuck.expect_err("hello never completes").message,
+            "worker hello timed out"
+        );
+    }
+
+    #[tokio::test]
+    /// Verifies a call lock timeout too large for an instant waits instead of overflowing.
+    async fn huge_call_lock_timeout_does_not_overflow() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        manager.set_call_lock_timeout(Duration::MAX);
+        let cell_id = CellId("cell-patient".to_string());
+
+        let response = manager
+            .call(&cell_id, ExecRequest::Ping {})
+            .await
+            .expect("ping worker");
+        assert!(matches!(response, ExecResponse::Pong {}));
+    }
+
+    #[tokio::test]
+    /// Verifies a missing or non-executable override is rejected before anything is spawned.
+    async fn worker_bin_override_must_be_executable() {
+        let tmp = tempdir().expect("tempdir");
+        let manager = WorkerManager::with_worker_bin(
+            tmp.path().to_path_buf(),
+            tmp.path().join("missing-planter-execd"),
+        );
+        let cell_id = CellId("cell-broken".to_string());
+        let not_executable = tmp.path().join("planter-execd.txt");
+        fs::write(&not_executable, "#!/bin/sh\n").expect("write worker");
+        fs::set_permissions(&not_executable, fs::Permissions::from_mode(0o644))
+            .expect("chmod worker");
+
+        for worker_bin in [not_executable, tmp.path().join("absent-planter-execd")] {
+            manager
+                .set_worker_bin_override(&cell_id, Some(worker_bin.clone()))
+                .expect("set override");
+            let err = manager
+                .call(&cell_id, ExecRequest::Ping {})
+                .await
+                .expect_err("override should be rejected");
+            assert_eq!(err.code, ErrorCode::InvalidRequest);
+            assert!(
+                err.message.contains(&worker_bin.display().to_string()),
+                "{}",
+                err.message
+            );
+        }
+    }
+
+    #[tokio::test]
+    /// Verifies an older worker's hello rejection names both protocol versions and the binary.
+    async fn version_skewed_worker_reports_protocol_and_binary() {
+        let (client_stream, mut worker_stream) = make_socket_pair().expect("socket pair");
+        let old_protocol = EXECD_PROTOCOL_VERSION - 1;
+        let worker = tokio::spawn(async move {
+            let frame = read_frame(&mut worker_stream).await.expect("read hello");
+            let request: ExecRequestEnvelope = decode(&frame).expect("decode hello");
+            let ExecRequest::Hello { protocol, .. } = request.body else {
+                panic!("expected hello");
+            };
+            let response = ExecResponseEnvelope {
+                req_id: request.req_id,
+                body: ExecResponse::ExecError {
+                    code: ExecErrorCode::InvalidRequest,
+                    message: UNSUPPORTED_PROTOCOL_MESSAGE.to_string(),
+                    detail: Some(format!("expected={old_protocol} got={protocol}")),
+                },
+            };
+            let payload = encode(&response).expect("encode rejection");
+            write_frame(&mut worker_stream, &payload)
+                .await
+                .expect("write rejection");
+        });
+
+        let mut client = WorkerClient::new(client_stream);
+        let err = client
+            .hello(
+                "token".to_string(),
+                "cell-1".to_string(),
+                Path::new("/srv/planter"),
+            )
+            .await
+            .expect_err("skewed hello should fail");
+        worker.await.expect("fake worker");
+        let err = name_skewed_worker(Path::new("/opt/old/planter-execd"), err);
+
+        assert_eq!(err.code, ErrorCode::ProtocolMismatch);
+        assert!(
+            err.message.contains("/opt/old/planter-execd"),
+            "{}",
+            err.message
+        );
+        assert_eq!(
+            err.detail.as_deref(),
+            Some(format!("expected={EXECD_PROTOCOL_VERSION} got={old_protocol}").as_str())
+        );
+    }
+}